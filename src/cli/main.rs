@@ -1,19 +1,43 @@
 use std::env;
 use std::fs;
+use std::path::Path;
+use std::sync::Arc;
 
-use crate::cli::planner::{plan_cli_action, CliAction};
+use crate::cli::planner::{plan_cli_action, CliAction, RunOptions};
+use crate::cli::tmux::{self, TmuxPopup};
 use crate::cli::tty::check_tty_requirements;
+use crate::fuzzy::history::{now_secs, History};
+use crate::fuzzy::{CandidateIndex, Dedup, ScoringConfig};
 use crate::get_build_info;
 use crate::help;
-use crate::input::{read_input, read_piped_stdin, reopen_stdin_from_tty, send_input_to_channel};
-use crate::tui::ui::{create_items_channel, run_tui_with_config};
-use crate::tui::TuiConfig;
+use crate::input::{
+    clean_record, read_input, read_piped_stdin_records, reopen_stdin_from_tty,
+    send_input_to_channel, send_records_to_channel, RecordSeparator,
+};
+use crate::tui::ui::{
+    create_items_channel, run_tui_with_config, run_tui_with_output, ItemSource, Tab, TuiOutput,
+    DEFAULT_PROMPT,
+};
+use crate::tui::{ColorDepth, TuiConfig};
+use tokio::sync::mpsc;
 
 /// Read items from a file.
 pub fn read_items_from_file(file_path: &str) -> Result<Vec<String>, String> {
+    read_records_from_file(file_path, &RecordSeparator::Newline)
+}
+
+/// Read items from a file, split into records by `separator`.
+pub fn read_records_from_file(
+    file_path: &str,
+    separator: &RecordSeparator,
+) -> Result<Vec<String>, String> {
     match fs::read_to_string(file_path) {
         Ok(content) => {
-            let items: Vec<String> = content.lines().map(|l| l.trim().to_string()).collect();
+            let items: Vec<String> = separator
+                .split(&content)
+                .into_iter()
+                .map(clean_record)
+                .collect();
             Ok(items)
         }
         Err(e) => Err(format!("Failed to read file: {e}")),
@@ -169,6 +193,218 @@ pub async fn run_async_tui_with_height_validation(
     }
 }
 
+/// Tabs for `--tab NAME=SOURCE`, each source opened like the input source
+/// (a directory path lists its entries)
+fn tabs(options: &RunOptions) -> Vec<Tab> {
+    options
+        .tabs
+        .iter()
+        .map(|(name, source)| {
+            let source = if std::path::Path::new(source).is_dir() {
+                format!("dir:{source}")
+            } else {
+                source.clone()
+            };
+            let record_sep = options.record_sep.clone();
+            Tab {
+                name: name.clone(),
+                source: ItemSource::new(move || {
+                    open_items(vec![source.clone()], record_sep.clone())
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Start sending the items named on the command line: the records of a
+/// file or socket, a directory's entries, or the arguments themselves.
+/// Called again to reload them.
+fn open_items(items: Vec<String>, record_sep: RecordSeparator) -> mpsc::Receiver<String> {
+    let (sender, receiver) = create_items_channel();
+    // The sender moves into the task, so the channel closes when it ends
+    tokio::spawn(async move {
+        if items.len() == 1 {
+            let item = &items[0];
+            if item.starts_with("unix://")
+                || item.starts_with("http://")
+                || item.starts_with("https://")
+            {
+                let _ = send_records_to_channel(item, sender, &record_sep).await;
+            } else if let Some(dir_path) = item.strip_prefix("dir:") {
+                let _ = send_input_to_channel(&format!("dir:{}", dir_path), sender).await;
+            } else if looks_like_file_path(item) {
+                let _ = send_records_to_channel(item, sender, &record_sep).await;
+            } else {
+                // Direct items
+                for direct_item in items {
+                    let _ = sender.send(direct_item).await;
+                }
+            }
+        } else {
+            // Multiple direct items
+            for direct_item in items {
+                let _ = sender.send(direct_item).await;
+            }
+        }
+    });
+    receiver
+}
+
+/// Print what precedes the selected items of an accepted session: the query
+/// with `--print-query`, then with `--expect` the key that accepted it (an
+/// empty line for Enter).
+fn print_output_header(options: &RunOptions, output: &TuiOutput) {
+    if !output.accepted {
+        return;
+    }
+    if options.print_query {
+        println!("{}", output.query);
+    }
+    if !options.expect.is_empty() {
+        println!("{}", output.key.as_deref().unwrap_or_default());
+    }
+    if !options.tabs.is_empty() {
+        println!("{}", output.tab.as_deref().unwrap_or_default());
+    }
+}
+
+/// Exit status of a finished session, as with fzf: 130 when it was
+/// cancelled, 1 when it was accepted without a selection (nothing matched),
+/// otherwise 0.
+pub fn exit_status(output: &TuiOutput) -> i32 {
+    if !output.accepted {
+        130
+    } else if output.selected.is_empty() {
+        1
+    } else {
+        0
+    }
+}
+
+/// Build the TUI configuration for the given run options.
+pub fn build_tui_config(options: &RunOptions) -> TuiConfig {
+    TuiConfig {
+        fullscreen: options.height.is_none()
+            && options.height_percentage.is_none()
+            && !options.auto_height,
+        height: options.height,
+        height_percentage: options.height_percentage,
+        auto_height: options.auto_height,
+        show_help_text: options.show_help_text,
+        prompt: options
+            .prompt
+            .clone()
+            .unwrap_or_else(|| DEFAULT_PROMPT.to_string()),
+        query: options.query.clone(),
+        show_loading_indicator: true,
+        loading_message: None,
+        ready_message: None,
+        preview_rules: options.preview_rules.clone(),
+        preview_auto: options.preview_auto,
+        preview_scroll: options.preview_scroll.clone(),
+        mouse: !options.no_mouse,
+        ansi: options.ansi,
+        keep_right: options.keep_right,
+        columns: options.columns,
+        history: options.history.clone(),
+        expect: options.expect.clone(),
+        print_query: options.print_query,
+        bindings: options.bindings.clone(),
+        jump_labels: options.jump_labels.clone(),
+        reload: None,
+        tabs: Vec::new(),
+        wrap: options.wrap,
+        with_nth: options.with_nth.clone(),
+        icons: options.icons,
+        scrollbar: !options.no_scrollbar,
+        sort: !options.no_sort,
+        cycle: !options.no_cycle,
+        debug_scores: options.debug_scores,
+        accessible: options.accessible,
+        selection_order: options.selection_order,
+        // ff exits right after the session, so keeping the handler is fine
+        catch_interrupts: true,
+        max_select: options.max_select,
+        preselect: options.preselect.clone(),
+        select_one: options.select_one,
+        exit_zero: options.exit_zero,
+        keymap: options.keymap,
+        layout: options.layout,
+        info: options.info,
+        theme: options.theme,
+        color_depth: ColorDepth::detect(),
+        preview_root: options.preview_root.clone(),
+        scoring: scoring_config(options),
+        min_score: options.min_score,
+        max_results: options.max_results,
+        unique_similar: options.unique_similar,
+        group_similar: options.group_similar,
+        transforms: Vec::new(),
+        dedup: options.dedup.then_some(Dedup::Exact),
+        index: None,
+        index_cache: None,
+        hidden_separator: None,
+        weight_field: options.weight_field,
+    }
+}
+
+/// Use the cached candidate index for the records of `file` if there is
+/// one, otherwise have the session cache the index it builds for the next
+/// run over the unchanged file.
+fn use_index_cache(config: &mut TuiConfig, file: &str, record_sep: &RecordSeparator) {
+    let key = CandidateIndex::cache_key(Path::new(file), &format!("{record_sep:?}"));
+    let Some(path) = key.ok().and_then(CandidateIndex::cache_path) else {
+        return;
+    };
+    match CandidateIndex::load_cached(&path) {
+        Ok(index) => config.index = Some(index),
+        Err(_) => config.index_cache = Some(path),
+    }
+}
+
+/// Scoring options: the scheme's preset, with the mode, history and any
+/// extra boundary characters given on the command line.
+fn scoring_config(options: &RunOptions) -> ScoringConfig {
+    let mut scoring = ScoringConfig::preset(options.scheme);
+    scoring.mode = options.mode;
+    scoring
+        .boundary_chars
+        .extend(options.boundary_chars.iter().copied());
+    scoring.history = options.history_boost.then(|| Arc::new(load_history()));
+    scoring
+}
+
+/// Load the selection history from its default location (empty if unavailable).
+fn load_history() -> History {
+    History::default_path()
+        .and_then(|path| History::load(&path).ok())
+        .unwrap_or_default()
+}
+
+/// Record selected items in the persistent history.
+pub fn record_history(selected: &[(usize, String)]) {
+    let Some(path) = History::default_path() else {
+        return;
+    };
+    if selected.is_empty() {
+        return;
+    }
+    let mut history = History::load(&path).unwrap_or_default();
+    history.record(selected.iter().map(|(_, item)| item), now_secs());
+    if let Err(e) = history.save(&path) {
+        eprintln!("Warning: failed to save history: {e}");
+    }
+}
+
+/// Run in a tmux popup (`--tmux`), exiting with the status of ff in it
+fn run_tmux_popup(args: &[String], popup: &TmuxPopup) -> Result<(), Box<dyn std::error::Error>> {
+    let status = tmux::run_in_popup(args, popup)?;
+    if status != 0 {
+        std::process::exit(status);
+    }
+    Ok(())
+}
+
 /// Run the CLI application.
 pub fn cli_main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = env::args().collect();
@@ -181,71 +417,14 @@ pub fn cli_main() -> Result<(), Box<dyn std::error::Error>> {
             help::print_usage();
             Ok(())
         }
-        CliAction::RunAsyncTui {
-            items,
-            multi_select,
-            line_number,
-            height,
-            height_percentage,
-            show_help_text,
-            preview_rules,
-            preview_auto,
-        } => {
+        CliAction::RunAsyncTui { items, options } => {
+            if let Some(popup) = options.tmux.as_ref().filter(|_| tmux::in_tmux()) {
+                return run_tmux_popup(&args, popup);
+            }
             // For async TUI, we need to run it in a tokio runtime
             validate_tty_requirements()?;
             let rt = tokio::runtime::Runtime::new()?;
             let items_for_check = items.clone();
-            let result = rt.block_on(async {
-                // Create mpsc channel for items
-                let (sender, receiver) = create_items_channel();
-
-                // Spawn task to send items to the channel
-                // Move sender directly into the task (don't clone) so channel closes when done
-                let items_clone = items.clone();
-                tokio::spawn(async move {
-                    if items_clone.len() == 1 {
-                        let item = &items_clone[0];
-                        if item.starts_with("unix://")
-                            || item.starts_with("http://")
-                            || item.starts_with("https://")
-                        {
-                            let _ = send_input_to_channel(item, sender).await;
-                        } else if let Some(dir_path) = item.strip_prefix("dir:") {
-                            let _ =
-                                send_input_to_channel(&format!("dir:{}", dir_path), sender).await;
-                        } else if looks_like_file_path(item) {
-                            let _ = send_input_to_channel(item, sender).await;
-                        } else {
-                            // Direct items
-                            for direct_item in items_clone {
-                                let _ = sender.send(direct_item).await;
-                            }
-                        }
-                    } else {
-                        // Multiple direct items
-                        for direct_item in items_clone {
-                            let _ = sender.send(direct_item).await;
-                        }
-                    }
-                    // Sender will be dropped automatically when the task ends
-                });
-
-                let config = TuiConfig {
-                    fullscreen: height.is_none() && height_percentage.is_none(),
-                    height,
-                    height_percentage,
-                    show_help_text,
-                    show_loading_indicator: true,
-                    loading_message: None,
-                    ready_message: None,
-                    preview_rules,
-                    preview_auto,
-                };
-                let selected = run_tui_with_config(receiver, multi_select, config)
-                    .await
-                    .map_err(|e| e as Box<dyn std::error::Error>)?;
-                Ok::<Vec<(usize, String)>, Box<dyn std::error::Error>>(selected)
-            })?;
 
             // Determine if we are reading from a single file to format output
             let source_file = if items_for_check.len() == 1 {
@@ -269,9 +448,41 @@ pub fn cli_main() -> Result<(), Box<dyn std::error::Error>> {
                 None
             };
 
+            // Dedup drops lines and weight fields are stripped, so an index
+            // over the raw file would not line up
+            let cache_file = source_file.as_deref().filter(|_| {
+                options.index_cache && !options.dedup && options.weight_field.is_none()
+            });
+
+            let result = rt.block_on(async {
+                let mut config = build_tui_config(&options);
+                config.tabs = tabs(&options);
+                if let Some(file) = cache_file.filter(|_| config.tabs.is_empty()) {
+                    use_index_cache(&mut config, file, &options.record_sep);
+                }
+                let receiver = match config.tabs.first() {
+                    Some(tab) => tab.source.open(),
+                    None => open_items(items.clone(), options.record_sep.clone()),
+                };
+                let (items, record_sep) = (items.clone(), options.record_sep.clone());
+                config.reload = Some(ItemSource::new(move || {
+                    open_items(items.clone(), record_sep.clone())
+                }));
+                let output = run_tui_with_output(receiver, options.multi_select, config)
+                    .await
+                    .map_err(|e| e as Box<dyn std::error::Error>)?;
+                Ok::<TuiOutput, Box<dyn std::error::Error>>(output)
+            })?;
+
+            if options.history_boost {
+                record_history(&result.selected);
+            }
+            print_output_header(&options, &result);
+            let status = exit_status(&result);
+
             // Print each selected item
-            for (idx, item) in result {
-                if line_number {
+            for (idx, item) in result.selected {
+                if options.line_number {
                     if let Some(ref file) = source_file {
                         println!("{}:{1}", file, idx + 1);
                     } else {
@@ -281,20 +492,18 @@ pub fn cli_main() -> Result<(), Box<dyn std::error::Error>> {
                     println!("{item}");
                 }
             }
+            if status != 0 {
+                std::process::exit(status);
+            }
             Ok(())
         }
-        CliAction::RunAsyncTuiFromStdin {
-            multi_select,
-            line_number,
-            height,
-            height_percentage,
-            show_help_text,
-            preview_rules,
-            preview_auto,
-        } => {
+        CliAction::RunAsyncTuiFromStdin { options } => {
+            if let Some(popup) = options.tmux.as_ref().filter(|_| tmux::in_tmux()) {
+                return run_tmux_popup(&args, popup);
+            }
             validate_tty_requirements()?;
 
-            let items = read_piped_stdin().map_err(|e| {
+            let items = read_piped_stdin_records(&options.record_sep).map_err(|e| {
                 Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
                     as Box<dyn std::error::Error>
             })?;
@@ -323,30 +532,29 @@ pub fn cli_main() -> Result<(), Box<dyn std::error::Error>> {
                     }
                 });
 
-                let config = TuiConfig {
-                    fullscreen: height.is_none() && height_percentage.is_none(),
-                    height,
-                    height_percentage,
-                    show_help_text,
-                    show_loading_indicator: true,
-                    loading_message: None,
-                    ready_message: None,
-                    preview_rules,
-                    preview_auto,
-                };
-                let selected = run_tui_with_config(receiver, multi_select, config)
+                let config = build_tui_config(&options);
+                let output = run_tui_with_output(receiver, options.multi_select, config)
                     .await
                     .map_err(|e| e as Box<dyn std::error::Error>)?;
-                Ok::<Vec<(usize, String)>, Box<dyn std::error::Error>>(selected)
+                Ok::<TuiOutput, Box<dyn std::error::Error>>(output)
             })?;
 
-            for (idx, item) in result {
-                if line_number {
+            if options.history_boost {
+                record_history(&result.selected);
+            }
+            print_output_header(&options, &result);
+            let status = exit_status(&result);
+
+            for (idx, item) in result.selected {
+                if options.line_number {
                     println!("{}", idx + 1);
                 } else {
                     println!("{item}");
                 }
             }
+            if status != 0 {
+                std::process::exit(status);
+            }
             Ok(())
         }
         CliAction::Error(msg) => {
@@ -543,6 +751,24 @@ mod tests {
         assert_eq!(result, Vec::<String>::new());
     }
 
+    #[test]
+    fn test_exit_status() {
+        let cancelled = TuiOutput::default();
+        assert_eq!(exit_status(&cancelled), 130);
+        let unmatched = TuiOutput {
+            query: "new".to_string(),
+            accepted: true,
+            ..TuiOutput::default()
+        };
+        assert_eq!(exit_status(&unmatched), 1);
+        let selected = TuiOutput {
+            selected: vec![(0, "a".to_string())],
+            accepted: true,
+            ..TuiOutput::default()
+        };
+        assert_eq!(exit_status(&selected), 0);
+    }
+
     #[test]
     fn test_validate_tty_requirements() {
         // This test depends on the actual TTY check implementation
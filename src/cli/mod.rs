@@ -1,5 +1,6 @@
 pub mod main;
 pub mod planner;
+pub mod tmux;
 pub mod tty;
 
 pub use main::cli_main;
@@ -1,3 +1,126 @@
+use crate::cli::tmux::TmuxPopup;
+use crate::fuzzy::{MatchMode, Scheme};
+use crate::input::RecordSeparator;
+use crate::tui::fields::Fields;
+use crate::tui::icons::IconStyle;
+use crate::tui::jump::DEFAULT_JUMP_LABELS;
+use crate::tui::keymap::{KeyBinding, KeyName, Keymap};
+use crate::tui::layout::{Columns, Info, Layout};
+use crate::tui::preview::{PreviewRule, PreviewScroll};
+use crate::tui::theme::Theme;
+
+/// Options shared by every TUI run mode.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunOptions {
+    /// Whether multi-select mode is enabled
+    pub multi_select: bool,
+    /// Whether to output line numbers instead of content
+    pub line_number: bool,
+    /// Fixed height in lines
+    pub height: Option<u16>,
+    /// Height as percentage of terminal
+    pub height_percentage: Option<f32>,
+    /// Fit the height to the results (`--height auto`), up to the
+    /// percentage height or the terminal
+    pub auto_height: bool,
+    /// Show a footer with key hints and the current mode
+    pub show_help_text: bool,
+    /// Prompt drawn before the query (`None` for the default)
+    pub prompt: Option<String>,
+    /// Query to start with
+    pub query: String,
+    /// Preview rules (scanned in order; empty exts = default)
+    pub preview_rules: Vec<PreviewRule>,
+    /// Auto-show preview on cursor move
+    pub preview_auto: bool,
+    /// Initial scroll position of each preview
+    pub preview_scroll: Option<PreviewScroll>,
+    /// Directory relative items are previewed from
+    pub preview_root: Option<std::path::PathBuf>,
+    /// Scoring scheme
+    pub scheme: Scheme,
+    /// Matching mode
+    pub mode: MatchMode,
+    /// Extra word-boundary characters
+    pub boundary_chars: Vec<char>,
+    /// Record selections and boost frecent items
+    pub history_boost: bool,
+    /// Minimum match score
+    pub min_score: Option<i32>,
+    /// Maximum number of matches to keep
+    pub max_results: Option<usize>,
+    /// Collapse near-duplicate results
+    pub unique_similar: bool,
+    /// Group near-duplicate results under headers
+    pub group_similar: bool,
+    /// Reuse a cached candidate index for file input
+    pub index_cache: bool,
+    /// Drop duplicate input lines
+    pub dedup: bool,
+    /// How file, socket and stdin input is split into items
+    pub record_sep: RecordSeparator,
+    /// Tab-separated field (1-based) holding each item's score boost
+    pub weight_field: Option<usize>,
+    /// Fields of each item to display
+    pub with_nth: Option<Fields>,
+    /// File-type icons drawn in front of items (`None` without `--icons`,
+    /// or with `--no-icons`)
+    pub icons: Option<IconStyle>,
+    /// Run in a tmux popup of this position and size when inside tmux
+    pub tmux: Option<TmuxPopup>,
+    /// Leave the mouse to the terminal instead of capturing it
+    pub no_mouse: bool,
+    /// Strip ANSI escape sequences from the printed selection
+    pub ansi: bool,
+    /// Cut off long items at the start instead of the end
+    pub keep_right: bool,
+    /// Columns to lay results out in
+    pub columns: Columns,
+    /// File accepted queries are saved to and recalled from
+    pub history: Option<std::path::PathBuf>,
+    /// Keys that accept the selection and are printed ahead of it
+    pub expect: Vec<KeyName>,
+    /// Print the final query ahead of the selection
+    pub print_query: bool,
+    /// Keys bound to actions with `--bind`
+    pub bindings: Vec<KeyBinding>,
+    /// Named input sources from `--tab NAME=SOURCE`, in place of the
+    /// input source
+    pub tabs: Vec<(String, String)>,
+    /// Characters the `jump` actions label items with
+    pub jump_labels: String,
+    /// Wrap long items instead of cutting them off
+    pub wrap: bool,
+    /// Hide the scrollbar shown when the list overflows
+    pub no_scrollbar: bool,
+    /// Keep matches in input order instead of ranking them
+    pub no_sort: bool,
+    /// Stop the cursor at the ends of the results instead of wrapping
+    pub no_cycle: bool,
+    /// Show each result's score breakdown and matched positions
+    pub debug_scores: bool,
+    /// Draw for screen readers: text markers, no spinner
+    pub accessible: bool,
+    /// Print selections in the order they were made
+    pub selection_order: bool,
+    /// Most items that can be selected at once
+    pub max_select: Option<usize>,
+    /// Items selected from the start with `--select`
+    pub preselect: Vec<String>,
+    /// Accept a single match of the initial query without the TUI
+    pub select_one: bool,
+    /// Exit without the TUI when the initial query matches nothing
+    pub exit_zero: bool,
+    /// Keymap preset
+    pub keymap: Keymap,
+    /// Placement of the prompt and direction results grow in
+    pub layout: Layout,
+    /// Where the match counter is shown
+    pub info: Info,
+    /// Colors of the prompt, cursor row, matches and dim text
+    pub theme: Theme,
+}
+
 /// Actions that can be performed by the CLI application.
 #[derive(Debug, PartialEq)]
 pub enum CliAction {
@@ -9,42 +132,32 @@ pub enum CliAction {
     RunAsyncTui {
         /// Items to search through
         items: Vec<String>,
-        /// Whether multi-select mode is enabled
-        multi_select: bool,
-        /// Whether to output line numbers instead of content
-        line_number: bool,
-        /// Fixed height in lines
-        height: Option<u16>,
-        /// Height as percentage of terminal
-        height_percentage: Option<f32>,
-        /// Whether to show help text
-        show_help_text: bool,
-        /// Preview rules (scanned in order; empty exts = default)
-        preview_rules: Vec<crate::tui::preview::PreviewRule>,
-        /// Auto-show preview on cursor move
-        preview_auto: bool,
+        /// Run options
+        options: RunOptions,
     },
     /// Run TUI with piped stdin input
     RunAsyncTuiFromStdin {
-        /// Whether multi-select mode is enabled
-        multi_select: bool,
-        /// Whether to output line numbers instead of content
-        line_number: bool,
-        /// Fixed height in lines
-        height: Option<u16>,
-        /// Height as percentage of terminal
-        height_percentage: Option<f32>,
-        /// Whether to show help text
-        show_help_text: bool,
-        /// Preview rules (scanned in order; empty exts = default)
-        preview_rules: Vec<crate::tui::preview::PreviewRule>,
-        /// Auto-show preview on cursor move
-        preview_auto: bool,
+        /// Run options
+        options: RunOptions,
     },
     /// Error with message
     Error(String),
 }
 
+/// Return the value of a flag given as `--flag value` or `--flag=value` at position `i`.
+fn flag_value<'a>(args: &'a [String], i: usize, names: &[&str]) -> Option<&'a str> {
+    let arg = args[i].as_str();
+    for name in names {
+        if arg == *name {
+            return args.get(i + 1).map(|v| v.as_str());
+        }
+        if let Some(value) = arg.strip_prefix(name).and_then(|r| r.strip_prefix('=')) {
+            return Some(value);
+        }
+    }
+    None
+}
+
 /// Plan the CLI action based on command line arguments.
 pub fn plan_cli_action(args: &[String]) -> CliAction {
     if args.iter().any(|arg| arg == "--version" || arg == "-V") {
@@ -58,16 +171,73 @@ pub fn plan_cli_action(args: &[String]) -> CliAction {
         .iter()
         .any(|arg| arg == "--multi-select" || arg == "-m");
     let line_number = args.iter().any(|arg| arg == "--line-number" || arg == "-n");
+    let history_boost = args.iter().any(|arg| arg == "--history-boost");
+    let unique_similar = args.iter().any(|arg| arg == "--unique-similar");
+    let group_similar = args.iter().any(|arg| arg == "--group-similar");
+    let index_cache = args.iter().any(|arg| arg == "--index-cache");
+    let dedup = args.iter().any(|arg| arg == "--dedup");
+    let no_mouse = args.iter().any(|arg| arg == "--no-mouse");
+    let ansi = args.iter().any(|arg| arg == "--ansi");
+    let keep_right = args.iter().any(|arg| arg == "--keep-right");
+    let no_sort = args.iter().any(|arg| arg == "--no-sort");
+    // The last of --cycle and --no-cycle wins
+    let no_cycle = args
+        .iter()
+        .rev()
+        .find(|arg| *arg == "--cycle" || *arg == "--no-cycle")
+        .is_some_and(|arg| arg == "--no-cycle");
+    let debug_scores = args.iter().any(|arg| arg == "--debug-scores");
+    let accessible = args.iter().any(|arg| arg == "--accessible");
+    let selection_order = args.iter().any(|arg| arg == "--selection-order");
+    let no_scrollbar = args.iter().any(|arg| arg == "--no-scrollbar");
+    let wrap = args.iter().any(|arg| arg == "--wrap");
+    let print_query = args.iter().any(|arg| arg == "--print-query");
+    let select_one = args.iter().any(|arg| arg == "--select-1" || arg == "-1");
+    let exit_zero = args.iter().any(|arg| arg == "--exit-0" || arg == "-0");
+    if dedup && line_number {
+        return CliAction::Error(
+            "--dedup cannot be combined with --line-number (line numbers would shift)".to_string(),
+        );
+    }
 
     let mut height: Option<u16> = None;
     let mut height_percentage: Option<f32> = None;
+    let mut auto_height = false;
     let mut show_help_text = false;
-    let mut preview_rules: Vec<crate::tui::preview::PreviewRule> = Vec::new();
+    let mut preview_rules: Vec<PreviewRule> = Vec::new();
     let mut preview_auto = false;
+    let mut preview_scroll = None;
     let mut has_default = false;
+    let mut scheme = Scheme::default();
+    let mut mode = MatchMode::default();
+    let mut boundary_chars: Vec<char> = Vec::new();
+    let mut min_score: Option<i32> = None;
+    let mut max_results: Option<usize> = None;
+    let mut max_select: Option<usize> = None;
+    let mut record_sep = RecordSeparator::default();
+    let mut keymap = Keymap::default();
+    let mut layout = Layout::default();
+    let mut info = Info::default();
+    let mut columns = Columns::default();
+    let mut history = None;
+    let mut expect = Vec::new();
+    let mut bindings = Vec::new();
+    let mut tabs = Vec::new();
+    let mut preselect = Vec::new();
+    let mut jump_labels = DEFAULT_JUMP_LABELS.to_string();
+    let mut theme = Theme::default();
+    let mut prompt = None;
+    let mut query = String::new();
+    let mut weight_field: Option<usize> = None;
+    let mut with_nth = None;
+    let mut delimiter = None;
+    let mut icons = None;
+    let mut tmux = None;
 
     for (i, arg) in args.iter().enumerate() {
-        if arg == "--height" && i + 1 < args.len() {
+        if flag_value(args, i, &["--height"]) == Some("auto") {
+            auto_height = true;
+        } else if arg == "--height" && i + 1 < args.len() {
             if let Ok(h) = args[i + 1].parse::<u16>() {
                 height = Some(h);
             } else {
@@ -120,7 +290,7 @@ pub fn plan_cli_action(args: &[String]) -> CliAction {
         } else if arg == "--help-text" {
             show_help_text = true;
         } else if (arg == "--preview" || arg == "-p") && i + 1 < args.len() {
-            match crate::tui::preview::PreviewRule::parse(&args[i + 1]) {
+            match PreviewRule::parse(&args[i + 1]) {
                 Ok(rule) => {
                     if rule.exts.is_empty() {
                         if has_default {
@@ -136,7 +306,7 @@ pub fn plan_cli_action(args: &[String]) -> CliAction {
             }
         } else if arg.starts_with("--preview=") {
             if let Some(value) = arg.strip_prefix("--preview=") {
-                match crate::tui::preview::PreviewRule::parse(value) {
+                match PreviewRule::parse(value) {
                     Ok(rule) => {
                         if rule.exts.is_empty() {
                             if has_default {
@@ -153,6 +323,151 @@ pub fn plan_cli_action(args: &[String]) -> CliAction {
             }
         } else if arg == "--preview-auto" {
             preview_auto = true;
+        } else if let Some(value) = flag_value(args, i, &["--preview-scroll"]) {
+            match PreviewScroll::parse(value) {
+                Ok(scroll) => preview_scroll = Some(scroll),
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if let Some(value) = flag_value(args, i, &["--scheme"]) {
+            match Scheme::parse(value) {
+                Ok(s) => scheme = s,
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if let Some(value) = flag_value(args, i, &["--mode"]) {
+            match MatchMode::parse(value) {
+                Ok(m) => mode = m,
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if let Some(value) = flag_value(args, i, &["--weight-field"]) {
+            match value.parse::<usize>() {
+                Ok(n) if n > 0 => weight_field = Some(n),
+                _ => {
+                    return CliAction::Error(
+                        "Invalid weight field. Must be a positive integer.".to_string(),
+                    )
+                }
+            }
+        } else if let Some(value) = flag_value(args, i, &["--record-sep"]) {
+            match RecordSeparator::parse(value) {
+                Ok(sep) => record_sep = sep,
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if let Some(value) = flag_value(args, i, &["--query", "-q"]) {
+            query = value.to_string();
+        } else if let Some(value) = flag_value(args, i, &["--prompt"]) {
+            prompt = Some(value.to_string());
+        } else if let Some(value) = flag_value(args, i, &["--layout"]) {
+            match Layout::parse(value) {
+                Ok(l) => layout = l,
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if let Some(value) = flag_value(args, i, &["--info"]) {
+            match Info::parse(value) {
+                Ok(style) => info = style,
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if let Some(value) = flag_value(args, i, &["--columns"]) {
+            match Columns::parse(value) {
+                Ok(c) => columns = c,
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if let Some(value) = flag_value(args, i, &["--history"]) {
+            history = Some(std::path::PathBuf::from(value));
+        } else if let Some(value) = flag_value(args, i, &["--expect"]) {
+            match KeyName::parse_list(value) {
+                Ok(keys) => expect = keys,
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if let Some(value) = flag_value(args, i, &["--tab"]) {
+            match value.split_once('=') {
+                Some((name, source)) if !name.is_empty() && !source.is_empty() => {
+                    tabs.push((name.to_string(), source.to_string()));
+                }
+                _ => {
+                    return CliAction::Error(format!("Invalid tab '{value}'. Expected NAME=SOURCE"))
+                }
+            }
+        } else if let Some(value) = flag_value(args, i, &["--bind"]) {
+            match KeyBinding::parse(value) {
+                Ok(binding) => bindings.push(binding),
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if let Some(value) = flag_value(args, i, &["--with-nth"]) {
+            with_nth = Some(value);
+        } else if let Some(value) = flag_value(args, i, &["--delimiter", "-d"]) {
+            let mut chars = value.chars();
+            delimiter = match (value, chars.next(), chars.next()) {
+                ("\\t", _, _) => Some('\t'),
+                (_, Some(c), None) => Some(c),
+                _ => {
+                    return CliAction::Error(
+                        "Invalid delimiter. Expected a single character.".to_string(),
+                    )
+                }
+            };
+        } else if arg == "--icons" {
+            icons = Some(IconStyle::Nerd);
+        } else if let Some(value) = arg.strip_prefix("--icons=") {
+            match IconStyle::parse(value) {
+                Ok(style) => icons = Some(style),
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if arg == "--tmux" {
+            tmux = Some(TmuxPopup::default());
+        } else if let Some(value) = arg.strip_prefix("--tmux=") {
+            match TmuxPopup::parse(value) {
+                Ok(popup) => tmux = Some(popup),
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if let Some(value) = flag_value(args, i, &["--select"]) {
+            preselect.push(value.to_string());
+        } else if let Some(value) = flag_value(args, i, &["--jump-labels"]) {
+            let mut seen = std::collections::HashSet::new();
+            if value.is_empty() || !value.chars().all(|c| seen.insert(c)) {
+                return CliAction::Error(
+                    "Invalid jump labels. Expected distinct characters.".to_string(),
+                );
+            }
+            jump_labels = value.to_string();
+        } else if let Some(value) = flag_value(args, i, &["--color"]) {
+            match Theme::parse(value) {
+                Ok(t) => theme = t,
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if let Some(value) = flag_value(args, i, &["--keymap"]) {
+            match Keymap::parse(value) {
+                Ok(k) => keymap = k,
+                Err(e) => return CliAction::Error(e),
+            }
+        } else if let Some(value) = flag_value(args, i, &["--boundary-chars"]) {
+            boundary_chars = value.chars().collect();
+        } else if let Some(value) = flag_value(args, i, &["--min-score"]) {
+            match value.parse::<i32>() {
+                Ok(s) => min_score = Some(s),
+                Err(_) => {
+                    return CliAction::Error(
+                        "Invalid min score value. Must be an integer.".to_string(),
+                    )
+                }
+            }
+        } else if let Some(value) = flag_value(args, i, &["--max-results"]) {
+            match value.parse::<usize>() {
+                Ok(n) => max_results = Some(n),
+                Err(_) => {
+                    return CliAction::Error(
+                        "Invalid max results value. Must be a non-negative integer.".to_string(),
+                    )
+                }
+            }
+        } else if let Some(value) = flag_value(args, i, &["--max-select"]) {
+            match value.parse::<usize>() {
+                Ok(n) if n > 0 => max_select = Some(n),
+                _ => {
+                    return CliAction::Error(
+                        "Invalid max select value. Must be a positive integer.".to_string(),
+                    )
+                }
+            }
         }
     }
 
@@ -169,19 +484,158 @@ pub fn plan_cli_action(args: &[String]) -> CliAction {
         if (arg == "--preview" || arg == "-p") && i + 1 >= args.len() {
             return CliAction::Error("Missing preview command after --preview".to_string());
         }
+        if arg == "--preview-scroll" && i + 1 >= args.len() {
+            return CliAction::Error("Missing scroll position after --preview-scroll".to_string());
+        }
+        if arg == "--scheme" && i + 1 >= args.len() {
+            return CliAction::Error("Missing scheme name after --scheme".to_string());
+        }
+        if arg == "--min-score" && i + 1 >= args.len() {
+            return CliAction::Error("Missing score value after --min-score".to_string());
+        }
+        if arg == "--max-results" && i + 1 >= args.len() {
+            return CliAction::Error("Missing count after --max-results".to_string());
+        }
+        if arg == "--max-select" && i + 1 >= args.len() {
+            return CliAction::Error("Missing count after --max-select".to_string());
+        }
+        if arg == "--mode" && i + 1 >= args.len() {
+            return CliAction::Error("Missing mode name after --mode".to_string());
+        }
+        if arg == "--weight-field" && i + 1 >= args.len() {
+            return CliAction::Error("Missing field number after --weight-field".to_string());
+        }
+        if arg == "--record-sep" && i + 1 >= args.len() {
+            return CliAction::Error("Missing separator after --record-sep".to_string());
+        }
+        if (arg == "--query" || arg == "-q") && i + 1 >= args.len() {
+            return CliAction::Error("Missing query after --query".to_string());
+        }
+        if arg == "--prompt" && i + 1 >= args.len() {
+            return CliAction::Error("Missing prompt text after --prompt".to_string());
+        }
+        if arg == "--layout" && i + 1 >= args.len() {
+            return CliAction::Error("Missing layout name after --layout".to_string());
+        }
+        if arg == "--info" && i + 1 >= args.len() {
+            return CliAction::Error("Missing info style after --info".to_string());
+        }
+        if arg == "--columns" && i + 1 >= args.len() {
+            return CliAction::Error("Missing column count after --columns".to_string());
+        }
+        if arg == "--history" && i + 1 >= args.len() {
+            return CliAction::Error("Missing file after --history".to_string());
+        }
+        if arg == "--expect" && i + 1 >= args.len() {
+            return CliAction::Error("Missing keys after --expect".to_string());
+        }
+        if arg == "--bind" && i + 1 >= args.len() {
+            return CliAction::Error("Missing binding after --bind".to_string());
+        }
+        if arg == "--tab" && i + 1 >= args.len() {
+            return CliAction::Error("Missing NAME=SOURCE after --tab".to_string());
+        }
+        if arg == "--with-nth" && i + 1 >= args.len() {
+            return CliAction::Error("Missing fields after --with-nth".to_string());
+        }
+        if (arg == "--delimiter" || arg == "-d") && i + 1 >= args.len() {
+            return CliAction::Error("Missing character after --delimiter".to_string());
+        }
+        if arg == "--select" && i + 1 >= args.len() {
+            return CliAction::Error("Missing item after --select".to_string());
+        }
+        if arg == "--jump-labels" && i + 1 >= args.len() {
+            return CliAction::Error("Missing characters after --jump-labels".to_string());
+        }
+        if arg == "--color" && i + 1 >= args.len() {
+            return CliAction::Error("Missing color spec after --color".to_string());
+        }
+        if arg == "--keymap" && i + 1 >= args.len() {
+            return CliAction::Error("Missing keymap name after --keymap".to_string());
+        }
+        if arg == "--boundary-chars" && i + 1 >= args.len() {
+            return CliAction::Error("Missing characters after --boundary-chars".to_string());
+        }
+    }
+
+    if line_number && record_sep != RecordSeparator::Newline {
+        return CliAction::Error(
+            "--record-sep cannot be combined with --line-number (records span lines)".to_string(),
+        );
+    }
+
+    let with_nth = match with_nth.map(|spec| Fields::parse(spec, delimiter)) {
+        Some(Ok(fields)) => Some(fields),
+        Some(Err(e)) => return CliAction::Error(e),
+        None => None,
+    };
+
+    let mut options = RunOptions {
+        multi_select,
+        line_number,
+        height,
+        height_percentage,
+        auto_height,
+        show_help_text,
+        prompt,
+        query,
+        preview_rules,
+        preview_auto,
+        preview_scroll,
+        preview_root: None,
+        scheme,
+        mode,
+        boundary_chars,
+        history_boost,
+        min_score,
+        max_results,
+        unique_similar,
+        group_similar,
+        index_cache,
+        dedup,
+        record_sep,
+        weight_field,
+        with_nth,
+        icons: icons.filter(|_| !args.iter().any(|arg| arg == "--no-icons")),
+        tmux,
+        no_mouse,
+        ansi,
+        keep_right,
+        columns,
+        history,
+        expect,
+        print_query,
+        bindings,
+        tabs,
+        jump_labels,
+        wrap,
+        no_scrollbar,
+        no_sort,
+        no_cycle,
+        debug_scores,
+        accessible,
+        selection_order,
+        max_select,
+        preselect,
+        select_one,
+        exit_zero,
+        keymap,
+        layout,
+        info,
+        theme,
+    };
+
+    // Tabs bring their own input sources
+    if !options.tabs.is_empty() {
+        return CliAction::RunAsyncTui {
+            items: Vec::new(),
+            options,
+        };
     }
 
     // Check if stdin is piped - if so, use that as input source
     if super::tty::is_stdin_piped() {
-        return CliAction::RunAsyncTuiFromStdin {
-            multi_select,
-            line_number,
-            height,
-            height_percentage,
-            show_help_text,
-            preview_rules,
-            preview_auto,
-        };
+        return CliAction::RunAsyncTuiFromStdin { options };
     }
 
     if args.len() < 2 {
@@ -202,13 +656,7 @@ pub fn plan_cli_action(args: &[String]) -> CliAction {
     {
         return CliAction::RunAsyncTui {
             items: vec![input_source],
-            multi_select,
-            line_number,
-            height,
-            height_percentage,
-            show_help_text,
-            preview_rules,
-            preview_auto,
+            options,
         };
     }
 
@@ -216,26 +664,23 @@ pub fn plan_cli_action(args: &[String]) -> CliAction {
     if std::path::Path::new(&input_source).exists() {
         let path = std::path::Path::new(&input_source);
         if path.is_dir() {
+            // Directory entries are previewed with the built-in previewer
+            // unless another preview was asked for
+            options.preview_root = Some(path.to_path_buf());
+            if options.preview_rules.is_empty() {
+                options
+                    .preview_rules
+                    .push(PreviewRule::parse("auto").expect("valid rule"));
+                options.preview_auto = true;
+            }
             return CliAction::RunAsyncTui {
                 items: vec![format!("dir:{}", input_source)],
-                multi_select,
-                line_number,
-                height,
-                height_percentage,
-                show_help_text,
-                preview_rules,
-                preview_auto,
+                options,
             };
         } else {
             return CliAction::RunAsyncTui {
                 items: vec![input_source],
-                multi_select,
-                line_number,
-                height,
-                height_percentage,
-                show_help_text,
-                preview_rules,
-                preview_auto,
+                options,
             };
         }
     }
@@ -262,16 +707,97 @@ pub fn plan_cli_action(args: &[String]) -> CliAction {
             continue;
         }
 
-        if *arg == "--height" || *arg == "--height-percentage" {
+        if *arg == "--height"
+            || *arg == "--height-percentage"
+            || *arg == "--scheme"
+            || *arg == "--mode"
+            || *arg == "--boundary-chars"
+            || *arg == "--min-score"
+            || *arg == "--max-results"
+            || *arg == "--max-select"
+            || *arg == "--record-sep"
+            || *arg == "--weight-field"
+            || *arg == "--with-nth"
+            || *arg == "--delimiter"
+            || *arg == "-d"
+            || *arg == "--preview-scroll"
+            || *arg == "--keymap"
+            || *arg == "--layout"
+            || *arg == "--info"
+            || *arg == "--columns"
+            || *arg == "--history"
+            || *arg == "--expect"
+            || *arg == "--bind"
+            || *arg == "--tab"
+            || *arg == "--jump-labels"
+            || *arg == "--select"
+            || *arg == "--color"
+            || *arg == "--prompt"
+            || *arg == "--query"
+            || *arg == "-q"
+        {
             skip_next = true;
             continue;
         }
 
-        if arg.starts_with("--height=") || arg.starts_with("--height-percentage=") {
+        if arg.starts_with("--height=")
+            || arg.starts_with("--height-percentage=")
+            || arg.starts_with("--scheme=")
+            || arg.starts_with("--mode=")
+            || arg.starts_with("--boundary-chars=")
+            || arg.starts_with("--min-score=")
+            || arg.starts_with("--max-results=")
+            || arg.starts_with("--max-select=")
+            || arg.starts_with("--record-sep=")
+            || arg.starts_with("--weight-field=")
+            || arg.starts_with("--with-nth=")
+            || arg.starts_with("--icons=")
+            || arg.starts_with("--tmux=")
+            || arg.starts_with("--tab=")
+            || arg.starts_with("--delimiter=")
+            || arg.starts_with("--preview-scroll=")
+            || arg.starts_with("--keymap=")
+            || arg.starts_with("--layout=")
+            || arg.starts_with("--info=")
+            || arg.starts_with("--columns=")
+            || arg.starts_with("--history=")
+            || arg.starts_with("--expect=")
+            || arg.starts_with("--bind=")
+            || arg.starts_with("--jump-labels=")
+            || arg.starts_with("--select=")
+            || arg.starts_with("--color=")
+            || arg.starts_with("--prompt=")
+            || arg.starts_with("--query=")
+        {
             continue;
         }
 
-        if *arg == "--help-text" {
+        if *arg == "--help-text"
+            || *arg == "--history-boost"
+            || *arg == "--unique-similar"
+            || *arg == "--group-similar"
+            || *arg == "--index-cache"
+            || *arg == "--dedup"
+            || *arg == "--no-mouse"
+            || *arg == "--ansi"
+            || *arg == "--keep-right"
+            || *arg == "--no-sort"
+            || *arg == "--cycle"
+            || *arg == "--no-cycle"
+            || *arg == "--debug-scores"
+            || *arg == "--accessible"
+            || *arg == "--selection-order"
+            || *arg == "--icons"
+            || *arg == "--no-icons"
+            || *arg == "--tmux"
+            || *arg == "--no-scrollbar"
+            || *arg == "--wrap"
+            || *arg == "--print-query"
+            || *arg == "--select-1"
+            || *arg == "-1"
+            || *arg == "--exit-0"
+            || *arg == "-0"
+        {
             continue;
         }
 
@@ -295,13 +821,7 @@ pub fn plan_cli_action(args: &[String]) -> CliAction {
 
     CliAction::RunAsyncTui {
         items: direct_items,
-        multi_select,
-        line_number,
-        height,
-        height_percentage,
-        show_help_text,
-        preview_rules,
-        preview_auto,
+        options,
     }
 }
 
@@ -313,6 +833,15 @@ mod tests {
         args.iter().map(|s| s.to_string()).collect()
     }
 
+    /// Options for running the TUI with `args`, from files or piped stdin
+    fn run_options(args: &[&str]) -> RunOptions {
+        match plan_cli_action(&to_args(args)) {
+            CliAction::RunAsyncTui { options, .. }
+            | CliAction::RunAsyncTuiFromStdin { options } => options,
+            other => panic!("unexpected action: {other:?}"),
+        }
+    }
+
     #[test]
     fn detects_version_flag() {
         let args = to_args(&["ff", "--version"]);
@@ -351,6 +880,15 @@ mod tests {
         assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
     }
 
+    #[test]
+    fn parses_auto_height() {
+        for height in [&["--height", "auto"][..], &["--height=auto"]] {
+            let options = run_options(&[&["ff", "a"][..], height].concat());
+            assert!(options.auto_height);
+            assert_eq!(options.height, None);
+        }
+    }
+
     #[test]
     fn detects_invalid_height_percentage_value() {
         let args = to_args(&["ff", "file.txt", "--height-percentage", "invalid"]);
@@ -380,4 +918,448 @@ mod tests {
         let args = to_args(&["ff", "file.txt", "--height-percentage"]);
         assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
     }
+
+    #[test]
+    fn parses_scheme_flag() {
+        let options = run_options(&["ff", "a", "b", "--scheme", "path"]);
+        assert_eq!(options.scheme, Scheme::Path);
+    }
+
+    #[test]
+    fn parses_scheme_presets() {
+        for (name, scheme) in [
+            ("paths", Scheme::Path),
+            ("commands", Scheme::Commands),
+            ("prose", Scheme::Prose),
+            ("identifiers", Scheme::Identifiers),
+        ] {
+            let arg = format!("--scheme={name}");
+            let options = run_options(&["ff", "a", &arg]);
+            assert_eq!(options.scheme, scheme)
+        }
+    }
+
+    #[test]
+    fn parses_boundary_chars_flag() {
+        let options = run_options(&["ff", "a", "--boundary-chars", ",=@"]);
+        assert_eq!(options.boundary_chars, vec![',', '=', '@']);
+    }
+
+    #[test]
+    fn parses_index_cache_flag() {
+        let options = run_options(&["ff", "big.log", "--index-cache"]);
+        assert!(options.index_cache);
+    }
+
+    #[test]
+    fn parses_dedup_flag() {
+        let options = run_options(&["ff", "a", "--dedup"]);
+        assert!(options.dedup);
+
+        let args = to_args(&["ff", "a", "--dedup", "-n"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_ansi_flag() {
+        let options = run_options(&["ff", "a", "--ansi"]);
+        assert!(options.ansi);
+    }
+
+    #[test]
+    fn parses_help_text_flag() {
+        let options = run_options(&["ff", "a", "--help-text"]);
+        assert!(options.show_help_text);
+    }
+
+    #[test]
+    fn parses_wrap_flag() {
+        let options = run_options(&["ff", "a", "--wrap"]);
+        assert!(options.wrap);
+    }
+
+    #[test]
+    fn parses_no_scrollbar_flag() {
+        let options = run_options(&["ff", "a", "--no-scrollbar"]);
+        assert!(options.no_scrollbar);
+    }
+
+    #[test]
+    fn parses_no_sort_flag() {
+        let options = run_options(&["ff", "a", "--no-sort"]);
+        assert!(options.no_sort);
+    }
+
+    #[test]
+    fn parses_cycle_flags() {
+        let no_cycle = |args: &[&str]| run_options(args).no_cycle;
+        assert!(!no_cycle(&["ff", "a"]));
+        assert!(no_cycle(&["ff", "a", "--no-cycle"]));
+        assert!(!no_cycle(&["ff", "a", "--no-cycle", "--cycle"]));
+    }
+
+    #[test]
+    fn parses_icons_flag() {
+        let options = run_options(&["ff", "a", "--icons"]);
+        assert_eq!(options.icons, Some(IconStyle::Nerd));
+
+        let options = run_options(&["ff", "a", "--icons=ascii"]);
+        assert_eq!(options.icons, Some(IconStyle::Ascii));
+
+        // `--no-icons` wins, e.g. over `--icons` in an alias
+        let options = run_options(&["ff", "a", "--icons", "--no-icons"]);
+        assert_eq!(options.icons, None);
+
+        let args = to_args(&["ff", "a", "--icons=emoji"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_tmux_flag() {
+        let options = run_options(&["ff", "a", "--tmux"]);
+        assert_eq!(options.tmux, Some(TmuxPopup::default()));
+
+        let options = run_options(&["ff", "a", "--tmux=bottom,80%x40%"]);
+        assert_eq!(options.tmux, TmuxPopup::parse("bottom,80%x40%").ok());
+
+        let args = to_args(&["ff", "a", "--tmux=middle"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_debug_scores_flag() {
+        let options = run_options(&["ff", "a", "--debug-scores"]);
+        assert!(options.debug_scores);
+    }
+
+    #[test]
+    fn parses_selection_order_flag() {
+        let options = run_options(&["ff", "a", "-m", "--selection-order"]);
+        assert!(options.selection_order);
+    }
+
+    #[test]
+    fn parses_accessible_flag() {
+        let options = run_options(&["ff", "a", "--accessible"]);
+        assert!(options.accessible);
+    }
+
+    #[test]
+    fn parses_keep_right_flag() {
+        let options = run_options(&["ff", "a", "--keep-right"]);
+        assert!(options.keep_right);
+    }
+
+    #[test]
+    fn parses_no_mouse_flag() {
+        let options = run_options(&["ff", "a", "--no-mouse"]);
+        assert!(options.no_mouse);
+    }
+
+    #[test]
+    fn parses_unique_similar_flag() {
+        let options = run_options(&["ff", "a", "--unique-similar", "--group-similar"]);
+        assert!(options.unique_similar);
+        assert!(options.group_similar);
+    }
+
+    #[test]
+    fn parses_history_boost_flag() {
+        let args = ["ff", "a", "--history-boost", "b"];
+        assert!(run_options(&args).history_boost);
+        // The flag takes no value
+        if let CliAction::RunAsyncTui { items, .. } = plan_cli_action(&to_args(&args)) {
+            assert_eq!(items, vec!["a", "b"]);
+        }
+    }
+
+    #[test]
+    fn parses_min_score_flag() {
+        let options = run_options(&["ff", "a", "--min-score", "500"]);
+        assert_eq!(options.min_score, Some(500));
+        let args = to_args(&["ff", "a", "--min-score=high"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_max_results_flag() {
+        let options = run_options(&["ff", "a", "--max-results=100"]);
+        assert_eq!(options.max_results, Some(100));
+        let args = to_args(&["ff", "a", "--max-results", "-1"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_max_select_flag() {
+        let options = run_options(&["ff", "-m", "a", "--max-select", "2"]);
+        assert_eq!(options.max_select, Some(2));
+        for bad in ["0", "-1", "two"] {
+            let args = to_args(&["ff", "a", "--max-select", bad]);
+            assert!(
+                matches!(plan_cli_action(&args), CliAction::Error(_)),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_mode_flag() {
+        let options = run_options(&["ff", "a", "--mode=word"]);
+        assert_eq!(options.mode, MatchMode::Word);
+
+        let options = run_options(&["ff", "a", "--mode", "acronym"]);
+        assert_eq!(options.mode, MatchMode::Acronym);
+
+        let args = to_args(&["ff", "a", "--mode", "bogus"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_query_flag() {
+        let options = run_options(&["ff", "a", "-q", "src main"]);
+        assert_eq!(options.query, "src main");
+
+        assert_eq!(run_options(&["ff", "a", "--query=lib"]).query, "lib");
+        let args = to_args(&["ff", "a", "--query"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_prompt_flag() {
+        let options = run_options(&["ff", "a", "--prompt", "files> "]);
+        assert_eq!(options.prompt.as_deref(), Some("files> "));
+
+        let args = to_args(&["ff", "a", "--prompt"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_layout_flag() {
+        let options = run_options(&["ff", "a", "--layout", "default"]);
+        assert_eq!(options.layout, Layout::BottomUp);
+
+        let args = to_args(&["ff", "a", "--layout=sideways"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_info_flag() {
+        let options = run_options(&["ff", "a", "--info", "inline"]);
+        assert_eq!(options.info, Info::Inline);
+
+        let args = to_args(&["ff", "a", "--info=bogus"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_columns_flag() {
+        let options = run_options(&["ff", "a", "--columns", "4"]);
+        assert_eq!(options.columns, Columns::Fixed(4));
+
+        let options = run_options(&["ff", "a", "--columns=auto"]);
+        assert_eq!(options.columns, Columns::Auto);
+
+        let args = to_args(&["ff", "a", "--columns", "0"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_history_flag() {
+        let options = run_options(&["ff", "a", "--history", "/tmp/queries", "--history-boost"]);
+        assert_eq!(
+            options.history.as_deref(),
+            Some(std::path::Path::new("/tmp/queries"))
+        );
+        assert!(options.history_boost);
+
+        let args = to_args(&["ff", "a", "--history"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_print_query_flag() {
+        let options = run_options(&["ff", "a", "--print-query"]);
+        assert!(options.print_query);
+    }
+
+    #[test]
+    fn parses_select_one_and_exit_zero_flags() {
+        let options = run_options(&["ff", "a", "--select-1", "-0"]);
+        assert!(options.select_one);
+        assert!(options.exit_zero);
+    }
+
+    #[test]
+    fn parses_expect_flag() {
+        let options = run_options(&["ff", "a", "--expect=ctrl-o,f1"]);
+        let names: Vec<&str> = options.expect.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(names, ["ctrl-o", "f1"]);
+
+        let args = to_args(&["ff", "a", "--expect", "ctrl-"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+        let args = to_args(&["ff", "a", "--expect"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_tab_flag() {
+        let options = run_options(&["ff", "--tab", "files=dir:src", "--tab=notes=a b.txt"]);
+        let tabs = [
+            ("files".to_string(), "dir:src".to_string()),
+            ("notes".to_string(), "a b.txt".to_string()),
+        ];
+        assert_eq!(options.tabs, tabs);
+
+        for bad in ["files", "=src", "files="] {
+            let args = to_args(&["ff", "--tab", bad]);
+            assert!(
+                matches!(plan_cli_action(&args), CliAction::Error(_)),
+                "{bad}"
+            );
+        }
+        let args = to_args(&["ff", "--tab"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_bind_flag() {
+        let options = run_options(&[
+            "ff",
+            "a",
+            "--bind",
+            "ctrl-o:execute(open {})",
+            "--bind=f2:execute:less {}",
+        ]);
+        let keys: Vec<&str> = options
+            .bindings
+            .iter()
+            .map(|b| b.key.name.as_str())
+            .collect();
+        assert_eq!(keys, ["ctrl-o", "f2"]);
+
+        let args = to_args(&["ff", "a", "--bind", "ctrl-o:abort"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+        let args = to_args(&["ff", "a", "--bind"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_jump_labels_flag() {
+        let options = run_options(&["ff", "a", "--jump-labels", "jkl"]);
+        assert_eq!(options.jump_labels, "jkl");
+
+        let args = to_args(&["ff", "a", "--jump-labels", "jkj"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+        let args = to_args(&["ff", "a", "--jump-labels"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_select_flag() {
+        let options = run_options(&["ff", "-m", "a", "b", "--select", "b", "--select=c d"]);
+        assert_eq!(options.preselect, ["b", "c d"]);
+
+        let args = to_args(&["ff", "a", "--select"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_color_flag() {
+        let options = run_options(&["ff", "a", "--color", "light,hl:red"]);
+        let expected = Theme {
+            match_fg: Some(crossterm::style::Color::Red),
+            ..Theme::LIGHT
+        };
+        assert_eq!(options.theme, expected);
+
+        let args = to_args(&["ff", "a", "--color=hl:chartreuse"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_keymap_flag() {
+        let options = run_options(&["ff", "a", "--keymap", "vim"]);
+        assert_eq!(options.keymap, Keymap::Vim);
+
+        let args = to_args(&["ff", "a", "--keymap=nano"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+        let args = to_args(&["ff", "a", "--keymap"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_record_sep_flag() {
+        let options = run_options(&["ff", "a", "--record-sep", "blank"]);
+        assert_eq!(options.record_sep, RecordSeparator::Blank);
+
+        let args = to_args(&["ff", "a", "--record-sep"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_weight_field_flag() {
+        let options = run_options(&["ff", "a", "--weight-field=2"]);
+        assert_eq!(options.weight_field, Some(2));
+
+        let args = to_args(&["ff", "a", "--weight-field", "0"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_with_nth_flag() {
+        let options = run_options(&["ff", "a", "--with-nth", "2..", "-d", ":"]);
+        let expected = Fields::parse("2..", Some(':')).unwrap();
+        assert_eq!(options.with_nth, Some(expected));
+
+        let args = to_args(&["ff", "a", "--with-nth=0"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+        let args = to_args(&["ff", "a", "--with-nth=1", "--delimiter=ab"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn parses_preview_scroll_flag() {
+        let options = run_options(&["ff", "a", "--preview-scroll", "{2}-5"]);
+        assert_eq!(options.preview_scroll, PreviewScroll::parse("{2}-5").ok());
+
+        let args = to_args(&["ff", "a", "--preview-scroll=bogus"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
+
+    #[test]
+    fn previews_directory_input_with_builtin_previewer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        let args = to_args(&["ff", path]);
+        match plan_cli_action(&args) {
+            CliAction::RunAsyncTui { items, options } => {
+                assert_eq!(items, vec![format!("dir:{path}")]);
+                assert_eq!(options.preview_root.as_deref(), Some(dir.path()));
+                assert_eq!(
+                    options.preview_rules,
+                    vec![PreviewRule::parse("auto").unwrap()]
+                );
+                assert!(options.preview_auto);
+            }
+            // Piped stdin takes precedence over the directory argument
+            CliAction::RunAsyncTuiFromStdin { .. } => {}
+            other => panic!("unexpected action: {other:?}"),
+        }
+
+        // An explicit preview is kept
+        let args = to_args(&["ff", path, "-p", "ls -la"]);
+        if let CliAction::RunAsyncTui { options, .. } = plan_cli_action(&args) {
+            assert_eq!(
+                options.preview_rules,
+                vec![PreviewRule::parse("ls -la").unwrap()]
+            );
+            assert!(!options.preview_auto);
+        }
+    }
+
+    #[test]
+    fn detects_invalid_scheme() {
+        let args = to_args(&["ff", "a", "--scheme=bogus"]);
+        assert!(matches!(plan_cli_action(&args), CliAction::Error(_)));
+    }
 }
@@ -0,0 +1,221 @@
+//! `--tmux`: run ff in a tmux popup floating over the current pane.
+//!
+//! ff runs itself again inside `tmux display-popup`, without `--tmux`, with
+//! piped input and its output passed through temporary files.
+
+use std::fs::{self, File};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where the popup sits in the tmux window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopupPosition {
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// Position and size of the popup, as in `center,80%x60%`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmuxPopup {
+    pub position: PopupPosition,
+    /// Width in columns, or a percentage of the window as `N%`
+    pub width: String,
+    /// Height in rows, or a percentage of the window as `N%`
+    pub height: String,
+}
+
+impl Default for TmuxPopup {
+    fn default() -> Self {
+        Self {
+            position: PopupPosition::Center,
+            width: "50%".to_string(),
+            height: "50%".to_string(),
+        }
+    }
+}
+
+impl TmuxPopup {
+    /// Parse `[POSITION][,SIZE]`, where the position is `center`, `top`,
+    /// `bottom`, `left` or `right` and the size is `W` (for both) or `WxH`,
+    /// each a number or a percentage
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut popup = Self::default();
+        for part in spec.split(',') {
+            let position = match part {
+                "center" => Some(PopupPosition::Center),
+                "top" => Some(PopupPosition::Top),
+                "bottom" => Some(PopupPosition::Bottom),
+                "left" => Some(PopupPosition::Left),
+                "right" => Some(PopupPosition::Right),
+                _ => None,
+            };
+            if let Some(position) = position {
+                popup.position = position;
+                continue;
+            }
+            let (width, height) = part.split_once('x').unwrap_or((part, part));
+            let valid = |size: &str| {
+                let number = size.strip_suffix('%').unwrap_or(size);
+                !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+            };
+            if !valid(width) || !valid(height) {
+                return Err(format!(
+                    "Invalid tmux popup '{spec}'. Expected [center|top|bottom|left|right][,W[xH]]"
+                ));
+            }
+            popup.width = width.to_string();
+            popup.height = height.to_string();
+        }
+        Ok(popup)
+    }
+
+    /// `display-popup` options placing the popup
+    fn position_args(&self) -> [&'static str; 2] {
+        match self.position {
+            PopupPosition::Center => ["-xC", "-yC"],
+            PopupPosition::Top => ["-xC", "-y0"],
+            // The popup is moved up to fit
+            PopupPosition::Bottom => ["-xC", "-y9999"],
+            PopupPosition::Left => ["-x0", "-yC"],
+            PopupPosition::Right => ["-xR", "-yC"],
+        }
+    }
+}
+
+/// Whether ff runs inside tmux
+pub fn in_tmux() -> bool {
+    std::env::var_os("TMUX").is_some_and(|tmux| !tmux.is_empty())
+}
+
+/// `args` without `--tmux` (and its value), for ff inside the popup
+pub fn popup_args(args: &[String]) -> Vec<String> {
+    args.iter()
+        .filter(|arg| *arg != "--tmux" && !arg.starts_with("--tmux="))
+        .cloned()
+        .collect()
+}
+
+/// Quote `s` for a POSIX shell
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Shell command running `args` (the program first) with stdin from
+/// `input`, if any, stdout to `output`, and the exit status in `status`
+fn popup_command(args: &[String], input: Option<&Path>, output: &Path, status: &Path) -> String {
+    let command: Vec<String> = args.iter().map(|arg| quote(arg)).collect();
+    let mut command = command.join(" ");
+    if let Some(input) = input {
+        command.push_str(&format!(" < {}", quote(&input.to_string_lossy())));
+    }
+    format!(
+        "{command} > {}; echo $? > {}",
+        quote(&output.to_string_lossy()),
+        quote(&status.to_string_lossy())
+    )
+}
+
+/// Run ff with `args` (from `std::env::args`) in a tmux popup, copying its
+/// selection to stdout. Returns its exit status.
+pub fn run_in_popup(args: &[String], popup: &TmuxPopup) -> io::Result<i32> {
+    let dir = std::env::temp_dir().join(format!("ff-tmux-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let result = run_in_popup_with_dir(args, popup, &dir);
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+fn run_in_popup_with_dir(args: &[String], popup: &TmuxPopup, dir: &Path) -> io::Result<i32> {
+    // Piped input is read up front and handed to ff in the popup
+    let input = if io::stdin().is_terminal() {
+        None
+    } else {
+        let path = dir.join("input");
+        io::copy(&mut io::stdin().lock(), &mut File::create(&path)?)?;
+        Some(path)
+    };
+    let output = dir.join("output");
+    let status = dir.join("status");
+
+    let mut popup_args = popup_args(args);
+    popup_args[0] = std::env::current_exe()?.to_string_lossy().into_owned();
+    let command = popup_command(&popup_args, input.as_deref(), &output, &status);
+    let cwd: PathBuf = std::env::current_dir()?;
+    let tmux = Command::new("tmux")
+        .arg("display-popup")
+        .arg("-E")
+        .args(popup.position_args())
+        .arg(format!("-w{}", popup.width))
+        .arg(format!("-h{}", popup.height))
+        .arg("-d")
+        .arg(&cwd)
+        // In `sh`, whatever the tmux default shell
+        .arg(format!("sh -c {}", quote(&command)))
+        .status()?;
+    if !tmux.success() && !status.exists() {
+        return Err(io::Error::other("tmux display-popup failed"));
+    }
+
+    if let Ok(mut selection) = File::open(&output) {
+        let mut stdout = io::stdout().lock();
+        io::copy(&mut selection, &mut stdout)?;
+        stdout.flush()?;
+    }
+    // A popup closed without ff finishing counts as cancelled
+    let code = fs::read_to_string(&status)
+        .ok()
+        .and_then(|status| status.trim().parse().ok())
+        .unwrap_or(130);
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(TmuxPopup::parse("center").unwrap(), TmuxPopup::default());
+        let popup = TmuxPopup::parse("bottom,80%x20").unwrap();
+        assert_eq!(popup.position, PopupPosition::Bottom);
+        assert_eq!((popup.width.as_str(), popup.height.as_str()), ("80%", "20"));
+        let popup = TmuxPopup::parse("70%").unwrap();
+        assert_eq!(popup.position, PopupPosition::Center);
+        assert_eq!(
+            (popup.width.as_str(), popup.height.as_str()),
+            ("70%", "70%")
+        );
+        for bad in ["middle", "80%x", "x20", "-5", "center,,"] {
+            assert!(TmuxPopup::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_popup_args() {
+        let args = to_args(&["ff", "--tmux", "a", "--tmux=top", "-m"]);
+        assert_eq!(popup_args(&args), to_args(&["ff", "a", "-m"]));
+    }
+
+    #[test]
+    fn test_popup_command() {
+        let args = to_args(&["/bin/ff", "it's", "-m"]);
+        let command = popup_command(
+            &args,
+            Some(Path::new("/tmp/in")),
+            Path::new("/tmp/out"),
+            Path::new("/tmp/status"),
+        );
+        assert_eq!(
+            command,
+            "'/bin/ff' 'it'\\''s' '-m' < '/tmp/in' > '/tmp/out'; echo $? > '/tmp/status'"
+        );
+    }
+}
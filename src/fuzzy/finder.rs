@@ -1,25 +1,265 @@
+use crate::fuzzy::index::CandidateIndex;
+use crate::fuzzy::lsh::{LSHConfig, LSHIndex};
 use crate::fuzzy::scoring;
-use crate::fuzzy::stream::ItemStream;
+use crate::fuzzy::stream::{Dedup, ItemStream, ItemTransform};
+use crate::fuzzy::suggest::Suggester;
+use crate::fuzzy::tree::TernaryTree;
+
+/// Item count at which a candidate index is built in the background
+pub const INDEX_THRESHOLD: usize = 100_000;
+
+/// Estimated similarity at which results are collapsed as near-duplicates
+pub const SIMILAR_THRESHOLD: f64 = 0.7;
 
 /// Match positions for highlighting
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MatchPositions {
     pub positions: Vec<usize>,
     pub score: i32,
+    /// Match quality tier (results rank by tier, then score)
+    pub tier: scoring::MatchTier,
+}
+
+/// Display order of two results: tier desc, score desc, index asc
+fn rank_order(a: &(usize, MatchPositions), b: &(usize, MatchPositions)) -> std::cmp::Ordering {
+    b.1.tier
+        .cmp(&a.1.tier)
+        .then_with(|| b.1.score.cmp(&a.1.score))
+        .then_with(|| a.0.cmp(&b.0))
+}
+
+/// An item with extra text that is matched but not displayed
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SearchItem {
+    /// Text shown in the list and returned when selected
+    pub display: String,
+    /// Extra searchable text (tags, description, full path); never shown
+    pub hidden: String,
+    /// Added to the item's match score to bias ranking
+    pub weight: i32,
+}
+
+impl SearchItem {
+    /// Create an item with hidden search text
+    pub fn new(display: impl Into<String>, hidden: impl Into<String>) -> Self {
+        Self {
+            display: display.into(),
+            hidden: hidden.into(),
+            weight: 0,
+        }
+    }
+
+    /// Set the score boost for this item
+    pub fn with_weight(mut self, weight: i32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Split `line` at the first `separator`: the part before is displayed,
+    /// the rest is hidden search text
+    pub fn split(line: String, separator: char) -> Self {
+        match line.split_once(separator) {
+            Some((display, hidden)) => Self::new(display, hidden),
+            None => Self::from(line),
+        }
+    }
+}
+
+impl From<String> for SearchItem {
+    fn from(display: String) -> Self {
+        Self {
+            display,
+            hidden: String::new(),
+            weight: 0,
+        }
+    }
+}
+
+/// Take the weight from tab-separated `field` (1-based) of `line`, returning
+/// the line without that field. `None` if the field is missing or not an integer.
+pub fn split_weight_field(line: &str, field: usize) -> Option<(String, i32)> {
+    let index = field.checked_sub(1)?;
+    let fields: Vec<&str> = line.split('\t').collect();
+    let weight = fields.get(index)?.trim().parse::<i32>().ok()?;
+    let rest: Vec<&str> = fields
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != index)
+        .map(|(_, f)| *f)
+        .collect();
+    Some((rest.join("\t"), weight))
+}
+
+/// Point-in-time copy of a finder's items, results and selections.
+///
+/// Owns its data, so it can be sent to another thread or persisted while
+/// the finder keeps filtering. Taking one copies every item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinderSnapshot {
+    /// Query the results were filtered with
+    pub query: String,
+    /// Every item, by original index
+    pub items: Vec<String>,
+    /// Original indices of the filtered results, in ranked order
+    pub filtered_indices: Vec<usize>,
+    /// Match positions of each filtered result
+    pub match_positions: Vec<MatchPositions>,
+    /// Original indices of the selected items, ascending
+    pub selected: Vec<usize>,
+    /// Cursor position within the filtered results
+    pub cursor: usize,
+    /// Whether the results are final for the query (not abandoned or still
+    /// being filtered progressively)
+    pub complete: bool,
+}
+
+impl FinderSnapshot {
+    /// Filtered results in ranked order, as `(original_index, item)`
+    pub fn filtered_items(&self) -> impl Iterator<Item = (usize, &str)> + '_ {
+        self.filtered_indices
+            .iter()
+            .map(|&idx| (idx, self.items[idx].as_str()))
+    }
+
+    /// Selected items in original order, as `(original_index, item)`
+    pub fn selected_items(&self) -> Vec<(usize, &str)> {
+        self.selected
+            .iter()
+            .map(|&idx| (idx, self.items[idx].as_str()))
+            .collect()
+    }
+
+    /// The result under the cursor, as `(original_index, item)`
+    pub fn current_item(&self) -> Option<(usize, &str)> {
+        let idx = *self.filtered_indices.get(self.cursor)?;
+        Some((idx, self.items[idx].as_str()))
+    }
+}
+
+/// A run of filtered results that belong together
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarGroup {
+    /// Position of the first result in the group
+    pub start: usize,
+    /// Number of results in the group
+    pub len: usize,
+    /// Whether the results are near-duplicates (false for the ungrouped tail)
+    pub clustered: bool,
+}
+
+/// Reorder `v` so that element `i` is the old element at `order[i]`
+fn permute<T>(v: &mut Vec<T>, order: &[usize]) {
+    let mut old: Vec<Option<T>> = std::mem::take(v).into_iter().map(Some).collect();
+    *v = order.iter().filter_map(|&i| old[i].take()).collect();
+}
+
+/// Remove the elements at `removed` (ascending positions; those past the
+/// end are ignored)
+fn drop_positions<T>(v: &mut Vec<T>, removed: &[usize]) {
+    let mut removed = removed.iter().peekable();
+    let mut pos = 0;
+    v.retain(|_| {
+        let dropped = removed.next_if_eq(&&pos).is_some();
+        pos += 1;
+        !dropped
+    });
+}
+
+/// Keep only the elements at `keep` (ascending positions)
+fn retain_positions<T>(v: &mut Vec<T>, keep: &[usize]) {
+    let mut keep = keep.iter().peekable();
+    let mut pos = 0;
+    v.retain(|_| {
+        let kept = keep.next_if_eq(&&pos).is_some();
+        pos += 1;
+        kept
+    });
 }
 
 /// Async fuzzy finder with streaming capabilities
 pub struct FuzzyFinder {
     pub(crate) stream: ItemStream,
     pub(crate) query: String,
+    /// Byte offset of the editing cursor in the query
+    pub(crate) query_cursor: usize,
     pub(crate) filtered_items: Vec<String>,
     pub(crate) filtered_indices: Vec<usize>,
     pub(crate) match_positions: Vec<MatchPositions>,
-    pub(crate) selected_items: std::collections::HashSet<usize>,
+    /// Selected items by original index, so a selection stays with its
+    /// item (not its text or row) through re-filtering and streaming, each
+    /// with the number of the selection that made it
+    pub(crate) selected_items: std::collections::HashMap<usize, u64>,
+    /// Selections made so far, numbering each in `selected_items`
+    pub(crate) selections_made: u64,
+    /// Return selections in the order they were made rather than in item
+    /// order
+    pub(crate) selection_order: bool,
+    /// Item texts selected as they are added (see [`FuzzyFinder::preselect`])
+    pub(crate) preselected: std::collections::HashSet<String>,
+    /// Most items that can be selected at once (`None` for no limit)
+    pub(crate) max_selected: Option<usize>,
+    /// Whether the last change to the selection stopped at `max_selected`
+    pub(crate) selection_capped: bool,
     pub(crate) cursor_position: usize,
     pub(crate) multi_select: bool,
     /// Cache stores (filtered_items, filtered_indices, match_positions) for each query
     pub(crate) query_cache: crate::fuzzy::finder::QueryCache,
+    /// Stream generation the cached results were computed for
+    pub(crate) cache_generation: u64,
+    /// Stream generation the current results were computed for
+    pub(crate) results_generation: u64,
+    /// Scoring options used when ranking matches
+    pub(crate) scoring: scoring::ScoringConfig,
+    /// Matches scoring below this are dropped (non-empty queries only)
+    pub(crate) min_score: Option<i32>,
+    /// Stop collecting after this many ranked matches
+    pub(crate) max_results: Option<usize>,
+    /// Rank matches by score; when off, matches keep their input order
+    pub(crate) sort: bool,
+    /// Whether moving the cursor past the last result wraps to the first
+    /// (and back), rather than stopping there
+    pub(crate) cycle: bool,
+    /// Candidate index used to prune items before scoring, once built
+    pub(crate) index: Option<CandidateIndex>,
+    /// Background task building the candidate index
+    pub(crate) index_task: Option<tokio::task::JoinHandle<CandidateIndex>>,
+    /// Item count at which to build the index (`None` disables it)
+    pub(crate) index_threshold: Option<usize>,
+    /// Cancels an in-flight filter pass when set from another thread
+    pub(crate) cancel: scoring::CancelToken,
+    /// Whether the last filter pass was cancelled, leaving results out of date
+    pub(crate) stale: bool,
+    /// Prefix trie answering `^anchored` queries, built on first use
+    pub(crate) prefix_tree: Option<TernaryTree>,
+    /// Collapse near-duplicate results into one representative
+    pub(crate) unique_similar: bool,
+    /// Per filtered item: how many results it represents (empty when not collapsing)
+    pub(crate) similar_counts: Vec<usize>,
+    /// Group near-duplicate results together
+    pub(crate) group_similar: bool,
+    /// Groups of contiguous filtered results (empty when not grouping)
+    pub(crate) similar_groups: Vec<SimilarGroup>,
+    /// MinHash signatures by original index, reused across queries
+    pub(crate) signature_cache: std::collections::HashMap<usize, Vec<u64>>,
+    /// Text matched for each item: display plus hidden text (empty until an
+    /// item with hidden text is added; items are matched as displayed until then)
+    pub(crate) search_texts: Vec<String>,
+    /// Split added items into display and hidden text at this character
+    pub(crate) hidden_separator: Option<char>,
+    /// Score boost per item (empty until a weighted item is added; missing
+    /// entries count as 0)
+    pub(crate) weights: Vec<i32>,
+    /// Tab-separated field (1-based) of added items holding their weight
+    pub(crate) weight_field: Option<usize>,
+    /// Items scored per step of a progressive filter pass (`None` scores
+    /// every item at once)
+    pub(crate) filter_chunk: Option<usize>,
+    /// Items still to be scored by the running progressive filter pass
+    pub(crate) filter_pass: Option<std::ops::Range<usize>>,
+    /// Vocabulary of item words for query corrections, built on first use
+    pub(crate) suggester: Option<Suggester>,
+    /// Last correction computed: query, stream generation and suggestion
+    pub(crate) suggestion: Option<(String, u64, Option<String>)>,
 }
 
 /// Type alias for the fuzzy finder query cache.
@@ -33,13 +273,45 @@ impl FuzzyFinder {
         Self {
             stream,
             query: String::new(),
+            query_cursor: 0,
             filtered_items: Vec::new(),
             filtered_indices: Vec::new(),
             match_positions: Vec::new(),
-            selected_items: std::collections::HashSet::new(),
+            selected_items: std::collections::HashMap::new(),
+            selections_made: 0,
+            selection_order: false,
+            preselected: std::collections::HashSet::new(),
+            max_selected: None,
+            selection_capped: false,
             cursor_position: 0,
             multi_select,
             query_cache: std::collections::HashMap::new(),
+            cache_generation: 0,
+            results_generation: 0,
+            scoring: scoring::ScoringConfig::default(),
+            min_score: None,
+            max_results: None,
+            sort: true,
+            cycle: true,
+            index: None,
+            index_task: None,
+            index_threshold: Some(INDEX_THRESHOLD),
+            cancel: scoring::CancelToken::new(),
+            stale: false,
+            prefix_tree: None,
+            unique_similar: false,
+            similar_counts: Vec::new(),
+            group_similar: false,
+            similar_groups: Vec::new(),
+            signature_cache: std::collections::HashMap::new(),
+            search_texts: Vec::new(),
+            hidden_separator: None,
+            weights: Vec::new(),
+            weight_field: None,
+            filter_chunk: None,
+            filter_pass: None,
+            suggester: None,
+            suggestion: None,
         }
     }
 
@@ -51,69 +323,560 @@ impl FuzzyFinder {
     }
 
     /// Update the filtered items based on the current query
+    ///
+    /// With [`set_filter_chunk`](Self::set_filter_chunk), a query that is not
+    /// cached only scores the first chunk of items here; the rest is scored
+    /// by [`filter_next_chunk`](Self::filter_next_chunk).
     pub async fn update_filter(&mut self) {
+        self.stale = false;
+        self.filter_pass = None;
+        // Cached results only hold for the items they were computed from
+        let generation = self.stream.generation();
+        if self.cache_generation != generation {
+            self.query_cache.clear();
+            self.cache_generation = generation;
+        }
         if self.query.is_empty() {
-            let all_items = self.stream.get_all_items();
-            self.filtered_items = Vec::new();
-            self.filtered_indices = Vec::new();
-            for (idx, item) in all_items.iter().enumerate() {
-                if !item.is_empty() {
-                    self.filtered_items.push(item.clone());
-                    self.filtered_indices.push(idx);
-                }
-            }
-            self.match_positions = self
-                .filtered_items
-                .iter()
-                .map(|_| MatchPositions {
-                    positions: Vec::new(),
-                    score: 0,
-                })
-                .collect();
+            let ranked = self.unscored_items(0);
+            self.set_results(ranked);
         } else if let Some(cached) = self.query_cache.get(&self.query) {
             self.filtered_items = cached.0.clone();
             self.filtered_indices = cached.1.clone();
             self.match_positions = cached.2.clone();
+        } else if self.progressive_chunk().is_some() {
+            // Results fill in as chunks are scored, starting now
+            self.set_results(Vec::new());
+            self.similar_counts.clear();
+            self.similar_groups.clear();
+            self.results_generation = generation;
+            self.filter_pass = Some(0..self.stream.len());
+            self.filter_next_chunk().await;
+            return;
         } else {
-            let all_items = self.stream.get_all_items();
+            let Some(ranked) = self.score_items(0..self.stream.len()).await else {
+                // Abandoned for newer input: keep the previous results
+                self.stale = true;
+                return;
+            };
+            self.set_results(ranked);
+            self.cache_results();
+        }
+        self.results_generation = generation;
 
-            // Use the new scoring module for single-pass matching and scoring
-            let scored_results = scoring::score_batch(&all_items, &self.query);
+        self.similar_counts.clear();
+        self.similar_groups.clear();
+        if self.unique_similar {
+            self.collapse_similar();
+        } else if self.group_similar {
+            self.group_similar();
+        }
 
-            // Extract filtered items and match positions (already sorted by score)
-            self.filtered_items = scored_results
-                .iter()
-                .map(|(idx, _)| all_items[*idx].clone())
-                .collect();
+        self.clamp_cursor();
+    }
 
-            self.filtered_indices = scored_results.iter().map(|(idx, _)| *idx).collect();
+    /// Non-empty items from index `from` on, in input order, as unscored
+    /// results (up to the result limit)
+    fn unscored_items(&self, from: usize) -> Vec<(usize, MatchPositions)> {
+        let all_items = self.stream.as_slice();
+        (from..all_items.len())
+            .filter(|&idx| !all_items[idx].is_empty())
+            .take(self.max_results.unwrap_or(usize::MAX))
+            .map(|idx| {
+                let positions = MatchPositions {
+                    positions: Vec::new(),
+                    score: 0,
+                    tier: scoring::MatchTier::Fuzzy,
+                };
+                (idx, positions)
+            })
+            .collect()
+    }
 
-            self.match_positions = scored_results
-                .into_iter()
-                .map(|(_, result)| MatchPositions {
+    /// Chunk size for a progressive pass over the current items, if one
+    /// applies: enough items to split, and results that are not reshaped by
+    /// near-duplicate collapsing or grouping
+    fn progressive_chunk(&self) -> Option<usize> {
+        self.filter_chunk
+            .filter(|&chunk| self.stream.len() > chunk)
+            .filter(|_| !self.unique_similar && !self.group_similar)
+    }
+
+    /// Score the next chunk of a progressive filter pass and merge it into
+    /// the results. Returns whether items remain to be scored.
+    ///
+    /// Changing the query (or anything else that re-filters) supersedes the
+    /// running pass. If the chunk is cancelled through the
+    /// [`cancel_token`](Self::cancel_token), the pass is abandoned and
+    /// [`is_stale`](Self::is_stale) reports true.
+    pub async fn filter_next_chunk(&mut self) -> bool {
+        let Some(pass) = self.filter_pass.take() else {
+            return false;
+        };
+        let chunk = self.filter_chunk.unwrap_or(usize::MAX).max(1);
+        let end = pass.end.min(pass.start.saturating_add(chunk));
+        let Some(scored) = self.score_items(pass.start..end).await else {
+            self.stale = true;
+            return false;
+        };
+        self.merge_ranked(scored);
+        if end < pass.end {
+            self.filter_pass = Some(end..pass.end);
+        } else {
+            self.cache_results();
+        }
+        self.clamp_cursor();
+        self.filter_pass.is_some()
+    }
+
+    /// Whether a progressive filter pass still has items to score, so the
+    /// results may be incomplete
+    pub fn is_filtering(&self) -> bool {
+        self.filter_pass.is_some()
+    }
+
+    /// Score at most `chunk` items per step when filtering, so a caller such
+    /// as an event loop can stay responsive on huge inputs by calling
+    /// [`filter_next_chunk`](Self::filter_next_chunk) between other work
+    /// (`None` scores every item in one pass)
+    pub fn set_filter_chunk(&mut self, chunk: Option<usize>) {
+        self.filter_chunk = chunk;
+    }
+
+    /// Rank the items in `range` against the (non-empty) query.
+    /// Returns `None` if the pass was cancelled.
+    async fn score_items(
+        &mut self,
+        range: std::ops::Range<usize>,
+    ) -> Option<Vec<(usize, MatchPositions)>> {
+        self.sync_index().await;
+        let all_items = self.stream.as_slice();
+        let search_items = if self.search_texts.is_empty() {
+            all_items
+        } else {
+            &self.search_texts
+        };
+
+        // Use the new scoring module for single-pass matching and scoring,
+        // pruning with the candidate index when one is available.
+        // `^prefix` only keeps items starting with the prefix, looked up
+        // in the prefix trie.
+        let (candidates, query) = if let Some(prefix) = self.query.strip_prefix('^') {
+            let tree = self.prefix_tree.get_or_insert_with(TernaryTree::new);
+            if tree.len() < search_items.len() {
+                tree.extend(&search_items[tree.len()..]);
+            }
+            (Some(tree.with_prefix(&prefix.to_lowercase())), prefix)
+        } else if let Some(index) = &self.index {
+            // Word and acronym modes ignore query whitespace
+            let mut needed = self.query.to_lowercase();
+            if self.scoring.mode != scoring::MatchMode::Fuzzy {
+                needed.retain(|c| !c.is_whitespace());
+            }
+            // A prebuilt index may cover items not streamed in yet
+            let mut candidates = index.candidates(&needed);
+            candidates.truncate(candidates.partition_point(|&i| i < all_items.len()));
+            (Some(candidates), self.query.as_str())
+        } else {
+            (None, self.query.as_str())
+        };
+        let whole = range.start == 0 && range.end >= all_items.len();
+        let candidates = match candidates {
+            Some(mut candidates) if !whole => {
+                candidates.truncate(candidates.partition_point(|&i| i < range.end));
+                candidates.drain(..candidates.partition_point(|&i| i < range.start));
+                Some(candidates)
+            }
+            None if !whole => Some(range.collect()),
+            candidates => candidates,
+        };
+
+        // Unsorted, the result limit keeps the first matches rather than
+        // the best ones
+        let mut scored_results = scoring::score_batch_cancellable(
+            search_items,
+            &self.weights,
+            candidates.as_deref(),
+            query,
+            &self.scoring,
+            self.min_score,
+            self.max_results.filter(|_| self.sort),
+            &self.cancel,
+        )?;
+        if !self.sort {
+            scored_results.sort_unstable_by_key(|(idx, _)| *idx);
+            scored_results.truncate(self.max_results.unwrap_or(usize::MAX));
+        }
+
+        let hidden_text = !self.search_texts.is_empty();
+        let ranked = scored_results
+            .into_iter()
+            .map(|(idx, mut result)| {
+                // Only highlight matches inside the displayed text
+                if hidden_text {
+                    let shown = scoring::strip_ansi_sequences(&all_items[idx])
+                        .chars()
+                        .count();
+                    result.positions.retain(|&p| p < shown);
+                }
+                let positions = MatchPositions {
                     positions: result.positions,
                     score: result.score,
-                })
-                .collect();
+                    tier: result.tier,
+                };
+                (idx, positions)
+            })
+            .collect();
+        Some(ranked)
+    }
 
-            // Cache the results
-            self.query_cache.insert(
-                self.query.clone(),
-                (
-                    self.filtered_items.clone(),
-                    self.filtered_indices.clone(),
-                    self.match_positions.clone(),
-                ),
-            );
+    /// Replace the filtered results with `ranked` (already in display order)
+    fn set_results(&mut self, ranked: Vec<(usize, MatchPositions)>) {
+        let all_items = self.stream.as_slice();
+        self.filtered_items = ranked
+            .iter()
+            .map(|(idx, _)| all_items[*idx].clone())
+            .collect();
+        self.filtered_indices = ranked.iter().map(|(idx, _)| *idx).collect();
+        self.match_positions = ranked.into_iter().map(|(_, m)| m).collect();
+    }
+
+    /// Cache the current results for the current query (unless a
+    /// progressive pass has yet to complete them)
+    fn cache_results(&mut self) {
+        if self.filter_pass.is_some() {
+            return;
         }
+        self.query_cache.insert(
+            self.query.clone(),
+            (
+                self.filtered_items.clone(),
+                self.filtered_indices.clone(),
+                self.match_positions.clone(),
+            ),
+        );
+    }
 
-        // Adjust cursor position
+    /// Keep the cursor within the filtered results
+    fn clamp_cursor(&mut self) {
         if self.cursor_position >= self.filtered_items.len() {
-            self.cursor_position = if self.filtered_items.is_empty() {
-                0
-            } else {
-                self.filtered_items.len() - 1
+            self.cursor_position = self.filtered_items.len().saturating_sub(1);
+        }
+    }
+
+    /// Whether the current results can be patched in place instead of
+    /// re-filtering from scratch: they are up to date for `generation` and
+    /// not reshaped by near-duplicate collapsing or grouping
+    fn results_patchable(&self, generation: u64) -> bool {
+        self.results_generation == generation
+            && !self.stale
+            && !self.unique_similar
+            && !self.group_similar
+    }
+
+    /// Merge items added since `generation` (stored from index `from` on)
+    /// into the current results, scoring only the new items
+    async fn filter_added(&mut self, from: usize, generation: u64) {
+        if !self.results_patchable(generation) {
+            self.update_filter().await;
+            return;
+        }
+        let current = self.stream.generation();
+        if current == generation {
+            return;
+        }
+        self.query_cache.clear();
+        self.cache_generation = current;
+
+        let added = if self.query.is_empty() {
+            self.unscored_items(from)
+        } else {
+            let Some(added) = self.score_items(from..self.stream.len()).await else {
+                self.stale = true;
+                return;
             };
+            added
+        };
+
+        self.merge_ranked(added);
+        if !self.query.is_empty() {
+            self.cache_results();
+        }
+        self.results_generation = current;
+    }
+
+    /// Merge newly scored items (ranked among themselves) into the current
+    /// results, keeping them within the result limit
+    fn merge_ranked(&mut self, added: Vec<(usize, MatchPositions)>) {
+        let previous = std::mem::take(&mut self.filtered_indices)
+            .into_iter()
+            .zip(std::mem::take(&mut self.match_positions));
+        let mut ranked: Vec<(usize, MatchPositions)> = Vec::new();
+        let mut added = added.into_iter().peekable();
+        let sort = self.sort;
+        let before = |new: &(usize, MatchPositions), entry: &(usize, MatchPositions)| {
+            if sort {
+                rank_order(new, entry).is_lt()
+            } else {
+                new.0 < entry.0
+            }
+        };
+        for entry in previous {
+            while let Some(new) = added.next_if(|new| before(new, &entry)) {
+                ranked.push(new);
+            }
+            ranked.push(entry);
+        }
+        ranked.extend(added);
+        ranked.truncate(self.max_results.unwrap_or(usize::MAX));
+        self.set_results(ranked);
+    }
+
+    /// Assign each filtered result (in ranked order) to a leader: the first
+    /// earlier result it is a near-duplicate of, or itself. Returns the
+    /// leader's position for every position.
+    fn similar_leaders(&mut self) -> Vec<usize> {
+        let mut lsh = LSHIndex::new(LSHConfig::new(8, 4));
+        // LSH id -> position of that leader
+        let mut leader_positions: Vec<usize> = Vec::new();
+        let mut leaders = Vec::with_capacity(self.filtered_items.len());
+
+        for (pos, item) in self.filtered_items.iter().enumerate() {
+            let signature = self
+                .signature_cache
+                .entry(self.filtered_indices[pos])
+                .or_insert_with(|| lsh.signature(item));
+            let leader = lsh
+                .candidates_for(signature)
+                .into_iter()
+                .next()
+                .filter(|&id| {
+                    lsh.get_signature(id).is_some_and(|leader_sig| {
+                        LSHIndex::estimate_similarity(signature, leader_sig) >= SIMILAR_THRESHOLD
+                    })
+                });
+            match leader {
+                Some(id) => leaders.push(leader_positions[id]),
+                None => {
+                    lsh.insert_signature(item, signature.clone());
+                    leader_positions.push(pos);
+                    leaders.push(pos);
+                }
+            }
+        }
+        leaders
+    }
+
+    /// Collapse near-duplicate results into their leader, recording how many
+    /// results each remaining one stands for
+    fn collapse_similar(&mut self) {
+        let leaders = self.similar_leaders();
+        let mut keep: Vec<usize> = Vec::new();
+        let mut counts: Vec<usize> = Vec::new();
+        // Leader position -> slot in `counts`
+        let mut slot = vec![0; leaders.len()];
+        for (pos, &leader) in leaders.iter().enumerate() {
+            if leader == pos {
+                slot[pos] = counts.len();
+                keep.push(pos);
+                counts.push(1);
+            } else {
+                counts[slot[leader]] += 1;
+            }
+        }
+
+        if keep.len() < self.filtered_items.len() {
+            retain_positions(&mut self.filtered_items, &keep);
+            retain_positions(&mut self.filtered_indices, &keep);
+            retain_positions(&mut self.match_positions, &keep);
+        }
+        self.similar_counts = counts;
+    }
+
+    /// Reorder results so near-duplicates are contiguous: clusters first,
+    /// ordered by their best-ranked member, then the ungrouped results
+    fn group_similar(&mut self) {
+        let leaders = self.similar_leaders();
+        let mut members: Vec<Vec<usize>> = vec![Vec::new(); leaders.len()];
+        for (pos, &leader) in leaders.iter().enumerate() {
+            members[leader].push(pos);
+        }
+
+        let mut order: Vec<usize> = Vec::with_capacity(leaders.len());
+        let mut groups: Vec<SimilarGroup> = Vec::new();
+        for cluster in members.iter().filter(|m| m.len() > 1) {
+            groups.push(SimilarGroup {
+                start: order.len(),
+                len: cluster.len(),
+                clustered: true,
+            });
+            order.extend(cluster);
+        }
+        let ungrouped_start = order.len();
+        order.extend(members.iter().filter(|m| m.len() == 1).flatten());
+        if order.len() > ungrouped_start {
+            groups.push(SimilarGroup {
+                start: ungrouped_start,
+                len: order.len() - ungrouped_start,
+                clustered: false,
+            });
+        }
+
+        permute(&mut self.filtered_items, &order);
+        permute(&mut self.filtered_indices, &order);
+        permute(&mut self.match_positions, &order);
+        self.similar_groups = groups;
+    }
+
+    /// Group near-duplicate results together (ignored while collapsing them)
+    pub async fn set_group_similar(&mut self, enabled: bool) {
+        self.group_similar = enabled;
+        self.update_filter().await;
+    }
+
+    /// Groups of contiguous filtered results (empty unless grouping)
+    pub fn get_similar_groups(&self) -> &[SimilarGroup] {
+        &self.similar_groups
+    }
+
+    /// Collapse near-duplicate results into one representative each
+    pub async fn set_unique_similar(&mut self, enabled: bool) {
+        self.unique_similar = enabled;
+        self.update_filter().await;
+    }
+
+    /// Number of results represented by the filtered item at `position`
+    /// (1 unless near-duplicates were collapsed into it)
+    pub fn get_similar_count(&self, position: usize) -> usize {
+        self.similar_counts.get(position).copied().unwrap_or(1)
+    }
+
+    /// Pick up a finished background index, index newly added items, and
+    /// start a background build once the item count crosses the threshold
+    async fn sync_index(&mut self) {
+        self.catch_up_index().await;
+        let items = if self.search_texts.is_empty() {
+            self.stream.as_slice()
+        } else {
+            &self.search_texts
+        };
+        if self.index.is_none()
+            && self.index_task.is_none()
+            && self.index_threshold.is_some_and(|t| items.len() >= t)
+        {
+            let snapshot = items.to_vec();
+            self.index_task = Some(tokio::task::spawn_blocking(move || {
+                CandidateIndex::build(&snapshot)
+            }));
+        }
+    }
+
+    /// Pick up a finished background index and index newly added items
+    async fn catch_up_index(&mut self) {
+        if self
+            .index_task
+            .as_ref()
+            .is_some_and(|task| task.is_finished())
+        {
+            if let Some(task) = self.index_task.take() {
+                self.index = task.await.ok();
+            }
+        }
+
+        let items = if self.search_texts.is_empty() {
+            self.stream.as_slice()
+        } else {
+            &self.search_texts
+        };
+        if let Some(index) = &mut self.index {
+            if index.len() < items.len() {
+                index.extend(&items[index.len()..]);
+            }
+        }
+    }
+
+    /// The candidate index over every item so far, e.g. to cache it, or
+    /// `None` if none was built (a background build still running is not
+    /// waited for)
+    pub async fn built_index(&mut self) -> Option<&CandidateIndex> {
+        self.catch_up_index().await;
+        self.index.as_ref()
+    }
+
+    /// Set the item count at which a candidate index is built in the
+    /// background to speed up filtering (`None` disables indexing)
+    pub fn set_index_threshold(&mut self, threshold: Option<usize>) {
+        self.index_threshold = threshold;
+        if threshold.is_none() {
+            if let Some(task) = self.index_task.take() {
+                task.abort();
+            }
+            self.index = None;
+        }
+    }
+
+    /// Whether a candidate index is in use for filtering
+    pub fn is_indexed(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// Token that abandons the running filter pass when cancelled (e.g. from
+    /// an input thread once newer keystrokes arrive). The results then stay
+    /// as they were and [`is_stale`](Self::is_stale) reports true until the
+    /// next [`update_filter`](Self::update_filter). Reset it before reuse.
+    pub fn cancel_token(&self) -> scoring::CancelToken {
+        self.cancel.clone()
+    }
+
+    /// Whether the last filter pass was cancelled, so the results do not
+    /// reflect the current query
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// A "did you mean" correction of the query when it matches nothing:
+    /// each unknown query word replaced by the closest word found in the
+    /// items. `None` while there are results or no close correction exists.
+    ///
+    /// The word vocabulary is built on first use and extended with items
+    /// added later; corrections are remembered per query.
+    pub fn suggestion(&mut self) -> Option<String> {
+        if self.query.trim().is_empty() || !self.filtered_items.is_empty() || self.is_filtering() {
+            return None;
+        }
+        let generation = self.stream.generation();
+        if let Some((query, cached_generation, suggestion)) = &self.suggestion {
+            if *query == self.query && *cached_generation == generation {
+                return suggestion.clone();
+            }
+        }
+        let items = if self.search_texts.is_empty() {
+            self.stream.as_slice()
+        } else {
+            &self.search_texts
+        };
+        let suggester = self.suggester.get_or_insert_with(Suggester::new);
+        if suggester.len() < items.len() {
+            suggester.extend(&items[suggester.len()..]);
+        }
+        let suggestion = suggester
+            .correct(self.query.trim_start_matches('^'))
+            .map(|corrected| match self.query.starts_with('^') {
+                true => format!("^{corrected}"),
+                false => corrected,
+            });
+        self.suggestion = Some((self.query.clone(), generation, suggestion.clone()));
+        suggestion
+    }
+
+    /// Replace the query with its [`suggestion`](Self::suggestion), if any.
+    /// Returns whether the query changed.
+    pub async fn accept_suggestion(&mut self) -> bool {
+        match self.suggestion() {
+            Some(corrected) => {
+                self.set_query(corrected).await;
+                true
+            }
+            None => false,
         }
     }
 
@@ -122,20 +885,277 @@ impl FuzzyFinder {
         self.match_positions.get(index)
     }
 
+    /// Explain the score of the filtered item at `index` for the current
+    /// query. Its weight (if any) counts under [`scoring::ScoreBreakdown::other`].
+    pub fn explain_match(&self, index: usize) -> Option<scoring::ScoreBreakdown> {
+        let original = *self.filtered_indices.get(index)?;
+        let text = self
+            .search_texts
+            .get(original)
+            .or_else(|| self.stream.as_slice().get(original))?;
+        let query = self.query.strip_prefix('^').unwrap_or(&self.query);
+        let mut parts = scoring::score_match_explain_with_config(text, query, &self.scoring)?;
+        let weight = self.weights.get(original).copied().unwrap_or(0);
+        parts.total += weight;
+        parts.other += weight;
+        Some(parts)
+    }
+
     /// Add new items asynchronously
+    ///
+    /// Only the new items are scored; they are merged into the current
+    /// results, keeping the cursor and selections.
     pub async fn add_items(&mut self, new_items: Vec<String>) {
-        self.stream.add_items(new_items).await;
-        // Clear cache when items change
+        let (from, generation) = (self.stream.len(), self.stream.generation());
+        if self.hidden_separator.is_none()
+            && self.weight_field.is_none()
+            && self.search_texts.is_empty()
+        {
+            self.stream.add_items(new_items).await;
+        } else {
+            for line in new_items {
+                let (line, weight) = match self.weight_field {
+                    Some(field) => split_weight_field(&line, field).unwrap_or((line, 0)),
+                    None => (line, 0),
+                };
+                let item = match self.hidden_separator {
+                    Some(separator) => SearchItem::split(line, separator),
+                    None => SearchItem::from(line),
+                };
+                self.push_item(item.with_weight(weight));
+            }
+        }
+        self.select_preselected(from);
+        self.filter_added(from, generation).await;
+    }
+
+    /// Add items carrying hidden search text and weights. The hidden text is
+    /// matched but never displayed, highlighted or returned on selection.
+    pub async fn add_search_items(&mut self, new_items: Vec<SearchItem>) {
+        let (from, generation) = (self.stream.len(), self.stream.generation());
+        for item in new_items {
+            self.push_item(item);
+        }
+        self.select_preselected(from);
+        self.filter_added(from, generation).await;
+    }
+
+    /// Remove every item equal to `item`. Returns whether any was removed.
+    pub async fn remove_item(&mut self, item: &str) -> bool {
+        self.remove_items(&[item]).await > 0
+    }
+
+    /// Remove every item equal to one of `items`, returning how many were
+    /// removed.
+    ///
+    /// Later items move up to fill the gaps, so their indices shift; the
+    /// selections and the cursor follow their items (the cursor moves on to
+    /// the next remaining result if its own was removed). Results are patched
+    /// in place unless removing items could promote others (a full result
+    /// limit) or results are collapsed or grouped, in which case they are
+    /// re-filtered. A candidate index is dropped and rebuilt in the
+    /// background if needed.
+    pub async fn remove_items<S: AsRef<str>>(&mut self, items: &[S]) -> usize {
+        let targets: std::collections::HashSet<&str> = items.iter().map(AsRef::as_ref).collect();
+        let generation = self.stream.generation();
+        let cursor_item = self.filtered_indices.get(self.cursor_position).copied();
+        let removed = self.stream.remove_where(|item| targets.contains(item));
+        if removed.is_empty() {
+            return 0;
+        }
+        // New index of an old one, or `None` if it was removed
+        let remap = |idx: usize| match removed.binary_search(&idx) {
+            Ok(_) => None,
+            Err(shift) => Some(idx - shift),
+        };
+
+        self.selected_items = self
+            .selected_items
+            .iter()
+            .filter_map(|(&i, &n)| remap(i).map(|i| (i, n)))
+            .collect();
+        self.signature_cache = std::mem::take(&mut self.signature_cache)
+            .into_iter()
+            .filter_map(|(i, sig)| remap(i).map(|i| (i, sig)))
+            .collect();
+        drop_positions(&mut self.weights, &removed);
+        drop_positions(&mut self.search_texts, &removed);
+        if let Some(task) = self.index_task.take() {
+            task.abort();
+        }
+        self.index = None;
+        self.prefix_tree = None;
+        self.suggester = None;
+
+        let limited = self
+            .max_results
+            .is_some_and(|max| self.filtered_indices.len() >= max);
+        if self.results_patchable(generation) && !limited && self.filter_pass.is_none() {
+            let mut keep = Vec::new();
+            for (pos, idx) in self.filtered_indices.iter_mut().enumerate() {
+                if let Some(new) = remap(*idx) {
+                    *idx = new;
+                    keep.push(pos);
+                }
+            }
+            retain_positions(&mut self.filtered_items, &keep);
+            retain_positions(&mut self.filtered_indices, &keep);
+            retain_positions(&mut self.match_positions, &keep);
+            self.query_cache.clear();
+            self.cache_generation = self.stream.generation();
+            self.results_generation = self.stream.generation();
+            if !self.query.is_empty() {
+                self.cache_results();
+            }
+            self.cursor_position = keep.partition_point(|&pos| pos < self.cursor_position);
+        } else {
+            self.update_filter().await;
+            if let Some(pos) = cursor_item
+                .and_then(remap)
+                .and_then(|idx| self.filtered_indices.iter().position(|&i| i == idx))
+            {
+                self.cursor_position = pos;
+            }
+        }
+        self.clamp_cursor();
+        removed.len()
+    }
+
+    /// Remove every item, e.g. before loading a fresh copy of the source.
+    /// The query is kept; selections, the cursor and any candidate index
+    /// are reset.
+    pub async fn clear_items(&mut self) {
+        self.stream.clear();
+        self.selected_items.clear();
+        self.signature_cache.clear();
+        self.weights.clear();
+        self.search_texts.clear();
+        if let Some(task) = self.index_task.take() {
+            task.abort();
+        }
+        self.index = None;
+        self.prefix_tree = None;
+        self.suggester = None;
+        self.cursor_position = 0;
+        self.update_filter().await;
+    }
+
+    /// Store one item, tracking its weight and (once hidden text is in use)
+    /// its search text
+    fn push_item(&mut self, item: SearchItem) {
+        if !self.stream.push(item.display) {
+            return;
+        }
+        if item.weight != 0 {
+            self.weights.resize(self.stream.len() - 1, 0);
+            self.weights.push(item.weight);
+        }
+        if item.hidden.is_empty() && self.search_texts.is_empty() {
+            return;
+        }
+        let items = self.stream.as_slice();
+        let last = items.len() - 1;
+        // Items added before the first hidden text are matched as displayed
+        self.search_texts
+            .extend(items[self.search_texts.len()..last].iter().cloned());
+        let display = &items[last];
+        self.search_texts.push(if item.hidden.is_empty() {
+            display.clone()
+        } else {
+            format!("{display} {}", item.hidden)
+        });
+    }
+
+    /// Read each item's weight from tab-separated `field` (1-based) of items
+    /// added afterwards; the field is removed from the displayed text. Items
+    /// whose field is missing or not an integer are kept whole with weight 0.
+    pub fn set_weight_field(&mut self, field: Option<usize>) {
+        self.weight_field = field;
+    }
+
+    /// Split items added afterwards at the first `separator` into displayed
+    /// text and hidden search text (`None` displays items whole)
+    pub fn set_hidden_separator(&mut self, separator: Option<char>) {
+        self.hidden_separator = separator;
+    }
+
+    /// Drop repeated items as they are added (`None` keeps every item).
+    /// Items added earlier are kept.
+    pub fn set_dedup(&mut self, mode: Option<Dedup>) {
+        self.stream.set_dedup(mode);
+    }
+
+    /// Register a map/filter hook applied to every item added afterwards
+    pub fn add_transform(&mut self, transform: ItemTransform) {
+        self.stream.add_transform(transform);
+    }
+
+    /// Set the scoring options and re-rank the current results
+    pub async fn set_scoring_config(&mut self, config: scoring::ScoringConfig) {
+        self.scoring = config;
+        self.query_cache.clear();
+        self.update_filter().await;
+    }
+
+    /// Get the scoring options
+    pub fn get_scoring_config(&self) -> &scoring::ScoringConfig {
+        &self.scoring
+    }
+
+    /// Set the minimum score a match needs to be shown (`None` keeps all matches)
+    pub async fn set_min_score(&mut self, min_score: Option<i32>) {
+        self.min_score = min_score;
+        self.query_cache.clear();
+        self.update_filter().await;
+    }
+
+    /// Get the minimum match score
+    pub fn get_min_score(&self) -> Option<i32> {
+        self.min_score
+    }
+
+    /// Cap the number of ranked matches kept per query (`None` for no limit)
+    pub async fn set_max_results(&mut self, max_results: Option<usize>) {
+        self.max_results = max_results;
+        self.query_cache.clear();
+        self.update_filter().await;
+    }
+
+    /// Get the maximum number of results
+    pub fn get_max_results(&self) -> Option<usize> {
+        self.max_results
+    }
+
+    /// Rank matches by score (the default), or keep them in input order,
+    /// e.g. for lists that are already ordered such as shell history
+    pub async fn set_sort(&mut self, sort: bool) {
+        self.sort = sort;
         self.query_cache.clear();
         self.update_filter().await;
     }
 
-    /// Move cursor up or down (wraps around)
+    /// Whether matches are ranked by score rather than kept in input order
+    pub fn is_sorted(&self) -> bool {
+        self.sort
+    }
+
+    /// Choose whether [`move_cursor`](Self::move_cursor) wraps around the
+    /// ends of the results (the default) or stops at them
+    pub fn set_cycle(&mut self, cycle: bool) {
+        self.cycle = cycle;
+    }
+
+    /// Move cursor up or down (wraps around unless turned off with
+    /// [`set_cycle`](Self::set_cycle))
     pub fn move_cursor(&mut self, direction: i32) {
         let len = self.filtered_items.len();
         if len == 0 {
             return;
         }
+        if !self.cycle {
+            self.move_cursor_clamped(direction);
+            return;
+        }
 
         // Handle large movements by using modulo arithmetic
         let current_pos = self.cursor_position as i32;
@@ -186,36 +1206,249 @@ impl FuzzyFinder {
             return;
         }
 
+        self.selection_capped = false;
         let selected_index = self.filtered_indices[self.cursor_position];
-        if self.selected_items.contains(&selected_index) {
-            self.selected_items.remove(&selected_index);
-        } else {
-            self.selected_items.insert(selected_index);
+        if self.selected_items.remove(&selected_index).is_none() {
+            self.select(selected_index);
+        }
+    }
+
+    /// Select the item at original index `index` unless the selection is
+    /// full, returning whether it is selected
+    fn select(&mut self, index: usize) -> bool {
+        if self.selected_items.contains_key(&index) {
+            return true;
+        }
+        if self
+            .max_selected
+            .is_some_and(|max| self.selected_items.len() >= max)
+        {
+            self.selection_capped = true;
+            return false;
+        }
+        self.selections_made += 1;
+        self.selected_items.insert(index, self.selections_made);
+        true
+    }
+
+    /// Cap how many items can be selected at once (`None` for no limit).
+    /// Items already selected are kept.
+    pub fn set_max_selected(&mut self, max: Option<usize>) {
+        self.max_selected = max;
+    }
+
+    /// Most items that can be selected at once, if limited
+    pub fn get_max_selected(&self) -> Option<usize> {
+        self.max_selected
+    }
+
+    /// Whether the last change to the selection left items unselected
+    /// because [`FuzzyFinder::set_max_selected`]'s limit was reached
+    pub fn is_selection_capped(&self) -> bool {
+        self.selection_capped
+    }
+
+    /// Select every item equal to one of `items`: those added already and
+    /// those added later, e.g. to start from an existing selection set.
+    /// Does nothing unless multi-select is enabled.
+    pub fn preselect<S: Into<String>>(&mut self, items: impl IntoIterator<Item = S>) {
+        if !self.multi_select {
+            return;
+        }
+        self.preselected.extend(items.into_iter().map(Into::into));
+        self.select_preselected(0);
+    }
+
+    /// Select the preselected items stored from index `from` on
+    fn select_preselected(&mut self, from: usize) {
+        if self.preselected.is_empty() {
+            return;
+        }
+        let items = self.stream.as_slice();
+        let matching: Vec<usize> = (from..items.len())
+            .filter(|&i| self.preselected.contains(&items[i]))
+            .collect();
+        for index in matching {
+            if !self.select(index) {
+                break;
+            }
+        }
+    }
+
+    /// Select every item matching the current query, in ranked order up to
+    /// the selection limit
+    pub fn select_all(&mut self) {
+        self.selection_capped = false;
+        for position in 0..self.filtered_indices.len() {
+            if !self.select(self.filtered_indices[position]) {
+                break;
+            }
+        }
+    }
+
+    /// Deselect every item matching the current query; selections hidden
+    /// by the query are kept
+    pub fn deselect_all(&mut self) {
+        self.selection_capped = false;
+        for index in &self.filtered_indices {
+            self.selected_items.remove(index);
+        }
+    }
+
+    /// Toggle the selection of every item matching the current query,
+    /// selecting in ranked order up to the selection limit
+    pub fn toggle_all(&mut self) {
+        self.selection_capped = false;
+        let unselected: Vec<usize> = self
+            .filtered_indices
+            .iter()
+            .copied()
+            .filter(|index| self.selected_items.remove(index).is_none())
+            .collect();
+        for index in unselected {
+            if !self.select(index) {
+                break;
+            }
         }
     }
 
     /// Get selected items
     pub fn get_selected_items(&self) -> Vec<(usize, String)> {
         let all_items = self.stream.get_all_items();
-        let mut selected: Vec<(usize, String)> = self
+        let mut selected: Vec<(usize, u64)> = self
             .selected_items
             .iter()
-            .map(|&idx| (idx, all_items[idx].clone()))
+            .map(|(&idx, &made)| (idx, made))
             .collect();
-        // Sort by index to preserve original order
-        selected.sort_by_key(|k| k.0);
+        if self.selection_order {
+            selected.sort_by_key(|&(_, made)| made);
+        } else {
+            // Sort by index to preserve original order
+            selected.sort_by_key(|&(idx, _)| idx);
+        }
         selected
+            .into_iter()
+            .map(|(idx, _)| (idx, all_items[idx].clone()))
+            .collect()
+    }
+
+    /// Return selections from [`get_selected_items`](Self::get_selected_items)
+    /// in the order they were made instead of in item order
+    pub fn set_selection_order(&mut self, selection_order: bool) {
+        self.selection_order = selection_order;
+    }
+
+    /// Number of selected items, including those hidden by the query
+    pub fn selected_count(&self) -> usize {
+        self.selected_items.len()
+    }
+
+    /// Check if an item is selected by its original index
+    pub fn is_selected(&self, original_index: usize) -> bool {
+        self.selected_items.contains_key(&original_index)
+    }
+
+    /// Set query and update filter, with the editing cursor at its end
+    pub async fn set_query(&mut self, query: String) {
+        self.query_cursor = query.len();
+        self.query = query;
+        self.update_filter().await;
+    }
+
+    /// Insert `c` at the query's editing cursor and update filter
+    pub async fn insert_query_char(&mut self, c: char) {
+        self.query.insert(self.query_cursor, c);
+        self.query_cursor += c.len_utf8();
+        self.update_filter().await;
+    }
+
+    /// Insert pasted `text` at the query's editing cursor as one edit,
+    /// filtering once. Trailing line breaks are dropped and other control
+    /// characters (e.g. inner line breaks) become spaces.
+    pub async fn insert_query_str(&mut self, text: &str) {
+        let text: String = text
+            .trim_end_matches(['\r', '\n'])
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+        if text.is_empty() {
+            return;
+        }
+        self.query.insert_str(self.query_cursor, &text);
+        self.query_cursor += text.len();
+        self.update_filter().await;
+    }
+
+    /// Delete the query character before the editing cursor (Backspace),
+    /// returning whether there was one
+    pub async fn delete_query_char_before(&mut self) -> bool {
+        let Some(c) = self.query[..self.query_cursor].chars().next_back() else {
+            return false;
+        };
+        let start = self.query_cursor - c.len_utf8();
+        self.delete_query_range(start..self.query_cursor).await
+    }
+
+    /// Delete the query character at the editing cursor (Delete),
+    /// returning whether there was one
+    pub async fn delete_query_char_after(&mut self) -> bool {
+        let Some(c) = self.query[self.query_cursor..].chars().next() else {
+            return false;
+        };
+        let end = self.query_cursor + c.len_utf8();
+        self.delete_query_range(self.query_cursor..end).await
+    }
+
+    /// Delete the query bytes in `range` (on character boundaries), keeping
+    /// the editing cursor on the same text, and update filter; returns
+    /// whether anything was deleted
+    pub async fn delete_query_range(&mut self, range: std::ops::Range<usize>) -> bool {
+        if range.is_empty() {
+            return false;
+        }
+        if self.query_cursor >= range.end {
+            self.query_cursor -= range.len();
+        } else if self.query_cursor > range.start {
+            self.query_cursor = range.start;
+        }
+        self.query.replace_range(range, "");
+        self.update_filter().await;
+        true
+    }
+
+    /// Move the query's editing cursor `chars` characters right (or left
+    /// when negative), stopping at either end; returns whether it moved
+    pub fn move_query_cursor(&mut self, chars: isize) -> bool {
+        let before = self.query_cursor;
+        for _ in 0..chars.unsigned_abs() {
+            let step = if chars < 0 {
+                self.query[..self.query_cursor].chars().next_back()
+            } else {
+                self.query[self.query_cursor..].chars().next()
+            };
+            match step {
+                Some(c) if chars < 0 => self.query_cursor -= c.len_utf8(),
+                Some(c) => self.query_cursor += c.len_utf8(),
+                None => break,
+            }
+        }
+        self.query_cursor != before
     }
 
-    /// Check if an item is selected by its original index
-    pub fn is_selected(&self, original_index: usize) -> bool {
-        self.selected_items.contains(&original_index)
+    /// Move the query's editing cursor to byte `offset` (clamped to the
+    /// query's end), returning whether it moved. Offsets inside a
+    /// character are ignored.
+    pub fn set_query_cursor(&mut self, offset: usize) -> bool {
+        let offset = offset.min(self.query.len());
+        if !self.query.is_char_boundary(offset) {
+            return false;
+        }
+        std::mem::replace(&mut self.query_cursor, offset) != offset
     }
 
-    /// Set query and update filter
-    pub async fn set_query(&mut self, query: String) {
-        self.query = query;
-        self.update_filter().await;
+    /// Number of items added (matched or not)
+    pub fn item_count(&self) -> usize {
+        self.stream.len()
     }
 
     /// Get filtered items
@@ -223,11 +1456,63 @@ impl FuzzyFinder {
         &self.filtered_items
     }
 
+    /// Get filtered matches as `(original_index, item, score, positions)` in ranked order.
+    ///
+    /// Positions are character indices into the item (ANSI sequences stripped),
+    /// suitable for highlighting without re-running the matcher.
+    pub fn get_filtered_matches(&self) -> Vec<(usize, &str, i32, &[usize])> {
+        self.filtered_indices
+            .iter()
+            .zip(&self.filtered_items)
+            .zip(&self.match_positions)
+            .map(|((&idx, item), matched)| {
+                (
+                    idx,
+                    item.as_str(),
+                    matched.score,
+                    matched.positions.as_slice(),
+                )
+            })
+            .collect()
+    }
+
+    /// Copy the current items, results and selections into an owned
+    /// [`FinderSnapshot`]
+    pub fn snapshot(&self) -> FinderSnapshot {
+        let mut selected: Vec<usize> = self.selected_items.keys().copied().collect();
+        selected.sort_unstable();
+        FinderSnapshot {
+            query: self.query.clone(),
+            items: self.stream.as_slice().to_vec(),
+            filtered_indices: self.filtered_indices.clone(),
+            match_positions: self.match_positions.clone(),
+            selected,
+            cursor: self.cursor_position,
+            complete: !self.stale && !self.is_filtering(),
+        }
+    }
+
     /// Get the original index for a filtered item at the given position
     pub fn get_original_index(&self, position: usize) -> Option<usize> {
         self.filtered_indices.get(position).cloned()
     }
 
+    /// Hidden search text of the item at `original_index` (see
+    /// [`SearchItem::hidden`]), if it has any
+    pub fn get_hidden_text(&self, original_index: usize) -> Option<&str> {
+        let display = self.stream.as_slice().get(original_index)?;
+        self.search_texts
+            .get(original_index)?
+            .strip_prefix(display.as_str())
+            .map(str::trim_start)
+            .filter(|hidden| !hidden.is_empty())
+    }
+
+    /// Score boost of the item at `original_index` (see [`SearchItem::weight`])
+    pub fn get_weight(&self, original_index: usize) -> i32 {
+        self.weights.get(original_index).copied().unwrap_or(0)
+    }
+
     /// Get cursor position
     pub fn get_cursor_position(&self) -> usize {
         self.cursor_position
@@ -238,6 +1523,11 @@ impl FuzzyFinder {
         &self.query
     }
 
+    /// Byte offset of the editing cursor in the query
+    pub fn get_query_cursor(&self) -> usize {
+        self.query_cursor
+    }
+
     /// Check if multi-select mode is enabled
     pub fn is_multi_select(&self) -> bool {
         self.multi_select
@@ -292,6 +1582,75 @@ mod tests {
         assert!(all_items.len() >= 3);
     }
 
+    #[tokio::test]
+    async fn test_query_cursor_editing() {
+        let items = vec!["café".to_string(), "cafe".to_string()];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("cfé".to_string()).await;
+        assert_eq!(finder.get_query_cursor(), "cfé".len());
+
+        assert!(finder.move_query_cursor(-2));
+        finder.insert_query_char('a').await;
+        assert_eq!(finder.get_query(), "café");
+        assert_eq!(finder.get_query_cursor(), 2);
+        assert_eq!(finder.get_filtered_items(), &["café".to_string()]);
+
+        assert!(finder.delete_query_char_after().await);
+        assert_eq!(finder.get_query(), "caé");
+        assert!(finder.delete_query_char_before().await);
+        assert_eq!(finder.get_query(), "cé");
+        assert_eq!(finder.get_query_cursor(), 1);
+
+        // Stops at either end
+        assert!(finder.move_query_cursor(5));
+        assert_eq!(finder.get_query_cursor(), "cé".len());
+        assert!(!finder.move_query_cursor(1));
+        assert!(!finder.delete_query_char_after().await);
+        assert!(finder.set_query_cursor(0));
+        assert!(!finder.set_query_cursor(0));
+        assert!(!finder.delete_query_char_before().await);
+        // Inside `é`
+        assert!(!finder.set_query_cursor(2));
+        assert!(finder.set_query_cursor(usize::MAX));
+        assert_eq!(finder.get_query_cursor(), "cé".len());
+    }
+
+    #[tokio::test]
+    async fn test_insert_query_str() {
+        let items = vec!["src/main.rs".to_string(), "src/lib.rs".to_string()];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("src/.rs".to_string()).await;
+        assert!(finder.set_query_cursor(4));
+        finder.insert_query_str("main\n").await;
+        assert_eq!(finder.get_query(), "src/main.rs");
+        assert_eq!(finder.get_query_cursor(), "src/main".len());
+        assert_eq!(finder.get_filtered_items(), &["src/main.rs".to_string()]);
+
+        finder.set_query(String::new()).await;
+        finder.insert_query_str("a\tb\r\nc\r\n").await;
+        assert_eq!(finder.get_query(), "a b  c");
+        finder.insert_query_str("\n").await;
+        assert_eq!(finder.get_query(), "a b  c");
+    }
+
+    #[tokio::test]
+    async fn test_delete_query_range_keeps_cursor_on_text() {
+        let mut finder = FuzzyFinder::with_items_async(vec!["a".to_string()], false).await;
+        finder.set_query("one two three".to_string()).await;
+        finder.set_query_cursor(8);
+        assert!(finder.delete_query_range(0..4).await);
+        assert_eq!(
+            (finder.get_query(), finder.get_query_cursor()),
+            ("two three", 4)
+        );
+        assert!(finder.delete_query_range(2..6).await);
+        assert_eq!(
+            (finder.get_query(), finder.get_query_cursor()),
+            ("twree", 2)
+        );
+        assert!(!finder.delete_query_range(3..3).await);
+    }
+
     #[tokio::test]
     async fn test_move_cursor_clamped_does_not_wrap() {
         let items = vec![
@@ -328,4 +1687,762 @@ mod tests {
         assert!(!finder.move_cursor_clamped(-1));
         assert_eq!(finder.get_cursor_position(), 0); // Still at 0
     }
+
+    #[tokio::test]
+    async fn test_move_cursor_without_cycle() {
+        let items = vec!["apple".to_string(), "banana".to_string()];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.move_cursor(-1);
+        assert_eq!(finder.get_cursor_position(), 1);
+
+        finder.set_cycle(false);
+        finder.move_cursor(1);
+        assert_eq!(finder.get_cursor_position(), 1);
+        finder.move_cursor(-5);
+        assert_eq!(finder.get_cursor_position(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_set_scoring_config_reranks() {
+        let items = vec![
+            "main_helpers/util.rs".to_string(),
+            "src/main.rs".to_string(),
+        ];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("main".to_string()).await;
+        assert_eq!(finder.get_filtered_items()[0], "main_helpers/util.rs");
+
+        finder
+            .set_scoring_config(scoring::ScoringConfig {
+                scheme: scoring::Scheme::Path,
+                ..Default::default()
+            })
+            .await;
+        assert_eq!(finder.get_filtered_items()[0], "src/main.rs");
+    }
+
+    #[tokio::test]
+    async fn test_min_score_drops_weak_matches() {
+        let items = vec!["config".to_string(), "c_o_n_f_i_g_scattered".to_string()];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("config".to_string()).await;
+        assert_eq!(finder.get_filtered_items().len(), 2);
+
+        finder.set_min_score(Some(1000)).await;
+        assert_eq!(finder.get_filtered_items(), ["config"]);
+
+        // An empty query still lists everything
+        finder.set_query(String::new()).await;
+        assert_eq!(finder.get_filtered_items().len(), 2);
+
+        finder.set_min_score(None).await;
+        finder.set_query("config".to_string()).await;
+        assert_eq!(finder.get_filtered_items().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_max_results_caps_filtered_items() {
+        let items: Vec<String> = (0..100).map(|i| format!("line {i}")).collect();
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+
+        finder.set_max_results(Some(10)).await;
+        assert_eq!(finder.get_filtered_items().len(), 10);
+
+        finder.set_query("line 4".to_string()).await;
+        assert_eq!(finder.get_filtered_items().len(), 10);
+        assert_eq!(finder.get_filtered_items()[0], "line 4");
+
+        finder.set_max_results(None).await;
+        assert!(finder.get_filtered_items().len() > 10);
+    }
+
+    #[tokio::test]
+    async fn test_get_filtered_matches() {
+        let items = vec![
+            "banana".to_string(),
+            "apple".to_string(),
+            "grape".to_string(),
+        ];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("ap".to_string()).await;
+
+        let matches = finder.get_filtered_matches();
+        assert_eq!(matches.len(), 2);
+        let (idx, item, score, positions) = matches[0];
+        assert_eq!((idx, item), (1, "apple"));
+        assert_eq!(positions, [0, 1]);
+        assert!(score > matches[1].2);
+        assert_eq!((matches[1].0, matches[1].1), (2, "grape"));
+        assert_eq!(matches[1].3, [2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_hidden_search_text() {
+        let mut finder = FuzzyFinder::with_items_async(vec!["notes.md".to_string()], false).await;
+        finder
+            .add_search_items(vec![
+                SearchItem::new("main.rs", "entry point binary"),
+                SearchItem::new("lib.rs", "library root"),
+            ])
+            .await;
+        finder.set_hidden_separator(Some('\t'));
+        finder
+            .add_items(vec!["todo.txt\ttasks notes".to_string()])
+            .await;
+
+        // Hidden text is matched but not displayed or highlighted
+        finder.set_query("binary".to_string()).await;
+        assert_eq!(finder.get_filtered_items(), &["main.rs"]);
+        assert!(finder.get_match_positions(0).unwrap().positions.is_empty());
+
+        finder.set_query("tasks".to_string()).await;
+        assert_eq!(finder.get_filtered_items(), &["todo.txt"]);
+
+        // Matches in the displayed part keep their highlights
+        finder.set_query("notes".to_string()).await;
+        assert_eq!(finder.get_filtered_items(), &["notes.md", "todo.txt"]);
+        assert_eq!(
+            finder.get_match_positions(0).unwrap().positions,
+            vec![0, 1, 2, 3, 4]
+        );
+
+        finder.toggle_selection();
+        assert_eq!(
+            finder.get_selected_items(),
+            vec![(0, "notes.md".to_string())]
+        );
+    }
+
+    /// Results a fresh finder computes for `query` over `items`
+    async fn fresh_results(items: &[String], query: &str) -> Vec<String> {
+        let mut finder = FuzzyFinder::with_items_async(items.to_vec(), false).await;
+        finder.set_query(query.to_string()).await;
+        finder.get_filtered_items().to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_selection_follows_item_identity() {
+        let items: Vec<String> = ["foo", "bar", "foo"].map(String::from).to_vec();
+        let mut finder = FuzzyFinder::with_items_async(items, true).await;
+        // Select the second "foo" only
+        finder.cursor_position = 2;
+        finder.toggle_selection();
+        assert!(!finder.is_selected(0));
+
+        // Hidden by the query while more copies stream in
+        finder.set_query("ba".to_string()).await;
+        finder
+            .add_items(["foo", "baz", "foo"].map(String::from).to_vec())
+            .await;
+        finder.set_query("foo".to_string()).await;
+
+        let marked: Vec<bool> = (0..finder.get_filtered_items().len())
+            .map(|p| finder.is_selected(finder.get_original_index(p).unwrap()))
+            .collect();
+        assert_eq!(marked.iter().filter(|&&m| m).count(), 1);
+        assert_eq!(finder.get_selected_items(), vec![(2, "foo".to_string())]);
+
+        // Toggling the first copy leaves the second one selected
+        finder.cursor_position = (0..finder.get_filtered_items().len())
+            .find(|&p| finder.get_original_index(p) == Some(0))
+            .unwrap();
+        finder.toggle_selection();
+        assert_eq!(
+            finder.get_selected_items(),
+            vec![(0, "foo".to_string()), (2, "foo".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_selection_order() {
+        let items: Vec<String> = ["a", "b", "c"].map(String::from).to_vec();
+        let mut finder = FuzzyFinder::with_items_async(items, true).await;
+        for position in [2, 0, 1] {
+            finder.cursor_position = position;
+            finder.toggle_selection();
+        }
+        // Deselecting and selecting again moves an item to the end
+        finder.cursor_position = 2;
+        finder.toggle_selection();
+        finder.toggle_selection();
+        assert_eq!(finder.get_selected_items().len(), 3);
+        assert_eq!(finder.get_selected_items()[0], (0, "a".to_string()));
+
+        finder.set_selection_order(true);
+        let order: Vec<usize> = finder
+            .get_selected_items()
+            .into_iter()
+            .map(|(idx, _)| idx)
+            .collect();
+        assert_eq!(order, vec![0, 1, 2]);
+        finder.cursor_position = 0;
+        finder.toggle_selection();
+        finder.toggle_selection();
+        let order: Vec<usize> = finder
+            .get_selected_items()
+            .into_iter()
+            .map(|(idx, _)| idx)
+            .collect();
+        assert_eq!(order, vec![1, 2, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_preselect_existing_and_streamed_items() {
+        let items: Vec<String> = ["foo", "bar"].map(String::from).to_vec();
+        let mut finder = FuzzyFinder::with_items_async(items, true).await;
+        finder.preselect(["bar", "qux"]);
+        assert_eq!(finder.get_selected_items(), vec![(1, "bar".to_string())]);
+
+        finder
+            .add_items(["qux", "baz"].map(String::from).to_vec())
+            .await;
+        assert_eq!(
+            finder.get_selected_items(),
+            vec![(1, "bar".to_string()), (2, "qux".to_string())]
+        );
+
+        let mut single = FuzzyFinder::with_items_async(vec!["foo".to_string()], false).await;
+        single.preselect(["foo"]);
+        assert!(single.get_selected_items().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unsorted_keeps_input_order() {
+        let items: Vec<String> = ["xaxbxc", "abc", "zabc", "ab_c"].map(String::from).to_vec();
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("abc".to_string()).await;
+        assert_eq!(finder.get_filtered_items()[0], "abc");
+
+        finder.set_sort(false).await;
+        assert_eq!(
+            finder.get_filtered_items(),
+            ["xaxbxc", "abc", "zabc", "ab_c"]
+        );
+        // Streamed items merge in by position, and the limit keeps the
+        // first matches
+        finder.add_items(vec!["abc!".to_string()]).await;
+        assert_eq!(finder.get_filtered_items().last().unwrap(), "abc!");
+        finder.set_max_results(Some(2)).await;
+        assert_eq!(finder.get_filtered_items(), ["xaxbxc", "abc"]);
+
+        finder.set_max_results(None).await;
+        finder.set_sort(true).await;
+        assert_eq!(finder.get_filtered_items()[0], "abc");
+    }
+
+    #[tokio::test]
+    async fn test_max_selected() {
+        let items: Vec<String> = ["a", "b", "c"].map(String::from).to_vec();
+        let mut finder = FuzzyFinder::with_items_async(items, true).await;
+        finder.set_max_selected(Some(2));
+
+        finder.select_all();
+        assert_eq!(finder.get_selected_items().len(), 2);
+        assert!(finder.is_selection_capped());
+
+        // The third item can't be toggled on until another is toggled off
+        finder.cursor_position = 2;
+        finder.toggle_selection();
+        assert!(!finder.is_selected(2));
+        assert!(finder.is_selection_capped());
+        finder.cursor_position = 0;
+        finder.toggle_selection();
+        assert!(!finder.is_selection_capped());
+        finder.cursor_position = 2;
+        finder.toggle_selection();
+        assert!(finder.is_selected(2));
+
+        // Toggling all frees both slots before filling them again
+        finder.toggle_all();
+        assert_eq!(finder.get_selected_items(), vec![(0, "a".to_string())]);
+        assert!(!finder.is_selection_capped());
+    }
+
+    #[tokio::test]
+    async fn test_incremental_add_matches_full_filter() {
+        let items: Vec<String> = (0..60)
+            .map(|i| format!("src/mod_{i}/file_{}.rs", i % 7))
+            .collect();
+        for query in ["", "f3", "mod_1", "^src/mod_2"] {
+            let mut finder = FuzzyFinder::new(false);
+            finder.set_query(query.to_string()).await;
+            for chunk in items.chunks(13) {
+                finder.add_items(chunk.to_vec()).await;
+            }
+            assert_eq!(
+                finder.get_filtered_items(),
+                fresh_results(&items, query).await,
+                "{query}"
+            );
+        }
+
+        let mut limited = FuzzyFinder::new(false);
+        limited.max_results = Some(5);
+        limited.set_query("file_2".to_string()).await;
+        for chunk in items.chunks(9) {
+            limited.add_items(chunk.to_vec()).await;
+        }
+        let full = fresh_results(&items, "file_2").await;
+        assert_eq!(limited.get_filtered_items(), &full[..5]);
+    }
+
+    #[tokio::test]
+    async fn test_clear_items() {
+        let items: Vec<String> = ["apple", "banana"].map(String::from).to_vec();
+        let mut finder = FuzzyFinder::with_items_async(items, true).await;
+        finder.set_query("an".to_string()).await;
+        finder.toggle_selection();
+
+        finder.clear_items().await;
+        assert_eq!(finder.item_count(), 0);
+        assert!(finder.get_filtered_items().is_empty());
+        assert!(finder.get_selected_items().is_empty());
+        assert_eq!(finder.get_query(), "an");
+
+        finder
+            .add_items(vec!["mango".to_string(), "kiwi".to_string()])
+            .await;
+        assert_eq!(finder.get_filtered_items(), &["mango".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_remove_items() {
+        let items: Vec<String> = ["apple", "banana", "apricot", "cherry", "avocado"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut finder = FuzzyFinder::with_items_async(items, true).await;
+        finder.set_query("a".to_string()).await;
+        // Select "apricot" and "avocado"
+        for name in ["apricot", "avocado"] {
+            finder.cursor_position = finder
+                .get_filtered_items()
+                .iter()
+                .position(|i| i == name)
+                .unwrap();
+            finder.toggle_selection();
+        }
+
+        assert_eq!(
+            finder.remove_items(&["banana", "apple", "missing"]).await,
+            2
+        );
+        assert!(!finder.remove_item("banana").await);
+        assert!(finder
+            .get_filtered_items()
+            .iter()
+            .all(|i| i != "apple" && i != "banana"));
+        assert_eq!(
+            finder.get_selected_items(),
+            vec![(0, "apricot".to_string()), (2, "avocado".to_string())]
+        );
+        assert_eq!(
+            finder.get_filtered_items(),
+            fresh_results(&["apricot", "cherry", "avocado"].map(String::from), "a").await
+        );
+
+        // Indices of the patched results point at the right items
+        finder.set_query(String::new()).await;
+        assert_eq!(finder.filtered_indices, vec![0, 1, 2]);
+        finder.add_items(vec!["apple".to_string()]).await;
+        assert_eq!(finder.get_filtered_items().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_remove_items_keeps_cursor_on_its_item() {
+        let items: Vec<String> = ["a1", "a2", "a3", "a4", "a5"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        for max_results in [None, Some(5)] {
+            let mut finder = FuzzyFinder::with_items_async(items.clone(), false).await;
+            finder.max_results = max_results;
+            finder.cursor_position = 3;
+            // Rows above the cursor: it stays on "a4"
+            finder.remove_items(&["a1", "a2"]).await;
+            assert_eq!(finder.get_cursor_position(), 1);
+            assert_eq!(finder.get_filtered_items()[1], "a4");
+            // Its own row: it moves on to the next one
+            finder.remove_item("a4").await;
+            assert_eq!(
+                finder.get_filtered_items()[finder.get_cursor_position()],
+                "a5"
+            );
+            // The last rows: it stays within the results
+            finder.remove_items(&["a5"]).await;
+            assert_eq!(finder.get_cursor_position(), 0);
+            assert_eq!(finder.get_filtered_items(), &["a3"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_cache_invalidated_by_new_items() {
+        let items = vec!["apple".to_string(), "grape".to_string()];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("ap".to_string()).await;
+        finder.set_query("a".to_string()).await;
+        assert!(finder.query_cache.contains_key("ap"));
+
+        // Items added straight to the stream bypass `add_items`
+        finder.stream.try_send("apricot".to_string()).unwrap();
+        finder.set_query("ap".to_string()).await;
+        assert_eq!(finder.get_filtered_items(), &["apple", "apricot", "grape"]);
+
+        // Adding only duplicates leaves the items, and the cache, unchanged
+        finder.set_dedup(Some(Dedup::Exact));
+        finder.add_items(vec!["apple".to_string()]).await;
+        assert!(finder.query_cache.contains_key("ap"));
+    }
+
+    #[tokio::test]
+    async fn test_progressive_filter_matches_full_filter() {
+        let items: Vec<String> = (0..100)
+            .map(|i| format!("src/mod_{i}/file_{}.rs", i % 7))
+            .collect();
+        for max_results in [None, Some(4)] {
+            let mut finder = FuzzyFinder::with_items_async(items.clone(), false).await;
+            finder.max_results = max_results;
+            finder.set_filter_chunk(Some(30));
+            finder.set_query("file_3".to_string()).await;
+            assert!(finder.is_filtering());
+            // 100 items in chunks of 30: three more chunks after the first
+            let mut chunks = 1;
+            loop {
+                chunks += 1;
+                if !finder.filter_next_chunk().await {
+                    break;
+                }
+            }
+            assert_eq!(chunks, 4);
+            assert!(!finder.is_filtering());
+
+            let mut full = FuzzyFinder::with_items_async(items.clone(), false).await;
+            full.max_results = max_results;
+            full.set_query("file_3".to_string()).await;
+            assert_eq!(finder.get_filtered_items(), full.get_filtered_items());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_progressive_filter_superseded_by_new_query() {
+        let items: Vec<String> = (0..100).map(|i| format!("item_{i}")).collect();
+        let mut finder = FuzzyFinder::with_items_async(items.clone(), false).await;
+        finder.set_filter_chunk(Some(40));
+        finder.set_query("item_9".to_string()).await;
+        assert!(finder.is_filtering());
+        // Only the first chunk is scored so far
+        assert_eq!(
+            finder.get_filtered_items(),
+            &["item_9", "item_19", "item_29", "item_39"]
+        );
+
+        finder.set_query("item_5".to_string()).await;
+        while finder.filter_next_chunk().await {}
+        assert_eq!(
+            finder.get_filtered_items(),
+            fresh_results(&items, "item_5").await
+        );
+
+        // Completed passes are cached
+        finder.set_query("item_9".to_string()).await;
+        finder.set_query("item_5".to_string()).await;
+        assert!(!finder.is_filtering());
+
+        // Items added mid-pass are merged in
+        finder.set_query("item_9".to_string()).await;
+        finder.add_items(vec!["item_9x".to_string()]).await;
+        while finder.filter_next_chunk().await {}
+        assert!(finder.get_filtered_items().contains(&"item_9x".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot() {
+        let items: Vec<String> = ["apple", "banana", "apricot"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut finder = FuzzyFinder::with_items_async(items, true).await;
+        finder.set_query("ap".to_string()).await;
+        finder.move_cursor(1);
+        finder.toggle_selection();
+
+        let snapshot = finder.snapshot();
+        let handle = std::thread::spawn(move || snapshot);
+        finder.set_query("ban".to_string()).await;
+        let snapshot = handle.join().unwrap();
+
+        assert_eq!(snapshot.query, "ap");
+        assert!(snapshot.complete);
+        assert_eq!(
+            snapshot.filtered_items().collect::<Vec<_>>(),
+            vec![(0, "apple"), (2, "apricot")]
+        );
+        assert_eq!(snapshot.current_item(), Some((2, "apricot")));
+        assert_eq!(snapshot.selected_items(), vec![(2, "apricot")]);
+        assert_eq!(snapshot.match_positions[0].positions, vec![0, 1]);
+        assert_eq!(finder.snapshot().filtered_indices, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_suggestion_on_zero_results() {
+        let items: Vec<String> = ["src/parser.rs", "src/lexer.rs", "docs/guide.md"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("parser".to_string()).await;
+        assert_eq!(finder.suggestion(), None);
+
+        finder.set_query("gudie".to_string()).await;
+        assert!(finder.get_filtered_items().is_empty());
+        assert_eq!(finder.suggestion().as_deref(), Some("guide"));
+
+        // Items added later join the vocabulary
+        finder.set_query("tokenzier".to_string()).await;
+        assert_eq!(finder.suggestion(), None);
+        finder.add_items(vec!["src/tokenizer.rs".to_string()]).await;
+        finder.set_query("tokenzier".to_string()).await;
+        assert!(finder.accept_suggestion().await);
+        assert_eq!(finder.get_query(), "tokenizer");
+        assert_eq!(finder.get_filtered_items(), &["src/tokenizer.rs"]);
+    }
+
+    #[test]
+    fn test_split_weight_field() {
+        assert_eq!(
+            split_weight_field("3\tsrc/main.rs", 1),
+            Some(("src/main.rs".to_string(), 3))
+        );
+        assert_eq!(
+            split_weight_field("a\t-2\tb", 2),
+            Some(("a\tb".to_string(), -2))
+        );
+        assert_eq!(split_weight_field("x\tsrc", 1), None);
+        assert_eq!(split_weight_field("src", 2), None);
+        assert_eq!(split_weight_field("3\tsrc", 0), None);
+    }
+
+    #[tokio::test]
+    async fn test_weight_field_biases_ranking() {
+        let mut finder = FuzzyFinder::new(false);
+        finder.set_weight_field(Some(1));
+        finder
+            .add_items(vec![
+                "0\tsrc/lib/parser.rs".to_string(),
+                "200\tdocs/parser.rs".to_string(),
+                "src/parse.rs".to_string(),
+            ])
+            .await;
+        assert_eq!(
+            finder.get_filtered_items(),
+            &["src/lib/parser.rs", "docs/parser.rs", "src/parse.rs"]
+        );
+
+        finder.set_query("parser".to_string()).await;
+        assert_eq!(
+            finder.get_filtered_items(),
+            &["docs/parser.rs", "src/lib/parser.rs", "src/parse.rs"]
+        );
+        assert_eq!(finder.weights, vec![0, 200]);
+    }
+
+    #[tokio::test]
+    async fn test_hidden_text_and_weight() {
+        let mut finder = FuzzyFinder::new(false);
+        finder
+            .add_search_items(vec![
+                SearchItem::from("plain".to_string()),
+                SearchItem::new("shown", "tags here").with_weight(5),
+            ])
+            .await;
+        assert_eq!(finder.get_hidden_text(0), None);
+        assert_eq!(finder.get_hidden_text(1), Some("tags here"));
+        assert_eq!(finder.get_hidden_text(2), None);
+        assert_eq!((finder.get_weight(0), finder.get_weight(1)), (0, 5));
+    }
+
+    #[tokio::test]
+    async fn test_explain_match_agrees_with_score() {
+        let mut finder = FuzzyFinder::new(false);
+        finder.set_weight_field(Some(1));
+        finder
+            .add_items(vec![
+                "200\tdocs/parser.rs".to_string(),
+                "src/parse.rs".to_string(),
+            ])
+            .await;
+        finder.set_query("prs".to_string()).await;
+        for index in 0..finder.get_filtered_items().len() {
+            let parts = finder.explain_match(index).unwrap();
+            let score = finder.get_match_positions(index).unwrap().score;
+            assert_eq!(parts.total, score);
+        }
+        assert!(finder.explain_match(5).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_background_index_matches_full_scan() {
+        let items: Vec<String> = (0..300)
+            .map(|i| format!("src/mod_{i}/file_{}.rs", i * 3))
+            .collect();
+        let mut plain = FuzzyFinder::with_items_async(items.clone(), false).await;
+        let mut indexed = FuzzyFinder::new(false);
+        indexed.set_index_threshold(Some(100));
+        indexed.add_items(items).await;
+
+        // The first query starts the background build
+        indexed.set_query("m1f".to_string()).await;
+        while let Some(task) = &indexed.index_task {
+            if task.is_finished() {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        let built = indexed.built_index().await;
+        assert_eq!(built.map(CandidateIndex::len), Some(300));
+
+        // Later items are indexed incrementally
+        let extra = vec!["zz/mod_1/f.rs".to_string()];
+        indexed.add_items(extra.clone()).await;
+        plain.add_items(extra).await;
+        assert!(indexed.is_indexed());
+        assert_eq!(indexed.index.as_ref().unwrap().len(), 301);
+
+        for query in ["m1f", "file_9", "zz", "nomatch"] {
+            plain.set_query(query.to_string()).await;
+            indexed.set_query(query.to_string()).await;
+            assert_eq!(plain.get_filtered_items(), indexed.get_filtered_items());
+        }
+
+        indexed.set_index_threshold(None);
+        assert!(!indexed.is_indexed());
+    }
+
+    #[tokio::test]
+    async fn test_prebuilt_index_ahead_of_stream() {
+        let all: Vec<String> = ["alpha", "beta", "gamma", "delta"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut finder = FuzzyFinder::new(false);
+        finder.index = Some(CandidateIndex::build(&all));
+        finder.add_items(all[..2].to_vec()).await;
+
+        finder.set_query("a".to_string()).await;
+        assert_eq!(finder.get_filtered_items(), &["alpha", "beta"]);
+
+        finder.add_items(all[2..].to_vec()).await;
+        finder.set_query("ta".to_string()).await;
+        assert_eq!(finder.get_filtered_items(), &["beta", "delta"]);
+        assert_eq!(finder.index.as_ref().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_filter_keeps_previous_results() {
+        let items = vec!["apple".to_string(), "banana".to_string()];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("ap".to_string()).await;
+        assert_eq!(finder.get_filtered_items(), &["apple"]);
+
+        let cancel = finder.cancel_token();
+        cancel.cancel();
+        finder.set_query("ban".to_string()).await;
+        assert!(finder.is_stale());
+        assert_eq!(finder.get_query(), "ban");
+        assert_eq!(finder.get_filtered_items(), &["apple"]);
+
+        cancel.reset();
+        finder.update_filter().await;
+        assert!(!finder.is_stale());
+        assert_eq!(finder.get_filtered_items(), &["banana"]);
+    }
+
+    #[tokio::test]
+    async fn test_anchored_query_matches_prefix_only() {
+        let items = vec![
+            "lib/src/main.rs".to_string(),
+            "src/main.rs".to_string(),
+            "Src/lib.rs".to_string(),
+            "".to_string(),
+        ];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("^src".to_string()).await;
+        assert_eq!(finder.get_filtered_items(), &["src/main.rs", "Src/lib.rs"]);
+        assert_eq!(
+            finder.get_match_positions(0).unwrap().positions,
+            vec![0, 1, 2]
+        );
+
+        // Items added later are inserted into the trie
+        finder.add_items(vec!["src/new.rs".to_string()]).await;
+        finder.set_query("^src/n".to_string()).await;
+        assert_eq!(finder.get_filtered_items(), &["src/new.rs"]);
+
+        // A bare ^ keeps every non-empty item
+        finder.set_query("^".to_string()).await;
+        assert_eq!(finder.get_filtered_items().len(), 4);
+        finder.set_query("^main".to_string()).await;
+        assert!(finder.get_filtered_items().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unique_similar_collapses_near_duplicates() {
+        let items = vec![
+            "12:00:01 ERROR connection refused to db-primary".to_string(),
+            "user alice logged in".to_string(),
+            "12:00:05 ERROR connection refused to db-primary".to_string(),
+            "12:00:09 ERROR connection refused to db-primary".to_string(),
+        ];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_unique_similar(true).await;
+        assert_eq!(finder.get_filtered_items().len(), 2);
+        assert_eq!(finder.get_original_index(0), Some(0));
+        assert_eq!(finder.get_similar_count(0), 3);
+        assert_eq!(finder.get_similar_count(1), 1);
+
+        finder.set_query("error".to_string()).await;
+        assert_eq!(finder.get_filtered_items().len(), 1);
+        assert_eq!(finder.get_similar_count(0), 3);
+        assert_eq!(finder.get_match_positions(0).unwrap().positions.len(), 5);
+
+        finder.set_unique_similar(false).await;
+        assert_eq!(finder.get_filtered_items().len(), 3);
+        assert_eq!(finder.get_similar_count(0), 1);
+    }
+
+    #[tokio::test]
+    async fn test_group_similar_orders_clusters_first() {
+        let items = vec![
+            "alpha unique entry".to_string(),
+            "GET /api/users/17 200 12ms".to_string(),
+            "zeta something else".to_string(),
+            "GET /api/users/18 200 13ms".to_string(),
+        ];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_group_similar(true).await;
+
+        let order: Vec<usize> = (0..4)
+            .filter_map(|p| finder.get_original_index(p))
+            .collect();
+        assert_eq!(order, vec![1, 3, 0, 2]);
+        assert_eq!(
+            finder.get_similar_groups(),
+            [
+                SimilarGroup {
+                    start: 0,
+                    len: 2,
+                    clustered: true
+                },
+                SimilarGroup {
+                    start: 2,
+                    len: 2,
+                    clustered: false
+                },
+            ]
+        );
+
+        finder.set_group_similar(false).await;
+        assert!(finder.get_similar_groups().is_empty());
+        assert_eq!(finder.get_original_index(0), Some(0));
+    }
 }
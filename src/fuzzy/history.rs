@@ -0,0 +1,223 @@
+//! Persistent selection history used for frecency ranking.
+//!
+//! Accepted items are recorded with a use count and the time they were last
+//! selected. When ranking, each item gets a bonus that grows with how often it
+//! was picked and decays with how long ago that was.
+//!
+//! The store is a plain text file with one `count<TAB>last_used<TAB>item` line
+//! per entry, by default at `$XDG_DATA_HOME/ff/history` (falling back to
+//! `~/.local/share/ff/history`).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Frecency halves every this many seconds (one week)
+const HALF_LIFE_SECS: f64 = 7.0 * 24.0 * 60.0 * 60.0;
+/// Score bonus per unit of frecency
+const BOOST_PER_USE: f64 = 20.0;
+/// Upper bound on the bonus so history never outweighs match quality
+const MAX_BOOST: i32 = 200;
+/// Maximum number of entries kept on disk (least frecent are dropped)
+const MAX_ENTRIES: usize = 1000;
+
+/// Usage record for a single item
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HistoryEntry {
+    /// Number of times the item was selected
+    pub count: u32,
+    /// Unix timestamp (seconds) of the last selection
+    pub last_used: u64,
+}
+
+/// Selection history keyed by item text
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct History {
+    entries: HashMap<String, HistoryEntry>,
+}
+
+/// Current Unix time in seconds
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl History {
+    /// Create an empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Default location of the history file, if a home directory can be found
+    pub fn default_path() -> Option<PathBuf> {
+        let data_dir = match std::env::var_os("XDG_DATA_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+        };
+        Some(data_dir.join("ff").join("history"))
+    }
+
+    /// Load history from `path`. A missing file yields an empty history;
+    /// malformed lines are skipped.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut entries = HashMap::new();
+        for line in content.lines() {
+            let mut fields = line.splitn(3, '\t');
+            let (Some(count), Some(last_used), Some(item)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let (Ok(count), Ok(last_used)) = (count.parse(), last_used.parse()) else {
+                continue;
+            };
+            entries.insert(item.to_string(), HistoryEntry { count, last_used });
+        }
+        Ok(Self { entries })
+    }
+
+    /// Write history to `path`, creating parent directories as needed
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let now = now_secs();
+        let mut entries: Vec<(&String, &HistoryEntry)> = self.entries.iter().collect();
+        entries.sort_by(|a, b| {
+            frecency(b.1, now)
+                .total_cmp(&frecency(a.1, now))
+                .then_with(|| a.0.cmp(b.0))
+        });
+        entries.truncate(MAX_ENTRIES);
+
+        let mut content = String::new();
+        for (item, entry) in entries {
+            content.push_str(&format!("{}\t{}\t{}\n", entry.count, entry.last_used, item));
+        }
+        std::fs::write(path, content)
+    }
+
+    /// Record that `items` were selected at time `now` (Unix seconds)
+    pub fn record<I, S>(&mut self, items: I, now: u64)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for item in items {
+            let item = item.as_ref();
+            // Entries are stored one per line
+            if item.is_empty() || item.contains('\n') {
+                continue;
+            }
+            let entry = self.entries.entry(item.to_string()).or_default();
+            entry.count = entry.count.saturating_add(1);
+            entry.last_used = now;
+        }
+    }
+
+    /// Get the usage record for an item
+    pub fn get(&self, item: &str) -> Option<&HistoryEntry> {
+        self.entries.get(item)
+    }
+
+    /// Number of items in the history
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the history is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Ranking bonus for `item` at time `now` (0 if never selected)
+    pub fn boost(&self, item: &str, now: u64) -> i32 {
+        self.entries.get(item).map_or(0, |entry| {
+            ((frecency(entry, now) * BOOST_PER_USE) as i32).min(MAX_BOOST)
+        })
+    }
+}
+
+/// Use count weighted by exponential decay since the last use
+fn frecency(entry: &HistoryEntry, now: u64) -> f64 {
+    let age = now.saturating_sub(entry.last_used) as f64;
+    entry.count as f64 * 0.5f64.powf(age / HALF_LIFE_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    #[test]
+    fn test_unknown_item_has_no_boost() {
+        let history = History::new();
+        assert_eq!(history.boost("anything", 0), 0);
+    }
+
+    #[test]
+    fn test_frequent_items_boost_more() {
+        let mut history = History::new();
+        history.record(["once"], 1000);
+        history.record(["often", "often", "often"], 1000);
+        assert!(history.boost("often", 1000) > history.boost("once", 1000));
+        assert_eq!(history.get("often").unwrap().count, 3);
+    }
+
+    #[test]
+    fn test_boost_decays_with_age() {
+        let mut history = History::new();
+        history.record(["item"], 0);
+        let fresh = history.boost("item", 0);
+        let week_old = history.boost("item", 7 * DAY);
+        assert_eq!(week_old * 2, fresh);
+        assert!(history.boost("item", 365 * DAY) < week_old);
+    }
+
+    #[test]
+    fn test_boost_is_capped() {
+        let mut history = History::new();
+        history.record(std::iter::repeat_n("item", 1000), 0);
+        assert_eq!(history.boost("item", 0), MAX_BOOST);
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("history");
+
+        let mut history = History::new();
+        history.record(["src/main.rs", "with\ttab"], 42);
+        history.record(["src/main.rs"], 100);
+        history.record(["multi\nline"], 100);
+        history.save(&path).unwrap();
+
+        let loaded = History::load(&path).unwrap();
+        assert_eq!(loaded, history);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(
+            loaded.get("src/main.rs"),
+            Some(&HistoryEntry {
+                count: 2,
+                last_used: 100
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = History::load(&dir.path().join("missing")).unwrap();
+        assert!(history.is_empty());
+    }
+}
@@ -0,0 +1,356 @@
+//! Inverted character index for pruning candidates on very large inputs.
+//!
+//! Every query character must occur in an item for it to match, so
+//! intersecting the posting lists of the query's characters yields a
+//! superset of the matching items and everything else can be skipped
+//! before scoring. Longer n-grams (bigrams, trigrams) would prune harder
+//! but are unsound for fuzzy matching, where query characters need not be
+//! adjacent in the item.
+//!
+//! Indices can be saved to a cache file and loaded again on the next run, so
+//! large files that rarely change are only indexed once. Cache files are
+//! named after the file indexed, as it was when indexed (see
+//! [`CandidateIndex::cache_key`]), and only the [`CACHE_CAPACITY`] most
+//! recently used are kept.
+
+use std::collections::HashMap;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Leading bytes of a cache file, including the format version
+const CACHE_MAGIC: &[u8; 8] = b"ffidx\0\0\x01";
+
+/// Number of cache files kept; saving another deletes the least recently
+/// used
+pub const CACHE_CAPACITY: usize = 8;
+
+/// Posting lists of item indices keyed by lowercase character
+#[derive(Debug, Default, Clone)]
+pub struct CandidateIndex {
+    postings: HashMap<char, Vec<u32>>,
+    len: usize,
+}
+
+impl CandidateIndex {
+    /// Create an empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build an index over `items`
+    pub fn build(items: &[String]) -> Self {
+        let mut index = Self::new();
+        index.extend(items);
+        index
+    }
+
+    /// Index `items`, which continue the sequence already indexed
+    pub fn extend(&mut self, items: &[String]) {
+        let mut seen: Vec<char> = Vec::new();
+        for item in items {
+            let id = self.len as u32;
+            seen.clear();
+            // Lowercase the whole string to match the scorer (e.g. final sigma)
+            for c in item.to_lowercase().chars() {
+                if !seen.contains(&c) {
+                    seen.push(c);
+                    self.postings.entry(c).or_default().push(id);
+                }
+            }
+            self.len += 1;
+        }
+    }
+
+    /// Number of items indexed
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no items are indexed
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Default cache file for an index with cache key `key`, under
+    /// `$XDG_CACHE_HOME/ff/index` (falling back to `~/.cache/ff/index`)
+    pub fn cache_path(key: u64) -> Option<PathBuf> {
+        let cache_dir = match std::env::var_os("XDG_CACHE_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+        };
+        Some(
+            cache_dir
+                .join("ff")
+                .join("index")
+                .join(format!("{key:016x}")),
+        )
+    }
+
+    /// Cache key for an index over the items of the file at `path`, `split`
+    /// describing how the file is split into items: a hash (64-bit FNV-1a)
+    /// of both with the file's canonical path, size and modification time,
+    /// so the file isn't read for it and a change to it makes a new key
+    pub fn cache_key(path: &Path, split: &str) -> std::io::Result<u64> {
+        let path = path.canonicalize()?;
+        let metadata = std::fs::metadata(&path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let fnv = |hash: u64, &byte: &u8| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        let hash = [
+            path.to_string_lossy().as_bytes(),
+            &metadata.len().to_le_bytes(),
+            &modified.as_nanos().to_le_bytes(),
+            split.as_bytes(),
+        ]
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, part| {
+            fnv(part.iter().fold(hash, fnv), &0)
+        });
+        Ok(hash)
+    }
+
+    /// Load the cache file `path`, marking it as recently used
+    pub fn load_cached(path: &Path) -> std::io::Result<Self> {
+        let index = Self::load(path)?;
+        // Best effort: at worst the file is evicted a little early
+        if let Ok(file) = std::fs::File::options().append(true).open(path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Ok(index)
+    }
+
+    /// Save the index to the cache file `path`, then delete the least
+    /// recently used files in its directory beyond [`CACHE_CAPACITY`]
+    pub fn save_cached(&self, path: &Path) -> std::io::Result<()> {
+        self.save(path)?;
+        match path.parent() {
+            Some(dir) => evict_least_recent(dir, CACHE_CAPACITY),
+            None => Ok(()),
+        }
+    }
+
+    /// Write the index to `path`, creating parent directories as needed
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write to a temporary file first so readers never see a partial index
+        let tmp = path.with_extension("tmp");
+        let mut out = BufWriter::new(std::fs::File::create(&tmp)?);
+        out.write_all(CACHE_MAGIC)?;
+        out.write_all(&(self.len as u64).to_le_bytes())?;
+        out.write_all(&(self.postings.len() as u64).to_le_bytes())?;
+        for (&c, list) in &self.postings {
+            out.write_all(&(c as u32).to_le_bytes())?;
+            out.write_all(&(list.len() as u64).to_le_bytes())?;
+            for id in list {
+                out.write_all(&id.to_le_bytes())?;
+            }
+        }
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        std::fs::rename(tmp, path)
+    }
+
+    /// Read an index written by [`save`](Self::save)
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        fn invalid(msg: &str) -> std::io::Error {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, msg.to_string())
+        }
+        fn read_u32(input: &mut impl Read) -> std::io::Result<u32> {
+            let mut buf = [0; 4];
+            input.read_exact(&mut buf)?;
+            Ok(u32::from_le_bytes(buf))
+        }
+        fn read_u64(input: &mut impl Read) -> std::io::Result<u64> {
+            let mut buf = [0; 8];
+            input.read_exact(&mut buf)?;
+            Ok(u64::from_le_bytes(buf))
+        }
+
+        let mut input = BufReader::new(std::fs::File::open(path)?);
+        let mut magic = [0; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != CACHE_MAGIC {
+            return Err(invalid("not an ff index cache"));
+        }
+
+        let len = read_u64(&mut input)? as usize;
+        let count = read_u64(&mut input)?;
+        let mut postings = HashMap::new();
+        for _ in 0..count {
+            let c = char::from_u32(read_u32(&mut input)?)
+                .ok_or_else(|| invalid("invalid character in index cache"))?;
+            let ids = read_u64(&mut input)?;
+            let mut list = Vec::new();
+            for _ in 0..ids {
+                let id = read_u32(&mut input)?;
+                if id as usize >= len {
+                    return Err(invalid("item id out of range in index cache"));
+                }
+                list.push(id);
+            }
+            postings.insert(c, list);
+        }
+        Ok(Self { postings, len })
+    }
+
+    /// Indices (ascending) of items containing every character of `query_lower`.
+    ///
+    /// Items may contain ANSI escape sequences; their characters are indexed
+    /// too, which can only add candidates, never drop one.
+    pub fn candidates(&self, query_lower: &str) -> Vec<usize> {
+        let mut chars: Vec<char> = query_lower.chars().collect();
+        chars.sort_unstable();
+        chars.dedup();
+        if chars.is_empty() {
+            return (0..self.len).collect();
+        }
+
+        let mut lists = Vec::with_capacity(chars.len());
+        for c in chars {
+            match self.postings.get(&c) {
+                Some(list) => lists.push(list),
+                None => return Vec::new(),
+            }
+        }
+        // Start from the rarest character so the working set is smallest
+        lists.sort_unstable_by_key(|list| list.len());
+
+        let mut result: Vec<u32> = lists[0].clone();
+        for list in &lists[1..] {
+            let mut cursor = 0;
+            result.retain(|id| match list[cursor..].binary_search(id) {
+                Ok(found) => {
+                    cursor += found + 1;
+                    true
+                }
+                Err(insert) => {
+                    cursor += insert;
+                    false
+                }
+            });
+            if result.is_empty() {
+                break;
+            }
+        }
+        result.into_iter().map(|id| id as usize).collect()
+    }
+}
+
+/// Delete all but the `keep` most recently modified files in `dir`
+fn evict_least_recent(dir: &Path, keep: usize) -> std::io::Result<()> {
+    let mut files: Vec<(SystemTime, PathBuf)> = std::fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some((metadata.modified().ok()?, entry.path()))
+        })
+        .collect();
+    files.sort_unstable_by_key(|&(modified, _)| std::cmp::Reverse(modified));
+    for (_, path) in files.into_iter().skip(keep) {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_candidates_require_every_char() {
+        let index = CandidateIndex::build(&items(&["apple", "banana", "grape", "Pear"]));
+        assert_eq!(index.candidates("ap"), vec![0, 2, 3]);
+        assert_eq!(index.candidates("pe"), vec![0, 2, 3]);
+        assert_eq!(index.candidates("gr"), vec![2]);
+        assert!(index.candidates("z").is_empty());
+        assert_eq!(index.candidates(""), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_extend_continues_numbering() {
+        let mut index = CandidateIndex::build(&items(&["alpha"]));
+        index.extend(&items(&["beta", "gamma"]));
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.candidates("ma"), vec![2]);
+        assert_eq!(index.candidates("a"), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("index");
+        let index = CandidateIndex::build(&items(&["alpha", "Beta", "gämma", ""]));
+        index.save(&path).unwrap();
+
+        let loaded = CandidateIndex::load(&path).unwrap();
+        assert_eq!(loaded.len(), 4);
+        for query in ["a", "be", "ä", "z", ""] {
+            assert_eq!(loaded.candidates(query), index.candidates(query));
+        }
+    }
+
+    #[test]
+    fn test_load_rejects_foreign_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index");
+        std::fs::write(&path, "count\tlast_used\titem\n").unwrap();
+        assert!(CandidateIndex::load(&path).is_err());
+        assert!(CandidateIndex::load(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_cache_key_follows_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("items");
+        std::fs::write(&path, "a\nb\n").unwrap();
+        let key = CandidateIndex::cache_key(&path, "\n").unwrap();
+        assert_eq!(CandidateIndex::cache_key(&path, "\n").unwrap(), key);
+        assert_ne!(CandidateIndex::cache_key(&path, "\0").unwrap(), key);
+
+        std::fs::write(&path, "a\nb\nc\n").unwrap();
+        assert_ne!(CandidateIndex::cache_key(&path, "\n").unwrap(), key);
+        assert!(CandidateIndex::cache_key(&dir.path().join("missing"), "\n").is_err());
+    }
+
+    #[test]
+    fn test_save_cached_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = CandidateIndex::build(&items(&["alpha"]));
+        let path = |i: usize| dir.path().join(format!("{i:016x}"));
+        for i in 0..CACHE_CAPACITY {
+            index.save(&path(i)).unwrap();
+            let age = std::time::Duration::from_secs((CACHE_CAPACITY - i) as u64 * 60);
+            let file = std::fs::File::options().append(true).open(path(i)).unwrap();
+            file.set_modified(SystemTime::now() - age).unwrap();
+        }
+        // Loading the oldest makes it the most recently used
+        CandidateIndex::load_cached(&path(0)).unwrap();
+
+        index.save_cached(&path(CACHE_CAPACITY)).unwrap();
+        assert!(path(0).exists());
+        assert!(!path(1).exists());
+        assert!((2..=CACHE_CAPACITY).all(|i| path(i).exists()));
+    }
+
+    #[test]
+    fn test_candidates_are_superset_of_matches() {
+        let list: Vec<String> = (0..500).map(|i| format!("file_{i}_{}.rs", i * 7)).collect();
+        let index = CandidateIndex::build(&list);
+        for query in ["f12", "rs9", "e_3", "77"] {
+            let candidates = index.candidates(query);
+            for (i, item) in list.iter().enumerate() {
+                if crate::fuzzy::score_match(item, query).is_some() {
+                    assert!(candidates.binary_search(&i).is_ok(), "{item} / {query}");
+                }
+            }
+        }
+    }
+}
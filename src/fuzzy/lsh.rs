@@ -0,0 +1,454 @@
+//! Locality sensitive hashing for near-duplicate detection.
+//!
+//! Items are broken into overlapping character shingles (lowercase
+//! k-grams), summarised by MinHash signatures, and the signatures are split
+//! into bands. Two items land in the same bucket for a band when all of that
+//! band's rows agree, which happens with high probability for items with a
+//! high Jaccard similarity and rarely otherwise.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Default number of bands
+pub const DEFAULT_BANDS: usize = 20;
+/// Default number of rows (hash functions) per band
+pub const DEFAULT_ROWS: usize = 5;
+/// Default shingle length in characters
+pub const DEFAULT_SHINGLE_SIZE: usize = 3;
+
+/// Shingle size and band/row layout of an [`LSHIndex`].
+///
+/// Signatures use `bands * rows` hash functions. More rows per band make
+/// buckets stricter (fewer, closer candidates); more bands make them more
+/// forgiving. Short strings generally want a smaller shingle size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LSHConfig {
+    /// Shingle length in characters
+    pub shingle_size: usize,
+    /// Number of bands
+    pub bands: usize,
+    /// Rows (hash functions) per band
+    pub rows: usize,
+}
+
+impl Default for LSHConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_BANDS, DEFAULT_ROWS)
+    }
+}
+
+impl LSHConfig {
+    /// Layout with `bands` bands of `rows` hash functions and the default shingle size
+    pub fn new(bands: usize, rows: usize) -> Self {
+        Self {
+            shingle_size: DEFAULT_SHINGLE_SIZE,
+            bands: bands.max(1),
+            rows: rows.max(1),
+        }
+    }
+
+    /// Set the shingle length
+    pub fn with_shingle_size(mut self, k: usize) -> Self {
+        self.shingle_size = k.max(1);
+        self
+    }
+
+    /// Pick the band/row split of `num_hashes` hash functions whose implied
+    /// threshold is closest to `threshold`
+    pub fn for_threshold(num_hashes: usize, threshold: f64) -> Self {
+        let num_hashes = num_hashes.max(1);
+        (1..=num_hashes)
+            .filter(|bands| num_hashes.is_multiple_of(*bands))
+            .map(|bands| Self::new(bands, num_hashes / bands))
+            .min_by(|a, b| {
+                (a.threshold() - threshold)
+                    .abs()
+                    .total_cmp(&(b.threshold() - threshold).abs())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Total number of hash functions (signature length)
+    pub fn num_hashes(&self) -> usize {
+        self.bands * self.rows
+    }
+
+    /// Approximate Jaccard similarity at which two items become likely to
+    /// share a bucket: `(1 / bands) ^ (1 / rows)`
+    pub fn threshold(&self) -> f64 {
+        (1.0 / self.bands as f64).powf(1.0 / self.rows as f64)
+    }
+
+    /// Probability that two items with Jaccard similarity `similarity` share
+    /// at least one bucket: `1 - (1 - s^rows)^bands`
+    pub fn collision_probability(&self, similarity: f64) -> f64 {
+        1.0 - (1.0 - similarity.powi(self.rows as i32)).powi(self.bands as i32)
+    }
+}
+
+/// MinHash LSH index over string items
+#[derive(Debug, Clone)]
+pub struct LSHIndex {
+    config: LSHConfig,
+    /// One seed per hash function (`bands * rows`)
+    seeds: Vec<u64>,
+    /// Every inserted item, by id
+    items: Vec<String>,
+    /// Signature of every inserted item, by id
+    signatures: Vec<Vec<u64>>,
+    /// Per band: band hash -> ids of items in that bucket
+    buckets: Vec<HashMap<u64, Vec<usize>>>,
+}
+
+impl Default for LSHIndex {
+    fn default() -> Self {
+        Self::new(LSHConfig::default())
+    }
+}
+
+/// Finalizer from SplitMix64, used to derive independent hash functions
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Hashes of the lowercase character k-grams of `s`.
+///
+/// Strings shorter than `k` yield a single shingle of the whole string.
+pub fn shingles(s: &str, k: usize) -> HashSet<u64> {
+    let chars: Vec<char> = s.to_lowercase().chars().collect();
+    let k = k.max(1);
+    let hash = |window: &[char]| {
+        let mut hasher = DefaultHasher::new();
+        window.hash(&mut hasher);
+        hasher.finish()
+    };
+    if chars.len() <= k {
+        return HashSet::from([hash(&chars)]);
+    }
+    chars.windows(k).map(hash).collect()
+}
+
+/// Exact Jaccard similarity of the shingle sets of `a` and `b`
+pub fn jaccard(a: &str, b: &str, k: usize) -> f64 {
+    let (a, b) = (shingles(a, k), shingles(b, k));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+impl LSHIndex {
+    /// Create an index with the given shingle size and band/row layout
+    pub fn new(config: LSHConfig) -> Self {
+        let config =
+            LSHConfig::new(config.bands, config.rows).with_shingle_size(config.shingle_size);
+        Self {
+            config,
+            seeds: (0..config.num_hashes())
+                .map(|i| mix(i as u64 + 1))
+                .collect(),
+            items: Vec::new(),
+            signatures: Vec::new(),
+            buckets: vec![HashMap::new(); config.bands],
+        }
+    }
+
+    /// The index configuration
+    pub fn config(&self) -> &LSHConfig {
+        &self.config
+    }
+
+    /// Number of items inserted
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    /// Whether no items have been inserted
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    /// MinHash signature of `item`: per hash function, the minimum over its shingles
+    pub fn signature(&self, item: &str) -> Vec<u64> {
+        let shingles = shingles(item, self.config.shingle_size);
+        self.seeds
+            .iter()
+            .map(|&seed| {
+                shingles
+                    .iter()
+                    .map(|&s| mix(s ^ seed))
+                    .min()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect()
+    }
+
+    /// Hash of each band of a signature
+    fn band_hashes<'a>(&'a self, signature: &'a [u64]) -> impl Iterator<Item = u64> + 'a {
+        signature.chunks(self.config.rows).map(|band| {
+            let mut hasher = DefaultHasher::new();
+            band.hash(&mut hasher);
+            hasher.finish()
+        })
+    }
+
+    /// Insert an item and return its id (ids are assigned sequentially from 0)
+    pub fn insert(&mut self, item: &str) -> usize {
+        let signature = self.signature(item);
+        self.insert_signature(item, signature)
+    }
+
+    /// Insert an item with its precomputed signature (from [`LSHIndex::signature`])
+    /// and return its id
+    pub fn insert_signature(&mut self, item: &str, signature: Vec<u64>) -> usize {
+        let id = self.signatures.len();
+        let hashes: Vec<u64> = self.band_hashes(&signature).collect();
+        for (band, hash) in hashes.into_iter().enumerate() {
+            self.buckets[band].entry(hash).or_default().push(id);
+        }
+        self.items.push(item.to_string());
+        self.signatures.push(signature);
+        id
+    }
+
+    /// Get an inserted item by id
+    pub fn get(&self, id: usize) -> Option<&str> {
+        self.items.get(id).map(|s| s.as_str())
+    }
+
+    /// Group inserted items into clusters of near-duplicates.
+    ///
+    /// Items whose estimated similarity is at least `threshold` are linked,
+    /// and clusters are the connected components. Clusters are ordered by
+    /// their first inserted member, and members by insertion order.
+    pub fn cluster(&self, threshold: f64) -> Vec<Vec<String>> {
+        let n = self.len();
+        let mut parent: Vec<usize> = (0..n).collect();
+        fn find(parent: &mut [usize], mut x: usize) -> usize {
+            while parent[x] != x {
+                parent[x] = parent[parent[x]];
+                x = parent[x];
+            }
+            x
+        }
+
+        for id in 0..n {
+            for other in self.candidates_for(&self.signatures[id]) {
+                if other <= id {
+                    continue;
+                }
+                let similarity =
+                    Self::estimate_similarity(&self.signatures[id], &self.signatures[other]);
+                if similarity >= threshold {
+                    let (a, b) = (find(&mut parent, id), find(&mut parent, other));
+                    // Keep the smallest id as root so cluster order is stable
+                    parent[a.max(b)] = a.min(b);
+                }
+            }
+        }
+
+        let mut cluster_of: Vec<Option<usize>> = vec![None; n];
+        let mut clusters: Vec<Vec<String>> = Vec::new();
+        for id in 0..n {
+            let root = find(&mut parent, id);
+            let slot = *cluster_of[root].get_or_insert_with(|| {
+                clusters.push(Vec::new());
+                clusters.len() - 1
+            });
+            clusters[slot].push(self.items[id].clone());
+        }
+        clusters
+    }
+
+    /// Fraction of agreeing MinHash rows, an estimate of Jaccard similarity
+    pub fn estimate_similarity(a: &[u64], b: &[u64]) -> f64 {
+        if a.is_empty() || a.len() != b.len() {
+            return 0.0;
+        }
+        let same = a.iter().zip(b).filter(|(x, y)| x == y).count();
+        same as f64 / a.len() as f64
+    }
+
+    /// Signature of an inserted item
+    pub fn get_signature(&self, id: usize) -> Option<&[u64]> {
+        self.signatures.get(id).map(|s| s.as_slice())
+    }
+
+    /// Ids of candidate near-duplicates of `item`: inserted items sharing at
+    /// least one band bucket, most similar first (ties by id)
+    pub fn find_similar(&self, item: &str) -> Vec<usize> {
+        let signature = self.signature(item);
+        self.candidates_for(&signature)
+    }
+
+    /// Approximate nearest neighbours: up to `k` indexed items most similar
+    /// to `query`, as `(id, item, estimated_similarity)`, most similar first.
+    ///
+    /// Only items sharing a bucket with the query are considered, so items
+    /// well below the configured threshold are usually not returned.
+    pub fn nearest(&self, query: &str, k: usize) -> Vec<(usize, &str, f64)> {
+        let signature = self.signature(query);
+        let mut scored = self.scored_candidates(&signature);
+        scored.truncate(k);
+        scored
+            .into_iter()
+            .map(|(id, similarity)| (id, self.items[id].as_str(), similarity))
+            .collect()
+    }
+
+    /// Candidate ids for a signature, most similar first (ties by id)
+    pub(crate) fn candidates_for(&self, signature: &[u64]) -> Vec<usize> {
+        self.scored_candidates(signature)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Candidates sharing a bucket with `signature` and their estimated
+    /// similarity, most similar first (ties by id)
+    fn scored_candidates(&self, signature: &[u64]) -> Vec<(usize, f64)> {
+        let mut ids: Vec<usize> = self
+            .band_hashes(signature)
+            .enumerate()
+            .filter_map(|(band, hash)| self.buckets[band].get(&hash))
+            .flatten()
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+
+        let mut scored: Vec<(usize, f64)> = ids
+            .into_iter()
+            .map(|id| {
+                (
+                    id,
+                    Self::estimate_similarity(signature, &self.signatures[id]),
+                )
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shingles_short_string() {
+        assert_eq!(shingles("ab", 3).len(), 1);
+        assert_eq!(shingles("abcd", 3).len(), 2);
+        assert_eq!(shingles("ABCD", 3), shingles("abcd", 3));
+    }
+
+    #[test]
+    fn test_jaccard() {
+        assert_eq!(jaccard("hello world", "hello world", 3), 1.0);
+        assert_eq!(jaccard("aaaa", "bbbb", 3), 0.0);
+        let j = jaccard("hello world", "hello there", 3);
+        assert!(j > 0.0 && j < 1.0);
+    }
+
+    #[test]
+    fn test_signature_is_deterministic() {
+        let index = LSHIndex::default();
+        assert_eq!(index.signature("some line"), index.signature("some line"));
+        assert_eq!(index.signature("x").len(), DEFAULT_BANDS * DEFAULT_ROWS);
+    }
+
+    #[test]
+    fn test_find_similar_returns_near_duplicates() {
+        let mut index = LSHIndex::default();
+        let a = index.insert("2024-01-01 12:00:01 ERROR connection refused to db-primary");
+        let b = index.insert("2024-01-01 12:00:07 ERROR connection refused to db-primary");
+        let c = index.insert("user alice logged in from 10.0.0.5");
+
+        let similar =
+            index.find_similar("2024-01-01 12:00:09 ERROR connection refused to db-primary");
+        assert!(similar.contains(&a));
+        assert!(similar.contains(&b));
+        assert!(!similar.contains(&c));
+
+        // Not only identical strings collide
+        assert!(index
+            .find_similar("user alice logged in from 10.0.0.6")
+            .contains(&c));
+    }
+
+    #[test]
+    fn test_cluster_groups_near_duplicates() {
+        let mut index = LSHIndex::default();
+        for item in [
+            "GET /api/users/17 200 12ms",
+            "POST /login 302",
+            "GET /api/users/18 200 13ms",
+            "POST /login 302 ",
+            "disk full on /var",
+        ] {
+            index.insert(item);
+        }
+        let clusters = index.cluster(0.5);
+        assert_eq!(
+            clusters,
+            vec![
+                vec!["GET /api/users/17 200 12ms", "GET /api/users/18 200 13ms"],
+                vec!["POST /login 302", "POST /login 302 "],
+                vec!["disk full on /var"],
+            ]
+        );
+        assert_eq!(index.cluster(1.01).len(), 5);
+    }
+
+    #[test]
+    fn test_nearest_returns_top_k_with_scores() {
+        let mut index = LSHIndex::default();
+        index.insert("src/components/button.tsx");
+        index.insert("src/components/button.test.tsx");
+        index.insert("docs/setup.md");
+        index.insert("src/components/buttons.tsx");
+
+        let nearest = index.nearest("src/components/button.tsx", 2);
+        assert_eq!(nearest.len(), 2);
+        assert_eq!(nearest[0], (0, "src/components/button.tsx", 1.0));
+        assert!(nearest[1].2 < 1.0 && nearest[1].2 > 0.5);
+        assert!(nearest.iter().all(|(id, _, _)| *id != 2));
+
+        assert!(index.nearest("something else entirely", 3).is_empty());
+        assert!(index.nearest("docs/setup.md", 0).is_empty());
+    }
+
+    #[test]
+    fn test_config_threshold() {
+        let config = LSHConfig::new(20, 5);
+        assert_eq!(config.num_hashes(), 100);
+        assert!((config.threshold() - 0.549).abs() < 0.01);
+        assert!(config.collision_probability(0.9) > 0.99);
+        assert!(config.collision_probability(0.2) < 0.01);
+
+        let tuned = LSHConfig::for_threshold(100, 0.8);
+        assert_eq!(tuned.num_hashes(), 100);
+        assert_eq!((tuned.bands, tuned.rows), (10, 10));
+    }
+
+    #[test]
+    fn test_shingle_size_is_configurable() {
+        let mut index = LSHIndex::new(LSHConfig::default().with_shingle_size(2));
+        assert_eq!(index.config().shingle_size, 2);
+        let id = index.insert("ab-12");
+        assert!(index.find_similar("ab-13").contains(&id));
+    }
+
+    #[test]
+    fn test_estimate_tracks_jaccard() {
+        let index = LSHIndex::new(LSHConfig::new(32, 4));
+        let (x, y) = ("the quick brown fox jumps", "the quick brown cat jumps");
+        let estimate = LSHIndex::estimate_similarity(&index.signature(x), &index.signature(y));
+        assert!((estimate - jaccard(x, y, 3)).abs() < 0.2);
+    }
+}
@@ -1,7 +1,22 @@
 pub mod finder;
+pub mod history;
+pub mod index;
+pub mod lsh;
 pub mod scoring;
 pub mod stream;
+pub mod suggest;
+pub mod tree;
 
-pub use finder::{FuzzyFinder, MatchPositions};
-pub use scoring::{score_batch, score_match, score_match_case_insensitive, MatchResult};
-pub use stream::ItemStream;
+pub use finder::{FinderSnapshot, FuzzyFinder, MatchPositions, SearchItem, SimilarGroup};
+pub use history::History;
+pub use index::CandidateIndex;
+pub use lsh::{LSHConfig, LSHIndex};
+pub use scoring::{
+    score_batch, score_batch_cancellable, score_batch_filtered, score_batch_with_config,
+    score_candidates, score_match, score_match_case_insensitive, score_match_explain,
+    score_match_explain_with_config, score_match_with_config, CancelToken, MatchMode, MatchResult,
+    Scheme, ScoreBreakdown, ScoringConfig,
+};
+pub use stream::{Dedup, ItemSender, ItemStream, ItemTransform};
+pub use suggest::Suggester;
+pub use tree::TernaryTree;
@@ -12,6 +12,18 @@
 //! - Word boundary matches: bonus for matches after separators or camelCase
 //! - Gap penalty: penalty for non-consecutive matches
 //! - Position bonus: earlier matches score higher
+//! - Basename bonus: with the `path` scheme, matches in the final path
+//!   component outrank matches in parent directories
+//! - History bonus: with a selection history, frequently and recently
+//!   picked items rank higher within their match tier
+//!
+//! Boundary characters, gap penalties and the basename bonus can be tuned,
+//! and [`ScoringConfig::preset`] bundles them per kind of item.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::fuzzy::history::{self, History};
 
 /// Scoring constants - tuned for good fuzzy matching behavior
 mod scores {
@@ -33,6 +45,183 @@ mod scores {
     pub const GAP_EXTEND: i32 = -1;
     /// Maximum gap penalty (don't penalize too harshly for long gaps)
     pub const GAP_MAX: i32 = -20;
+    /// Bonus when every query character lands on a word head (acronym match)
+    pub const ACRONYM: i32 = 150;
+    /// Bonus for a match that lies entirely in the final path component
+    pub const BASENAME: i32 = 50;
+}
+
+/// Scoring scheme: which assumptions to make about the shape of items.
+///
+/// Each scheme is also a preset of boundary characters, gap penalties and
+/// bonuses, see [`ScoringConfig::preset`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scheme {
+    /// Generic scoring with no assumptions about the items
+    #[default]
+    Default,
+    /// Items are paths: matches in the basename outrank matches in parent directories
+    Path,
+    /// Items are shell commands: arguments and `key=value` pairs start
+    /// words, and gaps between them are penalized lightly
+    Commands,
+    /// Items are sentences: punctuation starts words, and gaps are penalized
+    /// heavily so query words match whole
+    Prose,
+    /// Items are code identifiers and symbols (`Foo::bar<T>`, `$var`,
+    /// `snake_case`): sigils and brackets start words
+    Identifiers,
+}
+
+impl Scheme {
+    /// Parse a scheme name (`default`, `path`, `commands`, `prose` or
+    /// `identifiers`; plural and singular forms are both accepted)
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "default" => Ok(Scheme::Default),
+            "path" | "paths" => Ok(Scheme::Path),
+            "command" | "commands" => Ok(Scheme::Commands),
+            "prose" => Ok(Scheme::Prose),
+            "identifier" | "identifiers" => Ok(Scheme::Identifiers),
+            other => Err(format!(
+                "Invalid scheme '{other}'. Expected one of: default, paths, commands, prose, identifiers"
+            )),
+        }
+    }
+}
+
+/// Matching mode: how query characters may land in an item
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    /// Query characters match in order anywhere in the item
+    #[default]
+    Fuzzy,
+    /// Each whitespace-separated query token must match at the start of a
+    /// word (after a separator or at a camelCase hump)
+    Word,
+    /// Every query character must match the first character of a word, in
+    /// order (e.g. `rfc` for `ReadFileContents`)
+    Acronym,
+}
+
+impl MatchMode {
+    /// Parse a mode name (`fuzzy`, `word` or `acronym`)
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "fuzzy" => Ok(MatchMode::Fuzzy),
+            "word" => Ok(MatchMode::Word),
+            "acronym" => Ok(MatchMode::Acronym),
+            other => Err(format!(
+                "Invalid mode '{other}'. Expected one of: fuzzy, word, acronym"
+            )),
+        }
+    }
+}
+
+/// Shared flag for abandoning an in-flight batch scoring pass, e.g. when the
+/// query changed before the pass finished. Clones share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Create a token that is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every pass using this token to stop
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`cancel`](Self::cancel) was called since the last reset
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Clear the flag so the token can be reused for the next pass
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Options that tune how items are scored
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoringConfig {
+    /// Scoring scheme
+    pub scheme: Scheme,
+    /// Matching mode
+    pub mode: MatchMode,
+    /// Extra characters treated as word boundaries, on top of the built-in set
+    pub boundary_chars: Vec<char>,
+    /// Selection history used to boost frecent items
+    pub history: Option<Arc<History>>,
+    /// Penalty for starting a gap between matched characters (negative)
+    pub gap_start: i32,
+    /// Penalty per character skipped by a gap (negative)
+    pub gap_extend: i32,
+    /// Cap on the penalty for skipped characters in one gap (negative)
+    pub gap_max: i32,
+    /// Bonus for a match entirely in the final path component (`path` scheme)
+    pub basename_bonus: i32,
+}
+
+/// Default scoring options, for the functions that take none
+static DEFAULT_CONFIG: ScoringConfig = ScoringConfig {
+    scheme: Scheme::Default,
+    mode: MatchMode::Fuzzy,
+    boundary_chars: Vec::new(),
+    history: None,
+    gap_start: scores::GAP_START,
+    gap_extend: scores::GAP_EXTEND,
+    gap_max: scores::GAP_MAX,
+    basename_bonus: scores::BASENAME,
+};
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
+    }
+}
+
+impl ScoringConfig {
+    /// Options preset for a kind of item:
+    ///
+    /// - `Default`: built-in boundaries (`/ \ _ - . : space`) and penalties
+    /// - `Path`: as default, plus a bonus for matches in the file name
+    /// - `Commands`: `= | , ; ' "` also start words; gaps cost less, so
+    ///   fragments of several arguments match well
+    /// - `Prose`: `, ; ! ? ( ) ' "` also start words; gaps cost more, so
+    ///   query words prefer whole words
+    /// - `Identifiers`: `$ @ # < > ( ) [ ] ,` also start words
+    ///
+    /// Mode and history are left at their defaults.
+    pub fn preset(scheme: Scheme) -> Self {
+        let base = Self {
+            scheme,
+            ..Self::default()
+        };
+        match scheme {
+            Scheme::Default | Scheme::Path => base,
+            Scheme::Commands => Self {
+                boundary_chars: vec!['=', '|', ',', ';', '\'', '"'],
+                gap_start: -1,
+                gap_max: -10,
+                ..base
+            },
+            Scheme::Prose => Self {
+                boundary_chars: vec![',', ';', '!', '?', '(', ')', '\'', '"'],
+                gap_start: -8,
+                gap_extend: -2,
+                gap_max: -40,
+                ..base
+            },
+            Scheme::Identifiers => Self {
+                boundary_chars: vec!['$', '@', '#', '<', '>', '(', ')', '[', ']', ','],
+                ..base
+            },
+        }
+    }
 }
 
 /// Match quality tier — higher variants always outrank lower ones.
@@ -53,22 +242,52 @@ pub struct MatchResult {
     pub positions: Vec<usize>,
     /// Match quality tier
     pub tier: MatchTier,
+    /// Length of the matched item in characters
+    pub item_len: usize,
+}
+
+impl MatchResult {
+    /// Score normalized to `0.0..=1.0`, comparable across queries and items.
+    ///
+    /// Each tier maps to its own band (fuzzy below 0.5, substring 0.5–0.75,
+    /// prefix and exact 0.75–1.0). Within a band, results covering more of the
+    /// item rank higher; fuzzy results are additionally weighted by how close
+    /// the raw score is to a fully consecutive boundary match. An exact match
+    /// of the whole item scores 1.0; an empty query scores 0.0.
+    pub fn normalized_score(&self) -> f64 {
+        let matched = self.positions.len();
+        if matched == 0 {
+            return 0.0;
+        }
+        let coverage = (matched as f64 / self.item_len.max(matched) as f64).clamp(0.0, 1.0);
+        match self.tier {
+            MatchTier::Exact | MatchTier::Prefix => 0.75 + 0.25 * coverage,
+            MatchTier::Substring => 0.5 + 0.25 * coverage.min(0.99),
+            MatchTier::Fuzzy => {
+                let ideal = matched as f64
+                    * (scores::MATCH + scores::CONSECUTIVE + scores::BOUNDARY) as f64;
+                let quality = (self.score as f64 / ideal).clamp(0.0, 1.0);
+                0.5 * (0.5 * quality + 0.5 * coverage.min(0.99))
+            }
+        }
+    }
 }
 
-/// Check if a character is a word boundary indicator
+/// Check if a character is a word boundary indicator.
+/// `extra` holds user-supplied boundary characters.
 #[inline]
-fn is_boundary_char(c: char) -> bool {
-    matches!(c, '/' | '\\' | '_' | '-' | '.' | ' ' | ':')
+fn is_boundary_char(c: char, extra: &[char]) -> bool {
+    matches!(c, '/' | '\\' | '_' | '-' | '.' | ' ' | ':') || extra.contains(&c)
 }
 
 /// Check if we're at a word boundary (camelCase or after separator)
 #[inline]
-fn is_word_boundary(prev: Option<char>, current: char) -> bool {
+fn is_word_boundary(prev: Option<char>, current: char, extra: &[char]) -> bool {
     match prev {
         None => true, // First character is always a boundary
         Some(p) => {
             // After a boundary character
-            is_boundary_char(p)
+            is_boundary_char(p, extra)
             // camelCase boundary: lowercase followed by uppercase
             || (p.is_ascii_lowercase() && current.is_ascii_uppercase())
             // digit to letter or letter to digit
@@ -77,6 +296,42 @@ fn is_word_boundary(prev: Option<char>, current: char) -> bool {
     }
 }
 
+/// Whether `chars[i]` starts a word: not a separator itself, and at a word boundary
+#[inline]
+fn is_word_start(chars: &[char], i: usize, extra: &[char]) -> bool {
+    !is_boundary_char(chars[i], extra)
+        && is_word_boundary(i.checked_sub(1).map(|p| chars[p]), chars[i], extra)
+}
+
+/// Original-case chars for boundary detection, or the lowercase chars when
+/// lowercasing changed the char count
+fn cased_chars<'a>(original: &'a [char], lower: &'a [char]) -> &'a [char] {
+    if original.len() == lower.len() {
+        original
+    } else {
+        lower
+    }
+}
+
+/// Positions matching each query character, in order, to the earliest
+/// possible word start (`None` if there is no such acronym match)
+fn acronym_positions(
+    item_chars: &[char],
+    cased: &[char],
+    query_chars: &[char],
+    extra: &[char],
+) -> Option<Vec<usize>> {
+    let mut positions = Vec::with_capacity(query_chars.len());
+    let mut from = 0;
+    for &qc in query_chars {
+        let pos = (from..item_chars.len())
+            .find(|&i| item_chars[i] == qc && is_word_start(cased, i, extra))?;
+        positions.push(pos);
+        from = pos + 1;
+    }
+    Some(positions)
+}
+
 /// Score a fuzzy match between an item and a query.
 ///
 /// Returns `Some(MatchResult)` if all query characters are found in order,
@@ -98,6 +353,18 @@ pub fn score_match_with_original(
     item_original: &str,
     query: &str,
 ) -> Option<MatchResult> {
+    score_match_inner(item_lower, item_original, query, &DEFAULT_CONFIG)
+}
+
+/// Core matcher shared by all schemes, using the boundaries and penalties of
+/// `config` (but not its mode or scheme).
+fn score_match_inner(
+    item_lower: &str,
+    item_original: &str,
+    query: &str,
+    config: &ScoringConfig,
+) -> Option<MatchResult> {
+    let boundary_chars = config.boundary_chars.as_slice();
     let item = item_lower;
     // Empty query matches everything with score 0
     if query.is_empty() {
@@ -105,6 +372,7 @@ pub fn score_match_with_original(
             score: 0,
             positions: Vec::new(),
             tier: MatchTier::Fuzzy,
+            item_len: item.chars().count(),
         });
     }
 
@@ -115,11 +383,12 @@ pub fn score_match_with_original(
 
     // Fast path: exact match
     if item == query {
-        let positions: Vec<usize> = (0..item.chars().count()).collect();
+        let item_len = item.chars().count();
         return Some(MatchResult {
             score: scores::EXACT,
-            positions,
+            positions: (0..item_len).collect(),
             tier: MatchTier::Exact,
+            item_len,
         });
     }
 
@@ -132,205 +401,437 @@ pub fn score_match_with_original(
             score,
             positions,
             tier: MatchTier::Prefix,
+            item_len: item.chars().count(),
         });
     }
 
-    // Fast path: check if item contains query as substring
-    if let Some(start_idx) = item.find(query) {
+    // Fast path: check if item contains query as substring (SIMD byte search;
+    // a UTF-8 needle can only match at a char boundary)
+    if let Some(start_idx) = memchr::memmem::find(item.as_bytes(), query.as_bytes()) {
         // Substring match - calculate byte offset to char index
         let char_start = item[..start_idx].chars().count();
         let positions: Vec<usize> = (char_start..char_start + query.chars().count()).collect();
 
-        // Score based on position (earlier is better)
-        let position_bonus = ((item.len() - start_idx) as i32 * 2).min(100);
-        let score =
-            (scores::PREFIX / 2 + (query.len() as i32 * scores::CONSECUTIVE) + position_bonus)
-                .min(scores::PREFIX - 1);
-
         return Some(MatchResult {
-            score,
+            score: substring_score(item.len(), start_idx, query.len()),
             positions,
             tier: MatchTier::Substring,
+            item_len: item.chars().count(),
         });
     }
 
+    // ASCII fast path: byte index == char index, so candidate scans can use memchr
+    let ascii_bytes = (item.is_ascii() && query.is_ascii()).then_some(item.as_bytes());
+    if let Some(bytes) = ascii_bytes {
+        // Bail out before allocating if the query is not a subsequence
+        if !is_ascii_subsequence(bytes, query.as_bytes()) {
+            return None;
+        }
+    }
+
     // Full fuzzy matching with optimal position finding
     let item_chars: Vec<char> = item.chars().collect();
     let original_chars: Vec<char> = item_original.chars().collect();
     let query_chars: Vec<char> = query.chars().collect();
 
     // Find optimal match positions using DP
-    let positions = find_optimal_positions(&item_chars, &query_chars)?;
+    let mut positions = find_optimal_positions(
+        &item_chars,
+        &original_chars,
+        &query_chars,
+        config,
+        ascii_bytes,
+    )?;
 
     // Calculate score based on the optimal positions
-    let score =
-        calculate_score_for_positions(&positions, &item_chars, &original_chars, &query_chars);
+    let mut score = calculate_score_for_positions(
+        &positions,
+        &item_chars,
+        &original_chars,
+        &query_chars,
+        config,
+    );
+
+    // Prefer an acronym match (every query char on a word head) when one exists
+    if query_chars.len() >= 2 {
+        let cased = cased_chars(&original_chars, &item_chars);
+        if let Some(acronym) = acronym_positions(&item_chars, cased, &query_chars, boundary_chars) {
+            let acronym_score = calculate_score_for_positions(
+                &acronym,
+                &item_chars,
+                &original_chars,
+                &query_chars,
+                config,
+            ) + scores::ACRONYM;
+            if acronym_score >= score {
+                score = acronym_score;
+                positions = acronym;
+            }
+        }
+    }
 
     Some(MatchResult {
         score: score.min(scores::PREFIX / 2 - 1),
         positions,
         tier: MatchTier::Fuzzy,
+        item_len: item_chars.len(),
     })
 }
 
-/// Find optimal match positions that maximize consecutive runs.
-/// Uses dynamic programming to find the best positions for each query character.
-fn find_optimal_positions(item_chars: &[char], query_chars: &[char]) -> Option<Vec<usize>> {
-    let n = item_chars.len();
-    let m = query_chars.len();
+/// Score for a substring match starting at byte `start_idx` (earlier is better)
+fn substring_score(item_len: usize, start_idx: usize, query_len: usize) -> i32 {
+    let position_bonus = ((item_len - start_idx) as i32 * 2).min(100);
+    (scores::PREFIX / 2 + (query_len as i32 * scores::CONSECUTIVE) + position_bonus)
+        .min(scores::PREFIX - 1)
+}
 
-    if m == 0 {
-        return Some(Vec::new());
+/// Score a fuzzy match using the given scoring configuration.
+///
+/// Same inputs as `score_match_with_original`; `config` selects the mode and
+/// scheme (the scheme only applies to fuzzy matching).
+pub fn score_match_with_config(
+    item_lower: &str,
+    item_original: &str,
+    query: &str,
+    config: &ScoringConfig,
+) -> Option<MatchResult> {
+    match config.mode {
+        MatchMode::Fuzzy => {}
+        MatchMode::Word => return score_word_match(item_lower, item_original, query, config),
+        MatchMode::Acronym => return score_acronym_match(item_lower, item_original, query, config),
     }
-    if n < m {
-        return None;
+    match config.scheme {
+        Scheme::Path => score_path_match(item_lower, item_original, query, config),
+        Scheme::Default | Scheme::Commands | Scheme::Prose | Scheme::Identifiers => {
+            score_match_inner(item_lower, item_original, query, config)
+        }
     }
+}
 
-    // For each query character, find all positions where it matches in the item
-    let mut match_positions: Vec<Vec<usize>> = Vec::with_capacity(m);
-    for &qc in query_chars {
-        let positions: Vec<usize> = item_chars
-            .iter()
-            .enumerate()
-            .filter(|(_, &ic)| ic == qc)
-            .map(|(i, _)| i)
-            .collect();
+/// Word-mode scoring: every whitespace-separated query token must be a
+/// prefix of a distinct word in the item.
+///
+/// Tokens are matched in query order where possible (earliest word after the
+/// previous token), falling back to any unused word. Whole-word and in-order
+/// matches score higher. A contiguous match of the whole query that starts a
+/// word keeps its exact/prefix/substring tier.
+fn score_word_match(
+    item_lower: &str,
+    item_original: &str,
+    query: &str,
+    config: &ScoringConfig,
+) -> Option<MatchResult> {
+    let boundary_chars = config.boundary_chars.as_slice();
+    let tokens: Vec<Vec<char>> = query
+        .split_whitespace()
+        .map(|token| token.chars().collect())
+        .collect();
+    if tokens.is_empty() {
+        return score_match_inner(item_lower, item_original, "", config);
+    }
 
-        if positions.is_empty() {
-            return None; // Query char not found, no match possible
+    let item_chars: Vec<char> = item_lower.chars().collect();
+    let original_chars: Vec<char> = item_original.chars().collect();
+    let cased = cased_chars(&original_chars, &item_chars);
+    let is_word_start = |i: usize| is_word_start(cased, i, boundary_chars);
+
+    if let Some(result) = score_match_inner(item_lower, item_original, query, config) {
+        if result.tier > MatchTier::Fuzzy
+            && result.positions.first().is_some_and(|&p| is_word_start(p))
+        {
+            return Some(result);
         }
-        match_positions.push(positions);
     }
 
-    // DP to find best positions
-    // dp[i][j] = best score achievable for query[0..=i] ending at position match_positions[i][j]
-    // We also track the previous position index to reconstruct the path
+    let starts: Vec<usize> = (0..item_chars.len())
+        .filter(|&i| is_word_start(i))
+        .collect();
+    let mut used = vec![false; item_chars.len()];
+    let mut positions = Vec::new();
+    let mut score = 0;
+    let mut next_from = 0;
+    let mut in_order = true;
+
+    for token in &tokens {
+        let fits = |p: usize| !used[p] && item_chars[p..].starts_with(token);
+        let start = match starts.iter().copied().find(|&p| p >= next_from && fits(p)) {
+            Some(p) => p,
+            None => {
+                in_order = false;
+                starts.iter().copied().find(|&p| fits(p))?
+            }
+        };
+        let end = start + token.len();
+        used[start] = true;
+        positions.extend(start..end);
+        next_from = end;
+
+        score += token.len() as i32 * (scores::MATCH + scores::CONSECUTIVE) + scores::BOUNDARY;
+        if end == item_chars.len()
+            || is_word_boundary(Some(cased[end - 1]), cased[end], boundary_chars)
+        {
+            score += scores::BOUNDARY;
+        }
+    }
+
+    if in_order {
+        score += scores::CONSECUTIVE;
+    }
+    positions.sort_unstable();
+    positions.dedup();
+    if positions.first() == Some(&0) {
+        score += scores::FIRST_CHAR;
+    }
 
-    // For efficiency, we'll use a simpler greedy-with-lookahead approach:
-    // For each query char, pick the position that gives the best consecutive bonus
-    // considering the previous selected position
+    Some(MatchResult {
+        score: score.min(scores::PREFIX / 2 - 1),
+        positions,
+        tier: MatchTier::Fuzzy,
+        item_len: item_chars.len(),
+    })
+}
 
-    let mut selected_positions = Vec::with_capacity(m);
+/// Acronym-mode scoring: every query character (ignoring whitespace) must
+/// match the first character of a word, in order. Only an exact match is
+/// accepted otherwise.
+fn score_acronym_match(
+    item_lower: &str,
+    item_original: &str,
+    query: &str,
+    config: &ScoringConfig,
+) -> Option<MatchResult> {
+    let query_chars: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    if query_chars.is_empty() || item_lower == query {
+        return score_match_inner(item_lower, item_original, query, config);
+    }
 
-    // For first query character, prefer earlier positions (but consider future consecutive potential)
-    // Use DP approach: for each query char position, compute best score considering all options
+    let item_chars: Vec<char> = item_lower.chars().collect();
+    let original_chars: Vec<char> = item_original.chars().collect();
+    let cased = cased_chars(&original_chars, &item_chars);
+    let positions = acronym_positions(&item_chars, cased, &query_chars, &config.boundary_chars)?;
+    let score = calculate_score_for_positions(
+        &positions,
+        &item_chars,
+        &original_chars,
+        &query_chars,
+        config,
+    ) + scores::ACRONYM;
 
-    // dp[j] = (best_score, best_position) for query char i at match_positions[i][j]
-    // We'll iterate through query chars and update
+    Some(MatchResult {
+        score: score.min(scores::PREFIX / 2 - 1),
+        positions,
+        tier: MatchTier::Fuzzy,
+        item_len: item_chars.len(),
+    })
+}
 
-    // Initialize: for first query char, score based on position
-    let first_positions = &match_positions[0];
+/// Byte offset where the final path component starts, if the item contains a separator.
+/// Trailing separators (e.g. `src/`) are not treated as the start of the basename.
+fn basename_offset(item: &str) -> Option<usize> {
+    let trimmed = item.trim_end_matches(['/', '\\']);
+    trimmed.rfind(['/', '\\']).map(|idx| idx + 1)
+}
 
-    if m == 1 {
-        // Single character query - just pick the best position (earliest, prefer boundary)
-        let best_pos = *first_positions.first().unwrap();
-        return Some(vec![best_pos]);
+/// Path-aware scoring: prefer matches inside the basename.
+///
+/// If the query matches within the final path component, that match is used
+/// (with positions shifted back to the full item) plus a basename bonus.
+/// Otherwise the whole path is scored, but a prefix match that lies only in
+/// parent directories is demoted to a substring match.
+fn score_path_match(
+    item_lower: &str,
+    item_original: &str,
+    query: &str,
+    config: &ScoringConfig,
+) -> Option<MatchResult> {
+    let full = score_match_inner(item_lower, item_original, query, config);
+    if query.is_empty() {
+        return full;
     }
+    let (Some(lower_off), Some(orig_off)) =
+        (basename_offset(item_lower), basename_offset(item_original))
+    else {
+        return full;
+    };
+    let base_start = item_lower[..lower_off].chars().count();
+
+    let full = full.map(|mut result| {
+        let in_parent = result.positions.iter().all(|&p| p < base_start);
+        if result.tier == MatchTier::Prefix && in_parent {
+            result.tier = MatchTier::Substring;
+            result.score = substring_score(item_lower.len(), 0, query.len());
+        }
+        result
+    });
 
-    // Use DP: dp[i] = (max_score_to_reach_here, prev_position_index_in_prev_query_char_matches)
-    // But for simplicity and performance, use a greedy approach with consecutive lookahead
+    let base = score_match_inner(
+        &item_lower[lower_off..],
+        &item_original[orig_off..],
+        query,
+        config,
+    )
+    .map(|mut result| {
+        for pos in &mut result.positions {
+            *pos += base_start;
+        }
+        result.item_len += base_start;
+        result.score += config.basename_bonus;
+        result
+    });
 
-    // Actually, let's use a proper DP for correctness:
-    // State: dp[query_idx][pos_idx] = best score to match query[0..=query_idx] ending at match_positions[query_idx][pos_idx]
-    // Transition: dp[i][j] = max over all k where match_positions[i-1][k] < match_positions[i][j] of:
-    //             dp[i-1][k] + bonus(match_positions[i-1][k], match_positions[i][j])
+    match (base, full) {
+        (Some(base), Some(full)) if full.tier > base.tier => Some(full),
+        (Some(base), _) => Some(base),
+        (None, full) => full,
+    }
+}
 
-    // This is O(m * k^2) where k is avg positions per char, which is fine for typical inputs
+/// Check that every query byte occurs in `item` in order, using memchr to skip ahead.
+fn is_ascii_subsequence(item: &[u8], query: &[u8]) -> bool {
+    let mut start = 0;
+    for &qb in query {
+        match memchr::memchr(qb, &item[start..]) {
+            Some(offset) => start += offset + 1,
+            None => return false,
+        }
+    }
+    true
+}
 
-    // dp[j] represents the best score ending at match_positions[current_query_idx][j]
-    // prev[j] represents the index in the previous query char's positions that led to this best score
+/// Find the match positions that maximize the fuzzy score.
+///
+/// Dynamic programming over the candidate positions of each query
+/// character, using the same components as [`position_breakdown`] (gap
+/// starts are only charged once per run of gaps, so each state also tracks
+/// whether it ends in a gap). The positions returned are therefore the ones
+/// the score is computed from, and the ones highlighted.
+/// `ascii_bytes` is the item as bytes when it is pure ASCII, enabling memchr scans.
+fn find_optimal_positions(
+    item_chars: &[char],
+    original_chars: &[char],
+    query_chars: &[char],
+    config: &ScoringConfig,
+    ascii_bytes: Option<&[u8]>,
+) -> Option<Vec<usize>> {
+    let n = item_chars.len();
+    let m = query_chars.len();
 
-    let mut dp: Vec<i32> = first_positions
-        .iter()
-        .map(|&pos| {
-            // Score for first character at this position
-            let mut s = scores::MATCH + scores::FIRST_CHAR;
+    if m == 0 {
+        return Some(Vec::new());
+    }
+    if n < m {
+        return None;
+    }
+
+    // For each query character, find all positions where it matches in the item
+    let mut match_positions: Vec<Vec<usize>> = Vec::with_capacity(m);
+    for &qc in query_chars {
+        let positions: Vec<usize> = match ascii_bytes {
+            Some(bytes) if qc.is_ascii() => memchr::memchr_iter(qc as u8, bytes).collect(),
+            _ => item_chars
+                .iter()
+                .enumerate()
+                .filter(|(_, &ic)| ic == qc)
+                .map(|(i, _)| i)
+                .collect(),
+        };
+
+        if positions.is_empty() {
+            return None; // Query char not found, no match possible
+        }
+        match_positions.push(positions);
+    }
+
+    // Score of matching query char `qi` at `pos`, independent of its neighbours
+    let match_score = |qi: usize, pos: usize| {
+        let mut s = scores::MATCH;
+        if qi == 0 {
+            s += scores::FIRST_CHAR;
             if pos == 0 {
                 s += scores::BOUNDARY;
             }
-            // Position bonus (earlier is better)
-            s += (item_chars.len() as i32 - pos as i32).min(20);
-            s
-        })
+        }
+        let prev_char = pos
+            .checked_sub(1)
+            .and_then(|p| original_chars.get(p).copied());
+        let current = original_chars.get(pos).copied().unwrap_or(query_chars[qi]);
+        if is_word_boundary(prev_char, current, &config.boundary_chars) {
+            s += scores::BOUNDARY;
+        }
+        s + (n as i32 - pos as i32).min(20)
+    };
+
+    // dp[j][g]: best score for query[0..=qi] ending at match_positions[qi][j],
+    // where g is 1 if the last step was a gap. back[qi][j][g] is the
+    // (index, gap state) of the previous query char on that best path.
+    const NONE: (usize, usize) = (usize::MAX, 0);
+    let mut dp: Vec<[i32; 2]> = match_positions[0]
+        .iter()
+        .map(|&pos| [match_score(0, pos), i32::MIN])
         .collect();
-
-    let mut prev_indices: Vec<Vec<usize>> = vec![vec![usize::MAX; first_positions.len()]];
+    let mut back: Vec<Vec<[(usize, usize); 2]>> = vec![vec![[NONE; 2]; dp.len()]];
 
     for qi in 1..m {
         let curr_positions = &match_positions[qi];
         let prev_positions = &match_positions[qi - 1];
 
-        let mut new_dp = vec![i32::MIN; curr_positions.len()];
-        let mut new_prev = vec![usize::MAX; curr_positions.len()];
+        let mut new_dp = vec![[i32::MIN; 2]; curr_positions.len()];
+        let mut new_back = vec![[NONE; 2]; curr_positions.len()];
 
         for (cj, &curr_pos) in curr_positions.iter().enumerate() {
+            let here = match_score(qi, curr_pos);
             for (pj, &prev_pos) in prev_positions.iter().enumerate() {
                 if prev_pos >= curr_pos {
-                    continue; // Positions must be strictly increasing
+                    break; // Positions must be strictly increasing
                 }
-
-                let prev_score = dp[pj];
-                if prev_score == i32::MIN {
-                    continue;
-                }
-
-                // Calculate transition score
-                let mut trans_score = scores::MATCH;
-
-                // Consecutive bonus
-                if curr_pos == prev_pos + 1 {
-                    trans_score += scores::CONSECUTIVE;
-                } else {
-                    // Gap penalty
-                    trans_score += scores::GAP_START;
-                    let gap_size = (curr_pos - prev_pos - 1) as i32;
-                    trans_score += (gap_size * scores::GAP_EXTEND).max(scores::GAP_MAX);
-                }
-
-                // Position bonus
-                trans_score += (item_chars.len() as i32 - curr_pos as i32).min(20);
-
-                let total = prev_score + trans_score;
-                if total > new_dp[cj] {
-                    new_dp[cj] = total;
-                    new_prev[cj] = pj;
+                for (g, &prev_score) in dp[pj].iter().enumerate() {
+                    if prev_score == i32::MIN {
+                        continue;
+                    }
+                    let (step, new_g) = if curr_pos == prev_pos + 1 {
+                        (scores::CONSECUTIVE, 0)
+                    } else {
+                        let gap_size = (curr_pos - prev_pos - 1) as i32;
+                        let start = if g == 0 { config.gap_start } else { 0 };
+                        (
+                            start + (gap_size * config.gap_extend).max(config.gap_max),
+                            1,
+                        )
+                    };
+                    let total = prev_score + step + here;
+                    if total > new_dp[cj][new_g] {
+                        new_dp[cj][new_g] = total;
+                        new_back[cj][new_g] = (pj, g);
+                    }
                 }
             }
         }
 
         dp = new_dp;
-        prev_indices.push(new_prev);
+        back.push(new_back);
     }
 
-    // Find the best ending position
-    let last_positions = &match_positions[m - 1];
-    let mut best_score = i32::MIN;
-    let mut best_idx = 0;
-
-    for (j, &score) in dp.iter().enumerate() {
-        if score > best_score {
-            best_score = score;
-            best_idx = j;
+    // Find the best ending state (earliest position on ties)
+    let mut best = (i32::MIN, NONE);
+    for (j, scores) in dp.iter().enumerate() {
+        for (g, &score) in scores.iter().enumerate() {
+            if score > best.0 {
+                best = (score, (j, g));
+            }
         }
     }
-
-    if best_score == i32::MIN {
+    if best.0 == i32::MIN {
         return None; // No valid path found
     }
 
     // Reconstruct the path
-    selected_positions.resize(m, 0);
-    selected_positions[m - 1] = last_positions[best_idx];
-
-    let mut current_idx = best_idx;
+    let mut selected_positions = vec![0; m];
+    let (mut j, mut g) = best.1;
     for qi in (1..m).rev() {
-        let prev_idx = prev_indices[qi][current_idx];
-        if prev_idx == usize::MAX {
-            return None; // Should not happen if best_score is valid
-        }
-        selected_positions[qi - 1] = match_positions[qi - 1][prev_idx];
-        current_idx = prev_idx;
+        selected_positions[qi] = match_positions[qi][j];
+        (j, g) = back[qi][j][g];
     }
+    selected_positions[0] = match_positions[0][j];
 
     Some(selected_positions)
 }
@@ -341,39 +842,51 @@ fn calculate_score_for_positions(
     item_chars: &[char],
     original_chars: &[char],
     query_chars: &[char],
+    config: &ScoringConfig,
 ) -> i32 {
+    position_breakdown(positions, item_chars, original_chars, query_chars, config).total
+}
+
+/// Per-component fuzzy score for a set of match positions
+fn position_breakdown(
+    positions: &[usize],
+    item_chars: &[char],
+    original_chars: &[char],
+    query_chars: &[char],
+    config: &ScoringConfig,
+) -> ScoreBreakdown {
+    let mut parts = ScoreBreakdown::new(MatchTier::Fuzzy);
     if positions.is_empty() {
-        return 0;
+        return parts;
     }
 
-    let mut score: i32 = 0;
     let mut prev_pos: Option<usize> = None;
     let mut in_gap = false;
 
     for (qi, &pos) in positions.iter().enumerate() {
         // Base match score
-        score += scores::MATCH;
+        parts.matched += scores::MATCH;
 
         // First character bonus
         if qi == 0 {
-            score += scores::FIRST_CHAR;
+            parts.first_char += scores::FIRST_CHAR;
             if pos == 0 {
-                score += scores::BOUNDARY;
+                parts.boundary += scores::BOUNDARY;
             }
         }
 
         // Consecutive/gap handling
         if let Some(prev) = prev_pos {
             if pos == prev + 1 {
-                score += scores::CONSECUTIVE;
+                parts.consecutive += scores::CONSECUTIVE;
                 in_gap = false;
             } else {
                 if !in_gap {
-                    score += scores::GAP_START;
+                    parts.gaps += config.gap_start;
                     in_gap = true;
                 }
                 let gap_size = (pos - prev - 1) as i32;
-                score += (gap_size * scores::GAP_EXTEND).max(scores::GAP_MAX);
+                parts.gaps += (gap_size * config.gap_extend).max(config.gap_max);
             }
         }
 
@@ -384,21 +897,167 @@ fn calculate_score_for_positions(
             None
         };
         let current_original = original_chars.get(pos).copied().unwrap_or(query_chars[qi]);
-        if is_word_boundary(prev_char, current_original) {
-            score += scores::BOUNDARY;
+        if is_word_boundary(prev_char, current_original, &config.boundary_chars) {
+            parts.boundary += scores::BOUNDARY;
         }
 
         // Position bonus
-        score += (item_chars.len() as i32 - pos as i32).min(20);
+        parts.position += (item_chars.len() as i32 - pos as i32).min(20);
 
         prev_pos = Some(pos);
     }
 
     // Length penalty
-    let length_penalty = (item_chars.len() as i32 - query_chars.len() as i32).min(50);
-    score -= length_penalty;
+    parts.length_penalty = -(item_chars.len() as i32 - query_chars.len() as i32).min(50);
+    parts.total = parts.component_sum();
+    parts
+}
+
+/// How a match score is made up, for debugging rankings.
+///
+/// The components always sum to `total`, which equals the score of the
+/// corresponding [`MatchResult`]. Penalties (`gaps`, `length_penalty`) are
+/// negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoreBreakdown {
+    /// Match quality tier
+    pub tier: MatchTier,
+    /// Final score
+    pub total: i32,
+    /// Base score for the tier (exact, prefix or substring match)
+    pub exactness: i32,
+    /// Per-character match score
+    pub matched: i32,
+    /// Bonus for the first query character
+    pub first_char: i32,
+    /// Bonus for adjacent matched characters
+    pub consecutive: i32,
+    /// Bonus for matches at word boundaries and camelCase humps
+    pub boundary: i32,
+    /// Penalty for unmatched runs between matched characters
+    pub gaps: i32,
+    /// Bonus for matching early in the item
+    pub position: i32,
+    /// Penalty for item length beyond the query
+    pub length_penalty: i32,
+    /// Everything else: acronym, basename and history bonuses, word-mode
+    /// scoring, and clamping to the tier's range
+    pub other: i32,
+}
 
-    score
+impl ScoreBreakdown {
+    fn new(tier: MatchTier) -> Self {
+        Self {
+            tier,
+            total: 0,
+            exactness: 0,
+            matched: 0,
+            first_char: 0,
+            consecutive: 0,
+            boundary: 0,
+            gaps: 0,
+            position: 0,
+            length_penalty: 0,
+            other: 0,
+        }
+    }
+
+    fn component_sum(&self) -> i32 {
+        self.exactness
+            + self.matched
+            + self.first_char
+            + self.consecutive
+            + self.boundary
+            + self.gaps
+            + self.position
+            + self.length_penalty
+    }
+}
+
+impl std::fmt::Display for ScoreBreakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {}", self.tier, self.total)?;
+        let parts = [
+            ("exact", self.exactness),
+            ("match", self.matched),
+            ("first", self.first_char),
+            ("consec", self.consecutive),
+            ("bound", self.boundary),
+            ("gap", self.gaps),
+            ("pos", self.position),
+            ("len", self.length_penalty),
+            ("other", self.other),
+        ];
+        for (name, value) in parts.iter().filter(|(_, v)| *v != 0) {
+            write!(f, " {name}{value:+}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Explain how `item` scores against `query` with the default configuration.
+///
+/// Matching is case-insensitive and ignores ANSI escape sequences, as in
+/// batch scoring.
+/// Returns `None` if the item does not match.
+pub fn score_match_explain(item: &str, query: &str) -> Option<ScoreBreakdown> {
+    score_match_explain_with_config(item, query, &ScoringConfig::default())
+}
+
+/// Explain how `item` scores against `query` under `config`.
+///
+/// The match is scored normally, then its components are recomputed from the
+/// matched positions. Anything the mode or scheme adds on top of the base
+/// scoring ends up in [`ScoreBreakdown::other`].
+pub fn score_match_explain_with_config(
+    item: &str,
+    query: &str,
+    config: &ScoringConfig,
+) -> Option<ScoreBreakdown> {
+    let clean = strip_ansi_sequences(item);
+    let item_lower = clean.to_lowercase();
+    let query_lower = query.to_lowercase();
+    let mut result = score_match_with_config(&item_lower, &clean, &query_lower, config)?;
+    if let Some(history) = &config.history {
+        result.score += history.boost(item, history::now_secs());
+    }
+
+    let mut parts = match result.tier {
+        MatchTier::Exact => {
+            let mut parts = ScoreBreakdown::new(MatchTier::Exact);
+            parts.exactness = scores::EXACT;
+            parts
+        }
+        MatchTier::Prefix | MatchTier::Substring => {
+            let mut parts = ScoreBreakdown::new(result.tier);
+            parts.consecutive = query_lower.len() as i32 * scores::CONSECUTIVE;
+            if result.tier == MatchTier::Prefix {
+                parts.exactness = scores::PREFIX;
+            } else {
+                parts.exactness = scores::PREFIX / 2;
+                let start = result.positions.first().map_or(0, |&p| {
+                    item_lower.char_indices().nth(p).map_or(0, |(b, _)| b)
+                });
+                parts.position = ((item_lower.len() - start) as i32 * 2).min(100);
+            }
+            parts
+        }
+        MatchTier::Fuzzy => {
+            let item_chars: Vec<char> = item_lower.chars().collect();
+            let original_chars: Vec<char> = clean.chars().collect();
+            let query_chars: Vec<char> = query_lower.chars().collect();
+            position_breakdown(
+                &result.positions,
+                &item_chars,
+                &original_chars,
+                &query_chars,
+                config,
+            )
+        }
+    };
+    parts.total = result.score;
+    parts.other = result.score - parts.component_sum();
+    Some(parts)
 }
 
 /// Score and match an item against a query, handling case insensitivity.
@@ -412,7 +1071,7 @@ pub fn score_match_case_insensitive(item: &str, query: &str) -> Option<MatchResu
 }
 
 /// Strip ANSI escape sequences from a string
-fn strip_ansi_sequences(s: &str) -> String {
+pub(crate) fn strip_ansi_sequences(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
     let mut chars = s.chars();
     while let Some(ch) = chars.next() {
@@ -438,46 +1097,214 @@ fn strip_ansi_sequences(s: &str) -> String {
 /// ANSI escape sequences are stripped before matching so that colored
 /// items (e.g. from `eza --color=always`) still match correctly.
 pub fn score_batch(items: &[String], query: &str) -> Vec<(usize, MatchResult)> {
-    if query.is_empty() {
-        // Return all items with zero score, preserving order
-        return items
-            .iter()
-            .enumerate()
-            .map(|(idx, _)| {
-                (
-                    idx,
-                    MatchResult {
-                        score: 0,
-                        positions: Vec::new(),
-                        tier: MatchTier::Fuzzy,
-                    },
-                )
+    score_batch_with_config(items, query, &ScoringConfig::default())
+}
+
+/// Batch score multiple items against a query using the given scoring configuration.
+pub fn score_batch_with_config(
+    items: &[String],
+    query: &str,
+    config: &ScoringConfig,
+) -> Vec<(usize, MatchResult)> {
+    score_batch_filtered(items, query, config, None, None)
+}
+
+/// Item count above which batch scoring is split across threads
+const PARALLEL_THRESHOLD: usize = 20_000;
+
+/// Tiered ordering: tier desc, score desc, original index asc
+fn compare_results(a: &(usize, MatchResult), b: &(usize, MatchResult)) -> std::cmp::Ordering {
+    b.1.tier
+        .cmp(&a.1.tier)
+        .then_with(|| b.1.score.cmp(&a.1.score))
+        .then_with(|| a.0.cmp(&b.0))
+}
+
+/// Batch score items, dropping matches below `min_score` and keeping at most
+/// the `max_results` best-ranked matches.
+///
+/// With an empty query every item is returned in input order (capped at
+/// `max_results`) and `min_score` does not apply.
+pub fn score_batch_filtered(
+    items: &[String],
+    query: &str,
+    config: &ScoringConfig,
+    min_score: Option<i32>,
+    max_results: Option<usize>,
+) -> Vec<(usize, MatchResult)> {
+    score_batch_inner(
+        items,
+        &[],
+        None,
+        query,
+        config,
+        min_score,
+        max_results,
+        None,
+    )
+    .unwrap_or_default()
+}
+
+/// Like [`score_batch_filtered`], but only the items at `candidates`
+/// (ascending indices into `items`) are scored, e.g. after pruning with a
+/// [`CandidateIndex`](crate::fuzzy::index::CandidateIndex).
+pub fn score_candidates(
+    items: &[String],
+    candidates: &[usize],
+    query: &str,
+    config: &ScoringConfig,
+    min_score: Option<i32>,
+    max_results: Option<usize>,
+) -> Vec<(usize, MatchResult)> {
+    score_batch_inner(
+        items,
+        &[],
+        Some(candidates),
+        query,
+        config,
+        min_score,
+        max_results,
+        None,
+    )
+    .unwrap_or_default()
+}
+
+/// Like [`score_candidates`] (or [`score_batch_filtered`] when `candidates`
+/// is `None`), but checks `cancel` while scoring and returns `None` as soon
+/// as it is cancelled. `weights[i]` is added to the score of item `i`
+/// (items beyond the end of `weights` get no boost).
+#[allow(clippy::too_many_arguments)]
+pub fn score_batch_cancellable(
+    items: &[String],
+    weights: &[i32],
+    candidates: Option<&[usize]>,
+    query: &str,
+    config: &ScoringConfig,
+    min_score: Option<i32>,
+    max_results: Option<usize>,
+    cancel: &CancelToken,
+) -> Option<Vec<(usize, MatchResult)>> {
+    score_batch_inner(
+        items,
+        weights,
+        candidates,
+        query,
+        config,
+        min_score,
+        max_results,
+        Some(cancel),
+    )
+}
+
+/// Run `score` over `0..len`, split into ranges across threads for large inputs
+fn score_ranges<F>(len: usize, score: F) -> Vec<(usize, MatchResult)>
+where
+    F: Fn(std::ops::Range<usize>) -> Vec<(usize, MatchResult)> + Sync,
+{
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if len < PARALLEL_THRESHOLD || threads < 2 {
+        return score(0..len);
+    }
+
+    let chunk_size = len.div_ceil(threads);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..len)
+            .step_by(chunk_size)
+            .map(|start| {
+                let score = &score;
+                scope.spawn(move || score(start..(start + chunk_size).min(len)))
             })
             .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("scoring thread panicked"))
+            .collect()
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn score_batch_inner(
+    items: &[String],
+    weights: &[i32],
+    candidates: Option<&[usize]>,
+    query: &str,
+    config: &ScoringConfig,
+    min_score: Option<i32>,
+    max_results: Option<usize>,
+    cancel: Option<&CancelToken>,
+) -> Option<Vec<(usize, MatchResult)>> {
+    let cancelled = || cancel.is_some_and(CancelToken::is_cancelled);
+    let limit = max_results.unwrap_or(usize::MAX);
+
+    if query.is_empty() {
+        // Return all items with zero score, preserving order
+        let empty = |idx: usize| {
+            (
+                idx,
+                MatchResult {
+                    score: 0,
+                    positions: Vec::new(),
+                    tier: MatchTier::Fuzzy,
+                    item_len: items[idx].chars().count(),
+                },
+            )
+        };
+        return Some(match candidates {
+            Some(candidates) => candidates.iter().take(limit).map(|&i| empty(i)).collect(),
+            None => (0..items.len()).take(limit).map(empty).collect(),
+        });
     }
 
     let query_lower = query.to_lowercase();
+    let now = history::now_secs();
+
+    let score_one = |idx: usize| -> Option<(usize, MatchResult)> {
+        let item = &items[idx];
+        let clean = strip_ansi_sequences(item);
+        let clean_lower = clean.to_lowercase();
+        let mut result = score_match_with_config(&clean_lower, &clean, &query_lower, config)?;
+        if let Some(history) = &config.history {
+            result.score += history.boost(item, now);
+        }
+        result.score += weights.get(idx).copied().unwrap_or(0);
+        min_score
+            .is_none_or(|min| result.score >= min)
+            .then_some((idx, result))
+    };
+
+    // Each thread stops taking items once the pass is cancelled
+    let mut results: Vec<(usize, MatchResult)> = match candidates {
+        Some(candidates) => score_ranges(candidates.len(), |range| {
+            candidates[range]
+                .iter()
+                .take_while(|_| !cancelled())
+                .filter_map(|&i| score_one(i))
+                .collect()
+        }),
+        None => score_ranges(items.len(), |range| {
+            range
+                .take_while(|_| !cancelled())
+                .filter_map(score_one)
+                .collect()
+        }),
+    };
+    if cancelled() {
+        return None;
+    }
 
-    let mut results: Vec<(usize, MatchResult)> = items
-        .iter()
-        .enumerate()
-        .filter_map(|(idx, item)| {
-            let clean = strip_ansi_sequences(item);
-            let clean_lower = clean.to_lowercase();
-            score_match_with_original(&clean_lower, &clean, &query_lower)
-                .map(|result| (idx, result))
-        })
-        .collect();
+    // Partition out the best `limit` matches before sorting only those
+    if limit < results.len() {
+        if limit == 0 {
+            return Some(Vec::new());
+        }
+        results.select_nth_unstable_by(limit - 1, compare_results);
+        results.truncate(limit);
+    }
 
-    // Stable tiered sort: tier desc, score desc, original index asc
-    results.sort_by(|a, b| {
-        b.1.tier
-            .cmp(&a.1.tier)
-            .then_with(|| b.1.score.cmp(&a.1.score))
-            .then_with(|| a.0.cmp(&b.0))
-    });
+    // The index tie-break makes this deterministic
+    results.sort_unstable_by(compare_results);
 
-    results
+    Some(results)
 }
 
 #[cfg(test)]
@@ -737,7 +1564,14 @@ mod tests {
         let item_chars: Vec<char> = "xabcxabc".chars().collect();
         let query_chars: Vec<char> = "abc".chars().collect();
 
-        let positions = find_optimal_positions(&item_chars, &query_chars).unwrap();
+        let positions = find_optimal_positions(
+            &item_chars,
+            &item_chars,
+            &query_chars,
+            &DEFAULT_CONFIG,
+            None,
+        )
+        .unwrap();
 
         // Should find positions 1,2,3 (first "abc") which is consecutive
         // Not 1,2,7 or other scattered combinations
@@ -750,12 +1584,104 @@ mod tests {
         let item_chars: Vec<char> = "abxxxab".chars().collect();
         let query_chars: Vec<char> = "ab".chars().collect();
 
-        let positions = find_optimal_positions(&item_chars, &query_chars).unwrap();
+        let positions = find_optimal_positions(
+            &item_chars,
+            &item_chars,
+            &query_chars,
+            &DEFAULT_CONFIG,
+            None,
+        )
+        .unwrap();
 
         // Both 0,1 and 5,6 are consecutive, but 0,1 is earlier (higher position bonus)
         assert_eq!(positions, vec![0, 1]);
     }
 
+    /// Best fuzzy score over every way of matching `query` in `item`
+    fn brute_force_best_score(item: &str, query: &str) -> i32 {
+        fn search(
+            item: &[char],
+            query: &[char],
+            from: usize,
+            chosen: &mut Vec<usize>,
+            best: &mut i32,
+        ) {
+            if chosen.len() == query.len() {
+                let score =
+                    calculate_score_for_positions(chosen, item, item, query, &DEFAULT_CONFIG);
+                *best = (*best).max(score);
+                return;
+            }
+            for pos in from..item.len() {
+                if item[pos] == query[chosen.len()] {
+                    chosen.push(pos);
+                    search(item, query, pos + 1, chosen, best);
+                    chosen.pop();
+                }
+            }
+        }
+        let item: Vec<char> = item.chars().collect();
+        let query: Vec<char> = query.chars().collect();
+        let mut best = i32::MIN;
+        search(&item, &query, 0, &mut Vec::new(), &mut best);
+        best
+    }
+
+    #[test]
+    fn test_optimal_positions_maximize_score() {
+        let cases = [
+            ("src/fuzzy/scoring.rs", "fzsc"),
+            ("a_b_c ab_c abc", "abc"),
+            ("xa_xb_xc_abxc", "abc"),
+            ("foo/bar/foobar_baz", "fbz"),
+            ("my_awesome_input.rs", "main"),
+            ("aaabaaab", "ab"),
+        ];
+        for (item, query) in cases {
+            let item_chars: Vec<char> = item.chars().collect();
+            let query_chars: Vec<char> = query.chars().collect();
+            let positions = find_optimal_positions(
+                &item_chars,
+                &item_chars,
+                &query_chars,
+                &DEFAULT_CONFIG,
+                None,
+            )
+            .unwrap();
+            let score = calculate_score_for_positions(
+                &positions,
+                &item_chars,
+                &item_chars,
+                &query_chars,
+                &DEFAULT_CONFIG,
+            );
+            assert_eq!(
+                score,
+                brute_force_best_score(item, query),
+                "{item} / {query}: {positions:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_optimal_positions_prefer_word_start() {
+        let item_chars: Vec<char> = "xrx_rx".chars().collect();
+        let query_chars: Vec<char> = "r".chars().collect();
+        let positions = find_optimal_positions(
+            &item_chars,
+            &item_chars,
+            &query_chars,
+            &DEFAULT_CONFIG,
+            None,
+        )
+        .unwrap();
+        assert_eq!(positions, vec![4]);
+
+        // Highlights follow the boundary-heavy match the score was computed from
+        let result = score_match("xoxtxdx open_text_dir", "otd").unwrap();
+        assert_eq!(result.positions, vec![8, 13, 18]);
+    }
+
     #[test]
     fn test_tier_exact_beats_prefix() {
         let exact = score_match("ff", "ff").unwrap();
@@ -800,4 +1726,428 @@ mod tests {
         assert_eq!(prefix.tier, MatchTier::Prefix);
         assert!(exact.score > prefix.score);
     }
+
+    fn path_config() -> ScoringConfig {
+        ScoringConfig {
+            scheme: Scheme::Path,
+            ..ScoringConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_scheme_parse() {
+        assert_eq!(Scheme::parse("path").unwrap(), Scheme::Path);
+        assert_eq!(Scheme::parse("Default").unwrap(), Scheme::Default);
+        assert!(Scheme::parse("bogus").is_err());
+    }
+
+    #[test]
+    fn test_path_scheme_prefers_basename() {
+        let items = vec![
+            "main_helpers/util.rs".to_string(),
+            "src/main.rs".to_string(),
+        ];
+
+        // Default scheme: the directory prefix match wins
+        let results = score_batch(&items, "main");
+        assert_eq!(results[0].0, 0);
+
+        // Path scheme: the basename match wins
+        let results = score_batch_with_config(&items, "main", &path_config());
+        assert_eq!(results[0].0, 1);
+        assert_eq!(results[0].1.positions, vec![4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_path_scheme_without_separator_matches_default() {
+        let config = path_config();
+        let plain = score_match_with_original("readme.md", "README.md", "read").unwrap();
+        let path = score_match_with_config("readme.md", "README.md", "read", &config).unwrap();
+        assert_eq!(plain.score, path.score);
+        assert_eq!(plain.positions, path.positions);
+    }
+
+    #[test]
+    fn test_scheme_presets() {
+        assert_eq!(Scheme::parse("paths"), Ok(Scheme::Path));
+        assert_eq!(Scheme::parse("Identifier"), Ok(Scheme::Identifiers));
+        assert!(Scheme::parse("bogus").unwrap_err().contains("commands"));
+        assert_eq!(
+            ScoringConfig::preset(Scheme::Default),
+            ScoringConfig::default()
+        );
+        assert_eq!(ScoringConfig::preset(Scheme::Prose).scheme, Scheme::Prose);
+
+        let score = |item: &str, query: &str, scheme| {
+            score_match_with_config(item, item, query, &ScoringConfig::preset(scheme))
+                .unwrap()
+                .score
+        };
+        // Gaps cost least for commands and most for prose
+        let (item, query) = ("git commit --amend", "gcam");
+        assert!(score(item, query, Scheme::Commands) > score(item, query, Scheme::Default));
+        assert!(score(item, query, Scheme::Prose) < score(item, query, Scheme::Default));
+        // Brackets start words in identifiers
+        let (item, query) = ("vec<string>", "vs");
+        assert!(score(item, query, Scheme::Identifiers) > score(item, query, Scheme::Default));
+        // Values of `key=value` pairs start words in commands
+        let (item, query) = ("exec key=pval", "kp");
+        assert!(score(item, query, Scheme::Commands) > score(item, query, Scheme::Default));
+    }
+
+    #[test]
+    fn test_custom_boundary_chars() {
+        let config = ScoringConfig {
+            boundary_chars: vec!['='],
+            ..ScoringConfig::default()
+        };
+        let default = ScoringConfig::default();
+        let plain = score_match_with_config("key=value", "key=value", "kv", &default).unwrap();
+        let custom = score_match_with_config("key=value", "key=value", "kv", &config).unwrap();
+        assert_eq!(custom.positions, vec![0, 4]);
+        // `v` now starts a word, so `kv` also becomes an acronym match
+        assert_eq!(
+            custom.score - plain.score,
+            scores::BOUNDARY + scores::ACRONYM
+        );
+    }
+
+    #[test]
+    fn test_explain_sums_to_score() {
+        let cases = [
+            ("main.rs", "main.rs"),
+            ("main.rs", "mai"),
+            ("src/main.rs", "main"),
+            ("src/fuzzy/scoring.rs", "fzsc"),
+            ("ReadFileContents", "rfc"),
+        ];
+        for (item, query) in cases {
+            let parts = score_match_explain(item, query).unwrap();
+            let result = score_match_case_insensitive(item, query).unwrap();
+            assert_eq!(parts.total, result.score, "{item} / {query}");
+            assert_eq!(parts.tier, result.tier);
+            assert_eq!(parts.component_sum() + parts.other, parts.total);
+        }
+        assert!(score_match_explain("main.rs", "xyz").is_none());
+    }
+
+    #[test]
+    fn test_explain_components() {
+        let parts = score_match_explain("src/fuzzy/scoring.rs", "fzsc").unwrap();
+        assert_eq!(parts.tier, MatchTier::Fuzzy);
+        assert_eq!(parts.exactness, 0);
+        assert_eq!(parts.matched, 4 * scores::MATCH);
+        assert!(parts.gaps < 0);
+        assert!(parts.length_penalty < 0);
+        assert_eq!(parts.other, 0);
+
+        // The acronym bonus is reported separately from the position-based parts
+        let parts = score_match_explain("ReadFileContents", "rfc").unwrap();
+        assert_eq!(parts.boundary, 3 * scores::BOUNDARY + scores::BOUNDARY);
+        assert_eq!(parts.other, scores::ACRONYM);
+
+        let parts = score_match_explain("src/main.rs", "main").unwrap();
+        assert_eq!(parts.exactness, scores::PREFIX / 2);
+        assert!(parts.to_string().starts_with("Substring "));
+    }
+
+    #[test]
+    fn test_acronym_beats_scattered_letters() {
+        let items = vec![
+            "src/fractal.c".to_string(),
+            "ReadFileContents".to_string(),
+            "read_file_contents.rs".to_string(),
+        ];
+        let results = score_batch(&items, "rfc");
+        let order: Vec<usize> = results.iter().map(|(i, _)| *i).collect();
+        assert_eq!(order[2], 0);
+        let camel =
+            score_match_with_original("readfilecontents", "ReadFileContents", "rfc").unwrap();
+        assert_eq!(camel.positions, vec![0, 4, 8]);
+    }
+
+    #[test]
+    fn test_acronym_mode_requires_word_heads() {
+        let config = ScoringConfig {
+            mode: MatchMode::Acronym,
+            ..ScoringConfig::default()
+        };
+        let m = score_match_with_config(
+            "read_file_contents.rs",
+            "read_file_contents.rs",
+            "rfc",
+            &config,
+        )
+        .unwrap();
+        assert_eq!(m.positions, vec![0, 5, 10]);
+        assert!(
+            score_match_with_config("src/fractal.c", "src/fractal.c", "rfc", &config).is_none()
+        );
+        assert!(score_match_with_config("rfc", "rfc", "rfc", &config).is_some());
+        assert_eq!(MatchMode::parse("acronym"), Ok(MatchMode::Acronym));
+    }
+
+    #[test]
+    fn test_weights_boost_within_tier() {
+        let items = vec![
+            "src/main.rs".to_string(),
+            "src/mainline.rs".to_string(),
+            "main".to_string(),
+        ];
+        let cancel = CancelToken::new();
+        let config = ScoringConfig::default();
+        let results = score_batch_cancellable(
+            &items,
+            &[0, 500],
+            None,
+            "main",
+            &config,
+            None,
+            None,
+            &cancel,
+        )
+        .unwrap();
+        let order: Vec<usize> = results.iter().map(|(i, _)| *i).collect();
+        // The weight reorders substring matches but cannot beat the exact match
+        assert_eq!(order, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_history_boosts_within_tier() {
+        let items = vec![
+            "config.toml".to_string(),
+            "src/config.rs".to_string(),
+            "cfg".to_string(),
+        ];
+        let mut history = History::new();
+        history.record(["src/config.rs"], history::now_secs());
+        let config = ScoringConfig {
+            history: Some(Arc::new(history)),
+            ..ScoringConfig::default()
+        };
+
+        let results = score_batch(&items, "conf");
+        assert_eq!(results[0].0, 0);
+
+        // The boosted substring match overtakes the other substring match,
+        // but never the prefix match
+        let results = score_batch_with_config(&items, "config", &config);
+        assert_eq!(results[0].0, 0);
+        let results = score_batch_with_config(&items, "onfig", &config);
+        assert_eq!(results[0].0, 1);
+    }
+
+    #[test]
+    fn test_score_batch_filtered_limits() {
+        let items: Vec<String> = (0..50).map(|i| format!("item{i:02}")).collect();
+        let config = ScoringConfig::default();
+        let all = score_batch_with_config(&items, "item", &config);
+
+        let top = score_batch_filtered(&items, "item", &config, None, Some(5));
+        let indices = |r: &[(usize, MatchResult)]| r.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+        assert_eq!(indices(&top), indices(&all[..5]));
+
+        assert!(score_batch_filtered(&items, "item", &config, None, Some(0)).is_empty());
+        assert_eq!(
+            score_batch_filtered(&items, "", &config, None, Some(3)).len(),
+            3
+        );
+
+        let exact = score_batch_filtered(&items, "item07", &config, Some(scores::EXACT), None);
+        assert_eq!(exact.len(), 1);
+        assert_eq!(exact[0].0, 7);
+    }
+
+    #[test]
+    fn test_ties_keep_input_order() {
+        // Equal tier and score for every item: ranking must fall back to input order
+        let items: Vec<String> = (0..PARALLEL_THRESHOLD + 7)
+            .map(|i| format!("{}/log.txt", ["a", "b", "c"][i % 3]))
+            .collect();
+        let config = ScoringConfig::default();
+        let expected: Vec<usize> = (0..items.len()).collect();
+
+        let all = score_batch_with_config(&items, "log", &config);
+        assert_eq!(all.iter().map(|(i, _)| *i).collect::<Vec<_>>(), expected);
+
+        let top = score_batch_filtered(&items, "log", &config, None, Some(100));
+        assert_eq!(
+            top.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            expected[..100]
+        );
+    }
+
+    #[test]
+    fn test_cancelled_pass_returns_none() {
+        let items: Vec<String> = (0..PARALLEL_THRESHOLD + 1)
+            .map(|i| format!("item{i}"))
+            .collect();
+        let config = ScoringConfig::default();
+        let cancel = CancelToken::new();
+        let run = |cancel: &CancelToken| {
+            score_batch_cancellable(&items, &[], None, "it9", &config, None, None, cancel)
+        };
+
+        let full = run(&cancel).unwrap();
+        assert_eq!(full.len(), score_batch(&items, "it9").len());
+
+        let clone = cancel.clone();
+        clone.cancel();
+        assert!(cancel.is_cancelled());
+        assert!(run(&cancel).is_none());
+
+        cancel.reset();
+        assert_eq!(run(&cancel).unwrap().len(), full.len());
+    }
+
+    #[test]
+    fn test_word_mode_requires_word_starts() {
+        let config = ScoringConfig {
+            mode: MatchMode::Word,
+            ..ScoringConfig::default()
+        };
+        let word = |item: &str, query: &str| {
+            score_match_with_config(&item.to_lowercase(), item, query, &config)
+        };
+
+        // Subsequence and mid-word matches are rejected
+        assert!(score_match("fix parser crash", "fpc").is_some());
+        assert!(word("fix parser crash", "fpc").is_none());
+        assert!(word("prefix handling", "fix").is_none());
+
+        // Tokens match word prefixes in any order, including camelCase humps
+        let result = word("Fix parser crash on empty input", "crash fix").unwrap();
+        assert_eq!(result.positions, vec![0, 1, 2, 11, 12, 13, 14, 15]);
+        assert!(word("readFileContents", "file con").is_some());
+        assert!(word("fix fix", "fix fix").is_some());
+        assert!(word("fix parser", "fix fix").is_none());
+
+        // In-order whole words beat out-of-order partial words
+        let in_order = word("update parser tests", "parser tests").unwrap();
+        let shuffled = word("tests for parser update", "parser tes").unwrap();
+        assert!(in_order.score > shuffled.score);
+
+        // A contiguous match starting a word keeps its tier
+        assert_eq!(
+            word("parser tests", "parser").unwrap().tier,
+            MatchTier::Prefix
+        );
+    }
+
+    #[test]
+    fn test_ascii_fast_path_matches_char_path() {
+        let cases = [
+            ("src/fuzzy/scoring.rs", "fzsc"),
+            ("aaabaaab", "ab"),
+            ("my_awesome_input.rs", "main"),
+            ("abc", "cab"),
+        ];
+        for (item, query) in cases {
+            let item_chars: Vec<char> = item.chars().collect();
+            let query_chars: Vec<char> = query.chars().collect();
+            assert_eq!(
+                find_optimal_positions(
+                    &item_chars,
+                    &item_chars,
+                    &query_chars,
+                    &DEFAULT_CONFIG,
+                    Some(item.as_bytes())
+                ),
+                find_optimal_positions(
+                    &item_chars,
+                    &item_chars,
+                    &query_chars,
+                    &DEFAULT_CONFIG,
+                    None
+                ),
+                "{item} / {query}"
+            );
+        }
+        assert!(is_ascii_subsequence(b"scoring", b"srg"));
+        assert!(!is_ascii_subsequence(b"scoring", b"gs"));
+    }
+
+    #[test]
+    fn test_non_ascii_items_use_char_path() {
+        let result = score_match("café_menü", "cfm").unwrap();
+        assert_eq!(result.positions, vec![0, 2, 5]);
+        assert!(score_match("naïve", "ïv").is_some());
+    }
+
+    #[test]
+    fn test_parallel_batch_matches_sequential() {
+        let items: Vec<String> = (0..PARALLEL_THRESHOLD + 123)
+            .map(|i| format!("dir{}/file_{i}.txt", i % 17))
+            .collect();
+        let config = ScoringConfig::default();
+        let parallel = score_batch_with_config(&items, "d3fl7", &config);
+
+        let sequential: Vec<(usize, i32)> = items
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, item)| score_match(item, "d3fl7").map(|r| (idx, r.score)))
+            .collect();
+        assert_eq!(parallel.len(), sequential.len());
+
+        let mut got: Vec<(usize, i32)> = parallel.iter().map(|(i, r)| (*i, r.score)).collect();
+        got.sort_unstable();
+        assert_eq!(got, sequential);
+        assert!(parallel
+            .windows(2)
+            .all(|w| compare_results(&w[0], &w[1]).is_lt()));
+    }
+
+    #[test]
+    fn test_score_candidates_matches_full_batch() {
+        let items: Vec<String> = ["apple", "banana", "grape", "pineapple"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let config = ScoringConfig::default();
+        let full = score_batch_with_config(&items, "ape", &config);
+        let pruned = score_candidates(&items, &[0, 2, 3], "ape", &config, None, None);
+        let indices = |r: &[(usize, MatchResult)]| r.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+        assert_eq!(indices(&full), indices(&pruned));
+        assert!(score_candidates(&items, &[1], "ape", &config, None, None).is_empty());
+        assert_eq!(
+            score_candidates(&items, &[3, 1], "", &config, None, Some(1))[0].0,
+            3
+        );
+    }
+
+    #[test]
+    fn test_normalized_score_bands() {
+        let norm = |item: &str, query: &str| {
+            score_match_case_insensitive(item, query)
+                .unwrap()
+                .normalized_score()
+        };
+
+        assert_eq!(norm("main", "main"), 1.0);
+        let prefix = norm("main.rs", "main");
+        let substring = norm("src/main.rs", "main");
+        let fuzzy = norm("my_awesome_input.rs", "main");
+        assert!(prefix > substring && substring > fuzzy && fuzzy > 0.0);
+        assert!(prefix < 1.0);
+
+        // Shorter items with the same kind of match score higher
+        assert!(norm("main.rs", "main") > norm("maintenance.md", "main"));
+        assert_eq!(score_match("anything", "").unwrap().normalized_score(), 0.0);
+    }
+
+    #[test]
+    fn test_path_scheme_normalized_score_uses_full_length() {
+        let result =
+            score_match_with_config("src/main", "src/main", "main", &path_config()).unwrap();
+        assert_eq!(result.item_len, 8);
+        assert!(result.normalized_score() < 1.0);
+    }
+
+    #[test]
+    fn test_path_scheme_keeps_full_path_prefix() {
+        // A query spanning into the basename keeps its prefix tier
+        let config = path_config();
+        let result = score_match_with_config("src/main.rs", "src/main.rs", "src/ma", &config);
+        assert_eq!(result.unwrap().tier, MatchTier::Prefix);
+    }
 }
@@ -1,32 +1,416 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
 use futures::stream::{self, Stream};
 use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::{SendError, TrySendError};
+
+use crate::fuzzy::lsh::{LSHConfig, LSHIndex};
+use crate::fuzzy::scoring::strip_ansi_sequences;
+
+/// Capacity of the item channel created by [`ItemStream::new`]
+pub const DEFAULT_CAPACITY: usize = 1000;
+
+/// Map/filter hook applied to every item added to an [`ItemStream`];
+/// returning `None` drops the item
+#[derive(Clone)]
+pub struct ItemTransform(Arc<dyn Fn(String) -> Option<String> + Send + Sync>);
+
+impl ItemTransform {
+    /// Transform that rewrites an item or drops it by returning `None`
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(String) -> Option<String> + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    /// Transform that rewrites every item
+    pub fn map<F>(f: F) -> Self
+    where
+        F: Fn(String) -> String + Send + Sync + 'static,
+    {
+        Self::new(move |item| Some(f(item)))
+    }
+
+    /// Transform that keeps only items for which `f` returns true
+    pub fn filter<F>(f: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        Self::new(move |item| f(&item).then_some(item))
+    }
+
+    /// Transform that removes ANSI escape sequences (e.g. colors)
+    pub fn strip_ansi() -> Self {
+        Self::map(|item| strip_ansi_sequences(&item))
+    }
+
+    /// Apply the transform to one item
+    pub fn apply(&self, item: String) -> Option<String> {
+        (self.0)(item)
+    }
+}
+
+impl std::fmt::Debug for ItemTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ItemTransform(..)")
+    }
+}
+
+/// Which repeated items an [`ItemStream`] drops as they arrive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dedup {
+    /// Drop items identical to an earlier item
+    Exact,
+    /// Also drop items whose estimated similarity to an earlier item is at
+    /// least the given threshold (0.0-1.0)
+    Similar(f64),
+}
+
+/// Seen-item state for an active [`Dedup`] mode
+struct DedupState {
+    mode: Dedup,
+    seen: HashSet<String>,
+    /// Near-duplicate index and threshold for [`Dedup::Similar`]
+    similar: Option<(LSHIndex, f64)>,
+}
+
+impl DedupState {
+    fn new(mode: Dedup) -> Self {
+        let similar = match mode {
+            Dedup::Exact => None,
+            // Bucket somewhat below the threshold so near-duplicates reliably collide
+            Dedup::Similar(threshold) => Some((
+                LSHIndex::new(LSHConfig::for_threshold(32, threshold * 0.8)),
+                threshold,
+            )),
+        };
+        Self {
+            mode,
+            seen: HashSet::new(),
+            similar,
+        }
+    }
+
+    /// Whether `item` repeats an earlier item
+    fn is_duplicate(&self, item: &str) -> bool {
+        self.seen.contains(item)
+            || self.similar.as_ref().is_some_and(|(lsh, threshold)| {
+                lsh.nearest(item, 1)
+                    .first()
+                    .is_some_and(|&(_, _, similarity)| similarity >= *threshold)
+            })
+    }
+
+    /// Record a kept item so later repeats are dropped
+    fn remember(&mut self, item: &str) {
+        if let Some((lsh, _)) = &mut self.similar {
+            lsh.insert(item);
+        }
+        self.seen.insert(item.to_string());
+    }
+
+    /// Forget a removed item so it can be added again (near-duplicate
+    /// tracking keeps it)
+    fn forget(&mut self, item: &str) {
+        self.seen.remove(item);
+    }
+}
+
+/// An item on the channel: stored already, or sent by an [`ItemSender`] and
+/// stored once received
+struct Fed {
+    item: String,
+    stored: bool,
+}
+
+/// Sending half of the item channel
+#[derive(Clone)]
+enum Feed {
+    Bounded(mpsc::Sender<Fed>),
+    Unbounded(mpsc::UnboundedSender<Fed>),
+}
+
+impl Feed {
+    fn try_send(&self, fed: Fed) -> Result<(), TrySendError<String>> {
+        match self {
+            Feed::Bounded(tx) => tx.try_send(fed).map_err(|e| match e {
+                TrySendError::Full(fed) => TrySendError::Full(fed.item),
+                TrySendError::Closed(fed) => TrySendError::Closed(fed.item),
+            }),
+            Feed::Unbounded(tx) => tx.send(fed).map_err(|e| TrySendError::Closed(e.0.item)),
+        }
+    }
+}
+
+/// Receiving half of the item channel
+enum Drain {
+    Bounded(mpsc::Receiver<Fed>),
+    Unbounded(mpsc::UnboundedReceiver<Fed>),
+}
+
+/// Producer handle for an [`ItemStream`], for adding items from another task.
+///
+/// Items sent are transformed, deduplicated and stored as the stream
+/// [`receive`](ItemStream::receive)s them, so a producer awaiting
+/// [`send`](Self::send) on a bounded stream is held back until the consumer
+/// catches up.
+#[derive(Clone)]
+pub struct ItemSender {
+    tx: Feed,
+}
+
+impl ItemSender {
+    /// Send one item, waiting for channel capacity first. Fails, returning
+    /// the item, once the stream has been dropped.
+    pub async fn send(&self, item: String) -> Result<(), SendError<String>> {
+        let fed = Fed {
+            item,
+            stored: false,
+        };
+        match &self.tx {
+            Feed::Bounded(tx) => tx.send(fed).await,
+            Feed::Unbounded(tx) => tx.send(fed),
+        }
+        .map_err(|e| SendError(e.0.item))
+    }
+
+    /// Send one item if the channel has room, returning
+    /// [`TrySendError::Full`] with the item when it is at capacity
+    pub fn try_send(&self, item: String) -> Result<(), TrySendError<String>> {
+        self.tx.try_send(Fed {
+            item,
+            stored: false,
+        })
+    }
+}
 
 /// Async stream for processing items
 pub struct ItemStream {
     items: Vec<String>,
-    tx: mpsc::Sender<String>,
-    rx: mpsc::Receiver<String>,
+    tx: Feed,
+    rx: Drain,
+    dedup: Option<DedupState>,
+    transforms: Vec<ItemTransform>,
+    /// Bumped whenever the stored items change
+    generation: u64,
 }
 
 impl ItemStream {
-    /// Create a new async item stream
+    /// Create a new async item stream with a channel of [`DEFAULT_CAPACITY`]
     pub fn new() -> Self {
-        let (tx, rx) = mpsc::channel(1000);
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Create a stream whose channel holds at most `capacity` unreceived items.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (tx, rx) = mpsc::channel(capacity);
+        Self {
+            items: Vec::new(),
+            tx: Feed::Bounded(tx),
+            rx: Drain::Bounded(rx),
+            dedup: None,
+            transforms: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    /// Create a stream whose channel never fills up
+    pub fn unbounded() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
         Self {
             items: Vec::new(),
-            tx,
-            rx,
+            tx: Feed::Unbounded(tx),
+            rx: Drain::Unbounded(rx),
+            dedup: None,
+            transforms: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    /// Drop repeated items as they arrive (`None` keeps every item).
+    ///
+    /// Only affects items added afterwards; items already in the stream are
+    /// kept but count as seen.
+    pub fn set_dedup(&mut self, mode: Option<Dedup>) {
+        self.dedup = mode.map(|mode| {
+            let mut state = DedupState::new(mode);
+            for item in &self.items {
+                state.remember(item);
+            }
+            state
+        });
+    }
+
+    /// Register a transform applied, in registration order, to items added
+    /// afterwards (before dedup)
+    pub fn add_transform(&mut self, transform: ItemTransform) {
+        self.transforms.push(transform);
+    }
+
+    /// Remove all registered transforms
+    pub fn clear_transforms(&mut self) {
+        self.transforms.clear();
+    }
+
+    /// Run `item` through the registered transforms
+    fn transform(&self, item: String) -> Option<String> {
+        self.transforms
+            .iter()
+            .try_fold(item, |item, transform| transform.apply(item))
+    }
+
+    /// Whether `item` should be dropped under the active dedup mode
+    fn is_duplicate(&self, item: &str) -> bool {
+        self.dedup
+            .as_ref()
+            .is_some_and(|state| state.is_duplicate(item))
+    }
+
+    /// Store an item that was accepted
+    fn keep(&mut self, item: String) {
+        if let Some(state) = &mut self.dedup {
+            state.remember(&item);
+        }
+        self.items.push(item);
+        self.generation += 1;
+    }
+
+    /// Remove every stored item matching `remove`, returning the removed
+    /// indices in ascending order. Later items move up to fill the gaps.
+    /// Removed items may be added again, except under [`Dedup::Similar`]
+    /// which keeps rejecting their near-duplicates.
+    pub fn remove_where<F>(&mut self, mut remove: F) -> Vec<usize>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let mut removed = Vec::new();
+        let mut idx = 0;
+        let dedup = &mut self.dedup;
+        self.items.retain(|item| {
+            let drop = remove(item);
+            if drop {
+                removed.push(idx);
+                if let Some(state) = dedup.as_mut() {
+                    state.forget(item);
+                }
+            }
+            idx += 1;
+            !drop
+        });
+        if !removed.is_empty() {
+            self.generation += 1;
+        }
+        removed
+    }
+
+    /// Remove every stored item. Unlike [`remove_where`](Self::remove_where),
+    /// dedup starts over, so the same items may be added again.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        if let Some(state) = &mut self.dedup {
+            *state = DedupState::new(state.mode);
+        }
+        self.generation += 1;
+    }
+
+    /// Counter that changes whenever the stored items change, so callers
+    /// can tell whether results derived from them are out of date
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Remaining channel capacity (`None` for unbounded streams)
+    pub fn capacity(&self) -> Option<usize> {
+        match &self.tx {
+            Feed::Bounded(tx) => Some(tx.capacity()),
+            Feed::Unbounded(_) => None,
         }
     }
 
     /// Add items to the stream
+    ///
+    /// Items are always stored; the channel feed for [`receive`](Self::receive)
+    /// is best-effort and skips items while it is full. Use an
+    /// [`ItemSender`] or [`try_send`](Self::try_send) to apply backpressure
+    /// instead.
     pub async fn add_items(&mut self, new_items: Vec<String>) {
         for item in new_items {
-            self.items.push(item.clone());
-            if (self.tx.send(item).await).is_err() {
-                break;
+            self.push(item);
+        }
+    }
+
+    /// Transform, dedup, store and feed one item without waiting.
+    /// Returns whether the item was kept.
+    pub(crate) fn push(&mut self, item: String) -> bool {
+        let Some(item) = self.transform(item) else {
+            return false;
+        };
+        if self.is_duplicate(&item) {
+            return false;
+        }
+        self.keep(item.clone());
+        // Never block here, or adding more items than the channel's
+        // capacity would hang forever when nobody calls `receive`
+        let _ = self.tx.try_send(Fed { item, stored: true });
+        true
+    }
+
+    /// Combine several streams into one with the default channel capacity.
+    ///
+    /// Stored items are interleaved round-robin (first item of each stream,
+    /// then the second of each, ...) so no source crowds out the others.
+    /// Transforms and dedup modes of the inputs are not carried over.
+    pub fn merge(streams: Vec<ItemStream>) -> Self {
+        let mut merged = Self::new();
+        let mut sources: Vec<_> = streams.into_iter().map(|s| s.items.into_iter()).collect();
+        let mut remaining = true;
+        while remaining {
+            remaining = false;
+            for source in &mut sources {
+                if let Some(item) = source.next() {
+                    merged.push(item);
+                    remaining = true;
+                }
             }
         }
+        merged
+    }
+
+    /// Handle for adding items from another task (see [`ItemSender`])
+    pub fn sender(&self) -> ItemSender {
+        ItemSender {
+            tx: self.tx.clone(),
+        }
+    }
+
+    /// Add one item if the channel has room. Items dropped by a transform or
+    /// the dedup mode count as sent.
+    ///
+    /// Returns [`TrySendError::Full`] with the item (which is not stored) when
+    /// the channel is at capacity.
+    pub fn try_send(&mut self, item: String) -> Result<(), TrySendError<String>> {
+        let Some(item) = self.transform(item) else {
+            return Ok(());
+        };
+        if self.is_duplicate(&item) {
+            return Ok(());
+        }
+        self.tx.try_send(Fed {
+            item: item.clone(),
+            stored: true,
+        })?;
+        self.keep(item);
+        Ok(())
+    }
+
+    /// Borrow all items
+    pub fn as_slice(&self) -> &[String] {
+        &self.items
     }
 
     /// Get all items as a stream
@@ -62,9 +446,27 @@ impl ItemStream {
         futures::future::join_all(futures).await
     }
 
-    /// Get items from the receiver channel
+    /// Get items from the receiver channel: the next item added here, or
+    /// sent by an [`ItemSender`] (stored now, unless a transform or the
+    /// dedup mode drops it)
     pub async fn receive(&mut self) -> Option<String> {
-        self.rx.recv().await
+        loop {
+            let fed = match &mut self.rx {
+                Drain::Bounded(rx) => rx.recv().await,
+                Drain::Unbounded(rx) => rx.recv().await,
+            }?;
+            if fed.stored {
+                return Some(fed.item);
+            }
+            let Some(item) = self.transform(fed.item) else {
+                continue;
+            };
+            if self.is_duplicate(&item) {
+                continue;
+            }
+            self.keep(item.clone());
+            return Some(item);
+        }
     }
 
     /// Get all items as a vector
@@ -175,4 +577,187 @@ mod tests {
             panic!("Expected to receive an item");
         }
     }
+
+    #[tokio::test]
+    async fn test_add_items_beyond_channel_capacity() {
+        let mut stream = ItemStream::new();
+        let items: Vec<String> = (0..2500).map(|i| i.to_string()).collect();
+
+        stream.add_items(items).await;
+        assert_eq!(stream.len(), 2500);
+        assert_eq!(stream.receive().await.as_deref(), Some("0"));
+    }
+
+    #[tokio::test]
+    async fn test_try_send_reports_full_channel() {
+        let mut stream = ItemStream::with_capacity(2);
+        assert_eq!(stream.capacity(), Some(2));
+        stream.try_send("a".to_string()).unwrap();
+        stream.try_send("b".to_string()).unwrap();
+        assert_eq!(stream.capacity(), Some(0));
+
+        match stream.try_send("c".to_string()) {
+            Err(TrySendError::Full(item)) => assert_eq!(item, "c"),
+            other => panic!("expected a full channel, got {other:?}"),
+        }
+        assert_eq!(stream.len(), 2);
+
+        assert_eq!(stream.receive().await.as_deref(), Some("a"));
+        stream.try_send("c".to_string()).unwrap();
+        assert_eq!(stream.get_all_items(), vec!["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_sender_waits_for_receive() {
+        let mut stream = ItemStream::with_capacity(2);
+        stream.set_dedup(Some(Dedup::Exact));
+        let sender = stream.sender();
+        let producer = tokio::spawn(async move {
+            for i in 0..10 {
+                sender.send((i % 8).to_string()).await.unwrap();
+            }
+        });
+
+        let mut received = Vec::new();
+        while received.len() < 8 {
+            received.push(stream.receive().await.unwrap());
+        }
+        producer.await.unwrap();
+        assert_eq!(received, stream.get_all_items());
+        assert_eq!(stream.len(), 8);
+
+        let sender = stream.sender();
+        drop(stream);
+        assert_eq!(
+            sender.send("gone".to_string()).await,
+            Err(SendError("gone".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dedup_exact_drops_repeats() {
+        let mut stream = ItemStream::new();
+        stream.add_items(vec!["a".to_string()]).await;
+        stream.set_dedup(Some(Dedup::Exact));
+        stream
+            .add_items(vec!["b".to_string(), "a".to_string(), "b".to_string()])
+            .await;
+        stream.try_send("a".to_string()).unwrap();
+        stream.try_send("c".to_string()).unwrap();
+        assert_eq!(stream.get_all_items(), vec!["a", "b", "c"]);
+
+        stream.set_dedup(None);
+        stream.add_items(vec!["a".to_string()]).await;
+        assert_eq!(stream.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_similar_drops_near_duplicates() {
+        let mut stream = ItemStream::new();
+        stream.set_dedup(Some(Dedup::Similar(0.7)));
+        stream
+            .add_items(vec![
+                "12:00:01 ERROR connection refused to db-primary".to_string(),
+                "user alice logged in".to_string(),
+                "12:00:05 ERROR connection refused to db-primary".to_string(),
+            ])
+            .await;
+        assert_eq!(
+            stream.get_all_items(),
+            vec![
+                "12:00:01 ERROR connection refused to db-primary",
+                "user alice logged in"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transforms_apply_in_order() {
+        let mut stream = ItemStream::new();
+        stream.add_transform(ItemTransform::strip_ansi());
+        stream.add_transform(ItemTransform::filter(|item| !item.starts_with('#')));
+        stream.add_transform(ItemTransform::map(|item| {
+            item.strip_prefix("./").unwrap_or(&item).to_string()
+        }));
+        stream.set_dedup(Some(Dedup::Exact));
+        stream
+            .add_items(vec![
+                "./src/main.rs".to_string(),
+                "# comment".to_string(),
+                "\x1b[34msrc/main.rs\x1b[0m".to_string(),
+                "./lib.rs".to_string(),
+            ])
+            .await;
+        stream.try_send("#skip".to_string()).unwrap();
+        assert_eq!(stream.get_all_items(), vec!["src/main.rs", "lib.rs"]);
+
+        stream.clear_transforms();
+        stream.add_items(vec!["# kept".to_string()]).await;
+        assert_eq!(stream.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_remove_where() {
+        let mut stream = ItemStream::new();
+        stream.set_dedup(Some(Dedup::Exact));
+        stream
+            .add_items(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+            .await;
+        let generation = stream.generation();
+
+        assert_eq!(stream.remove_where(|item| item != "b"), vec![0, 2]);
+        assert_eq!(stream.as_slice(), &["b"]);
+        assert_ne!(stream.generation(), generation);
+
+        // Removed items are no longer duplicates
+        stream
+            .add_items(vec!["a".to_string(), "b".to_string()])
+            .await;
+        assert_eq!(stream.as_slice(), &["b", "a"]);
+        assert!(stream.remove_where(|item| item == "z").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_clear_resets_dedup() {
+        let mut stream = ItemStream::new();
+        stream.set_dedup(Some(Dedup::Similar(0.9)));
+        let items = vec!["src/main.rs".to_string(), "src/lib.rs".to_string()];
+        stream.add_items(items.clone()).await;
+        let generation = stream.generation();
+
+        stream.clear();
+        assert!(stream.is_empty());
+        assert_ne!(stream.generation(), generation);
+        stream.add_items(items).await;
+        assert_eq!(stream.as_slice(), &["src/main.rs", "src/lib.rs"]);
+    }
+
+    #[tokio::test]
+    async fn test_merge_interleaves_sources() {
+        let mut a = ItemStream::new();
+        a.add_items(vec!["a1".to_string(), "a2".to_string(), "a3".to_string()])
+            .await;
+        let mut b = ItemStream::unbounded();
+        b.add_items(vec!["b1".to_string()]).await;
+
+        let mut merged = ItemStream::merge(vec![a, b, ItemStream::new()]);
+        assert_eq!(merged.get_all_items(), vec!["a1", "b1", "a2", "a3"]);
+        assert_eq!(merged.receive().await.as_deref(), Some("a1"));
+    }
+
+    #[tokio::test]
+    async fn test_unbounded_stream_feeds_every_item() {
+        let mut stream = ItemStream::unbounded();
+        assert_eq!(stream.capacity(), None);
+        let items: Vec<String> = (0..2500).map(|i| i.to_string()).collect();
+        stream.add_items(items).await;
+
+        let mut received = 0;
+        while let Ok(Some(_)) =
+            tokio::time::timeout(std::time::Duration::from_millis(10), stream.receive()).await
+        {
+            received += 1;
+        }
+        assert_eq!(received, 2500);
+    }
 }
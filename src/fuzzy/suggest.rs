@@ -0,0 +1,157 @@
+//! "Did you mean" corrections for queries that match nothing.
+//!
+//! The distinct words of the items form a vocabulary, indexed with LSH over
+//! character bigrams so that misspelled query words find their likely
+//! targets without comparing against every word. Candidates are then ranked
+//! by edit distance, and only close corrections are suggested.
+
+use std::collections::HashSet;
+
+use crate::fuzzy::lsh::{LSHConfig, LSHIndex};
+use crate::fuzzy::scoring::strip_ansi_sequences;
+
+/// Words shorter than this are neither indexed nor corrected
+const MIN_WORD_LEN: usize = 3;
+/// Nearest LSH candidates re-ranked by edit distance per query word
+const CANDIDATES: usize = 32;
+
+/// Vocabulary of item words, suggesting corrections for query words
+pub struct Suggester {
+    lsh: LSHIndex,
+    words: HashSet<String>,
+    /// Number of items whose words have been added
+    indexed: usize,
+}
+
+impl Default for Suggester {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Suggester {
+    /// Create an empty vocabulary
+    pub fn new() -> Self {
+        // Single-row bands over bigrams: forgiving enough that a word with a
+        // typo or two still shares a bucket with the intended one
+        Self {
+            lsh: LSHIndex::new(LSHConfig::new(16, 1).with_shingle_size(2)),
+            words: HashSet::new(),
+            indexed: 0,
+        }
+    }
+
+    /// Number of items added so far
+    pub fn len(&self) -> usize {
+        self.indexed
+    }
+
+    /// Whether no items have been added
+    pub fn is_empty(&self) -> bool {
+        self.indexed == 0
+    }
+
+    /// Add the words of `items` to the vocabulary
+    pub fn extend<S: AsRef<str>>(&mut self, items: &[S]) {
+        for item in items {
+            for word in words(&strip_ansi_sequences(item.as_ref())) {
+                if self.words.insert(word.clone()) {
+                    self.lsh.insert(&word);
+                }
+            }
+        }
+        self.indexed += items.len();
+    }
+
+    /// Correct each query word to the closest vocabulary word, or `None`
+    /// if no word needs (or has) a close correction
+    pub fn correct(&self, query: &str) -> Option<String> {
+        let mut changed = false;
+        let mut corrected = Vec::new();
+        for word in query.split_whitespace() {
+            let lower = word.to_lowercase();
+            if lower.chars().count() < MIN_WORD_LEN || self.words.contains(&lower) {
+                corrected.push(word.to_string());
+                continue;
+            }
+            let best = self.closest(&lower)?;
+            changed = true;
+            corrected.push(best.to_string());
+        }
+        changed.then(|| corrected.join(" "))
+    }
+
+    /// Closest vocabulary word to `word` within the allowed edit distance
+    fn closest(&self, word: &str) -> Option<&str> {
+        let max_distance = (word.chars().count() / 3).max(1);
+        self.lsh
+            .nearest(word, CANDIDATES)
+            .into_iter()
+            .map(|(_, candidate, similarity)| {
+                (edit_distance(word, candidate), similarity, candidate)
+            })
+            .filter(|&(distance, _, _)| distance <= max_distance)
+            .min_by(|a, b| a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)))
+            .map(|(_, _, candidate)| candidate)
+    }
+}
+
+/// Lowercase alphanumeric words of `text` long enough to correct towards
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= MIN_WORD_LEN)
+        .map(str::to_lowercase)
+}
+
+/// Edit distance counting insertions, deletions, substitutions and swaps of
+/// adjacent characters (optimal string alignment)
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Rows i-2, i-1 and i of the distance table
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut row = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        row[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut before, &mut prev);
+        std::mem::swap(&mut prev, &mut row);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("parser", "parser"), 0);
+        assert_eq!(edit_distance("pasrer", "parser"), 1);
+        assert_eq!(edit_distance("parsr", "parser"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_correct_misspelled_words() {
+        let mut suggester = Suggester::new();
+        suggester.extend(&["src/parser.rs", "src/lexer.rs", "README.md"]);
+        assert_eq!(suggester.len(), 3);
+
+        assert_eq!(suggester.correct("pasrer").as_deref(), Some("parser"));
+        assert_eq!(suggester.correct("src lexre").as_deref(), Some("src lexer"));
+        // Known words and short words need no correction
+        assert_eq!(suggester.correct("readme"), None);
+        assert_eq!(suggester.correct("xy"), None);
+        // Nothing close enough
+        assert_eq!(suggester.correct("database"), None);
+    }
+}
@@ -0,0 +1,209 @@
+//! Ternary search trie for prefix lookups.
+//!
+//! Each node holds one character and three children: `lo` and `hi` for
+//! smaller and larger characters at the same depth, and `eq` for the next
+//! character. Finding the node for a prefix takes O(prefix length), after
+//! which every item below it shares that prefix.
+
+use std::cmp::Ordering;
+
+use crate::fuzzy::scoring::strip_ansi_sequences;
+
+/// Marker for a missing child
+const NONE: u32 = u32::MAX;
+
+#[derive(Debug, Clone)]
+struct Node {
+    ch: char,
+    lo: u32,
+    eq: u32,
+    hi: u32,
+    /// Ids of items that end at this node
+    ids: Vec<u32>,
+}
+
+/// Ternary search trie mapping lowercase items to their ids
+#[derive(Debug, Default, Clone)]
+pub struct TernaryTree {
+    nodes: Vec<Node>,
+    len: usize,
+}
+
+impl TernaryTree {
+    /// Create an empty trie
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a trie over `items`, with ids assigned by position
+    pub fn build<S: AsRef<str>>(items: &[S]) -> Self {
+        let mut tree = Self::new();
+        tree.extend(items);
+        tree
+    }
+
+    /// Insert `items`, which continue the sequence already inserted.
+    /// ANSI escape sequences are stripped so colored items match by their text.
+    pub fn extend<S: AsRef<str>>(&mut self, items: &[S]) {
+        for item in items {
+            let id = self.len as u32;
+            let key = strip_ansi_sequences(item.as_ref()).to_lowercase();
+            self.insert(&key, id);
+            self.len += 1;
+        }
+    }
+
+    /// Number of items inserted (including empty ones, which are not stored)
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no items have been inserted
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn new_node(&mut self, ch: char) -> u32 {
+        self.nodes.push(Node {
+            ch,
+            lo: NONE,
+            eq: NONE,
+            hi: NONE,
+            ids: Vec::new(),
+        });
+        (self.nodes.len() - 1) as u32
+    }
+
+    fn insert(&mut self, key: &str, id: u32) {
+        let mut chars = key.chars();
+        let Some(mut c) = chars.next() else {
+            return;
+        };
+        if self.nodes.is_empty() {
+            self.new_node(c);
+        }
+
+        let mut node = 0usize;
+        loop {
+            let ord = c.cmp(&self.nodes[node].ch);
+            let child = match ord {
+                Ordering::Less => self.nodes[node].lo,
+                Ordering::Greater => self.nodes[node].hi,
+                Ordering::Equal => match chars.next() {
+                    None => {
+                        self.nodes[node].ids.push(id);
+                        return;
+                    }
+                    Some(next) => {
+                        c = next;
+                        self.nodes[node].eq
+                    }
+                },
+            };
+            if child != NONE {
+                node = child as usize;
+                continue;
+            }
+
+            let created = self.new_node(c);
+            let parent = &mut self.nodes[node];
+            match ord {
+                Ordering::Less => parent.lo = created,
+                Ordering::Greater => parent.hi = created,
+                Ordering::Equal => parent.eq = created,
+            }
+            node = created as usize;
+        }
+    }
+
+    /// Node reached by following `prefix`, if any item starts with it
+    fn find(&self, prefix: &str) -> Option<u32> {
+        let mut chars = prefix.chars();
+        let mut c = chars.next()?;
+        let mut node = if self.nodes.is_empty() { NONE } else { 0 };
+        while node != NONE {
+            let n = &self.nodes[node as usize];
+            if c < n.ch {
+                node = n.lo;
+            } else if c > n.ch {
+                node = n.hi;
+            } else {
+                match chars.next() {
+                    None => return Some(node),
+                    Some(next) => {
+                        c = next;
+                        node = n.eq;
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Ids (ascending) of items whose lowercase form starts with `prefix_lower`.
+    /// An empty prefix matches every non-empty item.
+    pub fn with_prefix(&self, prefix_lower: &str) -> Vec<usize> {
+        let mut ids = Vec::new();
+        let mut stack = Vec::new();
+        if prefix_lower.is_empty() {
+            if !self.nodes.is_empty() {
+                stack.push(0);
+            }
+        } else if let Some(start) = self.find(prefix_lower) {
+            let node = &self.nodes[start as usize];
+            ids.extend(node.ids.iter().map(|&id| id as usize));
+            stack.push(node.eq);
+        }
+
+        while let Some(node) = stack.pop() {
+            if node == NONE {
+                continue;
+            }
+            let n = &self.nodes[node as usize];
+            ids.extend(n.ids.iter().map(|&id| id as usize));
+            stack.extend([n.lo, n.eq, n.hi]);
+        }
+        ids.sort_unstable();
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_prefix() {
+        let tree = TernaryTree::build(&["src/main.rs", "src/lib.rs", "README.md", "src", ""]);
+        assert_eq!(tree.len(), 5);
+        assert_eq!(tree.with_prefix("src"), vec![0, 1, 3]);
+        assert_eq!(tree.with_prefix("src/m"), vec![0]);
+        assert_eq!(tree.with_prefix("readme"), vec![2]);
+        assert!(tree.with_prefix("srcx").is_empty());
+        assert!(tree.with_prefix("z").is_empty());
+        assert_eq!(tree.with_prefix(""), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_duplicates_and_extend() {
+        let mut tree = TernaryTree::build(&["b", "a"]);
+        tree.extend(&["a", "ab", "Ä"]);
+        assert_eq!(tree.with_prefix("a"), vec![1, 2, 3]);
+        assert_eq!(tree.with_prefix("ab"), vec![3]);
+        assert_eq!(tree.with_prefix("ä"), vec![4]);
+    }
+
+    #[test]
+    fn test_ansi_sequences_are_ignored() {
+        let tree = TernaryTree::build(&["\x1b[34msrc\x1b[0m/main.rs", "lib.rs"]);
+        assert_eq!(tree.with_prefix("src/"), vec![0]);
+    }
+
+    #[test]
+    fn test_sorted_input_stays_correct() {
+        let items: Vec<String> = (0..1000).map(|i| format!("file{i:04}")).collect();
+        let tree = TernaryTree::build(&items);
+        assert_eq!(tree.with_prefix("file09").len(), 100);
+        assert_eq!(tree.with_prefix("file0999"), vec![999]);
+    }
+}
@@ -11,25 +11,106 @@ pub fn print_usage() {
     eprintln!("Options:");
     eprintln!("  -m, --multi-select             Enable multi-select mode");
     eprintln!("  -n, --line-number              Output line numbers (file input: 'file:line')");
-    eprintln!("      --height <N>               Set TUI height in lines (non-fullscreen)");
+    eprintln!("      --height <N|auto>          Set TUI height in lines, or fit it to the results");
     eprintln!("      --height-percentage <N>    Set TUI height as % of terminal (non-fullscreen)");
     eprintln!(
         "  -p, --preview <cmd>            Preview command (repeatable, {{ext1,ext2}} for filters)"
     );
+    eprintln!(
+        "                                 {{}} item, {{q}} query, {{n}} index, {{N}} field N"
+    );
+    eprintln!("                                 'auto' previews files and directories built in");
+    eprintln!("  -q, --query <TEXT>             Start with TEXT as the query");
+    eprintln!(
+        "      --layout <NAME>            reverse (prompt on top) or default (prompt at bottom)"
+    );
+    eprintln!("      --info <STYLE>             Match counter: default, inline, hidden");
+    eprintln!("      --color <SPEC>             Theme (dark, light) and KEY:COLOR overrides");
+    eprintln!("      --prompt <TEXT>            Prompt shown before the query (default '> ')");
     eprintln!("      --preview-auto             Auto-show preview on cursor move");
+    eprintln!(
+        "      --preview-scroll <POS>     Start previews at line POS (e.g. {{2}}-5 for grep -n)"
+    );
+    eprintln!(
+        "      --scheme <NAME>            Scoring preset: default, paths, commands, prose, identifiers"
+    );
+    eprintln!(
+        "      --mode <NAME>              Matching mode: fuzzy, word (word starts), acronym (initials)"
+    );
+    eprintln!("      --boundary-chars <CHARS>   Extra word-boundary characters (e.g. ',=@')");
+    eprintln!(
+        "      --history <FILE>           Save queries to FILE; Up at an empty query recalls them"
+    );
+    eprintln!("      --history-boost            Rank frequently/recently selected items higher");
+    eprintln!(
+        "      --expect <KEYS>            Also accept with KEYS (e.g. ctrl-o,f1), printed first"
+    );
+    eprintln!("      --print-query              Print the query first; Enter accepts it unmatched");
+    eprintln!("      --max-select <N>           Allow at most N selected items (-m)");
+    eprintln!("      --selection-order          Print selections in the order they were made (-m)");
+    eprintln!("      --select <ITEM>            Start with ITEM selected (-m, repeatable)");
+    eprintln!("  -1, --select-1                 Skip the TUI if the query matches one item");
+    eprintln!("  -0, --exit-0                   Skip the TUI and exit 1 if nothing matches");
+    eprintln!("      --bind <KEY:ACTION>        Bind KEY to execute(CMD) (run CMD for the current");
+    eprintln!("                                 item, then resume), reload (re-read the input),");
+    eprintln!("                                 jump, jump-accept (label items to type),");
+    eprintln!("                                 toggle-sort (ranked vs input order),");
+    eprintln!("                                 toggle-scores (see --debug-scores)");
+    eprintln!("      --jump-labels <CHARS>      Characters jump labels are made of");
+    eprintln!(
+        "      --tab <NAME=SOURCE>        A tab of items from SOURCE; F3 cycles (repeatable)"
+    );
+    eprintln!("      --wrap                     Wrap long items instead of cutting them off");
+    eprintln!("      --no-scrollbar             Hide the scrollbar of an overflowing list");
+    eprintln!("      --no-sort                  Keep matches in input order instead of ranking");
+    eprintln!("      --cycle                    Wrap the cursor past the ends (default)");
+    eprintln!("      --no-cycle                 Stop the cursor at the ends instead of wrapping");
+    eprintln!("      --debug-scores             Show each result's score breakdown and positions");
+    eprintln!("      --accessible               Text markers and no spinner, for screen readers");
+    eprintln!("      --min-score <N>            Hide matches scoring below N");
+    eprintln!("      --max-results <N>          Keep only the N best matches");
+    eprintln!("      --unique-similar           Collapse near-duplicate lines, showing (xN)");
+    eprintln!("      --group-similar            Group near-duplicate lines under headers");
+    eprintln!("      --dedup                    Drop duplicate input lines");
+    eprintln!("      --keymap <NAME>            Key bindings: default, emacs, vim");
+    eprintln!("      --help-text                Show a footer with key hints and the current mode");
+    eprintln!("      --keep-right               Cut long lines at the start, keeping the end");
+    eprintln!("      --columns <N|auto>         Lay results out in a grid of columns");
+    eprintln!("      --ansi                     Strip ANSI colors from the printed selection");
+    eprintln!("      --no-mouse                 Don't capture the mouse (no click or scroll)");
+    eprintln!("      --index-cache              Cache the search index of an unchanged file");
+    eprintln!(
+        "      --weight-field <N>         Tab-separated field N is a score boost (not shown)"
+    );
+    eprintln!(
+        "      --with-nth <FIELDS>        Show only these fields (e.g. 2.., 1,-1); matched whole"
+    );
+    eprintln!(
+        "  -d, --delimiter <CHAR>         Field delimiter for --with-nth (default: whitespace)"
+    );
+    eprintln!("      --icons[=nerd|ascii]       Show a file-type icon before each path");
+    eprintln!("      --no-icons                 Don't show icons (overrides --icons)");
+    eprintln!("      --tmux[=POS,WxH]           In tmux, open in a popup (e.g. center,80%x60%)");
+    eprintln!("      --record-sep <SEP>         Item separator: newline, nul, blank, or a string");
     eprintln!("  -h, --help                     Show this help message");
     eprintln!("  -V, --version                  Show version information");
     eprintln!();
+    eprintln!("Query syntax:");
+    eprintln!("  abc                            Fuzzy match a, b, c in order");
+    eprintln!("  ^abc                           Items starting with abc");
+    eprintln!();
     eprintln!("Examples:");
     eprintln!("  ff file.txt                    Select from file");
     eprintln!("  ff file.txt -m                 Multi-select from file");
-    eprintln!("  ff ./src/                      Select from directory listing");
+    eprintln!("  ff ./src/                      Select from directory listing, with previews");
     eprintln!("  ff apple banana cherry         Select from inline items");
     eprintln!("  ls | ff                        Select from piped input");
     eprintln!("  ff file.txt --height 10        Non-fullscreen, 10 lines");
     eprintln!("  ls | ff -p 'cat'               Preview with cat (default rule)");
     eprintln!("  ls | ff -p 'bat {{rs,toml}}' -p 'glow {{md}}' -p 'cat'");
     eprintln!("  ls | ff -p 'bat' --preview-auto");
+    eprintln!("  fd | ff --scheme path          Prefer matches in file names");
+    eprintln!("  ff app.log --unique-similar    Collapse repeated log lines");
 }
 
 #[cfg(test)]
@@ -5,6 +5,96 @@ use tokio::io::{AsyncReadExt, BufReader};
 use tokio::net::UnixStream;
 use tokio::sync::mpsc;
 
+/// How input is split into records (items).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum RecordSeparator {
+    /// One record per line
+    #[default]
+    Newline,
+    /// Records separated by NUL bytes (e.g. `find -print0`)
+    Nul,
+    /// Records separated by one or more blank lines, so multi-line blocks
+    /// (stack traces, `docker inspect` output) become single items
+    Blank,
+    /// Records separated by a literal string
+    Custom(String),
+}
+
+impl RecordSeparator {
+    /// Parse a separator name: `newline`, `nul`, `blank`, or any other
+    /// non-empty string used literally (`\n`, `\t` and `\0` are unescaped).
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "newline" => Ok(Self::Newline),
+            "nul" => Ok(Self::Nul),
+            "blank" => Ok(Self::Blank),
+            "" => Err("Record separator cannot be empty".to_string()),
+            other => {
+                let literal = other
+                    .replace("\\n", "\n")
+                    .replace("\\t", "\t")
+                    .replace("\\0", "\0");
+                Ok(match literal.as_str() {
+                    "\n" => Self::Newline,
+                    "\0" => Self::Nul,
+                    _ => Self::Custom(literal),
+                })
+            }
+        }
+    }
+
+    /// Split `content` into raw records. Records other than lines may span
+    /// several lines; callers trim them as they do lines.
+    pub fn split<'a>(&self, content: &'a str) -> Vec<&'a str> {
+        match self {
+            Self::Newline => content.lines().collect(),
+            Self::Nul => split_terminated(content, "\0"),
+            Self::Custom(sep) => split_terminated(content, sep),
+            Self::Blank => {
+                let mut records = Vec::new();
+                let mut start: Option<usize> = None;
+                let mut end = 0;
+                let mut offset = 0;
+                for line in content.split_inclusive('\n') {
+                    if line.trim().is_empty() {
+                        if let Some(s) = start.take() {
+                            records.push(&content[s..end]);
+                        }
+                    } else {
+                        start.get_or_insert(offset);
+                        end = offset + line.trim_end().len();
+                    }
+                    offset += line.len();
+                }
+                if let Some(s) = start {
+                    records.push(&content[s..end]);
+                }
+                records
+            }
+        }
+    }
+}
+
+/// Turn a raw record into an item: trim surrounding whitespace and
+/// normalize CRLF line endings inside multi-line records
+pub fn clean_record(record: &str) -> String {
+    let trimmed = record.trim();
+    if trimmed.contains('\r') {
+        trimmed.replace("\r\n", "\n")
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Split on `sep`, ignoring the empty record after a trailing separator
+fn split_terminated<'a>(content: &'a str, sep: &str) -> Vec<&'a str> {
+    let content = content.strip_suffix(sep).unwrap_or(content);
+    if content.is_empty() {
+        return Vec::new();
+    }
+    content.split(sep).collect()
+}
+
 /// Read input items from the specified source.
 pub async fn read_input(source: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
     if let Some(stripped) = source.strip_prefix("unix://") {
@@ -35,11 +125,19 @@ pub fn read_direct_items(items: Vec<String>) -> Result<Vec<String>, String> {
 
 /// Process content as if it came from stdin.
 pub fn process_stdin_content(content: &str) -> Result<Vec<String>, String> {
+    process_stdin_records(content, &RecordSeparator::Newline)
+}
+
+/// Process stdin content split into records by `separator`.
+pub fn process_stdin_records(
+    content: &str,
+    separator: &RecordSeparator,
+) -> Result<Vec<String>, String> {
     let mut items = Vec::new();
-    for line in content.lines() {
-        let trimmed = line.trim();
-        if !trimmed.is_empty() {
-            items.push(trimmed.to_string());
+    for record in separator.split(content) {
+        let item = clean_record(record);
+        if !item.is_empty() {
+            items.push(item);
         }
     }
     if items.is_empty() {
@@ -50,6 +148,11 @@ pub fn process_stdin_content(content: &str) -> Result<Vec<String>, String> {
 
 /// Read piped stdin synchronously before async runtime.
 pub fn read_piped_stdin() -> Result<Vec<String>, String> {
+    read_piped_stdin_records(&RecordSeparator::Newline)
+}
+
+/// Read piped stdin, split into records by `separator`.
+pub fn read_piped_stdin_records(separator: &RecordSeparator) -> Result<Vec<String>, String> {
     use std::io::{stdin, Read};
     let mut input = String::new();
     let mut stdin = stdin();
@@ -59,7 +162,7 @@ pub fn read_piped_stdin() -> Result<Vec<String>, String> {
     stdin
         .read_to_string(&mut input)
         .map_err(|e| format!("Failed to read stdin: {e}"))?;
-    process_stdin_content(&input)
+    process_stdin_records(&input, separator)
 }
 
 /// Reopen stdin from /dev/tty so crossterm can read keyboard events
@@ -111,18 +214,28 @@ pub fn process_file_content(content: &str) -> Result<Vec<String>, String> {
 pub async fn send_input_to_channel(
     source: &str,
     sender: mpsc::Sender<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    send_records_to_channel(source, sender, &RecordSeparator::Newline).await
+}
+
+/// Send records from the specified source to an mpsc channel, splitting file
+/// and socket content with `separator`.
+pub async fn send_records_to_channel(
+    source: &str,
+    sender: mpsc::Sender<String>,
+    separator: &RecordSeparator,
 ) -> Result<(), Box<dyn std::error::Error>> {
     if let Some(stripped) = source.strip_prefix("unix://") {
-        send_from_unix_socket(stripped, sender).await
+        send_from_unix_socket(stripped, sender, separator).await
     } else if source.starts_with("http://") || source.starts_with("https://") {
-        send_from_http_socket(source, sender).await
+        send_from_http_socket(source, sender, separator).await
     } else if let Some(stripped) = source.strip_prefix("dir:") {
         send_from_directory(stripped, sender).await
     } else if Path::new(source).exists() {
         if Path::new(source).is_dir() {
             send_from_directory(source, sender).await
         } else {
-            send_from_file(source, sender).await
+            send_from_file(source, sender, separator).await
         }
     } else {
         // Treat as space-separated list
@@ -202,10 +315,11 @@ async fn read_from_directory(dir_path: &str) -> Result<Vec<String>, Box<dyn std:
 async fn send_from_file(
     file_path: &str,
     sender: mpsc::Sender<String>,
+    separator: &RecordSeparator,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let content = fs::read_to_string(file_path).await?;
-    for line in content.lines() {
-        if sender.send(line.trim().to_string()).await.is_err() {
+    for record in separator.split(&content) {
+        if sender.send(clean_record(record)).await.is_err() {
             break; // Channel closed
         }
     }
@@ -215,6 +329,7 @@ async fn send_from_file(
 async fn send_from_unix_socket(
     socket_path: &str,
     sender: mpsc::Sender<String>,
+    separator: &RecordSeparator,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let stream = UnixStream::connect(socket_path)
         .await
@@ -232,8 +347,8 @@ async fn send_from_unix_socket(
     }
 
     let content = String::from_utf8(buffer)?;
-    for line in content.lines() {
-        if sender.send(line.trim().to_string()).await.is_err() {
+    for record in separator.split(&content) {
+        if sender.send(clean_record(record)).await.is_err() {
             break; // Channel closed
         }
     }
@@ -243,6 +358,7 @@ async fn send_from_unix_socket(
 async fn send_from_http_socket(
     url: &str,
     sender: mpsc::Sender<String>,
+    separator: &RecordSeparator,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Simple HTTP client implementation without external dependencies
     let url = url.replace("http://", "").replace("https://", "");
@@ -259,8 +375,8 @@ async fn send_from_http_socket(
         .map_err(|e| format!("Failed to read from HTTP socket: {e}"))?;
 
     let content = String::from_utf8_lossy(&buffer);
-    for line in content.lines() {
-        if sender.send(line.trim().to_string()).await.is_err() {
+    for record in separator.split(&content) {
+        if sender.send(clean_record(record)).await.is_err() {
             break; // Channel closed
         }
     }
@@ -293,6 +409,39 @@ async fn send_from_directory(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_record_separator_parse() {
+        assert_eq!(RecordSeparator::parse("nul"), Ok(RecordSeparator::Nul));
+        assert_eq!(RecordSeparator::parse("blank"), Ok(RecordSeparator::Blank));
+        assert_eq!(RecordSeparator::parse("\\0"), Ok(RecordSeparator::Nul));
+        assert_eq!(
+            RecordSeparator::parse("---"),
+            Ok(RecordSeparator::Custom("---".to_string()))
+        );
+        assert!(RecordSeparator::parse("").is_err());
+    }
+
+    #[test]
+    fn test_split_records() {
+        assert_eq!(RecordSeparator::Nul.split("a\nb\0c\0"), vec!["a\nb", "c"]);
+        let blocks = "Error: boom\n  at main\n\n\n  \nWarning: x\r\n  at lib\n";
+        assert_eq!(
+            RecordSeparator::Blank.split(blocks),
+            vec!["Error: boom\n  at main", "Warning: x\r\n  at lib"]
+        );
+        assert_eq!(
+            RecordSeparator::Custom("--".to_string()).split("a--b--"),
+            vec!["a", "b"]
+        );
+        assert!(RecordSeparator::Nul.split("").is_empty());
+    }
+
+    #[test]
+    fn test_process_stdin_records() {
+        let items = process_stdin_records("one\r\n two\r\n\r\nthree\n", &RecordSeparator::Blank);
+        assert_eq!(items.unwrap(), vec!["one\n two", "three"]);
+    }
+
     #[tokio::test]
     async fn test_read_input_space_separated() {
         let result = read_input("item1 item2 item3").await;
@@ -1,11 +1,11 @@
 //! # ff - Fuzzy Finder Library
 //!
-//! A fast, lightweight fuzzy finder library with async streaming, LSH, and quicksort.
+//! A fast, lightweight fuzzy finder library with async streaming and LSH.
 //!
 //! ## Features
 //! - Async fuzzy matching with streaming
 //! - Locality Sensitive Hashing (LSH) for similarity grouping
-//! - Quicksort for fast result sorting
+//! - Deterministic ranking with top-k selection for large result sets
 //! - Multi-select support
 //! - TUI interface with keyboard navigation
 //! - Configurable height for the TUI
@@ -36,7 +36,7 @@ use tokio::sync::mpsc;
 
 // === Public API Exports ===
 
-/// Async fuzzy finder with streaming capabilities, LSH, and quicksort.
+/// Async fuzzy finder with streaming capabilities and LSH.
 ///
 /// Supports async operations, locality sensitive hashing, and efficient sorting.
 ///
@@ -55,6 +55,30 @@ use tokio::sync::mpsc;
 /// ```
 pub use fuzzy::FuzzyFinder;
 
+/// Owned copy of a finder's items, results and selections, from
+/// [`FuzzyFinder::snapshot`], that can be handed to another thread.
+///
+/// # Example
+/// ```
+/// use ff::FuzzyFinder;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let items = vec!["apple".to_string(), "banana".to_string()];
+///     let mut finder = FuzzyFinder::with_items_async(items, false).await;
+///     finder.set_query("ban".to_string()).await;
+///     let snapshot = finder.snapshot();
+///     let results = std::thread::spawn(move || {
+///         snapshot
+///             .filtered_items()
+///             .map(|(_, item)| item.to_string())
+///             .collect::<Vec<_>>()
+///     });
+///     assert_eq!(results.join().unwrap(), vec!["banana"]);
+/// }
+/// ```
+pub use fuzzy::FinderSnapshot;
+
 /// Run an interactive TUI for fuzzy finding through an mpsc receiver of items.
 ///
 /// - Real-time fuzzy filtering as you type
@@ -83,18 +107,64 @@ pub use tui::run_tui;
 /// - `Err(e)`: An error occurred during TUI operation
 pub use tui::run_tui_with_config;
 
+/// Run the TUI like [`run_tui_with_config`], returning the whole outcome:
+/// the selection, the final query, which of the [`TuiConfig::expect`] keys
+/// accepted it, and whether the session was cancelled.
+///
+/// # Example
+/// ```no_run
+/// use ff::{create_items_channel, run_tui_with_output, TuiConfig};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// let (sender, receiver) = create_items_channel();
+/// tokio::spawn(async move { sender.send("item".to_string()).await });
+/// let output = run_tui_with_output(receiver, false, TuiConfig::default()).await?;
+/// if !output.accepted {
+///     eprintln!("cancelled with query {:?}", output.query);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub use tui::run_tui_with_output;
+
+/// Outcome of an interactive session, returned by [`run_tui_with_output`]
+/// and [`run_tui_with_indicators_output`].
+pub use tui::TuiOutput;
+
 /// Create an mpsc channel for sending items to the TUI.
 ///
 /// # Returns
 /// - `(sender, receiver)`: A tuple containing the sender and receiver for the channel
 pub use tui::create_items_channel;
 
+/// Merge several item channels (e.g. two directory listings plus stdin) into
+/// one receiver that feeds a single TUI session.
+///
+/// # Example
+/// ```no_run
+/// use ff::{create_items_channel, merge_items_channels, run_tui};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// let (src, src_rx) = create_items_channel();
+/// let (docs, docs_rx) = create_items_channel();
+/// tokio::spawn(async move { src.send("src/main.rs".to_string()).await });
+/// tokio::spawn(async move { docs.send("docs/guide.md".to_string()).await });
+/// let selected = run_tui(merge_items_channels(vec![src_rx, docs_rx]), false).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub use tui::merge_items_channels;
+
 /// Configuration for TUI display mode and height.
 ///
 /// # Example
 /// ```no_run
 /// use ff::TuiConfig;
 /// let config = TuiConfig::with_height(10);
+/// let config = TuiConfig {
+///     prompt: "files> ".to_string(),
+///     ..TuiConfig::fullscreen()
+/// };
 /// ```
 pub use tui::TuiConfig;
 
@@ -147,6 +217,122 @@ pub use tui::create_command_channel;
 /// - `Err(e)`: An error occurred during TUI operation
 pub use tui::run_tui_with_indicators;
 
+/// Run the TUI like [`run_tui_with_indicators`], returning the whole
+/// [`TuiOutput`] rather than only the selection.
+pub use tui::run_tui_with_indicators_output;
+
+/// Scoring options and schemes used to rank matches.
+///
+/// [`ScoringConfig::preset`] gives the boundary characters and gap penalties
+/// suited to a scheme (`paths`, `commands`, `prose`, `identifiers`).
+///
+/// # Example
+/// ```no_run
+/// use ff::{Scheme, ScoringConfig, TuiConfig};
+/// let config = TuiConfig {
+///     scoring: ScoringConfig {
+///         scheme: Scheme::Path,
+///         boundary_chars: vec!['=', ','],
+///         ..ScoringConfig::default()
+///     },
+///     ..TuiConfig::default()
+/// };
+/// ```
+pub use fuzzy::{Scheme, ScoringConfig};
+
+/// Item with hidden search text: matched, but never displayed or returned.
+///
+/// # Example
+/// ```
+/// use ff::{FuzzyFinder, SearchItem};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut finder = FuzzyFinder::new(false);
+///     finder
+///         .add_search_items(vec![SearchItem::new("main.rs", "entry point")])
+///         .await;
+///     finder.set_query("entry".to_string()).await;
+///     assert_eq!(finder.get_filtered_items(), &["main.rs"]);
+/// }
+/// ```
+///
+/// The TUI splits incoming items the same way with
+/// [`TuiConfig::hidden_separator`](crate::TuiConfig::hidden_separator).
+pub use fuzzy::SearchItem;
+
+/// Breakdown of a match score into its components, for debugging rankings.
+///
+/// # Example
+/// ```
+/// use ff::score_match_explain;
+/// let parts = score_match_explain("src/fuzzy/scoring.rs", "fzsc").unwrap();
+/// assert!(parts.gaps < 0);
+/// println!("{parts}");
+/// ```
+pub use fuzzy::{score_match_explain, score_match_explain_with_config, ScoreBreakdown};
+
+/// Persistent selection history used to boost frequently and recently picked items.
+///
+/// # Example
+/// ```no_run
+/// use std::sync::Arc;
+/// use ff::{History, ScoringConfig};
+/// let path = History::default_path().unwrap();
+/// let history = History::load(&path).unwrap_or_default();
+/// let scoring = ScoringConfig {
+///     history: Some(Arc::new(history)),
+///     ..ScoringConfig::default()
+/// };
+/// ```
+pub use fuzzy::History;
+
+/// MinHash locality sensitive hashing index for finding near-duplicate items.
+///
+/// # Example
+/// ```
+/// use ff::LSHIndex;
+/// let mut index = LSHIndex::default();
+/// let id = index.insert("12:00:01 GET /api/users 200");
+/// assert!(index.find_similar("12:00:02 GET /api/users 200").contains(&id));
+///
+/// // Standalone nearest-neighbour lookup with estimated similarity
+/// let (nearest_id, item, similarity) = index.nearest("12:00:03 GET /api/users 200", 1)[0];
+/// assert_eq!((nearest_id, item), (id, "12:00:01 GET /api/users 200"));
+/// assert!(similarity > 0.5);
+/// ```
+pub use fuzzy::LSHIndex;
+
+/// Shingle size and band/row layout for [`LSHIndex`].
+///
+/// # Example
+/// ```
+/// use ff::{LSHConfig, LSHIndex};
+/// // 64 hash functions split to catch items that are roughly 70% similar
+/// let config = LSHConfig::for_threshold(64, 0.7).with_shingle_size(2);
+/// assert_eq!(config.num_hashes(), 64);
+/// let index = LSHIndex::new(config);
+/// ```
+pub use fuzzy::LSHConfig;
+
+/// Map/filter hook applied to every incoming item before it reaches the finder,
+/// plus the dedup modes applied after it.
+///
+/// # Example
+/// ```no_run
+/// use ff::{Dedup, ItemTransform, TuiConfig};
+/// let config = TuiConfig {
+///     transforms: vec![
+///         ItemTransform::strip_ansi(),
+///         ItemTransform::filter(|line| !line.starts_with('#')),
+///         ItemTransform::map(|line| line.trim_start_matches("./").to_string()),
+///     ],
+///     dedup: Some(Dedup::Exact),
+///     ..TuiConfig::default()
+/// };
+/// ```
+pub use fuzzy::{Dedup, ItemTransform};
+
 /// Preview state for the fuzzy finder TUI.
 pub use tui::PreviewState;
 
@@ -156,6 +342,34 @@ pub use tui::PreviewResult;
 /// Preview rule: command template + optional extension filter.
 pub use tui::PreviewRule;
 
+/// Keymap preset (`Default`, `Emacs` or `Vim`) for [`TuiConfig::keymap`].
+pub use tui::Keymap;
+
+/// A key name such as `ctrl-o` or `f1`, for [`TuiConfig::expect`].
+pub use tui::KeyName;
+
+/// A key bound to an action with `--bind`, for [`TuiConfig::bindings`].
+pub use tui::{BindAction, KeyBinding};
+
+/// Re-openable item source for [`TuiConfig::reload`].
+pub use tui::ItemSource;
+
+/// Prompt placement (`Reverse` or `BottomUp`) for [`TuiConfig::layout`].
+pub use tui::Layout;
+
+/// Match counter placement (`Default`, `Inline` or `Hidden`) for [`TuiConfig::info`].
+pub use tui::Info;
+
+/// Grid layout of results (`One`, `Fixed(n)` or `Auto`) for [`TuiConfig::columns`].
+pub use tui::Columns;
+
+/// Colors of the prompt, cursor, matches and dim text for [`TuiConfig::theme`].
+pub use tui::Theme;
+
+/// Colors the terminal supports (`NoColor`, `Ansi16`, `Ansi256` or `TrueColor`)
+/// for [`TuiConfig::color_depth`].
+pub use tui::ColorDepth;
+
 /// A session handle for the fuzzy finder, allowing asynchronous item ingestion.
 ///
 /// This struct provides a high-level interface to the fuzzy finder TUI,
@@ -1,8 +1,11 @@
 //! Double-buffered screen rendering to eliminate flickering.
 //!
 //! This module provides a `ScreenBuffer` that accumulates all drawing operations
-//! in memory, then renders the entire frame to the terminal in a single write.
+//! in memory, then renders the frame to the terminal in a single write: in
+//! full, or only the cells that changed since the previous frame.
 
+use crate::tui::theme::ColorDepth;
+use crate::tui::width::char_width;
 use crossterm::style::Color;
 use std::fmt::Write as FmtWrite;
 
@@ -50,6 +53,9 @@ impl Style {
     }
 }
 
+/// Placeholder for the second column of a wide character; not rendered.
+pub const WIDE_CONTINUATION: char = '\0';
+
 /// A single cell in the screen buffer, representing one character position.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Cell {
@@ -63,6 +69,8 @@ pub struct Cell {
     pub bold: bool,
     /// Whether the cell is underlined
     pub underline: bool,
+    /// Whether the cell is drawn in reverse video
+    pub reverse: bool,
 }
 
 impl Default for Cell {
@@ -73,6 +81,7 @@ impl Default for Cell {
             bg: None,
             bold: false,
             underline: false,
+            reverse: false,
         }
     }
 }
@@ -100,6 +109,7 @@ impl Cell {
             bg,
             bold,
             underline,
+            reverse: false,
         }
     }
 }
@@ -112,6 +122,10 @@ pub struct ScreenBuffer {
     cells: Vec<Cell>,
     width: u16,
     height: u16,
+    color_depth: ColorDepth,
+    /// Cells, width and start row of the frame last rendered by
+    /// [`render_diff`](Self::render_diff)
+    drawn: Option<(Vec<Cell>, u16, u16)>,
 }
 
 impl ScreenBuffer {
@@ -122,9 +136,23 @@ impl ScreenBuffer {
             cells: vec![Cell::default(); size],
             width,
             height,
+            color_depth: ColorDepth::default(),
+            drawn: None,
         }
     }
 
+    /// Set the color depth colors are reduced to when rendering.
+    pub fn set_color_depth(&mut self, color_depth: ColorDepth) {
+        self.color_depth = color_depth;
+    }
+
+    /// The colors of `cell` as rendered at this buffer's color depth.
+    fn render_colors(&self, cell: &Cell) -> (Option<Color>, Option<Color>) {
+        let approximate =
+            |color: Option<Color>| color.and_then(|c| self.color_depth.approximate(c));
+        (approximate(cell.fg), approximate(cell.bg))
+    }
+
     /// Clear the buffer, resetting all cells to default (spaces).
     pub fn clear(&mut self) {
         for cell in &mut self.cells {
@@ -162,15 +190,41 @@ impl ScreenBuffer {
         }
     }
 
+    /// Get the cell at the given position.
+    pub fn get_cell(&self, x: u16, y: u16) -> Option<&Cell> {
+        self.index(x, y).map(|idx| &self.cells[idx])
+    }
+
     /// Set a single cell at the given position.
     pub fn set_cell(&mut self, x: u16, y: u16, cell: Cell) {
         if let Some(idx) = self.index(x, y) {
+            self.split_wide(x, idx);
             self.cells[idx] = cell;
         }
     }
 
+    /// Draw the cell at the given position in reverse video, e.g. as a
+    /// text cursor.
+    pub fn set_reverse(&mut self, x: u16, y: u16) {
+        if let Some(idx) = self.index(x, y) {
+            self.cells[idx].reverse = true;
+        }
+    }
+
+    /// Blank the other half of a wide character about to be partly
+    /// overwritten at `x` (cell `idx`).
+    fn split_wide(&mut self, x: u16, idx: usize) {
+        if self.cells[idx].ch == WIDE_CONTINUATION && x > 0 {
+            self.cells[idx - 1].ch = ' ';
+        }
+        if x + 1 < self.width && self.cells[idx + 1].ch == WIDE_CONTINUATION {
+            self.cells[idx + 1].ch = ' ';
+        }
+    }
+
     /// Put a string at the given position with styling.
-    /// Returns the number of characters actually written.
+    /// Returns the number of columns actually written (wide characters
+    /// take two).
     #[allow(clippy::too_many_arguments)]
     pub fn put_str(
         &mut self,
@@ -182,14 +236,13 @@ impl ScreenBuffer {
         bold: bool,
         underline: bool,
     ) -> u16 {
-        let mut written = 0;
-        for (i, ch) in text.chars().enumerate() {
-            let cell_x = x.saturating_add(i as u16);
+        let mut written: u16 = 0;
+        for ch in text.chars() {
+            let cell_x = x.saturating_add(written);
             if cell_x >= self.width {
                 break;
             }
-            self.set_cell(cell_x, y, Cell::styled(ch, fg, bg, bold, underline));
-            written += 1;
+            written += self.put_char(cell_x, y, ch, fg, bg, bold, underline);
         }
         written
     }
@@ -200,6 +253,9 @@ impl ScreenBuffer {
     }
 
     /// Put a single character at the given position with styling.
+    /// Returns the number of columns written: 2 for a wide character, which
+    /// becomes a space if only one column is left, and 0 for a zero-width
+    /// one, which is dropped.
     #[allow(clippy::too_many_arguments)]
     pub fn put_char(
         &mut self,
@@ -210,161 +266,170 @@ impl ScreenBuffer {
         bg: Option<Color>,
         bold: bool,
         underline: bool,
-    ) {
-        self.set_cell(x, y, Cell::styled(ch, fg, bg, bold, underline));
+    ) -> u16 {
+        let Some(idx) = self.index(x, y) else {
+            return 0;
+        };
+        match char_width(ch) {
+            0 => 0,
+            2 if x + 1 < self.width => {
+                self.split_wide(x + 1, idx + 1);
+                self.set_cell(x, y, Cell::styled(ch, fg, bg, bold, underline));
+                self.cells[idx + 1] = Cell::styled(WIDE_CONTINUATION, fg, bg, bold, underline);
+                2
+            }
+            2 => {
+                self.set_cell(x, y, Cell::styled(' ', fg, bg, bold, underline));
+                1
+            }
+            _ => {
+                self.set_cell(x, y, Cell::styled(ch, fg, bg, bold, underline));
+                1
+            }
+        }
     }
 
     /// Render the buffer to a string containing ANSI escape sequences.
     /// This produces the complete output that can be written to the terminal.
     pub fn render(&self, start_row: u16) -> String {
         let mut output = String::with_capacity((self.width as usize + 20) * self.height as usize);
-
-        // Track current style state to minimize escape sequences
-        let mut current_fg: Option<Color> = None;
-        let mut current_bg: Option<Color> = None;
-        let mut current_bold = false;
-        let mut current_underline = false;
-
+        let mut pen = Pen::default();
         for y in 0..self.height {
-            // Move cursor to start of line
-            let _ = write!(output, "\x1b[{};1H", start_row + y + 1);
-
-            // Clear the line first
-            let _ = write!(output, "\x1b[2K");
-
-            for x in 0..self.width {
-                let idx = (y as usize) * (self.width as usize) + (x as usize);
-                let cell = &self.cells[idx];
-
-                // Handle style changes
-                let mut style_changed = false;
-
-                // Check if we need to reset (going from styled to unstyled)
-                let needs_reset = (current_bold && !cell.bold)
-                    || (current_underline && !cell.underline)
-                    || (current_fg.is_some() && cell.fg.is_none())
-                    || (current_bg.is_some() && cell.bg.is_none());
-
-                if needs_reset {
-                    let _ = write!(output, "\x1b[0m");
-                    current_fg = None;
-                    current_bg = None;
-                    current_bold = false;
-                    current_underline = false;
-                    style_changed = true;
-                }
-
-                // Apply bold if needed
-                if cell.bold && !current_bold {
-                    let _ = write!(output, "\x1b[1m");
-                    current_bold = true;
-                    style_changed = true;
-                }
-
-                // Apply underline if needed
-                if cell.underline && !current_underline {
-                    let _ = write!(output, "\x1b[4m");
-                    current_underline = true;
-                    style_changed = true;
-                }
-
-                // Apply foreground color if changed
-                if cell.fg != current_fg && cell.fg.is_some() {
-                    if let Some(color) = cell.fg {
-                        write_fg_color(&mut output, color);
-                        current_fg = cell.fg;
-                        style_changed = true;
-                    }
-                }
-
-                // Apply background color if changed
-                if cell.bg != current_bg && cell.bg.is_some() {
-                    if let Some(color) = cell.bg {
-                        write_bg_color(&mut output, color);
-                        current_bg = cell.bg;
-                        style_changed = true;
-                    }
-                }
-
-                let _ = style_changed; // Suppress warning
-                output.push(cell.ch);
-            }
+            // Move cursor to start of line and clear it
+            let _ = write!(output, "\x1b[{};1H\x1b[2K", start_row + y + 1);
+            self.write_cells(&mut output, &mut pen, self.row_cells(y));
         }
-
         // Reset all attributes at the end
         let _ = write!(output, "\x1b[0m");
-
         output
     }
 
     /// Render the buffer for fullscreen mode (starting at row 0).
     pub fn render_fullscreen(&self) -> String {
         let mut output = String::with_capacity((self.width as usize + 20) * self.height as usize);
-
         // Move to top-left and clear screen
         let _ = write!(output, "\x1b[H\x1b[2J");
-
-        // Track current style state
-        let mut current_fg: Option<Color> = None;
-        let mut current_bg: Option<Color> = None;
-        let mut current_bold = false;
-        let mut current_underline = false;
-
+        let mut pen = Pen::default();
         for y in 0..self.height {
-            // Move cursor to start of line
             let _ = write!(output, "\x1b[{};1H", y + 1);
-
-            for x in 0..self.width {
-                let idx = (y as usize) * (self.width as usize) + (x as usize);
-                let cell = &self.cells[idx];
-
-                // Check if we need to reset
-                let needs_reset = (current_bold && !cell.bold)
-                    || (current_underline && !cell.underline)
-                    || (current_fg.is_some() && cell.fg.is_none())
-                    || (current_bg.is_some() && cell.bg.is_none());
-
-                if needs_reset {
-                    let _ = write!(output, "\x1b[0m");
-                    current_fg = None;
-                    current_bg = None;
-                    current_bold = false;
-                    current_underline = false;
-                }
-
-                if cell.bold && !current_bold {
-                    let _ = write!(output, "\x1b[1m");
-                    current_bold = true;
-                }
-
-                if cell.underline && !current_underline {
-                    let _ = write!(output, "\x1b[4m");
-                    current_underline = true;
-                }
-
-                if cell.fg != current_fg && cell.fg.is_some() {
-                    if let Some(color) = cell.fg {
-                        write_fg_color(&mut output, color);
-                        current_fg = cell.fg;
-                    }
-                }
-
-                if cell.bg != current_bg && cell.bg.is_some() {
-                    if let Some(color) = cell.bg {
-                        write_bg_color(&mut output, color);
-                        current_bg = cell.bg;
-                    }
-                }
-
-                output.push(cell.ch);
-            }
+            self.write_cells(&mut output, &mut pen, self.row_cells(y));
         }
-
-        // Reset all attributes at the end
         let _ = write!(output, "\x1b[0m");
+        output
+    }
+
+    /// Render only what changed since the last call, for the buffer drawn
+    /// at `start_row`: the changed span of each row is rewritten in place,
+    /// so nothing is cleared and the terminal never shows a blank frame.
+    /// The first frame, and any after a resize, a move or
+    /// [`invalidate`](Self::invalidate), is rendered in full like
+    /// [`render`](Self::render).
+    pub fn render_diff(&mut self, start_row: u16) -> String {
+        let previous = match self.drawn.take() {
+            Some((cells, width, row))
+                if width == self.width && row == start_row && cells.len() == self.cells.len() =>
+            {
+                cells
+            }
+            _ => {
+                self.drawn = Some((self.cells.clone(), self.width, start_row));
+                return self.render(start_row);
+            }
+        };
 
+        let mut output = String::new();
+        let width = self.width as usize;
+        for y in 0..self.height {
+            let row = self.row_cells(y);
+            let changed = |x: &usize| self.cells[row.start + x] != previous[row.start + x];
+            let Some(first) = (0..width).find(changed) else {
+                continue;
+            };
+            let last = (0..width).rev().find(changed).unwrap_or(first);
+            // Redraw wide characters whole
+            let is_continuation = |x: usize| self.cells[row.start + x].ch == WIDE_CONTINUATION;
+            let mut from = first;
+            while from > 0 && is_continuation(from) {
+                from -= 1;
+            }
+            let mut to = last + 1;
+            while to < width && is_continuation(to) {
+                to += 1;
+            }
+            let _ = write!(output, "\x1b[{};{}H", start_row + y + 1, from + 1);
+            let mut pen = Pen::default();
+            self.write_cells(&mut output, &mut pen, row.start + from..row.start + to);
+            let _ = write!(output, "\x1b[0m");
+        }
+        let mut drawn = previous;
+        drawn.clone_from(&self.cells);
+        self.drawn = Some((drawn, self.width, start_row));
         output
     }
+
+    /// Forget the last frame, so the next [`render_diff`](Self::render_diff)
+    /// draws everything, e.g. after something else drew on the terminal.
+    pub fn invalidate(&mut self) {
+        self.drawn = None;
+    }
+
+    /// Indices of the cells of row `y`
+    fn row_cells(&self, y: u16) -> std::ops::Range<usize> {
+        let start = (y as usize) * (self.width as usize);
+        start..start + self.width as usize
+    }
+
+    /// Write `cells`, switching styles as needed from what `pen` last set
+    fn write_cells(&self, output: &mut String, pen: &mut Pen, cells: std::ops::Range<usize>) {
+        for cell in &self.cells[cells] {
+            if cell.ch == WIDE_CONTINUATION {
+                continue;
+            }
+            let (fg, bg) = self.render_colors(cell);
+
+            // Reset when going from styled to unstyled
+            let needs_reset = (pen.bold && !cell.bold)
+                || (pen.underline && !cell.underline)
+                || (pen.reverse && !cell.reverse)
+                || (pen.fg.is_some() && fg.is_none())
+                || (pen.bg.is_some() && bg.is_none());
+            if needs_reset {
+                let _ = write!(output, "\x1b[0m");
+                *pen = Pen::default();
+            }
+            if cell.bold && !pen.bold {
+                let _ = write!(output, "\x1b[1m");
+                pen.bold = true;
+            }
+            if cell.underline && !pen.underline {
+                let _ = write!(output, "\x1b[4m");
+                pen.underline = true;
+            }
+            if cell.reverse && !pen.reverse {
+                let _ = write!(output, "\x1b[7m");
+                pen.reverse = true;
+            }
+            if let Some(color) = fg.filter(|_| fg != pen.fg) {
+                write_fg_color(output, color);
+                pen.fg = fg;
+            }
+            if let Some(color) = bg.filter(|_| bg != pen.bg) {
+                write_bg_color(output, color);
+                pen.bg = bg;
+            }
+            output.push(cell.ch);
+        }
+    }
+}
+
+/// Style last set on the terminal while rendering, to skip repeating it
+#[derive(Default)]
+struct Pen {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    bold: bool,
+    underline: bool,
+    reverse: bool,
 }
 
 /// Write foreground color escape sequence
@@ -597,6 +662,28 @@ mod tests {
         assert!(buffer.cells[idx].underline);
     }
 
+    #[test]
+    fn test_buffer_wide_chars() {
+        let mut buffer = ScreenBuffer::new(5, 1);
+        assert_eq!(buffer.put_str(0, 0, "日本語", None, None, false, false), 5);
+        assert_eq!(buffer.cells[0].ch, '日');
+        assert_eq!(buffer.cells[1].ch, WIDE_CONTINUATION);
+        // No room for the second half of the last one
+        assert_eq!(buffer.cells[4].ch, ' ');
+        assert!(buffer.render(0).contains("日本 "));
+
+        // Overwriting half of a wide character blanks the other half
+        buffer.put_char(1, 0, 'x', None, None, false, false);
+        let row: String = buffer.cells.iter().map(|c| c.ch).collect();
+        assert_eq!(row, " x本\0 ");
+
+        // Zero-width characters are dropped
+        assert_eq!(
+            buffer.put_str(0, 0, "e\u{0301}", None, None, false, false),
+            1
+        );
+    }
+
     #[test]
     fn test_buffer_out_of_bounds() {
         let mut buffer = ScreenBuffer::new(10, 10);
@@ -620,6 +707,71 @@ mod tests {
         assert!(output.ends_with("\x1b[0m"));
     }
 
+    #[test]
+    fn test_render_reverse() {
+        let mut buffer = ScreenBuffer::new(3, 1);
+        buffer.put_str(0, 0, "abc", None, None, false, false);
+        buffer.set_reverse(1, 0);
+        assert!(buffer.get_cell(1, 0).unwrap().reverse);
+        assert!(buffer.render(0).contains("a\x1b[7mb\x1b[0mc"));
+        assert!(buffer.render_fullscreen().contains("a\x1b[7mb\x1b[0mc"));
+        // Drawing over the cell clears it
+        buffer.put_char(1, 0, 'x', None, None, false, false);
+        assert!(!buffer.get_cell(1, 0).unwrap().reverse);
+    }
+
+    #[test]
+    fn test_render_reduces_colors_to_depth() {
+        let mut buffer = ScreenBuffer::new(4, 1);
+        let orange = Color::Rgb {
+            r: 255,
+            g: 135,
+            b: 0,
+        };
+        buffer.put_str(0, 0, "ab", Some(orange), None, true, false);
+        assert!(buffer.render(0).contains("\x1b[38;2;255;135;0m"));
+
+        buffer.set_color_depth(ColorDepth::Ansi256);
+        assert!(buffer.render(0).contains("\x1b[38;5;208m"));
+
+        buffer.set_color_depth(ColorDepth::NoColor);
+        let output = buffer.render_fullscreen();
+        assert!(!output.contains("\x1b[38"));
+        // Attributes still apply without color
+        assert!(output.contains("\x1b[1m"));
+    }
+
+    #[test]
+    fn test_render_diff() {
+        let mut buffer = ScreenBuffer::new(6, 3);
+        buffer.put_str_plain(0, 0, "abc");
+        buffer.put_str_plain(0, 1, "日本");
+        // The first frame is drawn in full
+        assert_eq!(buffer.render_diff(2), buffer.render(2));
+
+        // Unchanged frames draw nothing
+        buffer.clear();
+        buffer.put_str_plain(0, 0, "abc");
+        buffer.put_str_plain(0, 1, "日本");
+        assert_eq!(buffer.render_diff(2), "");
+
+        // Only the changed span of a row is redrawn, from the start of a
+        // wide character
+        buffer.put_str(1, 0, "X", Some(Color::Red), None, false, false);
+        buffer.put_char(3, 1, 'x', None, None, false, false);
+        let output = buffer.render_diff(2);
+        assert!(output.contains("\x1b[3;2H\x1b[31mX\x1b[0m"));
+        assert!(output.contains("\x1b[4;3H x\x1b[0m"));
+        assert!(!output.contains("\x1b[2K"));
+
+        // Moving, resizing or invalidating redraws everything
+        assert_eq!(buffer.render_diff(1), buffer.render(1));
+        buffer.invalidate();
+        assert_eq!(buffer.render_diff(1), buffer.render(1));
+        buffer.resize(5, 3);
+        assert_eq!(buffer.render_diff(1), buffer.render(1));
+    }
+
     #[test]
     fn test_render_fullscreen() {
         let mut buffer = ScreenBuffer::new(10, 2);
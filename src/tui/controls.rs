@@ -1,6 +1,10 @@
 use crate::fuzzy::FuzzyFinder;
+use crate::tui::layout::ScreenLayout;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
+/// Rows moved by PageUp/PageDown when the list has not been drawn
+const DEFAULT_PAGE: usize = 10;
+
 /// Actions that can be performed by the TUI
 #[derive(Debug, PartialEq)]
 pub enum Action {
@@ -14,6 +18,9 @@ pub enum Action {
 
 /// Handle key events and return appropriate actions
 pub fn handle_key_event(key_event: &KeyEvent, fuzzy_finder: &mut FuzzyFinder) -> Action {
+    if handle_page_key(key_event, fuzzy_finder, &ScreenLayout::default()) {
+        return Action::Continue;
+    }
     match key_event.code {
         KeyCode::Char(c) => {
             if (c == 'q' || c == 'c') && key_event.modifiers.contains(KeyModifiers::CONTROL) {
@@ -76,6 +83,64 @@ pub fn handle_key_event(key_event: &KeyEvent, fuzzy_finder: &mut FuzzyFinder) ->
     }
 }
 
+/// Handle PageUp/PageDown/Home/End, returning whether `key_event` was one.
+///
+/// Home and End jump to the first and last result. PageUp and PageDown
+/// first move the cursor to the top or bottom of the rows visible in
+/// `layout`, then by a page of its height, so that keeping the cursor in
+/// view scrolls the list a page at a time. When results grow upward,
+/// PageUp moves towards the last result instead.
+pub fn handle_page_key(
+    key_event: &KeyEvent,
+    fuzzy_finder: &mut FuzzyFinder,
+    layout: &ScreenLayout,
+) -> bool {
+    if !matches!(
+        key_event.code,
+        KeyCode::PageUp | KeyCode::PageDown | KeyCode::Home | KeyCode::End
+    ) {
+        return false;
+    }
+    let len = fuzzy_finder.get_filtered_items().len();
+    if len == 0 {
+        return true;
+    }
+    let cursor = fuzzy_finder.get_cursor_position();
+    let per_row = layout.per_row();
+    let visible = || {
+        layout
+            .rows
+            .iter()
+            .flatten()
+            .flat_map(move |&first| first..first + per_row)
+    };
+    let page = if layout.rows.is_empty() {
+        DEFAULT_PAGE
+    } else {
+        layout.rows.len() * per_row
+    };
+    let code = match key_event.code {
+        KeyCode::PageUp if layout.upward => KeyCode::PageDown,
+        KeyCode::PageDown if layout.upward => KeyCode::PageUp,
+        code => code,
+    };
+    let target = match code {
+        KeyCode::Home => 0,
+        KeyCode::End => len - 1,
+        KeyCode::PageUp => match visible().min() {
+            Some(top) if cursor > top => top,
+            _ => cursor.saturating_sub(page),
+        },
+        _ => match visible().max() {
+            Some(bottom) if cursor < bottom => bottom,
+            _ => cursor + page,
+        }
+        .min(len - 1),
+    };
+    fuzzy_finder.move_cursor_clamped(target as i32 - cursor as i32);
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -319,4 +384,80 @@ mod tests {
 
         assert_eq!(action, Action::Continue);
     }
+
+    #[tokio::test]
+    async fn test_handle_key_event_home_end() {
+        let items: Vec<String> = (0..30).map(|i| format!("item{i}")).collect();
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+
+        let end = KeyEvent::new(KeyCode::End, KeyModifiers::empty());
+        assert_eq!(handle_key_event(&end, &mut finder), Action::Continue);
+        assert_eq!(finder.get_cursor_position(), 29);
+
+        let home = KeyEvent::new(KeyCode::Home, KeyModifiers::empty());
+        handle_key_event(&home, &mut finder);
+        assert_eq!(finder.get_cursor_position(), 0);
+
+        // Without a drawn list, pages are a default size
+        let page_down = KeyEvent::new(KeyCode::PageDown, KeyModifiers::empty());
+        handle_key_event(&page_down, &mut finder);
+        assert_eq!(finder.get_cursor_position(), DEFAULT_PAGE);
+    }
+
+    #[tokio::test]
+    async fn test_handle_page_key_pages_through_visible_rows() {
+        let items: Vec<String> = (0..12).map(|i| format!("item{i}")).collect();
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        let page_down = KeyEvent::new(KeyCode::PageDown, KeyModifiers::empty());
+        let page_up = KeyEvent::new(KeyCode::PageUp, KeyModifiers::empty());
+        let layout = |first: usize| ScreenLayout {
+            rows: (first..first + 5).map(Some).collect(),
+            ..ScreenLayout::default()
+        };
+
+        // First to the bottom of the view, then a page further
+        assert!(handle_page_key(&page_down, &mut finder, &layout(0)));
+        assert_eq!(finder.get_cursor_position(), 4);
+        assert!(handle_page_key(&page_down, &mut finder, &layout(0)));
+        assert_eq!(finder.get_cursor_position(), 9);
+        // Clamped to the last result
+        handle_page_key(&page_down, &mut finder, &layout(5));
+        assert_eq!(finder.get_cursor_position(), 11);
+
+        handle_page_key(&page_up, &mut finder, &layout(7));
+        assert_eq!(finder.get_cursor_position(), 7);
+        handle_page_key(&page_up, &mut finder, &layout(7));
+        assert_eq!(finder.get_cursor_position(), 2);
+
+        let up = KeyEvent::new(KeyCode::Up, KeyModifiers::empty());
+        assert!(!handle_page_key(&up, &mut finder, &layout(0)));
+
+        // Results growing upward page the other way
+        let upward = ScreenLayout {
+            upward: true,
+            ..layout(0)
+        };
+        handle_page_key(&page_up, &mut finder, &upward);
+        assert_eq!(finder.get_cursor_position(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_handle_page_key_pages_through_grid() {
+        let items: Vec<String> = (0..30).map(|i| format!("item{i}")).collect();
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        let page_down = KeyEvent::new(KeyCode::PageDown, KeyModifiers::empty());
+        // Two rows of four
+        let layout = |first: usize| ScreenLayout {
+            rows: vec![Some(first), Some(first + 4)],
+            columns: 4,
+            cell_width: 10,
+            len: 30,
+            ..ScreenLayout::default()
+        };
+
+        handle_page_key(&page_down, &mut finder, &layout(0));
+        assert_eq!(finder.get_cursor_position(), 7);
+        handle_page_key(&page_down, &mut finder, &layout(0));
+        assert_eq!(finder.get_cursor_position(), 15);
+    }
 }
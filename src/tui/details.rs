@@ -0,0 +1,173 @@
+//! Detail pane: the current item in full, wrapped, with its place in the
+//! results, score and hidden text. Toggled with F2, for items cut off in
+//! the list.
+
+use crate::fuzzy::FuzzyFinder;
+use crate::tui::buffer::ScreenBuffer;
+use crate::tui::preview::strip_ansi_sequences;
+use crate::tui::theme::Theme;
+use crate::tui::width::{char_width, truncate_to_width, wrap_ranges};
+use std::ops::Range;
+
+/// What the detail pane shows for an item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Details {
+    /// Result and item numbers, score and weight, as in
+    /// `3/120 · item 45 · Fuzzy 412`
+    pub summary: String,
+    /// Hidden search text, if any
+    pub hidden: Option<String>,
+    /// The item as printed, without ANSI styles
+    pub text: String,
+}
+
+impl Details {
+    /// Details of the result under the cursor (`None` without results)
+    pub fn of_current(finder: &FuzzyFinder) -> Option<Self> {
+        let position = finder.get_cursor_position();
+        let item = finder.get_filtered_items().get(position)?;
+        let original = finder.get_original_index(position)?;
+        let mut summary = vec![
+            format!("{}/{}", position + 1, finder.get_filtered_items().len()),
+            format!("item {}", original + 1),
+        ];
+        if let Some(m) = finder
+            .get_match_positions(position)
+            .filter(|_| !finder.get_query().is_empty())
+        {
+            summary.push(format!("{:?} {}", m.tier, m.score));
+        }
+        let weight = finder.get_weight(original);
+        if weight != 0 {
+            summary.push(format!("weight {weight:+}"));
+        }
+        Some(Self {
+            summary: summary.join(" · "),
+            hidden: finder.get_hidden_text(original).map(str::to_string),
+            text: strip_ansi_sequences(item),
+        })
+    }
+
+    /// Lines to draw at `width` columns: the summary, hidden text, then
+    /// each line of the item wrapped
+    fn lines(&self, width: usize) -> Vec<(String, bool)> {
+        let mut lines = vec![(self.summary.clone(), true)];
+        if let Some(hidden) = &self.hidden {
+            lines.push((format!("hidden: {hidden}"), true));
+        }
+        for line in self.text.split('\n') {
+            let chars: Vec<char> = line.chars().collect();
+            let widths: Vec<usize> = chars.iter().map(|&ch| char_width(ch) as usize).collect();
+            for range in wrap_ranges(&widths, width) {
+                lines.push((chars[range].iter().collect(), false));
+            }
+        }
+        lines
+    }
+}
+
+/// Draw `details` in a box `width` columns wide from column `left`, over
+/// at most half of `rows`: at their top if `at_top`, otherwise at their
+/// bottom. Lines that don't fit are cut off.
+pub fn draw_details(
+    buffer: &mut ScreenBuffer,
+    details: &Details,
+    left: u16,
+    width: u16,
+    rows: Range<u16>,
+    at_top: bool,
+    theme: &Theme,
+) {
+    if width < 4 {
+        return;
+    }
+    let inner = (width - 4) as usize;
+    let lines = details.lines(inner);
+    let room = (rows.len() as u16 / 2).max(3).min(rows.len() as u16);
+    let height = (lines.len() as u16 + 2).min(room);
+    if height < 3 {
+        return;
+    }
+    let top = if at_top {
+        rows.start
+    } else {
+        rows.end - height
+    };
+    let border = Some(theme.border);
+    for row in top..top + height {
+        let (first, fill, last) = if row == top {
+            ('┌', '─', '┐')
+        } else if row == top + height - 1 {
+            ('└', '─', '┘')
+        } else {
+            ('│', ' ', '│')
+        };
+        buffer.put_char(left, row, first, border, None, false, false);
+        for col in left + 1..left + width - 1 {
+            buffer.put_char(col, row, fill, border, None, false, false);
+        }
+        buffer.put_char(left + width - 1, row, last, border, None, false, false);
+    }
+    for (i, (line, dim)) in lines.iter().take(height as usize - 2).enumerate() {
+        let row = top + 1 + i as u16;
+        let text = truncate_to_width(line, inner);
+        if *dim {
+            buffer.put_str(left + 2, row, text, Some(theme.dim), None, false, false);
+        } else {
+            buffer.put_str_plain(left + 2, row, text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzy::SearchItem;
+
+    fn row_text(buffer: &ScreenBuffer, row: u16) -> String {
+        (0..buffer.width())
+            .map(|col| buffer.get_cell(col, row).unwrap().ch)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_details_of_current() {
+        let mut finder = FuzzyFinder::new(false);
+        assert_eq!(Details::of_current(&finder), None);
+        finder
+            .add_search_items(vec![
+                SearchItem::from("alpha".to_string()),
+                SearchItem::new("\x1b[1mbeta\x1b[0m", "tag").with_weight(3),
+            ])
+            .await;
+        finder.set_query("bet".to_string()).await;
+        let details = Details::of_current(&finder).unwrap();
+        assert_eq!(details.text, "beta");
+        assert_eq!(details.hidden.as_deref(), Some("tag"));
+        assert!(details.summary.starts_with("1/1 · item 2 · Prefix "));
+        assert!(details.summary.ends_with(" · weight +3"));
+    }
+
+    #[test]
+    fn test_draw_details() {
+        let details = Details {
+            summary: "1/1".to_string(),
+            hidden: None,
+            text: "abcdefgh".to_string(),
+        };
+        let mut buffer = ScreenBuffer::new(10, 8);
+        draw_details(&mut buffer, &details, 0, 10, 0..8, false, &Theme::default());
+        assert_eq!(row_text(&buffer, 3).trim(), "");
+        assert_eq!(row_text(&buffer, 4), "┌────────┐");
+        assert_eq!(row_text(&buffer, 5), "│ 1/1    │");
+        assert_eq!(row_text(&buffer, 6), "│ abcdef │");
+        assert_eq!(row_text(&buffer, 7), "└────────┘");
+
+        // More rows: the whole item, wrapped
+        let mut buffer = ScreenBuffer::new(10, 10);
+        draw_details(&mut buffer, &details, 0, 10, 0..10, true, &Theme::default());
+        assert_eq!(row_text(&buffer, 2), "│ abcdef │");
+        assert_eq!(row_text(&buffer, 3), "│ gh     │");
+        assert_eq!(row_text(&buffer, 4), "└────────┘");
+    }
+}
@@ -1,13 +1,19 @@
 use crate::fuzzy::FuzzyFinder;
-use crate::tui::controls::Action;
+use crate::tui::controls::{handle_page_key, Action};
+use crate::tui::layout::ScreenLayout;
 use crate::tui::preview::PreviewState;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 
-/// Handle key events in async mode
+/// Preview lines scrolled per mouse wheel step
+const WHEEL_LINES: usize = 3;
+
+/// Handle key events in async mode; `layout` is where the list was last
+/// drawn, for paging through it
 pub async fn handle_async_key_event(
     key_event: &KeyEvent,
     fuzzy_finder: &mut FuzzyFinder,
     preview_state: &mut PreviewState,
+    layout: &ScreenLayout,
 ) -> Action {
     // Preview-focused navigation
     if preview_state.focused {
@@ -21,6 +27,15 @@ pub async fn handle_async_key_event(
                 preview_state.scroll_down(1, max);
                 return Action::Continue;
             }
+            KeyCode::PageUp => {
+                preview_state.scroll_up(available_height_for_preview(preview_state));
+                return Action::Continue;
+            }
+            KeyCode::PageDown => {
+                let max = preview_state.lines.len();
+                preview_state.scroll_down(available_height_for_preview(preview_state), max);
+                return Action::Continue;
+            }
             KeyCode::Left => {
                 preview_state.focused = false;
                 return Action::Continue;
@@ -47,6 +62,45 @@ pub async fn handle_async_key_event(
         }
     }
 
+    // Scroll the preview from the list with Shift
+    if preview_state.visible && key_event.modifiers.contains(KeyModifiers::SHIFT) {
+        let max = preview_state.lines.len();
+        let page = available_height_for_preview(preview_state);
+        match key_event.code {
+            KeyCode::Up => preview_state.scroll_up(1),
+            KeyCode::Down => preview_state.scroll_down(1, max),
+            KeyCode::PageUp => preview_state.scroll_up(page),
+            KeyCode::PageDown => preview_state.scroll_down(page, max),
+            _ => return handle_list_key(key_event, fuzzy_finder, preview_state, layout).await,
+        }
+        return Action::Continue;
+    }
+
+    handle_list_key(key_event, fuzzy_finder, preview_state, layout).await
+}
+
+/// Handle a key for the result list and query
+async fn handle_list_key(
+    key_event: &KeyEvent,
+    fuzzy_finder: &mut FuzzyFinder,
+    preview_state: &mut PreviewState,
+    layout: &ScreenLayout,
+) -> Action {
+    // Home and End move the query's editing cursor first, then jump
+    // through the list; with Ctrl (as the keymaps' first and last result
+    // keys are translated) they only jump
+    let plain = !key_event.modifiers.contains(KeyModifiers::CONTROL);
+    let moved_in_query = match key_event.code {
+        KeyCode::Home if plain => fuzzy_finder.set_query_cursor(0),
+        KeyCode::End if plain => fuzzy_finder.set_query_cursor(usize::MAX),
+        _ => false,
+    };
+    if moved_in_query
+        || handle_page_key(key_event, fuzzy_finder, layout)
+        || handle_query_edit_key(key_event, fuzzy_finder, preview_state).await
+    {
+        return Action::Continue;
+    }
     match key_event.code {
         KeyCode::Char(c) => {
             if (c == 'q' || c == 'c') && key_event.modifiers.contains(KeyModifiers::CONTROL) {
@@ -54,6 +108,19 @@ pub async fn handle_async_key_event(
             } else if c == ' ' && fuzzy_finder.is_multi_select() {
                 fuzzy_finder.toggle_selection();
                 Action::Continue
+            } else if key_event.modifiers.contains(KeyModifiers::CONTROL)
+                && fuzzy_finder.is_multi_select()
+                && matches!(c, 'a' | 'd' | 't')
+            {
+                match c {
+                    'a' => fuzzy_finder.select_all(),
+                    'd' => fuzzy_finder.deselect_all(),
+                    _ => fuzzy_finder.toggle_all(),
+                }
+                Action::Continue
+            } else if c == 'y' && key_event.modifiers.contains(KeyModifiers::CONTROL) {
+                fuzzy_finder.accept_suggestion().await;
+                Action::Continue
             } else if c == 'p' && key_event.modifiers.contains(KeyModifiers::CONTROL) {
                 preview_state.toggle_visible();
                 Action::Continue
@@ -69,34 +136,52 @@ pub async fn handle_async_key_event(
                 }
                 Action::Continue
             } else {
-                let mut query = fuzzy_finder.get_query().to_string();
-                query.push(c);
-                fuzzy_finder.set_query(query).await;
+                fuzzy_finder.insert_query_char(c).await;
                 Action::Continue
             }
         }
         KeyCode::Backspace => {
-            let mut query = fuzzy_finder.get_query().to_string();
-            query.pop();
-            fuzzy_finder.set_query(query).await;
+            fuzzy_finder.delete_query_char_before().await;
             Action::Continue
         }
+        KeyCode::Delete => {
+            fuzzy_finder.delete_query_char_after().await;
+            Action::Continue
+        }
+        // Keys move up and down the screen, whichever way results grow
         KeyCode::Up => {
-            fuzzy_finder.move_cursor(-1);
+            move_rows(fuzzy_finder, layout, if layout.upward { 1 } else { -1 });
             Action::Continue
         }
         KeyCode::Down => {
-            fuzzy_finder.move_cursor(1);
+            move_rows(fuzzy_finder, layout, if layout.upward { -1 } else { 1 });
             Action::Continue
         }
+        // In a grid, Left and Right move along the row. Otherwise, or at
+        // the row's ends, they move the query's editing cursor while they
+        // can, and Right past its end focuses the preview.
         KeyCode::Left => {
-            if preview_state.visible {
+            let cursor = fuzzy_finder.get_cursor_position();
+            if layout.per_row() > 1 && !cursor.is_multiple_of(layout.per_row()) {
+                fuzzy_finder.move_cursor_clamped(-1);
+            } else if fuzzy_finder.move_query_cursor(-1) {
+                // Moved within the query
+            } else if preview_state.visible {
                 preview_state.focused = false;
             }
             Action::Continue
         }
         KeyCode::Right => {
-            if preview_state.visible {
+            let cursor = fuzzy_finder.get_cursor_position();
+            let per_row = layout.per_row();
+            if per_row > 1
+                && cursor % per_row < per_row - 1
+                && cursor + 1 < fuzzy_finder.get_filtered_items().len()
+            {
+                fuzzy_finder.move_cursor_clamped(1);
+            } else if fuzzy_finder.move_query_cursor(1) {
+                // Moved within the query
+            } else if preview_state.visible {
                 preview_state.focused = true;
             }
             Action::Continue
@@ -146,8 +231,122 @@ pub async fn handle_async_key_event(
     }
 }
 
-/// Helper for Ctrl+U/D scroll amount in preview pane
+/// Handle mouse events: clicking an item moves the cursor to it (clicking
+/// the cursor's item again, or middle-clicking, toggles its selection in
+/// multi-select mode), and the wheel scrolls the list or the preview
+/// under the pointer
+pub fn handle_mouse_event(
+    mouse_event: &MouseEvent,
+    fuzzy_finder: &mut FuzzyFinder,
+    preview_state: &mut PreviewState,
+    layout: &ScreenLayout,
+) -> Action {
+    let (column, row) = (mouse_event.column, mouse_event.row);
+    let in_preview = preview_state.visible && layout.in_preview(column);
+    match mouse_event.kind {
+        MouseEventKind::Down(MouseButton::Left) if in_preview => {
+            preview_state.focused = true;
+        }
+        MouseEventKind::Down(button @ (MouseButton::Left | MouseButton::Middle)) => {
+            let Some(position) = layout.item_at(column, row) else {
+                return Action::Continue;
+            };
+            preview_state.focused = false;
+            let cursor = fuzzy_finder.get_cursor_position();
+            let toggle = button == MouseButton::Middle || position == cursor;
+            fuzzy_finder.move_cursor_clamped(position as i32 - cursor as i32);
+            if toggle && fuzzy_finder.is_multi_select() {
+                fuzzy_finder.toggle_selection();
+            }
+        }
+        MouseEventKind::ScrollUp if in_preview => preview_state.scroll_up(WHEEL_LINES),
+        MouseEventKind::ScrollDown if in_preview => {
+            preview_state.scroll_down(WHEEL_LINES, preview_state.lines.len());
+        }
+        MouseEventKind::ScrollUp => {
+            fuzzy_finder.move_cursor_clamped(if layout.upward { 1 } else { -1 });
+        }
+        MouseEventKind::ScrollDown => {
+            fuzzy_finder.move_cursor_clamped(if layout.upward { -1 } else { 1 });
+        }
+        _ => {}
+    }
+    Action::Continue
+}
+
+/// Handle readline-style query editing keys, returning whether `key_event`
+/// was one: Ctrl+W deletes the word before the editing cursor (up to a
+/// space) and Alt+Backspace the alphanumeric word, Ctrl+U and Ctrl+K delete
+/// to the start and end, Ctrl+A and Ctrl+E move to the start and end, and
+/// Alt+B and Alt+F move by alphanumeric words. Ctrl+A selects all in
+/// multi-select mode and Ctrl+U scrolls a shown preview instead.
+async fn handle_query_edit_key(
+    key_event: &KeyEvent,
+    fuzzy_finder: &mut FuzzyFinder,
+    preview_state: &PreviewState,
+) -> bool {
+    let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key_event.modifiers.contains(KeyModifiers::ALT);
+    let query = fuzzy_finder.get_query();
+    let cursor = fuzzy_finder.get_query_cursor();
+    let alphanumeric = |c: char| c.is_alphanumeric();
+    // Where the edit reaches, and whether to delete up to it or move there
+    let (offset, delete) = match key_event.code {
+        KeyCode::Char('w') if ctrl => (word_start(query, cursor, |c| !c.is_whitespace()), true),
+        KeyCode::Backspace if alt => (word_start(query, cursor, alphanumeric), true),
+        KeyCode::Char('u') if ctrl && !preview_state.visible => (0, true),
+        KeyCode::Char('k') if ctrl => (query.len(), true),
+        KeyCode::Char('a') if ctrl && !fuzzy_finder.is_multi_select() => (0, false),
+        KeyCode::Char('e') if ctrl => (query.len(), false),
+        KeyCode::Char('b') if alt => (word_start(query, cursor, alphanumeric), false),
+        KeyCode::Char('f') if alt => (word_end(query, cursor, alphanumeric), false),
+        _ => return false,
+    };
+    if delete {
+        fuzzy_finder
+            .delete_query_range(offset.min(cursor)..offset.max(cursor))
+            .await;
+    } else {
+        fuzzy_finder.set_query_cursor(offset);
+    }
+    true
+}
+
+/// Start of the word before byte `cursor` in `query`: back over characters
+/// that aren't `is_word`, then over those that are
+fn word_start(query: &str, cursor: usize, is_word: impl Fn(char) -> bool) -> usize {
+    let before = query[..cursor].trim_end_matches(|c| !is_word(c));
+    before.trim_end_matches(is_word).len()
+}
+
+/// End of the word after byte `cursor` in `query`: forward over characters
+/// that aren't `is_word`, then over those that are
+fn word_end(query: &str, cursor: usize, is_word: impl Fn(char) -> bool) -> usize {
+    let after = query[cursor..].trim_start_matches(|c| !is_word(c));
+    query.len() - after.trim_start_matches(is_word).len()
+}
+
+/// Move the cursor `rows` list rows. A single column wraps around (see
+/// [`FuzzyFinder::set_cycle`]); a grid stays put rather than move past its
+/// first or last row.
+fn move_rows(fuzzy_finder: &mut FuzzyFinder, layout: &ScreenLayout, rows: i32) {
+    let per_row = layout.per_row();
+    if per_row == 1 {
+        fuzzy_finder.move_cursor(rows);
+        return;
+    }
+    let target = fuzzy_finder.get_cursor_position() as i64 + rows as i64 * per_row as i64;
+    if target >= 0 && (target as usize) < fuzzy_finder.get_filtered_items().len() {
+        fuzzy_finder.move_cursor_clamped(rows * per_row as i32);
+    }
+}
+
+/// Rows in a page of the preview pane, for paging and Ctrl+U/D
 fn available_height_for_preview(preview_state: &PreviewState) -> usize {
-    // Approximate: we don't have config here, use a reasonable default
-    preview_state.lines.len().min(20)
+    if preview_state.height > 0 {
+        preview_state.height
+    } else {
+        // Not drawn yet: approximate
+        preview_state.lines.len().min(20)
+    }
 }
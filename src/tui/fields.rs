@@ -0,0 +1,253 @@
+//! `--with-nth`: the fields of each item that are displayed. Items are
+//! still matched and printed whole; only what is drawn changes.
+
+use crate::fuzzy::finder::MatchPositions;
+use crate::tui::preview::strip_ansi_sequences;
+use std::ops::Range;
+
+/// A field number or range, 1-based and inclusive; negative numbers count
+/// from the last field (`-1`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FieldRange {
+    start: Option<i64>,
+    end: Option<i64>,
+}
+
+impl FieldRange {
+    fn parse(s: &str) -> Option<Self> {
+        let bound = |b: &str| match b.trim() {
+            "" => Some(None),
+            b => b.parse::<i64>().ok().filter(|&n| n != 0).map(Some),
+        };
+        match s.split_once("..") {
+            Some((start, end)) => Some(Self {
+                start: bound(start)?,
+                end: bound(end)?,
+            }),
+            None => {
+                let n = bound(s)?;
+                n.map(|_| Self { start: n, end: n })
+            }
+        }
+    }
+
+    /// 0-based field indices among `count` fields
+    fn resolve(&self, count: usize) -> Range<usize> {
+        let index = |n: i64| {
+            if n > 0 {
+                n as usize - 1
+            } else {
+                count.saturating_sub(n.unsigned_abs() as usize)
+            }
+        };
+        let start = self.start.map_or(0, index).min(count);
+        let end = self.end.map_or(count, |n| (index(n) + 1).min(count));
+        start..end.max(start)
+    }
+}
+
+/// Fields of each item to display, split at a delimiter or, by default, at
+/// runs of whitespace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fields {
+    ranges: Vec<FieldRange>,
+    delimiter: Option<char>,
+}
+
+impl Fields {
+    /// Parse comma-separated field numbers and ranges: `N`, `N..M`, `N..`
+    /// or `..M`, with negative numbers counting from the last field
+    pub fn parse(spec: &str, delimiter: Option<char>) -> Result<Self, String> {
+        let ranges = spec
+            .split(',')
+            .map(|part| {
+                FieldRange::parse(part).ok_or_else(|| {
+                    format!("Invalid field '{part}'. Expected N, N..M, N.. or ..M (N not 0)")
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { ranges, delimiter })
+    }
+
+    /// Character ranges of the fields of `text` to display. A field keeps
+    /// the delimiter (or whitespace) after it, except the last one shown.
+    pub fn shown(&self, text: &str) -> Vec<Range<usize>> {
+        let fields = self.split(text);
+        let mut shown: Vec<Range<usize>> = Vec::new();
+        for range in &self.ranges {
+            for field in &fields[range.resolve(fields.len())] {
+                match shown.last_mut() {
+                    Some(last) if last.end == field.start => last.end = field.end,
+                    _ => shown.push(field.clone()),
+                }
+            }
+        }
+        // Drop the delimiter (or whitespace) trailing the last field shown
+        let chars: Vec<char> = text.chars().collect();
+        if let Some(last) = shown.last_mut() {
+            let field = &chars[last.clone()];
+            last.end -= match self.delimiter {
+                Some(delimiter) => usize::from(field.last() == Some(&delimiter)),
+                None => field
+                    .iter()
+                    .rev()
+                    .take_while(|ch| ch.is_whitespace())
+                    .count(),
+            };
+        }
+        shown
+    }
+
+    /// `item` with only the fields to display, keeping its ANSI styles, and
+    /// `positions` (in the item's plain text) moved to match
+    pub fn apply(
+        &self,
+        item: &str,
+        positions: Option<&MatchPositions>,
+    ) -> (String, Option<MatchPositions>) {
+        let shown = self.shown(&strip_ansi_sequences(item));
+        let is_shown = |i: usize| shown.iter().any(|range| range.contains(&i));
+
+        let mut display = String::with_capacity(item.len());
+        let mut chars = item.chars();
+        let mut index = 0;
+        while let Some(ch) = chars.next() {
+            if ch == '\x1b' {
+                // Escape sequences are kept, so styles carry over
+                display.push(ch);
+                match chars.next() {
+                    Some('[') => {
+                        display.push('[');
+                        for next in chars.by_ref() {
+                            display.push(next);
+                            if next.is_ascii_alphabetic() || matches!(next, '@' | '~' | '_' | '`') {
+                                break;
+                            }
+                        }
+                    }
+                    Some(next) => display.push(next),
+                    None => {}
+                }
+                continue;
+            }
+            if is_shown(index) {
+                display.push(ch);
+            }
+            index += 1;
+        }
+
+        let positions = positions.map(|m| {
+            // Chars shown before position `p`, for the shown ones
+            let moved = m
+                .positions
+                .iter()
+                .filter(|&&p| is_shown(p))
+                .map(|&p| {
+                    shown
+                        .iter()
+                        .map(|range| range.start.min(p)..range.end.min(p))
+                        .map(|range| range.len())
+                        .sum()
+                })
+                .collect();
+            MatchPositions {
+                positions: moved,
+                score: m.score,
+                tier: m.tier,
+            }
+        });
+        (display, positions)
+    }
+
+    /// Character ranges of each field of `text`, with its trailing separator
+    fn split(&self, text: &str) -> Vec<Range<usize>> {
+        let mut fields = Vec::new();
+        let mut start = 0;
+        let (mut seen_text, mut in_separator) = (false, false);
+        for (i, ch) in text.chars().enumerate() {
+            match self.delimiter {
+                Some(delimiter) if ch == delimiter => {
+                    fields.push(start..i + 1);
+                    start = i + 1;
+                }
+                Some(_) => {}
+                // Whitespace belongs to the field before it (leading
+                // whitespace to the first)
+                None if ch.is_whitespace() => in_separator = seen_text,
+                None => {
+                    if in_separator {
+                        fields.push(start..i);
+                        start = i;
+                    }
+                    (seen_text, in_separator) = (true, false);
+                }
+            }
+        }
+        let len = text.chars().count();
+        if start < len || fields.is_empty() {
+            fields.push(start..len);
+        }
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzy::scoring::MatchTier;
+
+    fn shown_text(fields: &Fields, text: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        fields
+            .shown(text)
+            .into_iter()
+            .map(|range| chars[range].iter().collect::<String>())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(Fields::parse("2", None).is_ok());
+        assert!(Fields::parse("1,3..,-1,..2", Some('\t')).is_ok());
+        for bad in ["0", "x", "1..y", "", "1,,2"] {
+            assert!(Fields::parse(bad, None).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_whitespace_fields() {
+        let fields = Fields::parse("2..", None).unwrap();
+        assert_eq!(
+            shown_text(&fields, "42  src/main.rs  ok"),
+            "src/main.rs  ok"
+        );
+        let fields = Fields::parse("1,-1", None).unwrap();
+        assert_eq!(shown_text(&fields, "  a b c"), "  a c");
+        // Missing fields show nothing
+        let fields = Fields::parse("5", None).unwrap();
+        assert_eq!(shown_text(&fields, "a b"), "");
+        assert_eq!(shown_text(&fields, ""), "");
+    }
+
+    #[test]
+    fn test_delimited_fields() {
+        let fields = Fields::parse("2", Some(':')).unwrap();
+        assert_eq!(shown_text(&fields, "id:name:extra"), "name");
+        let fields = Fields::parse("..2", Some(':')).unwrap();
+        assert_eq!(shown_text(&fields, "a::c"), "a:");
+    }
+
+    #[test]
+    fn test_apply_moves_positions() {
+        let fields = Fields::parse("2", Some('\t')).unwrap();
+        let positions = MatchPositions {
+            positions: vec![0, 3, 4],
+            score: 1,
+            tier: MatchTier::Fuzzy,
+        };
+        let (display, moved) = fields.apply("17\t\x1b[31mred\x1b[0m", Some(&positions));
+        assert_eq!(display, "\x1b[31mred\x1b[0m");
+        // `1` is hidden; `r` and `e` are the first two shown
+        assert_eq!(moved.unwrap().positions, vec![0, 1]);
+    }
+}
@@ -0,0 +1,189 @@
+//! Help overlay: the active keys, custom bindings and query syntax, drawn
+//! over the TUI by F1 (`?` in Vim normal mode) until the next key.
+
+use crate::tui::buffer::ScreenBuffer;
+use crate::tui::keymap::{BindAction, KeyBinding, KeyName, KeyTranslator};
+use crate::tui::theme::Theme;
+use crate::tui::width::{str_width, truncate_to_width};
+
+/// A titled group of `(keys, description)` rows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpSection {
+    pub title: &'static str,
+    pub entries: Vec<(String, String)>,
+}
+
+/// Sections for the overlay: the keys of `keys`' keymap, then `bindings`
+/// and `expect` keys if any, then the query syntax
+pub fn help_sections(
+    keys: &KeyTranslator,
+    bindings: &[KeyBinding],
+    expect: &[KeyName],
+) -> Vec<HelpSection> {
+    let owned = |entries: Vec<(&str, &str)>| {
+        entries
+            .into_iter()
+            .map(|(key, action)| (key.to_string(), action.to_string()))
+            .collect()
+    };
+    let mut sections = vec![HelpSection {
+        title: "Keys",
+        entries: owned(keys.key_help()),
+    }];
+    let mut bound: Vec<(String, String)> = bindings
+        .iter()
+        .map(|binding| (binding.key.name.clone(), describe(&binding.action)))
+        .collect();
+    bound.extend(
+        expect
+            .iter()
+            .map(|key| (key.name.clone(), "Accept, reporting the key".to_string())),
+    );
+    if !bound.is_empty() {
+        sections.push(HelpSection {
+            title: "Bindings",
+            entries: bound,
+        });
+    }
+    sections.push(HelpSection {
+        title: "Query",
+        entries: owned(vec![
+            ("abc", "Items containing a, b, c in order"),
+            ("^abc", "Items starting with abc"),
+        ]),
+    });
+    sections
+}
+
+fn describe(action: &BindAction) -> String {
+    match action {
+        BindAction::Execute(command) => format!("Run {command}"),
+        BindAction::Reload => "Reload the input".to_string(),
+        BindAction::Jump => "Jump to a labelled item".to_string(),
+        BindAction::JumpAccept => "Accept a labelled item".to_string(),
+        BindAction::ToggleSort => "Toggle sorting by score".to_string(),
+        BindAction::ToggleScores => "Show or hide scores".to_string(),
+    }
+}
+
+/// Draw `sections` in a box centred on `buffer`, clipped to fit
+pub fn draw_help_overlay(buffer: &mut ScreenBuffer, sections: &[HelpSection], theme: &Theme) {
+    let key_width = sections
+        .iter()
+        .flat_map(|section| &section.entries)
+        .map(|(key, _)| str_width(key))
+        .max()
+        .unwrap_or(0);
+    let mut lines: Vec<(String, Option<usize>)> = Vec::new();
+    for (i, section) in sections.iter().enumerate() {
+        if i > 0 {
+            lines.push((String::new(), None));
+        }
+        lines.push((section.title.to_string(), None));
+        for (key, action) in &section.entries {
+            let padding = " ".repeat(key_width - str_width(key) + 2);
+            lines.push((format!("{key}{padding}{action}"), Some(str_width(key))));
+        }
+    }
+    let content_width = lines.iter().map(|(line, _)| str_width(line)).max();
+    let width = (content_width.unwrap_or(0) as u16 + 4).min(buffer.width());
+    let height = (lines.len() as u16 + 2).min(buffer.height());
+    if width < 4 || height < 3 {
+        return;
+    }
+    let left = (buffer.width() - width) / 2;
+    let top = (buffer.height() - height) / 2;
+    let border = Some(theme.border);
+    for row in top..top + height {
+        let (first, fill, last) = if row == top {
+            ('┌', '─', '┐')
+        } else if row == top + height - 1 {
+            ('└', '─', '┘')
+        } else {
+            ('│', ' ', '│')
+        };
+        buffer.put_char(left, row, first, border, None, false, false);
+        for col in left + 1..left + width - 1 {
+            buffer.put_char(col, row, fill, border, None, false, false);
+        }
+        buffer.put_char(left + width - 1, row, last, border, None, false, false);
+    }
+    let available = (width - 4) as usize;
+    for (i, (line, key_width)) in lines.iter().take(height as usize - 2).enumerate() {
+        let row = top + 1 + i as u16;
+        let col = left + 2;
+        let text = truncate_to_width(line, available);
+        match *key_width {
+            // A section title
+            None => {
+                buffer.put_str(col, row, text, Some(theme.prompt), None, true, false);
+            }
+            Some(key_width) => {
+                let key = truncate_to_width(text, key_width);
+                let written =
+                    buffer.put_str(col, row, key, Some(theme.selected), None, false, false);
+                buffer.put_str_plain(col + written, row, &text[key.len()..]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::keymap::Keymap;
+
+    fn row_text(buffer: &ScreenBuffer, row: u16) -> String {
+        (0..buffer.width())
+            .map(|col| buffer.get_cell(col, row).unwrap().ch)
+            .collect()
+    }
+
+    #[test]
+    fn test_help_sections() {
+        let keys = KeyTranslator::new(Keymap::Emacs, false);
+        let bindings = [KeyBinding::parse("ctrl-r:reload").unwrap()];
+        let expect = KeyName::parse_list("ctrl-o").unwrap();
+        let sections = help_sections(&keys, &bindings, &expect);
+        let titles: Vec<&str> = sections.iter().map(|s| s.title).collect();
+        assert_eq!(titles, ["Keys", "Bindings", "Query"]);
+        assert!(sections[0]
+            .entries
+            .contains(&("Alt+P".to_string(), "Show or hide the preview".to_string())));
+        assert_eq!(
+            sections[1].entries,
+            [
+                ("ctrl-r".to_string(), "Reload the input".to_string()),
+                (
+                    "ctrl-o".to_string(),
+                    "Accept, reporting the key".to_string()
+                ),
+            ]
+        );
+
+        // No bindings, no section for them
+        let sections = help_sections(&keys, &[], &[]);
+        assert_eq!(sections.len(), 2);
+    }
+
+    #[test]
+    fn test_draw_help_overlay() {
+        let sections = [HelpSection {
+            title: "Keys",
+            entries: vec![("F1".to_string(), "This help".to_string())],
+        }];
+        let mut buffer = ScreenBuffer::new(21, 6);
+        draw_help_overlay(&mut buffer, &sections, &Theme::default());
+        assert_eq!(row_text(&buffer, 0).trim(), "");
+        assert_eq!(row_text(&buffer, 1), "  ┌───────────────┐  ");
+        assert_eq!(row_text(&buffer, 2), "  │ Keys          │  ");
+        assert_eq!(row_text(&buffer, 3), "  │ F1  This help │  ");
+        assert_eq!(row_text(&buffer, 4), "  └───────────────┘  ");
+
+        // Clipped to a small buffer
+        let mut buffer = ScreenBuffer::new(10, 3);
+        draw_help_overlay(&mut buffer, &sections, &Theme::default());
+        assert_eq!(row_text(&buffer, 1), "│ Keys   │");
+        assert_eq!(row_text(&buffer, 2), "└────────┘");
+    }
+}
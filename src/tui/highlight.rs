@@ -0,0 +1,287 @@
+//! Basic syntax highlighting for the built-in file previewer.
+//!
+//! A small per-language table of keywords, comment and string syntax is
+//! enough to color keywords, strings, comments and numbers; anything more
+//! is left to external previewers like `bat`.
+
+use crate::tui::preview::StyledLine;
+use crossterm::style::Color;
+
+const KEYWORD: Color = Color::Magenta;
+const STRING: Color = Color::Green;
+const COMMENT: Color = Color::DarkGrey;
+const NUMBER: Color = Color::Yellow;
+
+/// Comment, string and keyword syntax of a language
+struct Syntax {
+    line_comment: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    keywords: &'static [&'static str],
+}
+
+const RUST: Syntax = Syntax {
+    line_comment: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"'],
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ],
+};
+
+const C_LIKE: Syntax = Syntax {
+    line_comment: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\''],
+    keywords: &[
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "do",
+        "else",
+        "enum",
+        "export",
+        "extends",
+        "false",
+        "final",
+        "for",
+        "func",
+        "function",
+        "go",
+        "if",
+        "import",
+        "interface",
+        "let",
+        "new",
+        "nil",
+        "null",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "struct",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "type",
+        "var",
+        "void",
+        "while",
+    ],
+};
+
+const PYTHON: Syntax = Syntax {
+    line_comment: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "False", "finally", "for", "from", "if", "import", "in", "is",
+        "lambda", "None", "not", "or", "pass", "raise", "return", "True", "try", "while", "with",
+        "yield",
+    ],
+};
+
+const SHELL: Syntax = Syntax {
+    line_comment: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "then", "while",
+    ],
+};
+
+/// Config files: comments, strings and numbers only
+const CONFIG: Syntax = Syntax {
+    line_comment: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    keywords: &["false", "true", "null"],
+};
+
+const JSON: Syntax = Syntax {
+    line_comment: &[],
+    block_comment: None,
+    quotes: &['"'],
+    keywords: &["false", "true", "null"],
+};
+
+/// Syntax for a lowercase file extension, `None` if unknown
+fn syntax_for(ext: &str) -> Option<&'static Syntax> {
+    Some(match ext {
+        "rs" => &RUST,
+        "c" | "h" | "cc" | "cpp" | "hpp" | "java" | "js" | "jsx" | "ts" | "tsx" | "go" | "cs"
+        | "kt" | "swift" => &C_LIKE,
+        "py" => &PYTHON,
+        "sh" | "bash" | "zsh" => &SHELL,
+        "toml" | "yaml" | "yml" | "ini" | "conf" => &CONFIG,
+        "json" => &JSON,
+        _ => return None,
+    })
+}
+
+/// Split `text` into styled lines, highlighted for the file extension `ext`
+/// (plain lines for unknown extensions)
+pub fn highlight(text: &str, ext: &str) -> Vec<StyledLine> {
+    let Some(syntax) = syntax_for(&ext.to_lowercase()) else {
+        return text.lines().map(|line| vec![plain(line)]).collect();
+    };
+    let mut in_block = false;
+    text.lines()
+        .map(|line| highlight_line(line, syntax, &mut in_block))
+        .collect()
+}
+
+fn plain(text: &str) -> (String, Option<Color>, Option<Color>, bool, bool) {
+    (text.to_string(), None, None, false, false)
+}
+
+/// Push `text` onto `line`, merging it into the last segment if the color
+/// is the same
+fn push(line: &mut StyledLine, text: &str, fg: Option<Color>) {
+    if text.is_empty() {
+        return;
+    }
+    match line.last_mut() {
+        Some(last) if last.1 == fg => last.0.push_str(text),
+        _ => line.push((text.to_string(), fg, None, false, false)),
+    }
+}
+
+/// Highlight one line; `in_block` carries an open block comment across lines
+fn highlight_line(line: &str, syntax: &Syntax, in_block: &mut bool) -> StyledLine {
+    let mut styled = StyledLine::new();
+    let mut rest = line;
+    while !rest.is_empty() {
+        if *in_block {
+            let (_, end) = syntax.block_comment.expect("in a block comment");
+            match rest.find(end) {
+                Some(pos) => {
+                    push(&mut styled, &rest[..pos + end.len()], Some(COMMENT));
+                    rest = &rest[pos + end.len()..];
+                    *in_block = false;
+                }
+                None => {
+                    push(&mut styled, rest, Some(COMMENT));
+                    rest = "";
+                }
+            }
+            continue;
+        }
+        if syntax.line_comment.iter().any(|c| rest.starts_with(c)) {
+            push(&mut styled, rest, Some(COMMENT));
+            break;
+        }
+        if let Some((start, _)) = syntax.block_comment.filter(|(s, _)| rest.starts_with(s)) {
+            push(&mut styled, start, Some(COMMENT));
+            rest = &rest[start.len()..];
+            *in_block = true;
+            continue;
+        }
+        let ch = rest.chars().next().expect("rest is not empty");
+        let len = if syntax.quotes.contains(&ch) {
+            let end = string_end(rest, ch);
+            push(&mut styled, &rest[..end], Some(STRING));
+            end
+        } else if ch.is_alphanumeric() || ch == '_' {
+            let end = rest
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            let fg = if ch.is_ascii_digit() {
+                Some(NUMBER)
+            } else if syntax.keywords.contains(&word) {
+                Some(KEYWORD)
+            } else {
+                None
+            };
+            push(&mut styled, word, fg);
+            end
+        } else {
+            push(&mut styled, &rest[..ch.len_utf8()], None);
+            ch.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+    styled
+}
+
+/// Byte length of the string literal opening `text` with `quote`, up to and
+/// including the closing quote (or the end of the line)
+fn string_end(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, ch) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == quote {
+            return i + ch.len_utf8();
+        }
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colors(line: &StyledLine) -> Vec<(&str, Option<Color>)> {
+        line.iter().map(|s| (s.0.as_str(), s.1)).collect()
+    }
+
+    #[test]
+    fn test_highlight_rust() {
+        let lines = highlight("let s = \"a \\\" b\"; // done\nfn f() -> u8 { 42 }", "rs");
+        assert_eq!(
+            colors(&lines[0]),
+            vec![
+                ("let", Some(KEYWORD)),
+                (" s = ", None),
+                ("\"a \\\" b\"", Some(STRING)),
+                ("; ", None),
+                ("// done", Some(COMMENT)),
+            ]
+        );
+        assert_eq!(
+            colors(&lines[1]),
+            vec![
+                ("fn", Some(KEYWORD)),
+                (" f() -> u8 { ", None),
+                ("42", Some(NUMBER)),
+                (" }", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_block_comment_spans_lines() {
+        let lines = highlight("a /* one\ntwo */ b", "c");
+        assert_eq!(
+            colors(&lines[0]),
+            vec![("a ", None), ("/* one", Some(COMMENT))]
+        );
+        assert_eq!(
+            colors(&lines[1]),
+            vec![("two */", Some(COMMENT)), (" b", None)]
+        );
+    }
+
+    #[test]
+    fn test_highlight_unknown_extension_is_plain() {
+        let lines = highlight("fn main() // not rust", "txt");
+        assert_eq!(colors(&lines[0]), vec![("fn main() // not rust", None)]);
+    }
+}
@@ -0,0 +1,195 @@
+//! `--icons`: a file-type icon in front of each item, for items that are
+//! paths. Nerd Font glyphs by default, or plain ASCII markers for fonts
+//! without them.
+
+use crate::fuzzy::finder::MatchPositions;
+use crate::tui::preview::strip_ansi_sequences;
+use std::path::Path;
+
+/// How file-type icons are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconStyle {
+    /// Nerd Font glyphs, per directory, language or kind of file
+    Nerd,
+    /// One ASCII character per kind of file (`/` for directories)
+    Ascii,
+}
+
+impl IconStyle {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "nerd" => Ok(IconStyle::Nerd),
+            "ascii" => Ok(IconStyle::Ascii),
+            _ => Err(format!("Invalid icon style '{s}'. Expected nerd or ascii")),
+        }
+    }
+}
+
+/// Kinds of file told apart by their extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Directory,
+    Code,
+    Document,
+    Config,
+    Image,
+    Media,
+    Archive,
+    File,
+}
+
+impl Kind {
+    fn of(path: &str, is_dir: bool) -> Self {
+        if is_dir {
+            return Kind::Directory;
+        }
+        match extension(path).as_deref() {
+            Some(
+                "rs" | "py" | "js" | "ts" | "jsx" | "tsx" | "go" | "c" | "h" | "cpp" | "hpp"
+                | "java" | "kt" | "rb" | "php" | "lua" | "sh" | "bash" | "zsh" | "fish" | "swift"
+                | "zig" | "hs" | "ml" | "ex" | "exs" | "html" | "css" | "scss" | "vue" | "sql",
+            ) => Kind::Code,
+            Some("md" | "txt" | "rst" | "org" | "pdf" | "doc" | "docx" | "odt" | "tex") => {
+                Kind::Document
+            }
+            Some("toml" | "yaml" | "yml" | "json" | "ini" | "cfg" | "conf" | "lock" | "xml") => {
+                Kind::Config
+            }
+            Some("png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "bmp" | "ico") => Kind::Image,
+            Some("mp3" | "flac" | "wav" | "ogg" | "mp4" | "mkv" | "webm" | "mov" | "avi") => {
+                Kind::Media
+            }
+            Some("zip" | "tar" | "gz" | "tgz" | "xz" | "bz2" | "zst" | "7z" | "rar") => {
+                Kind::Archive
+            }
+            _ => Kind::File,
+        }
+    }
+
+    /// SGR color code of the icon
+    fn color(self) -> u8 {
+        match self {
+            Kind::Directory => 34,
+            Kind::Code => 32,
+            Kind::Document => 37,
+            Kind::Config => 33,
+            Kind::Image => 35,
+            Kind::Media => 36,
+            Kind::Archive => 31,
+            Kind::File => 90,
+        }
+    }
+}
+
+/// Lowercased extension of the file name at the end of `path`
+fn extension(path: &str) -> Option<String> {
+    Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+/// Icon for the item `path` (a directory if `is_dir`)
+pub fn icon(style: IconStyle, path: &str, is_dir: bool) -> char {
+    let kind = Kind::of(path, is_dir);
+    match style {
+        IconStyle::Ascii => match kind {
+            Kind::Directory => '/',
+            Kind::Code => '#',
+            Kind::Document => '=',
+            Kind::Config => '~',
+            Kind::Image => '%',
+            Kind::Media => '&',
+            Kind::Archive => '@',
+            Kind::File => '-',
+        },
+        IconStyle::Nerd => match (kind, extension(path).as_deref()) {
+            (Kind::Code, Some("rs")) => '\u{e7a8}',
+            (Kind::Code, Some("py")) => '\u{e606}',
+            (Kind::Code, Some("js" | "jsx")) => '\u{e74e}',
+            (Kind::Code, Some("ts" | "tsx")) => '\u{e628}',
+            (Kind::Code, Some("go")) => '\u{e626}',
+            (Kind::Code, Some("sh" | "bash" | "zsh" | "fish")) => '\u{f489}',
+            (Kind::Document, Some("md")) => '\u{e609}',
+            (Kind::Directory, _) => '\u{f07b}',
+            (Kind::Code, _) => '\u{f121}',
+            (Kind::Document, _) => '\u{f15c}',
+            (Kind::Config, _) => '\u{e615}',
+            (Kind::Image, _) => '\u{f1c5}',
+            (Kind::Media, _) => '\u{f1c8}',
+            (Kind::Archive, _) => '\u{f1c6}',
+            (Kind::File, _) => '\u{f15b}',
+        },
+    }
+}
+
+/// `item` with its icon and a space in front, and `positions` moved past
+/// them. Items ending in `/`, or naming a directory (under `root` if
+/// relative), get the directory icon.
+pub fn with_icon(
+    item: &str,
+    positions: Option<&MatchPositions>,
+    style: IconStyle,
+    root: Option<&Path>,
+) -> (String, Option<MatchPositions>) {
+    let path = strip_ansi_sequences(item);
+    let is_dir = path.ends_with('/')
+        || match root {
+            Some(root) => root.join(&path).is_dir(),
+            None => Path::new(&path).is_dir(),
+        };
+    let color = Kind::of(&path, is_dir).color();
+    let icon = icon(style, &path, is_dir);
+    let display = format!("\x1b[{color}m{icon}\x1b[39m {item}");
+    let positions = positions.map(|m| MatchPositions {
+        positions: m.positions.iter().map(|p| p + 2).collect(),
+        score: m.score,
+        tier: m.tier,
+    });
+    (display, positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzy::scoring::MatchTier;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(IconStyle::parse("nerd"), Ok(IconStyle::Nerd));
+        assert_eq!(IconStyle::parse("ascii"), Ok(IconStyle::Ascii));
+        assert!(IconStyle::parse("emoji").is_err());
+    }
+
+    #[test]
+    fn test_icon() {
+        assert_eq!(icon(IconStyle::Ascii, "src", true), '/');
+        assert_eq!(icon(IconStyle::Ascii, "src/main.rs", false), '#');
+        assert_eq!(icon(IconStyle::Ascii, "Cargo.TOML", false), '~');
+        assert_eq!(icon(IconStyle::Ascii, "LICENSE", false), '-');
+        assert_eq!(icon(IconStyle::Nerd, "src/main.rs", false), '\u{e7a8}');
+        assert_eq!(icon(IconStyle::Nerd, "src/main.c", false), '\u{f121}');
+        assert_eq!(icon(IconStyle::Nerd, "docs", true), '\u{f07b}');
+    }
+
+    #[test]
+    fn test_with_icon() {
+        let positions = MatchPositions {
+            positions: vec![0, 2],
+            score: 1,
+            tier: MatchTier::Fuzzy,
+        };
+        let (display, moved) = with_icon("notes/", Some(&positions), IconStyle::Ascii, None);
+        assert_eq!(display, "\x1b[34m/\x1b[39m notes/");
+        assert_eq!(moved.unwrap().positions, vec![2, 4]);
+
+        // Directories are looked up under the root
+        let root = std::env::temp_dir();
+        let dir = root.join("ff-icons-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let (display, _) = with_icon("ff-icons-test", None, IconStyle::Ascii, Some(&root));
+        assert!(display.contains('/'), "{display}");
+        let (display, _) = with_icon("ff-icons-test", None, IconStyle::Ascii, None);
+        assert!(!display.contains('/'), "{display}");
+        std::fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+//! Jump labels: one or two letters shown next to each visible item, typed
+//! to move the cursor straight to it, as with avy or EasyMotion.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Characters labels are made of by default, home row first
+pub const DEFAULT_JUMP_LABELS: &str = "asdfghjklqwertyuiopzxcvbnm";
+
+/// Labels for `count` visible items, in order: single characters of
+/// `alphabet` while there are enough of them, otherwise two-character
+/// pairs. Items past the last pair get no label.
+pub fn jump_labels(alphabet: &str, count: usize) -> Vec<String> {
+    let chars: Vec<char> = alphabet.chars().collect();
+    if count <= chars.len() {
+        return chars[..count].iter().map(char::to_string).collect();
+    }
+    chars
+        .iter()
+        .flat_map(|&first| chars.iter().map(move |&second| format!("{first}{second}")))
+        .take(count)
+        .collect()
+}
+
+/// What a key typed in jump mode did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JumpKey {
+    /// The first character of a two-character label; keep waiting
+    Pending,
+    /// A whole label was typed, for the visible item at this index
+    Jump(usize),
+    /// Not a label: leave jump mode
+    Cancel,
+}
+
+/// An active jump, started by [`BindAction::Jump`] or
+/// [`BindAction::JumpAccept`]
+///
+/// [`BindAction::Jump`]: crate::tui::keymap::BindAction::Jump
+/// [`BindAction::JumpAccept`]: crate::tui::keymap::BindAction::JumpAccept
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Jump {
+    /// Accept the item once its label is typed, rather than only moving
+    /// the cursor to it
+    pub accept: bool,
+    typed: String,
+}
+
+impl Jump {
+    pub fn new(accept: bool) -> Self {
+        Self {
+            accept,
+            typed: String::new(),
+        }
+    }
+
+    /// Whether `label` should still be shown, given what has been typed
+    pub fn shows(&self, label: &str) -> bool {
+        label.starts_with(&self.typed)
+    }
+
+    /// Handle `key_event` against the `labels` on screen
+    pub fn handle_key(&mut self, key_event: &KeyEvent, labels: &[String]) -> JumpKey {
+        let relevant = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        let KeyCode::Char(c) = key_event.code else {
+            return JumpKey::Cancel;
+        };
+        if key_event.modifiers.intersects(relevant) {
+            return JumpKey::Cancel;
+        }
+        self.typed.push(c);
+        if let Some(index) = labels.iter().position(|label| *label == self.typed) {
+            return JumpKey::Jump(index);
+        }
+        if labels.iter().any(|label| self.shows(label)) {
+            JumpKey::Pending
+        } else {
+            JumpKey::Cancel
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_single_letter_labels() {
+        assert_eq!(jump_labels("asd", 2), ["a", "s"]);
+        assert_eq!(jump_labels("asd", 3), ["a", "s", "d"]);
+        assert!(jump_labels("asd", 0).is_empty());
+    }
+
+    #[test]
+    fn test_two_letter_labels() {
+        assert_eq!(jump_labels("ab", 3), ["aa", "ab", "ba"]);
+        // Only as many items as there are pairs get a label
+        assert_eq!(jump_labels("ab", 10).len(), 4);
+    }
+
+    #[test]
+    fn test_jump_single() {
+        let labels = jump_labels("asd", 3);
+        let mut jump = Jump::new(false);
+        assert_eq!(jump.handle_key(&plain('s'), &labels), JumpKey::Jump(1));
+
+        let mut jump = Jump::new(false);
+        assert_eq!(jump.handle_key(&plain('x'), &labels), JumpKey::Cancel);
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(Jump::new(false).handle_key(&esc, &labels), JumpKey::Cancel);
+    }
+
+    #[test]
+    fn test_jump_pair() {
+        let labels = jump_labels("ab", 4);
+        let mut jump = Jump::new(true);
+        assert_eq!(jump.handle_key(&plain('b'), &labels), JumpKey::Pending);
+        assert!(jump.shows("ba"));
+        assert!(!jump.shows("ab"));
+        assert_eq!(jump.handle_key(&plain('a'), &labels), JumpKey::Jump(2));
+    }
+}
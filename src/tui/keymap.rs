@@ -0,0 +1,548 @@
+//! Keymap presets: Emacs- and Vim-style keys translated to the default keys
+//! the event handlers understand.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Keymap preset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Keymap {
+    /// Arrow keys and the default Ctrl bindings
+    #[default]
+    Default,
+    /// Ctrl+N/P to move, Ctrl+V/Alt+V to page, Alt+</> for the ends,
+    /// Ctrl+G to abort; the preview toggles with Alt+P
+    Emacs,
+    /// Insert mode types the query; Esc enters normal mode, where j/k move,
+    /// gg/G jump to the ends, Ctrl+F/B page, v toggles the selection and
+    /// i/a/`/` return to insert mode
+    Vim,
+}
+
+impl Keymap {
+    /// Parse a keymap name: `default`, `emacs` or `vim`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(Self::Default),
+            "emacs" => Ok(Self::Emacs),
+            "vim" => Ok(Self::Vim),
+            _ => Err(format!(
+                "Invalid keymap '{s}'. Expected one of: default, emacs, vim"
+            )),
+        }
+    }
+}
+
+/// A key named as in fzf's `--expect`: `ctrl-x`, `alt-x`, `f1`-`f12`,
+/// `enter`, `tab`, `btab`, `esc`, `space`, `bspace`, `del`, `up`, `down`,
+/// `left`, `right`, `home`, `end`, `pgup`, `pgdn`, or a single character
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyName {
+    /// The name as given, reported when the key is pressed
+    pub name: String,
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyName {
+    /// Parse a key name
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid key '{s}'");
+        let lower = s.to_lowercase();
+        let (modifiers, base) = if let Some(rest) = lower.strip_prefix("ctrl-") {
+            (KeyModifiers::CONTROL, rest)
+        } else if let Some(rest) = lower.strip_prefix("alt-") {
+            (KeyModifiers::ALT, rest)
+        } else {
+            (KeyModifiers::NONE, lower.as_str())
+        };
+        let code = match base {
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "btab" | "shift-tab" => KeyCode::BackTab,
+            "esc" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "bspace" | "bs" => KeyCode::Backspace,
+            "del" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pgup" | "page-up" => KeyCode::PageUp,
+            "pgdn" | "page-down" => KeyCode::PageDown,
+            _ => match base.strip_prefix('f').map(str::parse::<u8>) {
+                Some(Ok(n @ 1..=12)) if modifiers == KeyModifiers::NONE => KeyCode::F(n),
+                _ => {
+                    let mut chars = base.chars();
+                    match (chars.next(), chars.next()) {
+                        // Keep the case of plain characters
+                        (Some(_), None) if modifiers == KeyModifiers::NONE => {
+                            KeyCode::Char(s.chars().next().ok_or_else(invalid)?)
+                        }
+                        (Some(c), None) => KeyCode::Char(c),
+                        _ => return Err(invalid()),
+                    }
+                }
+            },
+        };
+        Ok(Self {
+            name: s.to_string(),
+            code,
+            modifiers,
+        })
+    }
+
+    /// Parse a comma-separated list of key names
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        s.split(',')
+            .filter(|name| !name.is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+
+    /// Whether `key_event` is this key. Shift is ignored, as terminals
+    /// report it inconsistently.
+    pub fn matches(&self, key_event: &KeyEvent) -> bool {
+        let relevant = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        key_event.code == self.code && key_event.modifiers & relevant == self.modifiers
+    }
+}
+
+/// What a [`KeyBinding`] does
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindAction {
+    /// Run a shell command (with the same placeholders as preview commands)
+    /// for the current item, handing it the terminal until it exits
+    Execute(String),
+    /// Clear the items and read them again from the original source
+    /// (see `TuiConfig::reload`), keeping the query
+    Reload,
+    /// Label the visible items (see `TuiConfig::jump_labels`) and move the
+    /// cursor to the one whose label is typed next
+    Jump,
+    /// Like [`BindAction::Jump`], accepting the labelled item right away
+    JumpAccept,
+    /// Switch between ranking matches by score and keeping input order
+    ToggleSort,
+    /// Show or hide each result's score breakdown and matched positions
+    ToggleScores,
+}
+
+/// A key bound to an action, as in `ctrl-o:execute(open {})`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBinding {
+    /// Key that triggers the action
+    pub key: KeyName,
+    /// Action run when the key is pressed
+    pub action: BindAction,
+}
+
+impl KeyBinding {
+    /// Parse `KEY:ACTION`, where the action is `execute(COMMAND)`,
+    /// `execute:COMMAND`, `reload`, `jump`, `jump-accept`, `toggle-sort` or
+    /// `toggle-scores`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        // Skip the first character so `:` itself can be bound
+        let split = s
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| c == ':')
+            .map(|(i, _)| i)
+            .ok_or_else(|| format!("Invalid binding '{s}'. Expected KEY:ACTION"))?;
+        let key = KeyName::parse(&s[..split])?;
+        let action = &s[split + 1..];
+        let simple = match action {
+            "reload" => Some(BindAction::Reload),
+            "jump" => Some(BindAction::Jump),
+            "jump-accept" => Some(BindAction::JumpAccept),
+            "toggle-sort" => Some(BindAction::ToggleSort),
+            "toggle-scores" => Some(BindAction::ToggleScores),
+            _ => None,
+        };
+        if let Some(action) = simple {
+            return Ok(Self { key, action });
+        }
+        let command = action
+            .strip_prefix("execute(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .or_else(|| action.strip_prefix("execute:"))
+            .filter(|command| !command.trim().is_empty())
+            .ok_or_else(|| {
+                format!(
+                    "Invalid action '{action}'. Expected execute(COMMAND), reload, jump, jump-accept, toggle-sort or toggle-scores"
+                )
+            })?;
+        Ok(Self {
+            key,
+            action: BindAction::Execute(command.to_string()),
+        })
+    }
+}
+
+/// Vim editing mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimMode {
+    /// Keys edit the query
+    Insert,
+    /// Keys navigate and select
+    Normal,
+}
+
+/// Translates keys of a [`Keymap`] to default keys, tracking the Vim mode
+#[derive(Debug, Clone)]
+pub struct KeyTranslator {
+    keymap: Keymap,
+    multi_select: bool,
+    mode: VimMode,
+    /// A `g` waiting for the second `g` of `gg`
+    pending_g: bool,
+}
+
+/// First result, whatever the query: what `gg` and Alt+`<` translate to,
+/// since a plain Home moves to the start of the query first
+pub const FIRST_RESULT: KeyEvent = KeyEvent::new(KeyCode::Home, KeyModifiers::CONTROL);
+/// Last result, whatever the query (see [`FIRST_RESULT`])
+pub const LAST_RESULT: KeyEvent = KeyEvent::new(KeyCode::End, KeyModifiers::CONTROL);
+
+impl KeyTranslator {
+    pub fn new(keymap: Keymap, multi_select: bool) -> Self {
+        Self {
+            keymap,
+            multi_select,
+            mode: VimMode::Insert,
+            pending_g: false,
+        }
+    }
+
+    /// Current Vim mode, `None` for other keymaps
+    pub fn vim_mode(&self) -> Option<VimMode> {
+        (self.keymap == Keymap::Vim).then_some(self.mode)
+    }
+
+    /// Every key of this keymap as `(keys, action)` rows, for the help
+    /// overlay; Vim lists the keys of both modes
+    pub fn key_help(&self) -> Vec<(&'static str, &'static str)> {
+        let mut help = match self.keymap {
+            Keymap::Default => vec![
+                ("↑/↓", "Move through the results"),
+                ("PgUp/PgDn", "Move a page"),
+                ("Home/End", "Start/end of the query, then first/last result"),
+            ],
+            Keymap::Emacs => vec![
+                ("Ctrl+N/P", "Move through the results"),
+                ("Ctrl+V/Alt+V", "Move a page"),
+                ("Alt+</>", "First/last result"),
+            ],
+            Keymap::Vim => vec![
+                ("Esc", "Normal mode"),
+                ("j/k", "Move through the results (normal)"),
+                ("Ctrl+F/B", "Move a page (normal)"),
+                ("gg/G", "First/last result (normal)"),
+                ("i/a//", "Insert mode (normal)"),
+            ],
+        };
+        help.push((
+            "←/→",
+            "Move along a grid row, then in the query; → at its end focuses the preview",
+        ));
+        help.push(("Enter", "Accept"));
+        if self.multi_select {
+            let toggle = if self.keymap == Keymap::Vim {
+                "Tab/v"
+            } else {
+                "Tab/Space"
+            };
+            help.push((toggle, "Toggle the selection"));
+            help.push(("Ctrl+A/D/T", "Select, deselect or toggle all"));
+        }
+        help.push(if self.keymap == Keymap::Emacs {
+            ("Alt+P", "Show or hide the preview")
+        } else {
+            ("Ctrl+P", "Show or hide the preview")
+        });
+        help.push(("Shift+↑/↓", "Scroll the preview"));
+        help.push(("F2", "Show or hide details of the current item"));
+        help.push(match self.keymap {
+            Keymap::Default => ("Esc", "Clear the query, then exit"),
+            Keymap::Emacs => ("Ctrl+G/Esc", "Exit"),
+            Keymap::Vim => ("q", "Exit (normal)"),
+        });
+        help.push(("Ctrl+C/Ctrl+Q", "Exit"));
+        help.push(if self.keymap == Keymap::Vim {
+            ("F1/?", "This help (? in normal mode)")
+        } else {
+            ("F1", "This help")
+        });
+        help
+    }
+
+    /// One-line hints for the keys that navigate, toggle, accept and abort
+    /// under this keymap, led by the current mode where there is one:
+    /// the Vim mode, or `PREVIEW` while the preview has focus. `preview`
+    /// adds the keys for showing and focusing the preview.
+    pub fn hints(&self, preview: bool, preview_focused: bool) -> String {
+        let normal = self.vim_mode() == Some(VimMode::Normal);
+        let navigate = match self.keymap {
+            Keymap::Emacs => "Ctrl+N/P",
+            Keymap::Vim if normal => "j/k",
+            _ => "↑/↓",
+        };
+        let mut mode = match self.vim_mode() {
+            Some(VimMode::Normal) => Some("NORMAL"),
+            Some(VimMode::Insert) => Some("INSERT"),
+            None => None,
+        };
+        let mut hints = Vec::new();
+        if preview_focused {
+            mode = Some("PREVIEW");
+            hints.push(format!("{navigate}: Scroll"));
+            hints.push("Enter: Accept".to_string());
+            hints.push("←/Esc: Back".to_string());
+        } else {
+            hints.push(format!("{navigate}: Navigate"));
+            if self.multi_select {
+                let toggle = if normal { "v/Tab" } else { "Tab/Space" };
+                hints.push(format!("{toggle}: Toggle"));
+                hints.push("Enter: Confirm".to_string());
+            } else {
+                hints.push("Enter: Select".to_string());
+            }
+            if preview {
+                let toggle = if self.keymap == Keymap::Emacs {
+                    "Alt+P"
+                } else {
+                    "Ctrl+P"
+                };
+                hints.push(format!("{toggle}: Preview | →/←: Focus"));
+            }
+            hints.push(
+                match self.keymap {
+                    Keymap::Default => "Esc/Ctrl+C/Ctrl+Q: Exit",
+                    Keymap::Emacs => "Ctrl+G/Esc: Exit",
+                    Keymap::Vim if normal => "i: Insert | q: Exit",
+                    Keymap::Vim => "Esc: Normal | Ctrl+C: Exit",
+                }
+                .to_string(),
+            );
+        }
+        let hints = hints.join(" | ");
+        match mode {
+            Some(mode) => format!("[{mode}] {hints}"),
+            None => hints,
+        }
+    }
+
+    /// Default key for `key_event`, or `None` if the key was consumed (e.g.
+    /// switching Vim modes) or does nothing
+    pub fn translate(&mut self, key_event: &KeyEvent) -> Option<KeyEvent> {
+        match self.keymap {
+            Keymap::Default => Some(*key_event),
+            Keymap::Emacs => Some(translate_emacs(key_event)),
+            Keymap::Vim => self.translate_vim(key_event),
+        }
+    }
+
+    fn translate_vim(&mut self, key_event: &KeyEvent) -> Option<KeyEvent> {
+        if self.mode == VimMode::Insert {
+            if key_event.code == KeyCode::Esc {
+                self.mode = VimMode::Normal;
+                return None;
+            }
+            return Some(*key_event);
+        }
+        let pending_g = std::mem::take(&mut self.pending_g);
+        let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        let code = match key_event.code {
+            KeyCode::Char('f') if ctrl => KeyCode::PageDown,
+            KeyCode::Char('b') if ctrl => KeyCode::PageUp,
+            // Other Ctrl keys (preview, exit, ...) work as usual
+            _ if ctrl => return Some(*key_event),
+            KeyCode::Char('j') => KeyCode::Down,
+            KeyCode::Char('k') => KeyCode::Up,
+            KeyCode::Char('g') if pending_g => return Some(FIRST_RESULT),
+            KeyCode::Char('g') => {
+                self.pending_g = true;
+                return None;
+            }
+            KeyCode::Char('G') => return Some(LAST_RESULT),
+            KeyCode::Char('v') if self.multi_select => KeyCode::Char(' '),
+            KeyCode::Char('i' | 'a' | '/') => {
+                self.mode = VimMode::Insert;
+                return None;
+            }
+            KeyCode::Char('q') => KeyCode::Esc,
+            KeyCode::Char('?') => KeyCode::F(1),
+            // Typing is for insert mode
+            KeyCode::Char(_) => return None,
+            code => code,
+        };
+        Some(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+}
+
+fn translate_emacs(key_event: &KeyEvent) -> KeyEvent {
+    let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key_event.modifiers.contains(KeyModifiers::ALT);
+    let code = match key_event.code {
+        KeyCode::Char('n') if ctrl => KeyCode::Down,
+        KeyCode::Char('p') if ctrl => KeyCode::Up,
+        KeyCode::Char('v') if ctrl => KeyCode::PageDown,
+        KeyCode::Char('v') if alt => KeyCode::PageUp,
+        KeyCode::Char('<') if alt => return FIRST_RESULT,
+        KeyCode::Char('>') if alt => return LAST_RESULT,
+        KeyCode::Char('g') if ctrl => KeyCode::Esc,
+        KeyCode::Char('h') if ctrl => KeyCode::Backspace,
+        KeyCode::Char('j' | 'm') if ctrl => KeyCode::Enter,
+        KeyCode::Char('p') if alt => return KeyEvent::new(key_event.code, KeyModifiers::CONTROL),
+        _ => return *key_event,
+    };
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn plain(c: char) -> KeyEvent {
+        key(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_keymap_parse() {
+        assert_eq!(Keymap::parse("vim"), Ok(Keymap::Vim));
+        assert_eq!(Keymap::parse("Emacs"), Ok(Keymap::Emacs));
+        assert_eq!(Keymap::parse("default"), Ok(Keymap::Default));
+        assert!(Keymap::parse("nano").is_err());
+    }
+
+    #[test]
+    fn test_hints() {
+        let keys = KeyTranslator::new(Keymap::Default, false);
+        assert_eq!(
+            keys.hints(false, false),
+            "↑/↓: Navigate | Enter: Select | Esc/Ctrl+C/Ctrl+Q: Exit"
+        );
+        assert!(keys.hints(true, false).contains("Ctrl+P: Preview"));
+        assert!(keys.hints(true, true).starts_with("[PREVIEW] ↑/↓: Scroll"));
+
+        let keys = KeyTranslator::new(Keymap::Emacs, true);
+        assert_eq!(
+            keys.hints(true, false),
+            "Ctrl+N/P: Navigate | Tab/Space: Toggle | Enter: Confirm \
+             | Alt+P: Preview | →/←: Focus | Ctrl+G/Esc: Exit"
+        );
+
+        let mut keys = KeyTranslator::new(Keymap::Vim, true);
+        assert!(keys
+            .hints(false, false)
+            .starts_with("[INSERT] ↑/↓: Navigate"));
+        keys.translate(&key(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(
+            keys.hints(false, false),
+            "[NORMAL] j/k: Navigate | v/Tab: Toggle | Enter: Confirm | i: Insert | q: Exit"
+        );
+    }
+
+    #[test]
+    fn test_key_name() {
+        let keys = KeyName::parse_list("ctrl-o,alt-E,f1,enter,btab,?").unwrap();
+        let names: Vec<&str> = keys.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(names, ["ctrl-o", "alt-E", "f1", "enter", "btab", "?"]);
+
+        assert!(keys[0].matches(&key(KeyCode::Char('o'), KeyModifiers::CONTROL)));
+        assert!(!keys[0].matches(&plain('o')));
+        assert!(keys[1].matches(&key(KeyCode::Char('e'), KeyModifiers::ALT)));
+        assert!(keys[2].matches(&key(KeyCode::F(1), KeyModifiers::NONE)));
+        assert!(keys[4].matches(&key(KeyCode::BackTab, KeyModifiers::SHIFT)));
+        assert!(keys[5].matches(&key(KeyCode::Char('?'), KeyModifiers::SHIFT)));
+
+        for bad in ["ctrl-", "f13", "hyper-x", "ctrl-enterx"] {
+            assert!(KeyName::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_key_binding() {
+        let binding = KeyBinding::parse("ctrl-o:execute(open {} && echo (done))").unwrap();
+        assert_eq!(binding.key.name, "ctrl-o");
+        assert_eq!(
+            binding.action,
+            BindAction::Execute("open {} && echo (done)".to_string())
+        );
+
+        let binding = KeyBinding::parse("ctrl-r:reload").unwrap();
+        assert_eq!(binding.action, BindAction::Reload);
+
+        let binding = KeyBinding::parse("ctrl-j:jump").unwrap();
+        assert_eq!(binding.action, BindAction::Jump);
+        let binding = KeyBinding::parse("alt-j:jump-accept").unwrap();
+        assert_eq!(binding.action, BindAction::JumpAccept);
+        let binding = KeyBinding::parse("ctrl-s:toggle-sort").unwrap();
+        assert_eq!(binding.action, BindAction::ToggleSort);
+        let binding = KeyBinding::parse("f2:toggle-scores").unwrap();
+        assert_eq!(binding.action, BindAction::ToggleScores);
+
+        let binding = KeyBinding::parse("::execute:less {}").unwrap();
+        assert!(binding.key.matches(&plain(':')));
+        assert_eq!(binding.action, BindAction::Execute("less {}".to_string()));
+
+        for bad in [
+            "ctrl-o",
+            "ctrl-o:accept",
+            "ctrl-o:execute()",
+            "nokey:execute(ls)",
+        ] {
+            assert!(KeyBinding::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_emacs_keys() {
+        let mut keys = KeyTranslator::new(Keymap::Emacs, false);
+        let ctrl = |c| key(KeyCode::Char(c), KeyModifiers::CONTROL);
+        assert_eq!(keys.translate(&ctrl('n')).unwrap().code, KeyCode::Down);
+        assert_eq!(keys.translate(&ctrl('p')).unwrap().code, KeyCode::Up);
+        assert_eq!(keys.translate(&ctrl('g')).unwrap().code, KeyCode::Esc);
+        let alt_p = key(KeyCode::Char('p'), KeyModifiers::ALT);
+        assert_eq!(keys.translate(&alt_p), Some(ctrl('p')));
+        assert_eq!(keys.translate(&plain('x')), Some(plain('x')));
+        assert_eq!(keys.vim_mode(), None);
+    }
+
+    #[test]
+    fn test_vim_modes() {
+        let mut keys = KeyTranslator::new(Keymap::Vim, true);
+        // Insert mode types
+        assert_eq!(keys.vim_mode(), Some(VimMode::Insert));
+        assert_eq!(keys.translate(&plain('j')), Some(plain('j')));
+
+        let esc = key(KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(keys.translate(&esc), None);
+        assert_eq!(keys.vim_mode(), Some(VimMode::Normal));
+        assert_eq!(keys.translate(&plain('j')).unwrap().code, KeyCode::Down);
+        assert_eq!(keys.translate(&plain('k')).unwrap().code, KeyCode::Up);
+        assert_eq!(keys.translate(&plain('v')), Some(plain(' ')));
+        assert_eq!(keys.translate(&plain('g')), None);
+        assert_eq!(keys.translate(&plain('g')), Some(FIRST_RESULT));
+        assert_eq!(keys.translate(&plain('G')), Some(LAST_RESULT));
+        assert_eq!(keys.translate(&plain('x')), None);
+        assert_eq!(keys.translate(&plain('?')).unwrap().code, KeyCode::F(1));
+        // Esc in normal mode clears the query, then exits
+        assert_eq!(keys.translate(&esc), Some(esc));
+
+        assert_eq!(keys.translate(&plain('i')), None);
+        assert_eq!(keys.vim_mode(), Some(VimMode::Insert));
+    }
+
+    #[test]
+    fn test_vim_v_ignored_in_single_select() {
+        let mut keys = KeyTranslator::new(Keymap::Vim, false);
+        keys.translate(&key(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(keys.translate(&plain('v')), None);
+    }
+}
@@ -1,4 +1,274 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use std::io::{self, Read, Write};
+use std::ops::Range;
+
+/// Placement of the prompt and the direction results grow in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// Prompt at the top, results growing downward
+    #[default]
+    Reverse,
+    /// Prompt at the bottom, results growing upward (fzf's default layout)
+    BottomUp,
+}
+
+impl Layout {
+    /// Parse a layout name: `reverse`, or `default` (as in fzf) or
+    /// `bottom-up` for [`Layout::BottomUp`]
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "reverse" => Ok(Self::Reverse),
+            "default" | "bottom-up" => Ok(Self::BottomUp),
+            _ => Err(format!(
+                "Invalid layout '{s}'. Expected one of: reverse, default, bottom-up"
+            )),
+        }
+    }
+}
+
+/// Where the match counter (`matched/total`) is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Info {
+    /// On its own line between the prompt and the results
+    #[default]
+    Default,
+    /// On the prompt line, saving a row
+    Inline,
+    /// Not shown
+    Hidden,
+}
+
+impl Info {
+    /// Parse an info style: `default`, `inline` or `hidden`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(Self::Default),
+            "inline" => Ok(Self::Inline),
+            "hidden" => Ok(Self::Hidden),
+            _ => Err(format!(
+                "Invalid info style '{s}'. Expected one of: default, inline, hidden"
+            )),
+        }
+    }
+}
+
+/// Columns an auto grid cell leaves between the longest item and the next
+/// cell's marker
+const GRID_GAP: usize = 1;
+
+/// How many columns results are laid out in. Grids fill row by row, so
+/// results still read in rank order left to right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Columns {
+    /// One result per row
+    #[default]
+    One,
+    /// A fixed number of columns
+    Fixed(usize),
+    /// As many columns as the widest result allows, like `ls`
+    Auto,
+}
+
+impl Columns {
+    /// Parse a column count: a positive number or `auto`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(Self::Auto);
+        }
+        match s.parse::<usize>() {
+            Ok(1) => Ok(Self::One),
+            Ok(n) if n > 1 => Ok(Self::Fixed(n)),
+            _ => Err(format!(
+                "Invalid columns '{s}'. Expected a positive number or auto"
+            )),
+        }
+    }
+
+    /// Results per row and the width of each cell in a list `width`
+    /// columns wide. `widest` gives the display width of the widest
+    /// result, and is only called for [`Columns::Auto`].
+    pub fn grid(self, width: u16, widest: impl FnOnce() -> usize) -> (usize, u16) {
+        let columns = match self {
+            Self::One => 1,
+            Self::Fixed(n) => n,
+            // The selection marker takes two columns
+            Self::Auto => width as usize / (widest() + 2 + GRID_GAP),
+        };
+        let columns = columns.clamp(1, (width as usize).max(1));
+        (columns, width / columns as u16)
+    }
+}
+
+/// Rows of the prompt, list, help text and preview within the TUI area
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TuiRows {
+    /// Row of the prompt
+    pub prompt: u16,
+    /// Row of the info line, next to the prompt (if shown)
+    pub info: Option<u16>,
+    /// Row of the help text (if shown)
+    pub help: u16,
+    /// First row of the preview pane
+    pub pane_top: u16,
+    /// Row of the first (best) result
+    first: u16,
+    upward: bool,
+}
+
+impl TuiRows {
+    /// Rows for `layout` in a TUI `height` rows tall, with a line for the
+    /// info line if `info_line`
+    pub fn new(layout: Layout, height: u16, show_help_text: bool, info_line: bool) -> Self {
+        let last = height.saturating_sub(1);
+        let skip = u16::from(info_line);
+        match layout {
+            Layout::Reverse => Self {
+                prompt: 0,
+                info: info_line.then_some(1),
+                help: last,
+                pane_top: 0,
+                first: 1 + skip,
+                upward: false,
+            },
+            Layout::BottomUp => Self {
+                prompt: last,
+                info: info_line.then(|| last.saturating_sub(1)),
+                help: 0,
+                pane_top: u16::from(show_help_text),
+                first: last.saturating_sub(1 + skip),
+                upward: true,
+            },
+        }
+    }
+
+    /// Row of the `index`th visible list row
+    pub fn list(&self, index: usize) -> u16 {
+        let index = index as u16;
+        if self.upward {
+            self.first.saturating_sub(index)
+        } else {
+            self.first + index
+        }
+    }
+}
+
+/// Where the list and preview were last drawn, for mapping mouse positions
+/// back to what is under them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScreenLayout {
+    /// Terminal row of the first list row
+    pub list_top: u16,
+    /// Whether later list rows are above the first one
+    pub upward: bool,
+    /// Filtered position of the item on each list row, starting from the
+    /// prompt (`None` for group headers). In a grid, the first item of
+    /// the row.
+    pub rows: Vec<Option<usize>>,
+    /// Items per row, when laid out in a grid
+    pub columns: usize,
+    /// Width of each grid cell
+    pub cell_width: u16,
+    /// Number of filtered items, where the last grid row ends
+    pub len: usize,
+    /// First column of the preview pane, if it is shown
+    pub preview_col: Option<u16>,
+}
+
+impl ScreenLayout {
+    /// Filtered position of the item drawn at terminal cell (`column`, `row`)
+    pub fn item_at(&self, column: u16, row: u16) -> Option<usize> {
+        if self.in_preview(column) {
+            return None;
+        }
+        let offset = if self.upward {
+            self.list_top.checked_sub(row)?
+        } else {
+            row.checked_sub(self.list_top)?
+        };
+        let first = self.rows.get(offset as usize).copied().flatten()?;
+        if self.per_row() == 1 {
+            return Some(first);
+        }
+        let cell = (column / self.cell_width.max(1)) as usize;
+        (cell < self.per_row() && first + cell < self.len).then_some(first + cell)
+    }
+
+    /// Filtered positions of the items on screen, row by row (once each,
+    /// though a wrapped item takes several rows)
+    pub fn visible_items(&self) -> Vec<usize> {
+        let mut items: Vec<usize> = self
+            .rows
+            .iter()
+            .flatten()
+            .flat_map(|&first| first..(first + self.per_row()).min(self.len))
+            .collect();
+        items.dedup();
+        items
+    }
+
+    /// Items on each list row: 1, or the number of grid columns
+    pub fn per_row(&self) -> usize {
+        self.columns.max(1)
+    }
+
+    /// Whether terminal column `column` is in the preview pane
+    pub fn in_preview(&self, column: u16) -> bool {
+        self.preview_col.is_some_and(|col| column >= col)
+    }
+}
+
+/// List rows covered by the scrollbar's thumb, for a list of `total` rows
+/// showing `visible` of them from row `first`; `None` when it all fits
+pub fn scrollbar_thumb(total: usize, visible: usize, first: usize) -> Option<Range<usize>> {
+    if visible == 0 || total <= visible {
+        return None;
+    }
+    let size = (visible * visible / total).max(1);
+    let hidden = total - visible;
+    let start = (first.min(hidden) * (visible - size) + hidden / 2) / hidden;
+    Some(start..start + size)
+}
+
+/// Characters one Shift+Left/Right scrolls the cursor row by
+const HSCROLL_STEP: isize = 8;
+
+/// Horizontal scroll of the cursor row, in characters from where the row is
+/// drawn by default. Resets when the cursor moves or the query changes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RowScroll {
+    delta: isize,
+    /// Cursor position and query the scroll applies to
+    row: Option<(usize, String)>,
+}
+
+impl RowScroll {
+    /// Scroll on Shift+Left/Right, returning whether the key was used
+    pub fn handle_key(&mut self, key_event: &KeyEvent) -> bool {
+        if !key_event.modifiers.contains(KeyModifiers::SHIFT) {
+            return false;
+        }
+        match key_event.code {
+            KeyCode::Left => self.delta -= HSCROLL_STEP,
+            KeyCode::Right => self.delta += HSCROLL_STEP,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Scroll of the cursor row at `cursor` under `query`, to be clamped by
+    /// the drawing code to how far the row can scroll
+    pub fn delta_for(&mut self, cursor: usize, query: &str) -> &mut isize {
+        let same_row = self
+            .row
+            .as_ref()
+            .is_some_and(|(c, q)| *c == cursor && q == query);
+        if !same_row {
+            self.row = Some((cursor, query.to_string()));
+            self.delta = 0;
+        }
+        &mut self.delta
+    }
+}
 
 /// Get cursor position by querying stderr (fallback for when stdout is redirected)
 pub fn get_cursor_position_from_stderr() -> io::Result<(u16, u16)> {
@@ -67,3 +337,153 @@ pub fn get_terminal_size_from_stderr() -> io::Result<(u16, u16)> {
 
     Err(io::Error::other("Failed to get terminal size from stderr"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_scroll_resets_on_new_row() {
+        let shift = |code| KeyEvent::new(code, KeyModifiers::SHIFT);
+        let mut scroll = RowScroll::default();
+        assert_eq!(*scroll.delta_for(0, "a"), 0);
+        assert!(scroll.handle_key(&shift(KeyCode::Right)));
+        assert!(scroll.handle_key(&shift(KeyCode::Right)));
+        assert!(scroll.handle_key(&shift(KeyCode::Left)));
+        assert!(!scroll.handle_key(&KeyEvent::new(KeyCode::Left, KeyModifiers::NONE)));
+        assert_eq!(*scroll.delta_for(0, "a"), HSCROLL_STEP);
+
+        // Clamped by the drawing code
+        *scroll.delta_for(0, "a") = 3;
+        assert_eq!(*scroll.delta_for(0, "a"), 3);
+
+        assert_eq!(*scroll.delta_for(1, "a"), 0);
+        scroll.handle_key(&shift(KeyCode::Right));
+        assert_eq!(*scroll.delta_for(1, "ab"), 0);
+    }
+
+    #[test]
+    fn test_screen_layout_item_at() {
+        let layout = ScreenLayout {
+            list_top: 6,
+            upward: false,
+            rows: vec![None, Some(3), Some(4)],
+            preview_col: Some(40),
+            ..Default::default()
+        };
+        // Prompt row and group header
+        assert_eq!(layout.item_at(0, 5), None);
+        assert_eq!(layout.item_at(0, 6), None);
+        assert_eq!(layout.item_at(10, 7), Some(3));
+        assert_eq!(layout.item_at(39, 8), Some(4));
+        // Above the TUI, below the list and in the preview
+        assert_eq!(layout.item_at(0, 2), None);
+        assert_eq!(layout.item_at(0, 9), None);
+        assert_eq!(layout.item_at(40, 7), None);
+        assert!(layout.in_preview(40));
+        assert!(!ScreenLayout::default().in_preview(40));
+
+        let upward = ScreenLayout {
+            list_top: 8,
+            upward: true,
+            ..layout
+        };
+        assert_eq!(upward.item_at(0, 8), None);
+        assert_eq!(upward.item_at(0, 7), Some(3));
+        assert_eq!(upward.item_at(0, 6), Some(4));
+        assert_eq!(upward.item_at(0, 9), None);
+    }
+
+    #[test]
+    fn test_screen_layout_item_at_grid() {
+        // Items 0-3 on the first row and 4-5 on the second
+        let layout = ScreenLayout {
+            list_top: 1,
+            rows: vec![Some(0), Some(4)],
+            columns: 4,
+            cell_width: 10,
+            len: 6,
+            ..Default::default()
+        };
+        assert_eq!(layout.item_at(0, 1), Some(0));
+        assert_eq!(layout.item_at(25, 1), Some(2));
+        assert_eq!(layout.item_at(39, 1), Some(3));
+        assert_eq!(layout.item_at(15, 2), Some(5));
+        // Past the last item and the last cell
+        assert_eq!(layout.item_at(25, 2), None);
+        assert_eq!(layout.item_at(40, 1), None);
+        assert_eq!(layout.visible_items(), [0, 1, 2, 3, 4, 5]);
+
+        let headed = ScreenLayout {
+            rows: vec![None, Some(2), None, Some(3)],
+            len: 4,
+            ..Default::default()
+        };
+        assert_eq!(headed.visible_items(), [2, 3]);
+    }
+
+    #[test]
+    fn test_columns() {
+        assert_eq!(Columns::parse("auto"), Ok(Columns::Auto));
+        assert_eq!(Columns::parse("1"), Ok(Columns::One));
+        assert_eq!(Columns::parse("3"), Ok(Columns::Fixed(3)));
+        assert!(Columns::parse("0").is_err());
+        assert!(Columns::parse("wide").is_err());
+
+        assert_eq!(Columns::One.grid(80, || unreachable!()), (1, 80));
+        assert_eq!(Columns::Fixed(3).grid(80, || unreachable!()), (3, 26));
+        // Each cell fits a marker, the widest item and a gap
+        assert_eq!(Columns::Auto.grid(80, || 7), (8, 10));
+        assert_eq!(Columns::Auto.grid(80, || 200), (1, 80));
+    }
+
+    #[test]
+    fn test_scrollbar_thumb() {
+        assert_eq!(scrollbar_thumb(5, 10, 0), None);
+        assert_eq!(scrollbar_thumb(10, 10, 0), None);
+        assert_eq!(scrollbar_thumb(20, 10, 0), Some(0..5));
+        assert_eq!(scrollbar_thumb(20, 10, 5), Some(3..8));
+        assert_eq!(scrollbar_thumb(20, 10, 10), Some(5..10));
+        // At least one row, reaching the bottom at the end
+        assert_eq!(scrollbar_thumb(1000, 10, 0), Some(0..1));
+        assert_eq!(scrollbar_thumb(1000, 10, 990), Some(9..10));
+    }
+
+    #[test]
+    fn test_tui_rows() {
+        let rows = TuiRows::new(Layout::Reverse, 10, true, false);
+        assert_eq!((rows.prompt, rows.help, rows.pane_top), (0, 9, 0));
+        assert_eq!((rows.list(0), rows.list(7)), (1, 8));
+        assert_eq!(rows.info, None);
+
+        let rows = TuiRows::new(Layout::BottomUp, 10, true, false);
+        assert_eq!((rows.prompt, rows.help, rows.pane_top), (9, 0, 1));
+        assert_eq!((rows.list(0), rows.list(7)), (8, 1));
+
+        let rows = TuiRows::new(Layout::BottomUp, 10, false, false);
+        assert_eq!(rows.pane_top, 0);
+        assert_eq!(rows.list(8), 0);
+
+        // The info line sits between the prompt and the list
+        let rows = TuiRows::new(Layout::Reverse, 10, true, true);
+        assert_eq!((rows.info, rows.list(0)), (Some(1), 2));
+        let rows = TuiRows::new(Layout::BottomUp, 10, true, true);
+        assert_eq!((rows.info, rows.list(0)), (Some(8), 7));
+    }
+
+    #[test]
+    fn test_info_parse() {
+        assert_eq!(Info::parse("inline"), Ok(Info::Inline));
+        assert_eq!(Info::parse("Hidden"), Ok(Info::Hidden));
+        assert_eq!(Info::parse("default"), Ok(Info::Default));
+        assert!(Info::parse("right").is_err());
+    }
+
+    #[test]
+    fn test_layout_parse() {
+        assert_eq!(Layout::parse("reverse"), Ok(Layout::Reverse));
+        assert_eq!(Layout::parse("default"), Ok(Layout::BottomUp));
+        assert_eq!(Layout::parse("bottom-up"), Ok(Layout::BottomUp));
+        assert!(Layout::parse("sideways").is_err());
+    }
+}
@@ -1,13 +1,33 @@
 pub mod buffer;
 pub mod controls;
+pub mod details;
 pub mod events;
+pub mod fields;
+pub mod help_overlay;
+pub mod highlight;
+pub mod icons;
+pub mod jump;
+pub mod keymap;
 pub mod layout;
 pub mod preview;
+pub mod query_history;
+pub mod theme;
 pub mod ui;
+pub mod viewport;
+#[cfg(feature = "ratatui")]
+pub mod widget;
+pub mod width;
 
 pub use buffer::ScreenBuffer;
-pub use preview::{parse_ansi_output, PreviewResult, PreviewRule, PreviewState, StyledLine};
+pub use keymap::{BindAction, KeyBinding, KeyName, Keymap};
+pub use layout::{Columns, Info, Layout};
+pub use preview::{
+    parse_ansi_output, PreviewResult, PreviewRule, PreviewScroll, PreviewState, StyledLine,
+};
+pub use query_history::QueryHistory;
+pub use theme::{ColorDepth, Theme};
 pub use ui::{
-    create_command_channel, create_items_channel, run_tui, run_tui_with_config,
-    run_tui_with_indicators, GlobalStatus, ItemIndicator, TuiCommand, TuiConfig,
+    create_command_channel, create_items_channel, merge_items_channels, run_tui,
+    run_tui_with_config, run_tui_with_indicators, run_tui_with_indicators_output,
+    run_tui_with_output, GlobalStatus, ItemIndicator, ItemSource, TuiCommand, TuiConfig, TuiOutput,
 };
@@ -1,16 +1,42 @@
-//! Preview pane: external command rendering with ANSI color support.
+//! Preview pane: external command rendering with ANSI color support, and a
+//! built-in previewer for file paths.
 
 use crate::tui::buffer::ScreenBuffer;
+use crate::tui::highlight::highlight;
 use crossterm::style::Color;
 use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 /// Sentinel value for the smart auto-preview rule
 const AUTO_SENTINEL: &str = "__auto__";
+/// Lines of a file shown by the built-in previewer
+const BUILTIN_MAX_LINES: usize = 1000;
+/// Bytes of a file read by the built-in previewer
+const BUILTIN_MAX_BYTES: u64 = 1 << 20;
+
+/// Whether `{inner}` is substituted in preview commands: `{}` (the item),
+/// `{q}` (the query), `{n}` (the item's index) or `{N}` (the item's `N`th
+/// `:`-separated field)
+fn is_placeholder(inner: &str) -> bool {
+    matches!(inner, "" | "q" | "n") || field_number(inner).is_some()
+}
+
+/// Field number (1-based) of a `{N}` placeholder's contents
+fn field_number(inner: &str) -> Option<usize> {
+    if !inner.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    inner.parse().ok().filter(|&n| n > 0)
+}
 
 /// Preview rule: command template + optional extension filter
 #[derive(Debug, Clone, PartialEq)]
 pub struct PreviewRule {
-    /// Command template ({} replaced with item path)
+    /// Command template: `{}` is replaced with the item, `{q}` with the
+    /// query, `{n}` with the item's index and `{N}` with its `N`th
+    /// `:`-separated field (the item is appended if the template has no
+    /// placeholder)
     pub cmd: String,
     /// File extensions this rule applies to (empty = default for all)
     pub exts: Vec<String>,
@@ -23,7 +49,11 @@ impl PreviewRule {
     /// - `"bat"` → default rule (no braces)
     /// - `"bat {rs,toml}"` → rule for .rs and .toml
     /// - `"bat {}"` → explicit default rule
-    /// - `"auto"` → smart auto-preview rule
+    /// - `"head -n 50 {} | grep -n {q}"` → placeholders anywhere in the command
+    /// - `"auto"` → built-in previewer: file contents or directory listings
+    ///
+    /// Only a trailing brace group that is not a placeholder is an
+    /// extension filter.
     pub fn parse(s: &str) -> Result<Self, String> {
         let trimmed = s.trim();
         if trimmed.eq_ignore_ascii_case("auto") {
@@ -32,26 +62,23 @@ impl PreviewRule {
                 exts: vec![],
             });
         }
-        if let Some(brace_start) = s.rfind('{') {
-            let cmd = s[..brace_start].trim().to_string();
-            let brace_content = &s[brace_start + 1..];
-            if !brace_content.ends_with('}') {
+        if let Some(brace_start) = trimmed.rfind('{') {
+            let brace_content = &trimmed[brace_start + 1..];
+            if !brace_content.contains('}') {
                 return Err("Missing closing brace in preview rule".to_string());
             }
-            let inner = &brace_content[..brace_content.len() - 1];
-            let exts: Vec<String> = if inner.is_empty() {
-                vec![]
-            } else {
-                inner.split(',').map(|e| e.trim().to_lowercase()).collect()
-            };
-            Ok(Self { cmd, exts })
-        } else {
-            // No braces: treat as default rule
-            Ok(Self {
-                cmd: trimmed.to_string(),
-                exts: vec![],
-            })
+            if let Some(inner) = brace_content.strip_suffix('}') {
+                if !is_placeholder(inner) {
+                    let cmd = trimmed[..brace_start].trim().to_string();
+                    let exts = inner.split(',').map(|e| e.trim().to_lowercase()).collect();
+                    return Ok(Self { cmd, exts });
+                }
+            }
         }
+        Ok(Self {
+            cmd: trimmed.to_string(),
+            exts: vec![],
+        })
     }
 
     /// Check if this rule matches a given file extension (case-insensitive)
@@ -59,6 +86,74 @@ impl PreviewRule {
         let ext_lower = ext.to_lowercase();
         self.exts.contains(&ext_lower)
     }
+
+    /// Whether this is the `auto` rule, shown by the built-in previewer
+    pub fn is_auto(&self) -> bool {
+        self.cmd == AUTO_SENTINEL
+    }
+}
+
+/// Initial scroll position of the preview: a 1-based line number, given
+/// literally or read from a `:`-separated field of the item (like the
+/// `file:line:text` output of `grep -n`), plus an offset.
+///
+/// Syntax: `[+]LINE[+N|-N]` where `LINE` is a number or `{FIELD}`, e.g.
+/// `{2}-5` shows line `{2}` with five lines of context above it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewScroll {
+    /// Field (1-based) holding the line number; `None` starts from line 0
+    pub field: Option<usize>,
+    /// Lines added to the field's value (the line itself without a field)
+    pub offset: i64,
+}
+
+impl PreviewScroll {
+    /// Parse a scroll expression such as `{2}-5`, `+{3}` or `10`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let invalid =
+            || format!("Invalid preview scroll '{s}'. Expected e.g. 10, {{2}} or {{2}}-5");
+        let expr = s.trim();
+        let expr = expr.strip_prefix('+').unwrap_or(expr);
+        let (field, rest) = match expr.strip_prefix('{') {
+            Some(braced) => {
+                let (field, rest) = braced.split_once('}').ok_or_else(invalid)?;
+                match field.trim().parse::<usize>() {
+                    Ok(n) if n > 0 => (Some(n), rest.trim()),
+                    _ => return Err(invalid()),
+                }
+            }
+            None => (None, expr),
+        };
+        let offset = match (field, rest) {
+            (Some(_), "") => 0,
+            (Some(_), rest) if rest.starts_with(['+', '-']) => {
+                let sign = if rest.starts_with('-') { -1 } else { 1 };
+                sign * rest[1..].trim().parse::<i64>().map_err(|_| invalid())?
+            }
+            (None, line) => line.parse::<i64>().map_err(|_| invalid())?,
+            _ => return Err(invalid()),
+        };
+        Ok(Self { field, offset })
+    }
+
+    /// Scroll offset (0-based top line) of the preview for `item`, or
+    /// `None` if the field is missing or not a number
+    pub fn offset_for(&self, item: &str) -> Option<usize> {
+        let line = match self.field {
+            Some(field) => {
+                let clean = strip_ansi_sequences(item);
+                let value = clean
+                    .split(':')
+                    .nth(field - 1)?
+                    .trim()
+                    .parse::<i64>()
+                    .ok()?;
+                value + self.offset
+            }
+            None => self.offset,
+        };
+        Some(usize::try_from(line - 1).unwrap_or(0))
+    }
 }
 
 /// Result of running a preview command
@@ -84,14 +179,16 @@ pub struct PreviewState {
     pub lines: Vec<StyledLine>,
     /// Scroll offset
     pub scroll: usize,
-    /// Cache: item text → parsed lines
+    /// Cache: preview key (the command run) → parsed lines
     pub cache: HashMap<String, Vec<StyledLine>>,
-    /// Currently previewed item
+    /// Key of the current preview (the command run)
     pub current_item: String,
     /// Waiting for command output
     pub loading: bool,
     /// Error message if command failed
     pub error: Option<String>,
+    /// Rows the preview pane was last drawn with (0 until drawn)
+    pub height: usize,
 }
 
 impl Default for PreviewState {
@@ -111,6 +208,7 @@ impl PreviewState {
             current_item: String::new(),
             loading: false,
             error: None,
+            height: 0,
         }
     }
 
@@ -142,6 +240,8 @@ impl PreviewState {
                 self.lines = lines.clone();
                 self.cache.insert(self.current_item.clone(), lines);
                 self.error = None;
+                // An initial scroll may point past the end of the output
+                self.scroll = self.scroll.min(self.lines.len().saturating_sub(1));
             }
             PreviewResult::Error(msg) => {
                 self.error = Some(msg);
@@ -191,7 +291,7 @@ pub fn inject_color_flag(cmd: &str) -> String {
 /// Spawn a preview command in a blocking task and send results back
 pub fn spawn_preview_task(
     command: String,
-    sender: std::sync::mpsc::Sender<PreviewResult>,
+    sender: tokio::sync::mpsc::UnboundedSender<PreviewResult>,
 ) -> tokio::task::JoinHandle<()> {
     let command = inject_color_flag(&command);
     tokio::task::spawn_blocking(move || {
@@ -505,7 +605,8 @@ pub fn strip_ansi_sequences(s: &str) -> String {
     result
 }
 
-/// Build a smart preview command based on filesystem metadata.
+/// Build a smart preview command based on filesystem metadata: the shell
+/// equivalent of [`builtin_preview`].
 ///
 /// Returns an empty string for non-existent paths (caller should render
 /// "(not a file)"), otherwise returns a coreutils-safe command.
@@ -532,35 +633,172 @@ fn shell_escape_single_quote(s: &str) -> String {
 ///   1. First rule whose exts contain the item's extension
 ///   2. First rule with empty exts (default)
 ///
-/// If no rule matches, returns empty string.
+/// If no rule matches, returns empty string. `{q}` is replaced with an empty
+/// query and `{n}` with 0; see [`build_preview_command_for`].
 pub fn build_preview_command(item: &str, rules: &[PreviewRule]) -> String {
+    build_preview_command_for(item, "", 0, rules)
+}
+
+/// Build the preview command for `item`, the result at original `index`
+/// for `query`. Every substituted value is shell-quoted.
+pub fn build_preview_command_for(
+    item: &str,
+    query: &str,
+    index: usize,
+    rules: &[PreviewRule],
+) -> String {
+    let clean_item = strip_ansi_sequences(item);
+    let Some(rule) = matching_rule(&clean_item, rules) else {
+        return String::new();
+    };
+
+    if rule.is_auto() {
+        return smart_preview_command(&clean_item);
+    }
+    expand_placeholders(&rule.cmd, &clean_item, query, index)
+}
+
+/// Substitute the placeholders in command `template` (see [`PreviewRule`])
+/// for `item`, the result at original `index` for `query`, shell-quoting
+/// every value. Without placeholders the item is appended.
+pub fn expand_placeholders(template: &str, item: &str, query: &str, index: usize) -> String {
     let clean_item = strip_ansi_sequences(item);
-    let ext = std::path::Path::new(&clean_item)
+    let quote = |s: &str| format!("'{}'", shell_escape_single_quote(s));
+    let value = |inner: &str| match inner {
+        "" => Some(quote(&clean_item)),
+        "q" => Some(quote(query)),
+        "n" => Some(index.to_string()),
+        field => {
+            let n = field_number(field)?;
+            Some(quote(clean_item.split(':').nth(n - 1).unwrap_or("")))
+        }
+    };
+
+    // Substitute in one pass, so placeholders inside values stay literal
+    let mut cmd = String::with_capacity(template.len() + clean_item.len());
+    let mut rest = template;
+    let mut substituted = false;
+    while let Some(start) = rest.find('{') {
+        let group = rest[start + 1..]
+            .find('}')
+            .map(|len| &rest[start + 1..start + 1 + len]);
+        let Some((inner, value)) = group.and_then(|inner| Some((inner, value(inner)?))) else {
+            cmd.push_str(&rest[..=start]);
+            rest = &rest[start + 1..];
+            continue;
+        };
+        cmd.push_str(&rest[..start]);
+        cmd.push_str(&value);
+        rest = &rest[start + inner.len() + 2..];
+        substituted = true;
+    }
+    cmd.push_str(rest);
+    if !substituted {
+        cmd.push(' ');
+        cmd.push_str(&quote(&clean_item));
+    }
+    cmd
+}
+
+/// Rule previewing `clean_item`: the first rule whose exts contain its
+/// extension, else the first default rule
+fn matching_rule<'a>(clean_item: &str, rules: &'a [PreviewRule]) -> Option<&'a PreviewRule> {
+    let ext = Path::new(clean_item)
         .extension()
         .and_then(|e| e.to_str())
         .map(|e| e.to_lowercase());
-
-    // Find matching rule
-    let rule = ext
-        .as_ref()
+    ext.as_ref()
         .and_then(|e| rules.iter().find(|r| r.matches_ext(e)))
-        .or_else(|| rules.iter().find(|r| r.exts.is_empty()));
+        .or_else(|| rules.iter().find(|r| r.exts.is_empty()))
+}
 
-    let Some(rule) = rule else {
-        return String::new();
-    };
+/// Path the built-in previewer shows for `item`, or `None` if the rule it
+/// matches runs a command. Relative paths are resolved against `root`.
+pub fn builtin_preview_path(
+    item: &str,
+    rules: &[PreviewRule],
+    root: Option<&Path>,
+) -> Option<PathBuf> {
+    let clean_item = strip_ansi_sequences(item);
+    if !matching_rule(&clean_item, rules)?.is_auto() {
+        return None;
+    }
+    Some(match root {
+        Some(root) => root.join(clean_item),
+        None => PathBuf::from(clean_item),
+    })
+}
 
-    if rule.cmd == AUTO_SENTINEL {
-        return smart_preview_command(&clean_item);
+/// Preview `path` without an external command: a listing for directories
+/// (subdirectories first) and the first lines of files, syntax-highlighted
+/// by extension.
+pub fn builtin_preview(path: &Path) -> PreviewResult {
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => list_directory(path),
+        Ok(meta) if meta.is_file() => read_file(path, meta.len()),
+        _ => PreviewResult::Error("(not a file)".to_string()),
     }
+}
 
-    let tmpl = &rule.cmd;
-    let escaped = format!("'{}'", shell_escape_single_quote(&clean_item));
-    if tmpl.contains("{}") {
-        tmpl.replace("{}", &escaped)
-    } else {
-        format!("{} {}", tmpl, escaped)
+fn list_directory(path: &Path) -> PreviewResult {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => return PreviewResult::Error(e.to_string()),
+    };
+    let mut entries: Vec<(bool, String)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let is_dir = entry.path().is_dir();
+            (!is_dir, entry.file_name().to_string_lossy().into_owned())
+        })
+        .collect();
+    entries.sort();
+    let lines = entries
+        .into_iter()
+        .map(|(is_file, name)| {
+            if is_file {
+                vec![(name, None, None, false, false)]
+            } else {
+                vec![(format!("{name}/"), Some(Color::Blue), None, true, false)]
+            }
+        })
+        .collect();
+    PreviewResult::Success(lines)
+}
+
+fn read_file(path: &Path, size: u64) -> PreviewResult {
+    let mut bytes = Vec::new();
+    let read = std::fs::File::open(path)
+        .and_then(|file| file.take(BUILTIN_MAX_BYTES).read_to_end(&mut bytes));
+    if let Err(e) = read {
+        return PreviewResult::Error(e.to_string());
     }
+    if memchr::memchr(0, &bytes).is_some() {
+        let msg = format!("(binary file, {size} bytes)");
+        return PreviewResult::Success(vec![vec![(
+            msg,
+            Some(Color::DarkGrey),
+            None,
+            false,
+            false,
+        )]]);
+    }
+    let text = String::from_utf8_lossy(&bytes).replace('\t', "    ");
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let mut lines = highlight(&text, ext);
+    lines.truncate(BUILTIN_MAX_LINES);
+    PreviewResult::Success(lines)
+}
+
+/// Run the built-in previewer for `path` in a blocking task and send the
+/// result back
+pub fn spawn_builtin_preview(
+    path: PathBuf,
+    sender: tokio::sync::mpsc::UnboundedSender<PreviewResult>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::task::spawn_blocking(move || {
+        let _ = sender.send(builtin_preview(&path));
+    })
 }
 
 #[cfg(test)]
@@ -614,10 +852,43 @@ mod tests {
     #[test]
     fn test_preview_rule_parse_explicit_default() {
         let r = PreviewRule::parse("cat {}").unwrap();
-        assert_eq!(r.cmd, "cat");
+        assert_eq!(r.cmd, "cat {}");
         assert!(r.exts.is_empty());
     }
 
+    #[test]
+    fn test_preview_rule_parse_placeholders() {
+        let r = PreviewRule::parse("head -n 50 {} | grep -n {q}").unwrap();
+        assert_eq!(r.cmd, "head -n 50 {} | grep -n {q}");
+        assert!(r.exts.is_empty());
+
+        let r = PreviewRule::parse("bat --line-range {n}: {} {rs}").unwrap();
+        assert_eq!(r.cmd, "bat --line-range {n}: {}");
+        assert_eq!(r.exts, vec!["rs"]);
+
+        assert!(PreviewRule::parse("bat {rs").is_err());
+    }
+
+    #[test]
+    fn test_build_preview_command_placeholders() {
+        let rules = vec![PreviewRule::parse("head {} | grep -n {q} # {n}").unwrap()];
+        assert_eq!(
+            build_preview_command_for("a.txt", "it's", 7, &rules),
+            "head 'a.txt' | grep -n 'it'\"'\"'s' # 7"
+        );
+        // Values are not re-scanned for placeholders
+        assert_eq!(
+            build_preview_command_for("{q}", "x", 0, &rules),
+            "head '{q}' | grep -n 'x' # 0"
+        );
+        // Other braces are kept
+        let rules = vec![PreviewRule::parse("awk '{print $1}' {}").unwrap()];
+        assert_eq!(
+            build_preview_command("a.txt", &rules),
+            "awk '{print $1}' 'a.txt'"
+        );
+    }
+
     #[test]
     fn test_preview_rule_matches_ext() {
         let r = PreviewRule::parse("bat {RS,toml}").unwrap();
@@ -743,4 +1014,110 @@ mod tests {
         // Since foo.md doesn't exist, auto returns ""
         assert_eq!(cmd, "");
     }
+
+    #[test]
+    fn test_builtin_preview_path() {
+        let rules = vec![
+            PreviewRule::parse("bat {rs}").unwrap(),
+            PreviewRule::parse("auto").unwrap(),
+        ];
+        assert_eq!(builtin_preview_path("main.rs", &rules, None), None);
+        assert_eq!(
+            builtin_preview_path("notes.md", &rules, Some(Path::new("docs"))),
+            Some(PathBuf::from("docs/notes.md"))
+        );
+        assert_eq!(builtin_preview_path("notes.md", &rules[..1], None), None);
+    }
+
+    #[test]
+    fn test_builtin_preview_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("main.rs");
+        std::fs::write(&file, "fn main() {\n\tlet x = 1;\n}\n").unwrap();
+        let PreviewResult::Success(lines) = builtin_preview(&file) else {
+            panic!("expected a preview");
+        };
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0][0].0, "fn");
+        assert_eq!(lines[0][0].1, Some(Color::Magenta));
+        // Tabs are expanded
+        assert_eq!(lines[1][0].0, "    ");
+
+        std::fs::write(&file, b"\x7fELF\0\0").unwrap();
+        let PreviewResult::Success(lines) = builtin_preview(&file) else {
+            panic!("expected a preview");
+        };
+        assert_eq!(lines[0][0].0, "(binary file, 6 bytes)");
+    }
+
+    #[test]
+    fn test_builtin_preview_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "").unwrap();
+        std::fs::create_dir(dir.path().join("z")).unwrap();
+        let PreviewResult::Success(lines) = builtin_preview(dir.path()) else {
+            panic!("expected a listing");
+        };
+        let names: Vec<&str> = lines.iter().map(|l| l[0].0.as_str()).collect();
+        assert_eq!(names, vec!["z/", "a.txt"]);
+
+        assert!(matches!(
+            builtin_preview(&dir.path().join("missing")),
+            PreviewResult::Error(msg) if msg == "(not a file)"
+        ));
+    }
+
+    #[test]
+    fn test_build_preview_command_fields() {
+        let rules = vec![PreviewRule::parse("bat {1} -H {2}").unwrap()];
+        assert!(rules[0].exts.is_empty());
+        assert_eq!(
+            build_preview_command("src/main.rs:42:fn main() {", &rules),
+            "bat 'src/main.rs' -H '42'"
+        );
+        // Missing fields are empty
+        assert_eq!(build_preview_command("notes", &rules), "bat 'notes' -H ''");
+    }
+
+    #[test]
+    fn test_preview_scroll_parse() {
+        let scroll = PreviewScroll::parse("{2}-5").unwrap();
+        assert_eq!(
+            scroll,
+            PreviewScroll {
+                field: Some(2),
+                offset: -5
+            }
+        );
+        assert_eq!(PreviewScroll::parse("+{3}").unwrap().field, Some(3));
+        assert_eq!(PreviewScroll::parse("{1} + 2").unwrap().offset, 2);
+        assert_eq!(
+            PreviewScroll::parse("10").unwrap(),
+            PreviewScroll {
+                field: None,
+                offset: 10
+            }
+        );
+        for invalid in ["", "{0}", "{x}", "{2", "{2}5", "abc"] {
+            assert!(PreviewScroll::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_preview_scroll_offset_for() {
+        let scroll = PreviewScroll::parse("{2}-5").unwrap();
+        assert_eq!(scroll.offset_for("src/main.rs:42:fn main() {"), Some(36));
+        assert_eq!(scroll.offset_for("src/main.rs:3:use std;"), Some(0));
+        assert_eq!(scroll.offset_for("src/main.rs"), None);
+        assert_eq!(PreviewScroll::parse("10").unwrap().offset_for("x"), Some(9));
+    }
+
+    #[test]
+    fn test_apply_result_clamps_scroll() {
+        let mut state = PreviewState::new();
+        state.start_loading("cat a");
+        state.scroll = 50;
+        state.apply_result(PreviewResult::Success(parse_ansi_output("1\n2\n3")));
+        assert_eq!(state.scroll, 2);
+    }
 }
@@ -0,0 +1,228 @@
+//! Persistent query history, recalled with Up/Down at an empty query.
+//!
+//! The store is a plain text file with one accepted query per line, oldest
+//! first. Several instances may share it: each write re-reads the file under
+//! an exclusive lock and merges in what others recorded since.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Maximum number of queries kept on disk (oldest are dropped)
+const MAX_ENTRIES: usize = 1000;
+
+/// Accepted queries and where browsing them has got to
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryHistory {
+    path: PathBuf,
+    /// Queries, oldest first, each once
+    entries: Vec<String>,
+    /// Entry last recalled, while the query still matches it
+    recalled: Option<usize>,
+}
+
+impl QueryHistory {
+    /// Create an empty history stored at `path`
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            entries: Vec::new(),
+            recalled: None,
+        }
+    }
+
+    /// Load history from `path`. A missing file yields an empty history.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut history = Self::new(path);
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(history),
+            Err(e) => return Err(e),
+        };
+        lock(&file, false)?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        history.entries = parse_entries(&content);
+        Ok(history)
+    }
+
+    /// Queries, oldest first
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Query to show for `key_event` with `query` typed, or `None` to handle
+    /// the key as usual. Up at an empty query recalls the latest entry and,
+    /// while the query is still the recalled entry, Up and Down move to
+    /// older and newer ones; Down past the newest clears the query.
+    pub fn recall(&mut self, key_event: &KeyEvent, query: &str) -> Option<String> {
+        if key_event.modifiers != KeyModifiers::NONE {
+            return None;
+        }
+        let browsing = self
+            .recalled
+            .filter(|&i| self.entries.get(i).is_some_and(|entry| entry == query));
+        if browsing.is_none() && !query.is_empty() {
+            self.recalled = None;
+            return None;
+        }
+        let next = match (key_event.code, browsing) {
+            (KeyCode::Up, None) => self.entries.len().checked_sub(1)?,
+            // Stay on the oldest
+            (KeyCode::Up, Some(i)) => i.saturating_sub(1),
+            (KeyCode::Down, Some(i)) if i + 1 < self.entries.len() => i + 1,
+            (KeyCode::Down, Some(_)) => {
+                self.recalled = None;
+                return Some(String::new());
+            }
+            _ => return None,
+        };
+        self.recalled = Some(next);
+        Some(self.entries[next].clone())
+    }
+
+    /// Record `query` as the newest entry (dropping any earlier copy) and
+    /// save, keeping queries other instances saved in the meantime
+    pub fn record(&mut self, query: &str) -> io::Result<()> {
+        // Entries are stored one per line
+        if query.is_empty() || query.contains('\n') {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)?;
+        lock(&file, true)?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+
+        let mut entries = parse_entries(&content);
+        entries.retain(|entry| entry != query);
+        entries.push(query.to_string());
+        let excess = entries.len().saturating_sub(MAX_ENTRIES);
+        entries.drain(..excess);
+
+        let mut content = entries.join("\n");
+        content.push('\n');
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(content.as_bytes())?;
+        self.entries = entries;
+        self.recalled = None;
+        Ok(())
+    }
+}
+
+/// Non-empty lines of a history file, keeping the last copy of each
+fn parse_entries(content: &str) -> Vec<String> {
+    let lines: Vec<&str> = content.lines().filter(|line| !line.is_empty()).collect();
+    let mut seen = std::collections::HashSet::new();
+    let mut entries: Vec<String> = lines
+        .iter()
+        .rev()
+        .filter(|line| seen.insert(**line))
+        .map(|line| line.to_string())
+        .collect();
+    entries.reverse();
+    entries
+}
+
+/// Lock `file` until it is closed: exclusively for writing, or shared for
+/// reading
+#[cfg(unix)]
+fn lock(file: &File, exclusive: bool) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let operation = if exclusive {
+        libc::LOCK_EX
+    } else {
+        libc::LOCK_SH
+    };
+    if unsafe { libc::flock(file.as_raw_fd(), operation) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn lock(_file: &File, _exclusive: bool) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_record_dedups_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("queries");
+        let mut history = QueryHistory::load(&path).unwrap();
+        assert!(history.entries().is_empty());
+
+        for query in ["foo", "bar", "", "foo"] {
+            history.record(query).unwrap();
+        }
+        assert_eq!(history.entries(), ["bar", "foo"]);
+        assert_eq!(QueryHistory::load(&path).unwrap().entries(), ["bar", "foo"]);
+    }
+
+    #[test]
+    fn test_record_merges_other_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queries");
+        let mut first = QueryHistory::load(&path).unwrap();
+        let mut second = QueryHistory::load(&path).unwrap();
+        second.record("from second").unwrap();
+        first.record("from first").unwrap();
+        assert_eq!(
+            QueryHistory::load(&path).unwrap().entries(),
+            ["from second", "from first"]
+        );
+    }
+
+    #[test]
+    fn test_recall() {
+        let mut history = QueryHistory::new(Path::new("unused"));
+        history.entries = vec!["old".to_string(), "new".to_string()];
+
+        // Only at an empty query, and Down has nothing newer to show
+        assert_eq!(history.recall(&key(KeyCode::Up), "typed"), None);
+        assert_eq!(history.recall(&key(KeyCode::Down), ""), None);
+
+        assert_eq!(
+            history.recall(&key(KeyCode::Up), "").as_deref(),
+            Some("new")
+        );
+        assert_eq!(
+            history.recall(&key(KeyCode::Up), "new").as_deref(),
+            Some("old")
+        );
+        assert_eq!(
+            history.recall(&key(KeyCode::Up), "old").as_deref(),
+            Some("old")
+        );
+        assert_eq!(
+            history.recall(&key(KeyCode::Down), "old").as_deref(),
+            Some("new")
+        );
+        assert_eq!(
+            history.recall(&key(KeyCode::Down), "new").as_deref(),
+            Some("")
+        );
+
+        // Editing a recalled query stops browsing
+        history.recall(&key(KeyCode::Up), "");
+        assert_eq!(history.recall(&key(KeyCode::Up), "newer"), None);
+    }
+}
@@ -0,0 +1,425 @@
+//! Color themes for the TUI.
+//!
+//! `--color` takes fzf-style comma-separated entries: a base theme name
+//! (`dark` or `light`) and/or `key:color` overrides, e.g.
+//! `light,hl:red,bg+:#303030`. The `hl` and `hl+` values may also carry
+//! `bold`, `underline` or `regular` attributes, e.g. `hl:underline` to
+//! only underline matches. Colors are reduced to what the terminal
+//! supports ([`ColorDepth`]) when the frame is rendered.
+
+use crossterm::style::Color;
+
+/// Colors used to draw the prompt, list and preview border
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Prompt text (`prompt`)
+    pub prompt: Color,
+    /// Text of the cursor row (`fg+`)
+    pub cursor_fg: Color,
+    /// Background of the cursor row (`bg+`)
+    pub cursor_bg: Color,
+    /// Selection marker (`marker`)
+    pub selected: Color,
+    /// Matched characters, `None` to keep the item's color (`hl`)
+    pub match_fg: Option<Color>,
+    /// Matched characters on the cursor row (`hl+`)
+    pub cursor_match: Color,
+    /// Background of matched characters, `None` to keep the row's (`hl-bg`)
+    pub match_bg: Option<Color>,
+    /// Whether matched characters are bold
+    pub match_bold: bool,
+    /// Whether matched characters are underlined
+    pub match_underline: bool,
+    /// Counter, help text, group headers and other dim text (`info`)
+    pub dim: Color,
+    /// Preview separator (`border`)
+    pub border: Color,
+    /// Loading spinner, also of spinning item indicators (`spinner`)
+    pub spinner: Color,
+    /// Ready message and success indicators (`success`)
+    pub success: Color,
+    /// Error indicators (`error`)
+    pub error: Color,
+    /// Warnings, e.g. a reached selection limit, and warning indicators
+    /// (`warning`)
+    pub warning: Color,
+}
+
+impl Theme {
+    /// The default theme, for dark terminal backgrounds
+    pub const DARK: Theme = Theme {
+        prompt: Color::Cyan,
+        cursor_fg: Color::Yellow,
+        cursor_bg: Color::DarkGrey,
+        selected: Color::Green,
+        match_fg: None,
+        cursor_match: Color::White,
+        match_bg: None,
+        match_bold: true,
+        match_underline: true,
+        dim: Color::DarkGrey,
+        border: Color::DarkGrey,
+        spinner: Color::Yellow,
+        success: Color::Green,
+        error: Color::Red,
+        warning: Color::Yellow,
+    };
+
+    /// A theme for light terminal backgrounds
+    pub const LIGHT: Theme = Theme {
+        prompt: Color::DarkBlue,
+        cursor_fg: Color::Black,
+        cursor_bg: Color::Grey,
+        selected: Color::DarkGreen,
+        match_fg: Some(Color::DarkMagenta),
+        cursor_match: Color::DarkRed,
+        match_bg: None,
+        match_bold: true,
+        match_underline: true,
+        dim: Color::DarkGrey,
+        border: Color::Grey,
+        spinner: Color::DarkYellow,
+        success: Color::DarkGreen,
+        error: Color::DarkRed,
+        warning: Color::DarkYellow,
+    };
+
+    /// Parse a `--color` spec: base theme names and `key:color` overrides,
+    /// applied left to right
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut theme = Theme::default();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((key, value)) = entry.split_once(':') else {
+                theme = match entry.to_lowercase().as_str() {
+                    "dark" => Theme::DARK,
+                    "light" => Theme::LIGHT,
+                    _ => {
+                        return Err(format!(
+                            "Invalid theme '{entry}'. Expected one of: dark, light"
+                        ))
+                    }
+                };
+                continue;
+            };
+            let key = key.trim();
+            if matches!(key, "hl" | "hl+") {
+                theme.parse_match_style(key, value)?;
+                continue;
+            }
+            let color = parse_color(value)?;
+            match key {
+                "prompt" => theme.prompt = color,
+                "fg+" => theme.cursor_fg = color,
+                "bg+" => theme.cursor_bg = color,
+                "marker" => theme.selected = color,
+                "hl-bg" => theme.match_bg = Some(color),
+                "info" => theme.dim = color,
+                "border" => theme.border = color,
+                "spinner" => theme.spinner = color,
+                "success" => theme.success = color,
+                "error" => theme.error = color,
+                "warning" => theme.warning = color,
+                _ => {
+                    return Err(format!(
+                        "Invalid color key '{key}'. Expected one of: prompt, fg+, bg+, marker, hl, hl+, hl-bg, info, border, spinner, success, error, warning"
+                    ))
+                }
+            }
+        }
+        Ok(theme)
+    }
+
+    /// Apply an `hl` or `hl+` value: a color and/or `bold`, `underline` or
+    /// `regular` (neither) attributes, separated by `:`. Attributes replace
+    /// the default bold underline of matches on every row.
+    fn parse_match_style(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let mut attributes = None;
+        for part in value.split(':').map(str::trim) {
+            match part.to_lowercase().as_str() {
+                "bold" => attributes.get_or_insert((false, false)).0 = true,
+                "underline" => attributes.get_or_insert((false, false)).1 = true,
+                "regular" => {
+                    attributes.get_or_insert((false, false));
+                }
+                _ if key == "hl" => self.match_fg = Some(parse_color(part)?),
+                _ => self.cursor_match = parse_color(part)?,
+            }
+        }
+        if let Some((bold, underline)) = attributes {
+            self.match_bold = bold;
+            self.match_underline = underline;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DARK
+    }
+}
+
+/// Parse a color name (`red`, `dark-grey`, ...), an ANSI 256-color number
+/// or `#rrggbb`
+fn parse_color(s: &str) -> Result<Color, String> {
+    let s = s.trim();
+    if let Some(hex) = s.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+        };
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb { r, g, b }),
+            _ => Err(format!("Invalid color '{s}'. Expected #rrggbb")),
+        };
+    }
+    if let Ok(n) = s.parse::<u8>() {
+        return Ok(Color::AnsiValue(n));
+    }
+    Color::try_from(s.replace('-', "_").as_str())
+        .map_err(|()| format!("Invalid color '{s}'. Expected a name, 0-255 or #rrggbb"))
+}
+
+/// How many colors the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorDepth {
+    /// No colors (`NO_COLOR`); bold and underline still apply
+    NoColor,
+    /// The 16 basic ANSI colors
+    Ansi16,
+    /// The 256-color palette
+    Ansi256,
+    /// 24-bit RGB
+    #[default]
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Detect the color depth from `NO_COLOR`, `COLORTERM` and `TERM`
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).ok();
+        Self::from_env(
+            var("NO_COLOR").is_some_and(|v| !v.is_empty()),
+            var("COLORTERM").as_deref(),
+            var("TERM").as_deref(),
+        )
+    }
+
+    fn from_env(no_color: bool, colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if no_color {
+            return ColorDepth::NoColor;
+        }
+        if matches!(colorterm, Some("truecolor" | "24bit")) {
+            return ColorDepth::TrueColor;
+        }
+        match term {
+            Some(t) if t.ends_with("-direct") => ColorDepth::TrueColor,
+            Some(t) if t.contains("256color") => ColorDepth::Ansi256,
+            _ => ColorDepth::Ansi16,
+        }
+    }
+
+    /// The closest color this depth can show, `None` for no color
+    pub fn approximate(self, color: Color) -> Option<Color> {
+        match (self, color) {
+            (ColorDepth::NoColor, _) => None,
+            (ColorDepth::TrueColor, _) | (_, Color::Reset) => Some(color),
+            (ColorDepth::Ansi256, Color::Rgb { r, g, b }) => {
+                Some(Color::AnsiValue(rgb_to_256(r, g, b)))
+            }
+            (ColorDepth::Ansi256, _) => Some(color),
+            (ColorDepth::Ansi16, Color::Rgb { r, g, b }) => Some(nearest_16((r, g, b))),
+            (ColorDepth::Ansi16, Color::AnsiValue(n)) => Some(match ANSI_16.get(n as usize) {
+                Some(&(named, _)) => named,
+                None => nearest_16(ansi_256_to_rgb(n)),
+            }),
+            (ColorDepth::Ansi16, _) => Some(color),
+        }
+    }
+}
+
+/// The 16 basic colors (in ANSI order) and their usual RGB values
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Channel levels of the 6x6x6 color cube (palette entries 16-231)
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).unsigned_abs().pow(2);
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, value)| distance(rgb, *value))
+        .map_or(Color::White, |&(named, _)| named)
+}
+
+/// Closest 256-color palette entry: a color cube entry or a grey
+fn rgb_to_256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |c: u8| {
+        (0..6)
+            .min_by_key(|&i| (i32::from(CUBE_LEVELS[i]) - i32::from(c)).abs())
+            .unwrap_or(0) as u8
+    };
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+    let average = (u32::from(r) + u32::from(g) + u32::from(b)) / 3;
+    let grey = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+    if distance((r, g, b), ansi_256_to_rgb(grey)) < distance((r, g, b), ansi_256_to_rgb(cube)) {
+        grey
+    } else {
+        cube
+    }
+}
+
+fn ansi_256_to_rgb(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => ANSI_16[n as usize].1,
+        16..=231 => {
+            let i = n - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let v = 8 + 10 * (n - 232);
+            (v, v, v)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_depth_from_env() {
+        let detect = ColorDepth::from_env;
+        assert_eq!(detect(true, Some("truecolor"), None), ColorDepth::NoColor);
+        assert_eq!(detect(false, Some("24bit"), None), ColorDepth::TrueColor);
+        assert_eq!(
+            detect(false, None, Some("xterm-direct")),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            detect(false, None, Some("xterm-256color")),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(detect(false, None, Some("xterm")), ColorDepth::Ansi16);
+        assert_eq!(detect(false, None, None), ColorDepth::Ansi16);
+    }
+
+    #[test]
+    fn test_color_depth_approximate() {
+        let orange = Color::Rgb {
+            r: 255,
+            g: 135,
+            b: 0,
+        };
+        assert_eq!(ColorDepth::TrueColor.approximate(orange), Some(orange));
+        assert_eq!(
+            ColorDepth::Ansi256.approximate(orange),
+            Some(Color::AnsiValue(208))
+        );
+        assert_eq!(
+            ColorDepth::Ansi256.approximate(Color::Rgb {
+                r: 0x30,
+                g: 0x30,
+                b: 0x30
+            }),
+            Some(Color::AnsiValue(236))
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.approximate(orange),
+            Some(Color::DarkYellow)
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.approximate(Color::AnsiValue(9)),
+            Some(Color::Red)
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.approximate(Color::AnsiValue(244)),
+            Some(Color::DarkGrey)
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.approximate(Color::Cyan),
+            Some(Color::Cyan)
+        );
+        assert_eq!(ColorDepth::NoColor.approximate(Color::Cyan), None);
+    }
+
+    #[test]
+    fn test_theme_parse_base_themes() {
+        assert_eq!(Theme::parse("dark"), Ok(Theme::DARK));
+        assert_eq!(Theme::parse("Light"), Ok(Theme::LIGHT));
+        assert_eq!(Theme::parse(""), Ok(Theme::default()));
+        assert!(Theme::parse("solarized").is_err());
+    }
+
+    #[test]
+    fn test_theme_parse_overrides() {
+        let theme = Theme::parse("light,hl:red,bg+:#303030,info:244,prompt:dark-cyan").unwrap();
+        assert_eq!(theme.cursor_fg, Theme::LIGHT.cursor_fg);
+        assert_eq!(theme.match_fg, Some(Color::Red));
+        assert_eq!(
+            theme.cursor_bg,
+            Color::Rgb {
+                r: 0x30,
+                g: 0x30,
+                b: 0x30
+            }
+        );
+        assert_eq!(theme.dim, Color::AnsiValue(244));
+        assert_eq!(theme.prompt, Color::DarkCyan);
+
+        let theme = Theme::parse("spinner:blue,success:#00ff00,error:9,warning:magenta").unwrap();
+        assert_eq!(theme.spinner, Color::Blue);
+        assert_eq!(theme.success, Color::Rgb { r: 0, g: 255, b: 0 });
+        assert_eq!(theme.error, Color::AnsiValue(9));
+        assert_eq!(theme.warning, Color::Magenta);
+    }
+
+    #[test]
+    fn test_theme_parse_match_style() {
+        let theme = Theme::parse("hl:underline").unwrap();
+        assert_eq!(theme.match_fg, None);
+        assert!(!theme.match_bold);
+        assert!(theme.match_underline);
+
+        let theme = Theme::parse("hl:black:bold,hl-bg:yellow,hl+:regular:red").unwrap();
+        assert_eq!(theme.match_fg, Some(Color::Black));
+        assert_eq!(theme.match_bg, Some(Color::Yellow));
+        assert_eq!(theme.cursor_match, Color::Red);
+        // Later attributes win
+        assert!(!theme.match_bold);
+        assert!(!theme.match_underline);
+    }
+
+    #[test]
+    fn test_theme_parse_errors() {
+        assert!(Theme::parse("hl:chartreuse").is_err());
+        assert!(Theme::parse("hl:bold:italic").is_err());
+        assert!(Theme::parse("hl:#12345").is_err());
+        assert!(Theme::parse("gutter:red").is_err());
+    }
+}
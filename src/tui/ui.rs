@@ -1,30 +1,85 @@
-use crate::fuzzy::FuzzyFinder;
+use crate::fuzzy::{
+    CancelToken, CandidateIndex, Dedup, FuzzyFinder, ItemTransform, MatchPositions, ScoringConfig,
+    SimilarGroup,
+};
 use crate::tui::buffer::ScreenBuffer;
 use crate::tui::controls::Action;
+use crate::tui::details::{draw_details, Details};
 use crate::tui::events;
-use crate::tui::layout;
+use crate::tui::fields::Fields;
+use crate::tui::help_overlay::{draw_help_overlay, help_sections};
+use crate::tui::icons::{with_icon, IconStyle};
+use crate::tui::jump::{jump_labels, Jump, JumpKey, DEFAULT_JUMP_LABELS};
+use crate::tui::keymap::{BindAction, KeyBinding, KeyName, KeyTranslator, Keymap, VimMode};
+use crate::tui::layout::{
+    self, scrollbar_thumb, Columns, Info, Layout, RowScroll, ScreenLayout, TuiRows,
+};
 use crate::tui::preview::{
-    build_preview_command, parse_ansi_output, render_preview_to_buffer, spawn_preview_task,
+    build_preview_command_for, builtin_preview_path, expand_placeholders, parse_ansi_output,
+    render_preview_to_buffer, spawn_builtin_preview, spawn_preview_task, strip_ansi_sequences,
     PreviewResult, PreviewState,
 };
+use crate::tui::query_history::QueryHistory;
+use crate::tui::theme::{ColorDepth, Theme};
+use crate::tui::viewport::{in_session, install_panic_hook, restore_terminal, Viewport};
+use crate::tui::width::{char_width, str_width, truncate_to_width, wrap_ranges};
 use crossterm::{
-    cursor::{position, Hide, MoveTo, Show},
-    event::{self, Event},
+    event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers},
     execute,
     style::{
         Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
     },
-    terminal::{disable_raw_mode, enable_raw_mode, size, Clear, ClearType},
+    terminal::size,
+};
+use futures::{
+    task::{ArcWake, AtomicWaker},
+    FutureExt, Stream, StreamExt,
 };
 use std::{
+    borrow::Cow,
+    collections::HashMap,
+    future::Future,
     io::{self, Write},
     mem,
+    ops::Range,
+    panic::AssertUnwindSafe,
+    path::PathBuf,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
     time::Instant,
 };
 use tokio::sync::mpsc;
 
 /// Built-in spinner frames (Braille dots pattern)
 const SPINNER_FRAMES: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+/// Loading indicator in place of the spinner with
+/// [`accessible`](TuiConfig::accessible)
+const STILL_LOADING: char = '…';
+
+/// Markers drawn before each item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Markers {
+    /// `✓` on selected items; the cursor row is told apart by its colors
+    Glyph,
+    /// `>` on the cursor row and, in multi-select mode, `[x]` or `[ ]`
+    Text { checkboxes: bool },
+}
+
+impl Markers {
+    fn marker(self, is_cursor: bool, is_selected: bool) -> &'static str {
+        match (self, is_cursor, is_selected) {
+            (Markers::Glyph, _, true) => "✓ ",
+            (Markers::Glyph, _, false) => "  ",
+            (Markers::Text { checkboxes: false }, true, _) => "> ",
+            (Markers::Text { checkboxes: false }, false, _) => "  ",
+            (Markers::Text { checkboxes: true }, true, true) => "> [x] ",
+            (Markers::Text { checkboxes: true }, true, false) => "> [ ] ",
+            (Markers::Text { checkboxes: true }, false, true) => "  [x] ",
+            (Markers::Text { checkboxes: true }, false, false) => "  [ ] ",
+        }
+    }
+}
 
 /// Global status indicator state
 #[derive(Debug, Clone, Default)]
@@ -73,18 +128,101 @@ pub enum TuiCommand {
     SetGlobalStatus(GlobalStatus),
 }
 
+/// Where the TUI's items come from: a channel of plain items, or of
+/// commands that also set indicators and the status
+enum Incoming {
+    Items(mpsc::Receiver<String>),
+    Commands(mpsc::Receiver<TuiCommand>),
+}
+
+impl Incoming {
+    /// Wait for the next command, plain items arriving as
+    /// [`TuiCommand::AddItem`]; `None` once the channel closed
+    async fn recv(&mut self) -> Option<TuiCommand> {
+        match self {
+            Incoming::Items(receiver) => receiver.recv().await.map(TuiCommand::AddItem),
+            Incoming::Commands(receiver) => receiver.recv().await,
+        }
+    }
+
+    fn try_recv(&mut self) -> Result<TuiCommand, mpsc::error::TryRecvError> {
+        match self {
+            Incoming::Items(receiver) => receiver.try_recv().map(TuiCommand::AddItem),
+            Incoming::Commands(receiver) => receiver.try_recv(),
+        }
+    }
+}
+
+/// Opens a fresh channel of items from a source, e.g. to re-read a file on
+/// [`BindAction::Reload`]
+#[derive(Clone)]
+pub struct ItemSource(Arc<dyn Fn() -> mpsc::Receiver<String> + Send + Sync>);
+
+impl ItemSource {
+    /// Source whose items `open` starts sending, each time it is called
+    pub fn new<F>(open: F) -> Self
+    where
+        F: Fn() -> mpsc::Receiver<String> + Send + Sync + 'static,
+    {
+        Self(Arc::new(open))
+    }
+
+    /// Start reading the source again
+    pub fn open(&self) -> mpsc::Receiver<String> {
+        (self.0)()
+    }
+}
+
+impl std::fmt::Debug for ItemSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ItemSource(..)")
+    }
+}
+
+/// A named source of items with its own query and selection, one of
+/// several cycled through with F3
+#[derive(Debug, Clone)]
+pub struct Tab {
+    /// Name shown in the tab bar and printed before the accepted items
+    pub name: String,
+    /// Where the tab's items come from
+    pub source: ItemSource,
+}
+
+/// A tab that is not shown: its results and the rest of its input
+struct TabState {
+    finder: FuzzyFinder,
+    receiver: Incoming,
+    exhausted: bool,
+    scroll_offset: usize,
+}
+
+/// Prompt drawn before the query unless configured otherwise
+pub const DEFAULT_PROMPT: &str = "> ";
+
 /// Configuration for TUI display mode and height
 #[derive(Debug, Clone)]
 pub struct TuiConfig {
-    /// Whether to use fullscreen mode
+    /// Whether to use fullscreen mode. Fullscreen runs on the terminal's
+    /// alternate screen, so the scrollback is left untouched; inline modes
+    /// clear the rows they drew on when they exit.
     pub fullscreen: bool,
     /// Fixed height in lines (non-fullscreen mode)
     pub height: Option<u16>,
     /// Height as percentage of terminal (non-fullscreen mode)
     pub height_percentage: Option<f32>,
-    /// Whether to show help/instructions text at the bottom
+    /// Shrink the inline TUI to fit its results, growing as they arrive up
+    /// to the fixed or percentage height (the whole terminal without one)
+    pub auto_height: bool,
+    /// Whether to show a footer with key hints and the current mode (see
+    /// [`KeyTranslator::hints`])
     pub show_help_text: bool,
+    /// Prompt drawn before the query
+    pub prompt: String,
+    /// Query the finder starts with
+    pub query: String,
     /// Whether to show a loading spinner while items are being received
+    /// (next to the match counter, or on the prompt line if it is hidden)
     pub show_loading_indicator: bool,
     /// Custom loading message (shown next to spinner)
     pub loading_message: Option<String>,
@@ -94,6 +232,125 @@ pub struct TuiConfig {
     pub preview_rules: Vec<crate::tui::preview::PreviewRule>,
     /// Auto-show preview on cursor move
     pub preview_auto: bool,
+    /// Initial scroll position of each preview (`None` starts at the top)
+    pub preview_scroll: Option<crate::tui::preview::PreviewScroll>,
+    /// Capture the mouse to click and scroll the list and preview
+    pub mouse: bool,
+    /// Strip ANSI escape sequences from the returned selection (items are
+    /// always drawn styled and matched without them)
+    pub ansi: bool,
+    /// Keymap preset translating keys to the default bindings
+    pub keymap: Keymap,
+    /// Placement of the prompt and direction results grow in
+    pub layout: Layout,
+    /// Where the match counter is shown
+    pub info: Info,
+    /// Colors of the prompt, cursor row, matches and dim text
+    pub theme: Theme,
+    /// Colors the terminal supports; theme and item colors are reduced to it
+    pub color_depth: ColorDepth,
+    /// Cut off items too long for their row at the start rather than the
+    /// end, keeping e.g. the file name of a long path visible
+    pub keep_right: bool,
+    /// Lay results out in a grid of this many columns
+    pub columns: Columns,
+    /// File accepted queries are saved to and recalled from with Up/Down
+    pub history: Option<std::path::PathBuf>,
+    /// Keys that accept the selection like Enter; the one pressed is
+    /// reported in [`TuiOutput::key`]
+    pub expect: Vec<KeyName>,
+    /// Let Enter accept the query even when nothing matches, for callers
+    /// that act on [`TuiOutput::query`] itself (e.g. to create a new item)
+    pub print_query: bool,
+    /// Keys bound to actions, checked before any other key handling
+    pub bindings: Vec<KeyBinding>,
+    /// Characters [`BindAction::Jump`] labels items with, as single
+    /// characters or, with more items than characters, pairs of them
+    pub jump_labels: String,
+    /// Where the [`BindAction::Reload`] action reads items from again; the
+    /// action does nothing without one. Only [`run_tui`] and its variants
+    /// reload, as [`run_tui_with_indicators`] is fed by its caller.
+    pub reload: Option<ItemSource>,
+    /// Named item sources, each with its own query and selection, cycled
+    /// with F3. The channel [`run_tui`] is given feeds the first; the
+    /// others are opened when first shown. Not with item indicators.
+    pub tabs: Vec<Tab>,
+    /// Fields of each item to display; items are still matched and output
+    /// whole
+    pub with_nth: Option<Fields>,
+    /// Draw a file-type icon in front of each item, for items that are
+    /// paths (relative to [`TuiConfig::preview_root`] if set)
+    pub icons: Option<IconStyle>,
+    /// Wrap long items onto further screen lines instead of cutting them
+    /// off (not in a grid, nor with item indicators)
+    pub wrap: bool,
+    /// Show a scrollbar along the list when it overflows
+    pub scrollbar: bool,
+    /// Rank matches by score; when off, matches keep their input order
+    /// (see [`BindAction::ToggleSort`])
+    pub sort: bool,
+    /// Wrap the cursor from the last result to the first and back; when
+    /// off, it stops at either end
+    pub cycle: bool,
+    /// Show each result's score breakdown and matched positions at the end
+    /// of its row (see [`BindAction::ToggleScores`])
+    pub debug_scores: bool,
+    /// Screen-reader-friendly drawing: `>` on the cursor row and `[x]` /
+    /// `[ ]` selection markers in multi-select mode rather than color-only
+    /// cues, and a still loading indicator, so the screen only changes
+    /// when something happens
+    pub accessible: bool,
+    /// Output selections in the order they were made rather than in input
+    /// order
+    pub selection_order: bool,
+    /// Also abort on SIGINT from outside the terminal (e.g. `kill -INT`),
+    /// or on Ctrl-C while a bound command runs. This installs a SIGINT
+    /// handler (Ctrl-C handler on Windows) that stays for the rest of the
+    /// process, which then no longer terminates on SIGINT by default, so
+    /// it is only meant for programs that exit after the session, like the
+    /// `ff` binary.
+    pub catch_interrupts: bool,
+    /// Most items that can be selected at once in multi-select mode
+    pub max_select: Option<usize>,
+    /// Items selected from the start in multi-select mode, by their text;
+    /// matching items are selected as they stream in
+    pub preselect: Vec<String>,
+    /// Accept the match without showing the TUI when the initial query
+    /// matches exactly one item
+    pub select_one: bool,
+    /// Return without showing the TUI when the initial query matches
+    /// nothing (e.g. there are no items)
+    pub exit_zero: bool,
+    /// Directory the built-in previewer resolves relative items against
+    /// (e.g. the directory whose entries are listed)
+    pub preview_root: Option<std::path::PathBuf>,
+    /// Scoring options used to rank matches
+    pub scoring: ScoringConfig,
+    /// Minimum score a match needs to be listed
+    pub min_score: Option<i32>,
+    /// Maximum number of matches to list
+    pub max_results: Option<usize>,
+    /// Collapse near-duplicate results, showing a `(xN)` count
+    pub unique_similar: bool,
+    /// Group near-duplicate results under headers
+    pub group_similar: bool,
+    /// Map/filter hooks applied, in order, to every received item
+    pub transforms: Vec<ItemTransform>,
+    /// Drop repeated items as they arrive
+    pub dedup: Option<Dedup>,
+    /// Prebuilt candidate index for the items about to be streamed (e.g.
+    /// loaded from the on-disk cache); built in the background if `None`
+    pub index: Option<CandidateIndex>,
+    /// Cache file to save the index built in the background to, once every
+    /// item has arrived, when the session ends (see
+    /// [`CandidateIndex::save_cached`])
+    pub index_cache: Option<PathBuf>,
+    /// Split each incoming item at the first occurrence of this character:
+    /// the part before it is displayed, the rest is only matched
+    pub hidden_separator: Option<char>,
+    /// Tab-separated field (1-based) of each incoming item holding a score
+    /// boost; the field is not displayed
+    pub weight_field: Option<usize>,
 }
 
 impl Default for TuiConfig {
@@ -102,12 +359,58 @@ impl Default for TuiConfig {
             fullscreen: true,
             height: None,
             height_percentage: None,
+            auto_height: false,
             show_help_text: true,
+            prompt: DEFAULT_PROMPT.to_string(),
+            query: String::new(),
             show_loading_indicator: true,
             loading_message: None,
             ready_message: None,
             preview_rules: Vec::new(),
             preview_auto: false,
+            preview_scroll: None,
+            mouse: true,
+            ansi: false,
+            keymap: Keymap::Default,
+            layout: Layout::Reverse,
+            info: Info::Default,
+            theme: Theme::default(),
+            color_depth: ColorDepth::detect(),
+            keep_right: false,
+            columns: Columns::One,
+            history: None,
+            expect: Vec::new(),
+            print_query: false,
+            bindings: Vec::new(),
+            jump_labels: DEFAULT_JUMP_LABELS.to_string(),
+            reload: None,
+            tabs: Vec::new(),
+            with_nth: None,
+            icons: None,
+            wrap: false,
+            scrollbar: true,
+            sort: true,
+            cycle: true,
+            debug_scores: false,
+            accessible: false,
+            selection_order: false,
+            catch_interrupts: false,
+            max_select: None,
+            preselect: Vec::new(),
+            select_one: false,
+            exit_zero: false,
+            preview_root: None,
+            scoring: ScoringConfig::default(),
+            min_score: None,
+            max_results: None,
+            unique_similar: false,
+            group_similar: false,
+            transforms: Vec::new(),
+            dedup: None,
+            index: None,
+            index_cache: None,
+            hidden_separator: None,
+            weight_field: None,
         }
     }
 }
@@ -123,13 +426,7 @@ impl TuiConfig {
         Self {
             fullscreen: false,
             height: Some(height),
-            height_percentage: None,
-            show_help_text: true,
-            show_loading_indicator: true,
-            loading_message: None,
-            ready_message: None,
-            preview_rules: Vec::new(),
-            preview_auto: false,
+            ..Self::default()
         }
     }
 
@@ -137,14 +434,19 @@ impl TuiConfig {
     pub fn with_height_percentage(percentage: f32) -> Self {
         Self {
             fullscreen: false,
-            height: None,
             height_percentage: Some(percentage),
-            show_help_text: true,
-            show_loading_indicator: true,
-            loading_message: None,
-            ready_message: None,
-            preview_rules: Vec::new(),
-            preview_auto: false,
+            ..Self::default()
+        }
+    }
+
+    /// Create an inline configuration that fits its results, up to `max`
+    /// lines
+    pub fn auto_height(max: u16) -> Self {
+        Self {
+            fullscreen: false,
+            height: Some(max),
+            auto_height: true,
+            ..Self::default()
         }
     }
 
@@ -152,14 +454,7 @@ impl TuiConfig {
     pub fn fullscreen() -> Self {
         Self {
             fullscreen: true,
-            height: None,
-            height_percentage: None,
-            show_help_text: true,
-            show_loading_indicator: true,
-            loading_message: None,
-            ready_message: None,
-            preview_rules: Vec::new(),
-            preview_auto: false,
+            ..Self::default()
         }
     }
 
@@ -176,6 +471,35 @@ impl TuiConfig {
             terminal_height
         }
     }
+
+    /// Height for showing `rows` list rows: with [`auto_height`](Self::auto_height)
+    /// just enough for them, the prompt and the info and help lines (at least
+    /// one list row), capped at [`calculate_height`](Self::calculate_height)
+    pub fn calculate_height_for(&self, terminal_height: u16, rows: usize) -> u16 {
+        let max = self.calculate_height(terminal_height);
+        if self.fullscreen || !self.auto_height {
+            return max;
+        }
+        let chrome = 1 + u16::from(self.show_help_text) + u16::from(self.info == Info::Default);
+        let rows = u16::try_from(rows.max(1)).unwrap_or(u16::MAX);
+        chrome.saturating_add(rows).min(max)
+    }
+}
+
+/// What an interactive session ended with
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TuiOutput {
+    /// Selected items (index, content); empty if none were selected
+    pub selected: Vec<(usize, String)>,
+    /// Name of the [`TuiConfig::expect`] key that accepted the selection,
+    /// or `None` if it was accepted with Enter or the session was cancelled
+    pub key: Option<String>,
+    /// Query typed when the session ended
+    pub query: String,
+    /// Whether the session was accepted rather than cancelled
+    pub accepted: bool,
+    /// Name of the [`TuiConfig::tabs`] tab the session ended on, if any
+    pub tab: Option<String>,
 }
 
 /// Run an async interactive TUI for fuzzy finding through an mpsc receiver of items.
@@ -192,41 +516,78 @@ pub async fn run_tui_with_config(
     multi_select: bool,
     config: TuiConfig,
 ) -> Result<Vec<(usize, String)>, Box<dyn std::error::Error + Send + Sync>> {
-    run_interactive_tui(items_receiver, multi_select, config).await
+    let output = guarded(run_interactive_tui(
+        Incoming::Items(items_receiver),
+        multi_select,
+        config,
+    ))
+    .await?;
+    Ok(output.selected)
 }
 
-/// Run the async interactive TUI
-async fn run_interactive_tui(
-    mut items_receiver: mpsc::Receiver<String>,
+/// Like [`run_tui_with_config`], also reporting the query, the accepting key
+/// and whether the session was cancelled.
+pub async fn run_tui_with_output(
+    items_receiver: mpsc::Receiver<String>,
     multi_select: bool,
     config: TuiConfig,
-) -> Result<Vec<(usize, String)>, Box<dyn std::error::Error + Send + Sync>> {
-    let mut fuzzy_finder = FuzzyFinder::new(multi_select);
-    let mut stdout = io::stderr();
+) -> Result<TuiOutput, Box<dyn std::error::Error + Send + Sync>> {
+    guarded(run_interactive_tui(
+        Incoming::Items(items_receiver),
+        multi_select,
+        config,
+    ))
+    .await
+}
 
-    // Enable raw mode and hide cursor
-    enable_raw_mode()?;
-    execute!(stdout, Hide)?;
-
-    let mut fullscreen = config.fullscreen;
-    let mut original_cursor = (0, 0);
-
-    if !fullscreen {
-        // Try to get cursor position. If it fails (e.g. stdout is not a TTY),
-        // try to fallback to stderr query or force fullscreen.
-        match position() {
-            Ok(pos) => original_cursor = pos,
-            Err(_) => {
-                match layout::get_cursor_position_from_stderr() {
-                    Ok(pos) => original_cursor = pos,
-                    Err(_) => {
-                        // Could not determine cursor position, fallback to fullscreen
-                        fullscreen = true;
-                    }
-                }
-            }
+/// Run a TUI `session`, giving the terminal back if it fails or panics
+/// partway through. Built with `panic = "abort"` a panic never gets back
+/// here; the panic hook restores the terminal instead.
+async fn guarded<T, E>(session: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+    install_panic_hook();
+    let mut session = std::pin::pin!(session);
+    let session = futures::future::poll_fn(|cx| in_session(|| session.as_mut().poll(cx)));
+    match AssertUnwindSafe(session).catch_unwind().await {
+        Ok(Ok(output)) => Ok(output),
+        Ok(Err(error)) => {
+            restore_terminal();
+            Err(error)
+        }
+        Err(panic) => {
+            // Normally done by the hook already, unless it was replaced
+            restore_terminal();
+            std::panic::resume_unwind(panic)
+        }
+    }
+}
+
+/// Run the async interactive TUI on items or commands from `incoming`
+async fn run_interactive_tui(
+    mut incoming: Incoming,
+    multi_select: bool,
+    mut config: TuiConfig,
+) -> Result<TuiOutput, Box<dyn std::error::Error + Send + Sync>> {
+    let mut fuzzy_finder = create_finder(multi_select, &mut config).await;
+    // Per-item indicators, keyed by item text
+    let mut item_indicators: HashMap<String, ItemIndicator> = HashMap::new();
+    let mut global_status = GlobalStatus::Loading(None);
+    if config.select_one || config.exit_zero {
+        // Decide on the whole input before touching the terminal
+        let mut items = Vec::new();
+        while let Some(command) = incoming.recv().await {
+            items.extend(apply_command(
+                command,
+                &mut item_indicators,
+                &mut global_status,
+            ));
+        }
+        fuzzy_finder.add_items(items).await;
+        while fuzzy_finder.filter_next_chunk().await {}
+        if let Some(output) = output_without_tui(&fuzzy_finder, &config) {
+            return Ok(output);
         }
     }
+    let mut stdout = io::stderr();
 
     let (_term_width, term_height) = match size() {
         Ok(s) => s,
@@ -235,62 +596,78 @@ async fn run_interactive_tui(
             layout::get_terminal_size_from_stderr().unwrap_or((80, 24))
         }
     };
-    let tui_height = config.calculate_height(term_height);
-
-    if fullscreen {
-        execute!(
-            &mut stdout,
-            crossterm::terminal::EnterAlternateScreen,
-            Clear(ClearType::All)
-        )?;
-    } else {
-        // If not enough space below, scroll the terminal down
-        if original_cursor.1 + tui_height > term_height {
-            let needed = (original_cursor.1 + tui_height).saturating_sub(term_height);
-            for _ in 0..needed {
-                writeln!(stdout)?;
-            }
-            stdout.flush()?;
-            // After scrolling, we should draw at the bottom of the terminal
-            original_cursor = (0, term_height.saturating_sub(tui_height));
-        }
-        // Always move to column 0 at the current line
-        execute!(&mut stdout, MoveTo(0, original_cursor.1))?;
-    }
+    let tui_height = config.calculate_height_for(term_height, 0);
+    let mut viewport = Viewport::enter(&mut stdout, config.fullscreen, tui_height, config.mouse)?;
 
     let mut selected_items = Vec::new();
+    let mut accepted_key = None;
+    let mut accepted = false;
     let mut needs_redraw = true;
     let mut items_buffer = Vec::new();
     let mut receiver_exhausted = false;
     let mut scroll_offset = 0;
+    // Where the list and preview were last drawn, for mouse events
+    let mut screen_layout = ScreenLayout::default();
+    let mut keys = KeyTranslator::new(config.keymap, multi_select);
+    let markers = if config.accessible {
+        Markers::Text {
+            checkboxes: multi_select,
+        }
+    } else {
+        Markers::Glyph
+    };
+    let mut row_scroll = RowScroll::default();
+    let mut jump: Option<Jump> = None;
+    // The help overlay, shown until the next key
+    let mut help_shown = false;
+    let mut show_scores = config.debug_scores;
+    // The detail pane of the current item, toggled with F2
+    let mut details_shown = false;
+    // The tab shown, and the others once opened
+    let mut tab = 0;
+    let mut other_tabs: Vec<Option<TabState>> = config.tabs.iter().map(|_| None).collect();
+    let mut history = config
+        .history
+        .as_deref()
+        .map(|path| QueryHistory::load(path).unwrap_or_else(|_| QueryHistory::new(path)));
 
     // Preview state
     let mut preview_state = PreviewState::new();
     if config.preview_auto && !config.preview_rules.is_empty() {
         preview_state.visible = true;
     }
-    let (preview_tx, preview_rx) = std::sync::mpsc::channel::<PreviewResult>();
+    let (preview_tx, mut preview_rx) = mpsc::unbounded_channel::<PreviewResult>();
     let mut preview_task: Option<tokio::task::JoinHandle<()>> = None;
 
     // Spinner animation state
     let mut spinner_frame: usize = 0;
     let mut last_spinner_update = Instant::now();
+    let mut last_frame: Option<Instant> = None;
     let spinner_interval = std::time::Duration::from_millis(80);
 
     // Create screen buffer for double-buffered rendering
     let (term_width, _) = size()?;
     let mut screen_buffer = ScreenBuffer::new(term_width, tui_height);
+    screen_buffer.set_color_depth(config.color_depth);
+
+    let mut terminal = TerminalInput::new();
+    let mut interrupts = config.catch_interrupts.then(interrupt_signal).flatten();
+    // Taken off the channel while waiting, ahead of the next batch
+    let mut received = None;
 
     loop {
-        // Process new items from mpsc receiver
+        // Process new items and commands from the channel
         if !receiver_exhausted {
             let mut batch_count = 0;
             const MAX_BATCH_SIZE: usize = 1000;
 
             loop {
-                match items_receiver.try_recv() {
-                    Ok(item) => {
-                        items_buffer.push(item);
+                match received.take().map_or_else(|| incoming.try_recv(), Ok) {
+                    Ok(command) => {
+                        match apply_command(command, &mut item_indicators, &mut global_status) {
+                            Some(item) => items_buffer.push(item),
+                            None => needs_redraw = true,
+                        }
                         batch_count += 1;
                         if batch_count >= MAX_BATCH_SIZE {
                             break;
@@ -301,6 +678,7 @@ async fn run_interactive_tui(
                     }
                     Err(mpsc::error::TryRecvError::Disconnected) => {
                         receiver_exhausted = true;
+                        global_status = GlobalStatus::Ready(config.ready_message.clone());
                         needs_redraw = true; // Redraw to show ready state
                         break;
                     }
@@ -313,15 +691,7 @@ async fn run_interactive_tui(
             }
         }
 
-        // Drain preview results
-        if let Ok(result) = preview_rx.try_recv() {
-            preview_state.apply_result(result);
-            needs_redraw = true;
-        }
-
         let (term_width, term_height) = size()?;
-        let tui_height = config.calculate_height(term_height);
-
         // Determine layout
         let preview_active =
             preview_state.visible && !config.preview_rules.is_empty() && term_width >= 40;
@@ -336,6 +706,26 @@ async fn run_interactive_tui(
             0
         };
         let separator_col = left_width;
+        // Grouped results keep to one column, under their headers
+        let (per_row, cell_width) = if fuzzy_finder.get_similar_groups().is_empty() {
+            config
+                .columns
+                .grid(left_width, || widest_item(&fuzzy_finder))
+        } else {
+            (1, left_width)
+        };
+        // An auto height TUI fits its results, but keeps its full height
+        // for the preview
+        let wanted_rows = if preview_active {
+            usize::MAX
+        } else {
+            fuzzy_finder.get_filtered_items().len().div_ceil(per_row)
+                + fuzzy_finder.get_similar_groups().len()
+        };
+        let tui_height = config.calculate_height_for(term_height, wanted_rows);
+        if viewport.resize(&mut stdout, tui_height, term_height)? {
+            needs_redraw = true;
+        }
 
         // Always reserve 1 line for prompt, 1 for result if possible, 1 for instructions
         let available_height = if tui_height > 2 {
@@ -349,95 +739,383 @@ async fn run_interactive_tui(
         } else {
             0 // Only room for prompt
         };
-
-        // Update scroll offset to keep cursor in view
-        let cursor_pos = fuzzy_finder.get_cursor_position();
-        if cursor_pos < scroll_offset {
-            scroll_offset = cursor_pos;
-        } else if cursor_pos >= scroll_offset + available_height as usize {
-            scroll_offset = cursor_pos - available_height as usize + 1;
+        // The info line takes a row from the list when it has more than one
+        let info_line = config.info == Info::Default && available_height > 1;
+        let available_height = available_height - u16::from(info_line);
+
+        // Update scroll offset to keep cursor in view, a whole row at a time
+        let cursor_row = fuzzy_finder.get_cursor_position() / per_row;
+        let first_row = scroll_offset / per_row;
+        if cursor_row < first_row {
+            scroll_offset = cursor_row * per_row;
+        } else if cursor_row >= first_row + available_height as usize {
+            scroll_offset = (cursor_row + 1 - available_height as usize) * per_row;
+        } else {
+            scroll_offset = first_row * per_row;
         }
 
         // Ensure scroll offset is valid (e.g. if list shrank)
         let total_items = fuzzy_finder.get_filtered_items().len();
         if scroll_offset > total_items {
-            scroll_offset = total_items.saturating_sub(available_height as usize);
+            let total_rows = total_items.div_ceil(per_row);
+            scroll_offset = total_rows.saturating_sub(available_height as usize) * per_row;
         }
 
         // Only redraw if needed (when query changes or cursor moves)
-        if needs_redraw {
+        if needs_redraw && until_next_frame(last_frame).is_zero() {
             // Resize buffer if terminal size changed
             let (term_width, _) = size()?;
             screen_buffer.resize(term_width, tui_height);
             screen_buffer.clear();
 
-            // Draw search prompt with optional status indicator (row 0 in buffer)
+            let tui_rows =
+                TuiRows::new(config.layout, tui_height, config.show_help_text, info_line);
+
+            // Draw search prompt with optional status indicator
             let mut col: u16 = 0;
-            col += screen_buffer.put_str(col, 0, "> ", Some(Color::Cyan), None, false, false);
-            col +=
-                screen_buffer.put_str(col, 0, fuzzy_finder.get_query(), None, None, false, false);
+            if keys.vim_mode() == Some(VimMode::Normal) {
+                col += screen_buffer.put_str(
+                    col,
+                    tui_rows.prompt,
+                    "[N] ",
+                    Some(config.theme.prompt),
+                    None,
+                    false,
+                    false,
+                );
+            }
+            col += screen_buffer.put_str(
+                col,
+                tui_rows.prompt,
+                &config.prompt,
+                Some(config.theme.prompt),
+                None,
+                false,
+                false,
+            );
+            col += draw_query(
+                &mut screen_buffer,
+                col,
+                tui_rows.prompt,
+                fuzzy_finder.get_query(),
+                fuzzy_finder.get_query_cursor(),
+            );
+            // While items stream in, the spinner sits next to the counter
+            // (or on the prompt line when the counter is hidden)
+            let loading = (config.show_loading_indicator
+                && matches!(global_status, GlobalStatus::Loading(_)))
+            .then(|| {
+                if config.accessible {
+                    STILL_LOADING
+                } else {
+                    SPINNER_FRAMES[spinner_frame % SPINNER_FRAMES.len()]
+                }
+            });
+            let counter_spinner = loading.filter(|_| config.info != Info::Hidden);
+            if config.info == Info::Inline {
+                col += draw_info(
+                    &mut screen_buffer,
+                    col,
+                    tui_rows.prompt,
+                    &fuzzy_finder,
+                    counter_spinner,
+                    &config.theme,
+                );
+            }
+            if let Some(row) = tui_rows.info {
+                draw_info(
+                    &mut screen_buffer,
+                    0,
+                    row,
+                    &fuzzy_finder,
+                    counter_spinner,
+                    &config.theme,
+                );
+            }
 
             // Draw status indicator (spinner or ready message)
             if config.show_loading_indicator {
-                col += screen_buffer.put_str(col, 0, " ", None, None, false, false);
-                if !receiver_exhausted {
-                    // Show spinner
-                    let frame = SPINNER_FRAMES[spinner_frame % SPINNER_FRAMES.len()];
-                    col += screen_buffer.put_str(
-                        col,
-                        0,
-                        &frame.to_string(),
-                        Some(Color::Yellow),
-                        None,
-                        false,
-                        false,
-                    );
-                    if let Some(ref msg) = config.loading_message {
-                        col += screen_buffer.put_str(col, 0, " ", None, None, false, false);
+                col += screen_buffer.put_str(col, tui_rows.prompt, " ", None, None, false, false);
+                match &global_status {
+                    GlobalStatus::Loading(msg) => {
+                        if let Some(frame) = loading.filter(|_| counter_spinner.is_none()) {
+                            col += screen_buffer.put_str(
+                                col,
+                                tui_rows.prompt,
+                                &frame.to_string(),
+                                Some(config.theme.spinner),
+                                None,
+                                false,
+                                false,
+                            );
+                        }
+                        if let Some(msg) = msg.as_ref().or(config.loading_message.as_ref()) {
+                            col += screen_buffer.put_str(
+                                col,
+                                tui_rows.prompt,
+                                " ",
+                                None,
+                                None,
+                                false,
+                                false,
+                            );
+                            screen_buffer.put_str(
+                                col,
+                                tui_rows.prompt,
+                                msg,
+                                Some(config.theme.dim),
+                                None,
+                                false,
+                                false,
+                            );
+                        }
+                    }
+                    GlobalStatus::Ready(Some(msg)) => {
                         screen_buffer.put_str(
                             col,
-                            0,
+                            tui_rows.prompt,
                             msg,
-                            Some(Color::DarkGrey),
+                            Some(config.theme.success),
                             None,
                             false,
                             false,
                         );
                     }
-                } else if let Some(ref msg) = config.ready_message {
-                    // Show ready message
-                    screen_buffer.put_str(col, 0, msg, Some(Color::Green), None, false, false);
+                    GlobalStatus::Custom(text) => {
+                        screen_buffer.put_str(col, tui_rows.prompt, text, None, None, false, false);
+                    }
+                    GlobalStatus::Ready(None) | GlobalStatus::Hidden => {}
+                }
+            }
+
+            if config.tabs.len() > 1 {
+                draw_tab_bar(
+                    &mut screen_buffer,
+                    tui_rows.prompt,
+                    &config.tabs,
+                    tab,
+                    col,
+                    left_width,
+                    &config.theme,
+                );
+            }
+
+            screen_layout = ScreenLayout {
+                list_top: viewport.top() + tui_rows.list(0),
+                upward: config.layout == Layout::BottomUp,
+                rows: Vec::new(),
+                columns: per_row,
+                cell_width,
+                len: fuzzy_finder.get_filtered_items().len(),
+                preview_col: preview_active.then_some(separator_col),
+            };
+
+            // Suggest a corrected query when nothing matches
+            if tui_height >= 2 && available_height > 0 {
+                if let Some(suggestion) = fuzzy_finder.suggestion() {
+                    draw_suggestion(
+                        &mut screen_buffer,
+                        tui_rows.list(0),
+                        &suggestion,
+                        left_width,
+                        &config.theme,
+                    );
                 }
             }
 
             // Draw items (confined to left pane when preview is active)
             if tui_height >= 2 && available_height > 0 {
                 let filtered_items = fuzzy_finder.get_filtered_items();
-                let visible_items = filtered_items
+                // Wrapped items keep clear of where the scrollbar may go
+                let wrap_width = (config.wrap && per_row == 1).then(|| {
+                    let marker = str_width(markers.marker(false, false)) as u16;
+                    left_width.saturating_sub(marker + u16::from(config.scrollbar)) as usize
+                });
+                let wrapped = |index: usize| {
+                    let width = wrap_width.unwrap_or(usize::MAX);
+                    let (item, _) = displayed_item(&fuzzy_finder, index, &config);
+                    wrapped_lines(&item, width, &config.theme).len()
+                };
+                let rows = list_rows(
+                    &fuzzy_finder,
+                    &mut scroll_offset,
+                    available_height as usize,
+                    per_row,
+                    wrap_width.map(|_| &wrapped as &dyn Fn(usize) -> usize),
+                );
+                screen_layout.rows = rows
                     .iter()
-                    .skip(scroll_offset)
-                    .take(available_height as usize);
-
-                for (i, item) in visible_items.enumerate() {
-                    let absolute_index = scroll_offset + i;
-                    let row = (i + 1) as u16; // Row in buffer (0 is prompt)
+                    .map(|list_row| match list_row {
+                        ListRow::Header(_) => None,
+                        ListRow::Item(index) | ListRow::Wrapped(index, _) => Some(*index),
+                    })
+                    .collect();
+                // Jump labels, handed out in the order items are drawn
+                let labels = match &jump {
+                    Some(_) => {
+                        jump_labels(&config.jump_labels, screen_layout.visible_items().len())
+                    }
+                    None => Vec::new(),
+                };
+                let mut labels = labels.into_iter();
+                // The scrollbar takes the list's last column when it
+                // overflows. Wrapped items are counted as lines at the rate
+                // of those on screen.
+                let (total_rows, first_row) = if wrap_width.is_some() {
+                    let shown = screen_layout.visible_items().len().max(1);
+                    let rate = |items: usize| items * rows.len() / shown;
+                    (rate(filtered_items.len()), rate(scroll_offset))
+                } else {
+                    let rows = filtered_items.len().div_ceil(per_row)
+                        + fuzzy_finder.get_similar_groups().len();
+                    (rows, scroll_offset / per_row)
+                };
+                let thumb = config
+                    .scrollbar
+                    .then(|| scrollbar_thumb(total_rows, available_height as usize, first_row))
+                    .flatten();
+                let list_width = match wrap_width {
+                    Some(_) => left_width - u16::from(config.scrollbar),
+                    None => left_width - u16::from(thumb.is_some()),
+                };
 
-                    let is_cursor = absolute_index == fuzzy_finder.get_cursor_position();
-                    let original_index = fuzzy_finder.get_original_index(absolute_index);
-                    let is_selected = if let Some(idx) = original_index {
-                        fuzzy_finder.is_selected(idx)
-                    } else {
-                        false
+                for (i, list_row) in rows.into_iter().enumerate() {
+                    let row = tui_rows.list(i);
+                    let first = match list_row {
+                        ListRow::Header(group) => {
+                            draw_group_header(
+                                &mut screen_buffer,
+                                row,
+                                &group,
+                                list_width,
+                                &config.theme,
+                            );
+                            continue;
+                        }
+                        ListRow::Wrapped(index, line) => {
+                            let (item, positions) = displayed_item(&fuzzy_finder, index, &config);
+                            draw_wrapped_item_line(
+                                &mut screen_buffer,
+                                row,
+                                &item,
+                                line,
+                                index == fuzzy_finder.get_cursor_position(),
+                                false,
+                                markers,
+                                positions.as_deref(),
+                                list_width,
+                                &config.theme,
+                            );
+                            continue;
+                        }
+                        ListRow::Item(index) => index,
                     };
-
-                    draw_item_to_buffer_left(
+                    let cells = first..(first + per_row).min(filtered_items.len());
+                    for absolute_index in cells {
+                        let start_col = (absolute_index - first) as u16 * cell_width;
+                        let end_col = (start_col + cell_width).min(list_width);
+                        let (item, positions) =
+                            displayed_item(&fuzzy_finder, absolute_index, &config);
+
+                        let is_cursor = absolute_index == fuzzy_finder.get_cursor_position();
+                        let original_index = fuzzy_finder.get_original_index(absolute_index);
+                        let is_selected = if let Some(idx) = original_index {
+                            fuzzy_finder.is_selected(idx)
+                        } else {
+                            false
+                        };
+                        let mut fixed = 0;
+                        let hscroll = if is_cursor {
+                            row_scroll.delta_for(absolute_index, fuzzy_finder.get_query())
+                        } else {
+                            &mut fixed
+                        };
+
+                        let indicator = item_indicators.get(&filtered_items[absolute_index]);
+                        if let Some(indicator) = indicator.filter(|_| wrap_width.is_none()) {
+                            draw_item_with_indicator_to_buffer_left(
+                                &mut screen_buffer,
+                                row,
+                                &item,
+                                is_cursor,
+                                is_selected,
+                                positions.as_deref(),
+                                Some(indicator),
+                                spinner_frame,
+                                start_col,
+                                end_col,
+                                &config.theme,
+                                config.keep_right,
+                                hscroll,
+                            );
+                        } else if wrap_width.is_some() {
+                            draw_wrapped_item_line(
+                                &mut screen_buffer,
+                                row,
+                                &item,
+                                0,
+                                is_cursor,
+                                is_selected,
+                                markers,
+                                positions.as_deref(),
+                                end_col,
+                                &config.theme,
+                            );
+                        } else {
+                            draw_item_to_buffer_left(
+                                &mut screen_buffer,
+                                row,
+                                &item,
+                                is_cursor,
+                                is_selected,
+                                markers,
+                                positions.as_deref(),
+                                start_col,
+                                end_col,
+                                &config.theme,
+                                config.keep_right,
+                                hscroll,
+                            );
+                        }
+                        let label_end = draw_similar_count(
+                            &mut screen_buffer,
+                            row,
+                            fuzzy_finder.get_similar_count(absolute_index),
+                            is_cursor,
+                            end_col,
+                            &config.theme,
+                        );
+                        if show_scores {
+                            draw_score_label(
+                                &mut screen_buffer,
+                                row,
+                                score_label(&fuzzy_finder, absolute_index).as_deref(),
+                                is_cursor,
+                                start_col,
+                                label_end,
+                                &config.theme,
+                            );
+                        }
+                        if let (Some(jump), Some(label)) = (&jump, labels.next()) {
+                            if jump.shows(&label) {
+                                draw_jump_label(
+                                    &mut screen_buffer,
+                                    row,
+                                    start_col,
+                                    &label,
+                                    &config.theme,
+                                );
+                            }
+                        }
+                    }
+                }
+                if let Some(thumb) = thumb {
+                    draw_scrollbar(
                         &mut screen_buffer,
-                        row,
-                        item,
-                        is_cursor,
-                        is_selected,
-                        fuzzy_finder.get_match_positions(absolute_index),
-                        left_width,
+                        &tui_rows,
+                        list_width,
+                        thumb,
+                        &config.theme,
                     );
                 }
             }
@@ -447,7 +1125,7 @@ async fn run_interactive_tui(
                     0,
                     1,
                     "Terminal too small. Please resize to continue...",
-                    Some(Color::Yellow),
+                    Some(config.theme.warning),
                     None,
                     false,
                     false,
@@ -458,12 +1136,13 @@ async fn run_interactive_tui(
             if preview_active {
                 // Vertical separator (heavy when preview is focused)
                 let sep_char = if preview_state.focused { '┃' } else { '│' };
-                for row in 0..tui_height.saturating_sub(1) {
+                let pane_rows = tui_height.saturating_sub(1);
+                for row in tui_rows.pane_top..tui_rows.pane_top + pane_rows {
                     screen_buffer.put_char(
                         separator_col,
                         row,
                         sep_char,
-                        Some(Color::DarkGrey),
+                        Some(config.theme.border),
                         None,
                         preview_state.focused,
                         false,
@@ -475,12 +1154,13 @@ async fn run_interactive_tui(
                 } else {
                     tui_height
                 };
+                preview_state.height = preview_height as usize;
                 render_preview_to_buffer(
                     &mut screen_buffer,
                     &preview_state.lines,
                     preview_state.scroll,
                     separator_col + 1,
-                    0,
+                    tui_rows.pane_top,
                     right_width,
                     preview_height,
                     preview_state.loading,
@@ -490,38 +1170,50 @@ async fn run_interactive_tui(
 
             // Draw instructions (always at the bottom of the TUI area)
             if config.show_help_text {
-                let instructions_row = tui_height.saturating_sub(1);
-                let instructions = if preview_active {
-                    if multi_select {
-                        "Tab/Space: Toggle | Enter: Confirm | Ctrl+P: Preview | →/←: Focus | Esc: Exit"
-                    } else {
-                        "↑/↓: Navigate | Enter: Select | Ctrl+P: Preview | →/←: Focus | Esc: Exit"
-                    }
-                } else if multi_select {
-                    "Tab/Space: Toggle | Enter: Confirm | Esc/Ctrl+C/Ctrl+Q: Exit"
-                } else {
-                    "↑/↓: Navigate | Enter: Select | Esc/Ctrl+C/Ctrl+Q: Exit"
-                };
+                let instructions_row = tui_rows.help;
+                let instructions = keys.hints(preview_active, preview_state.focused);
                 screen_buffer.put_str(
                     0,
                     instructions_row,
-                    instructions,
-                    Some(Color::DarkGrey),
+                    &instructions,
+                    Some(config.theme.dim),
                     None,
                     false,
                     false,
                 );
             }
 
-            // Render buffer to terminal in a single write
-            let rendered = if fullscreen {
-                screen_buffer.render_fullscreen()
-            } else {
-                screen_buffer.render(original_cursor.1)
-            };
+            let details = Details::of_current(&fuzzy_finder).filter(|_| details_shown);
+            if let Some(details) = details.filter(|_| available_height > 0) {
+                let near = tui_rows.list(0);
+                let far = tui_rows.list(available_height as usize - 1);
+                // At the end of the list away from the prompt
+                draw_details(
+                    &mut screen_buffer,
+                    &details,
+                    0,
+                    left_width,
+                    near.min(far)..near.max(far) + 1,
+                    far < near,
+                    &config.theme,
+                );
+            }
+
+            if help_shown {
+                let mut sections = help_sections(&keys, &config.bindings, &config.expect);
+                if config.tabs.len() > 1 {
+                    let entry = ("F3".to_string(), "Next tab".to_string());
+                    sections[0].entries.push(entry);
+                }
+                draw_help_overlay(&mut screen_buffer, &sections, &config.theme);
+            }
+
+            // Render what changed to the terminal in a single write
+            let rendered = screen_buffer.render_diff(viewport.top());
             write!(stdout, "{}", rendered)?;
             stdout.flush()?;
             needs_redraw = false;
+            last_frame = Some(Instant::now());
 
             // Trigger preview on initial load / redraw
             maybe_update_preview(
@@ -533,18 +1225,214 @@ async fn run_interactive_tui(
             );
         }
 
-        // Handle input with timeout to allow stream processing
-        if event::poll(std::time::Duration::from_millis(50))? {
-            if let Event::Key(key_event) = event::read()? {
-                let prev_cursor = fuzzy_finder.get_cursor_position();
-                let prev_visible = preview_state.visible;
-                match events::handle_async_key_event(
-                    &key_event,
+        // Finish a filter pass that was cancelled by typing, once input is idle
+        if fuzzy_finder.is_stale() && !terminal.has_input() {
+            let _watch = terminal.watch(&fuzzy_finder);
+            fuzzy_finder.update_filter().await;
+            needs_redraw = true;
+        } else if fuzzy_finder.is_filtering() && !terminal.has_input() {
+            // Score the next chunk of a large query while input is idle;
+            // a new keystroke starts over with the newer query
+            let _watch = terminal.watch(&fuzzy_finder);
+            fuzzy_finder.filter_next_chunk().await;
+            needs_redraw = true;
+        }
+
+        // Wait for input, streamed items, a preview or the next frame
+        let wakeup = next_wakeup(
+            &mut terminal,
+            (!receiver_exhausted).then_some(&mut incoming),
+            &mut preview_rx,
+            interrupts.as_mut(),
+            input_timeout(&fuzzy_finder, needs_redraw, last_frame),
+        )
+        .await?;
+        let input = match wakeup {
+            Wakeup::Input(event) => Some(event),
+            Wakeup::Received(item) => {
+                received = item;
+                None
+            }
+            Wakeup::Preview(result) => {
+                preview_state.apply_result(result);
+                needs_redraw = true;
+                None
+            }
+            Wakeup::Tick => None,
+            Wakeup::Interrupt => break,
+        };
+        if let Some(event) = input {
+            let prev_cursor = fuzzy_finder.get_cursor_position();
+            let prev_visible = preview_state.visible;
+            let action = match event {
+                Event::Key(key_event) if is_abort_key(&key_event, &config) => Some(Action::Exit),
+                Event::Key(_) if help_shown => {
+                    help_shown = false;
+                    Some(Action::Continue)
+                }
+                Event::Key(key_event) if jump.is_some() => Some(
+                    handle_jump_key(
+                        &mut jump,
+                        &key_event,
+                        &mut fuzzy_finder,
+                        &mut preview_state,
+                        &screen_layout,
+                        &config,
+                    )
+                    .await,
+                ),
+                Event::Key(key_event)
+                    if config.bindings.iter().any(|b| b.key.matches(&key_event)) =>
+                {
+                    let binding = config.bindings.iter().find(|b| b.key.matches(&key_event));
+                    if let Some(binding) = binding
+                        .filter(|b| matches!(b.action, BindAction::Jump | BindAction::JumpAccept))
+                    {
+                        jump = Some(Jump::new(binding.action == BindAction::JumpAccept));
+                    }
+                    if binding.is_some_and(|b| b.action == BindAction::ToggleSort) {
+                        fuzzy_finder.set_sort(!fuzzy_finder.is_sorted()).await;
+                    }
+                    if binding.is_some_and(|b| b.action == BindAction::ToggleScores) {
+                        show_scores = !show_scores;
+                    }
+                    let reload = config
+                        .tabs
+                        .get(tab)
+                        .map(|tab| &tab.source)
+                        .or(config.reload.as_ref())
+                        .filter(|_| binding.is_some_and(|b| b.action == BindAction::Reload));
+                    if let Some(source) = reload {
+                        // Dropping the old receiver stops its sender
+                        incoming = Incoming::Items(source.open());
+                        receiver_exhausted = false;
+                        global_status = GlobalStatus::Loading(None);
+                        items_buffer.clear();
+                        fuzzy_finder.clear_items().await;
+                    }
+                    if let Some(command) = binding.and_then(|b| bound_command(b, &fuzzy_finder)) {
+                        // The command reads the terminal itself, so stop
+                        // listening to it meanwhile
+                        drop(terminal);
+                        run_bound_command(&command, &mut viewport)?;
+                        // Ctrl-C while it ran was meant for the command
+                        drain_interrupts(interrupts.as_mut());
+                        terminal = TerminalInput::new();
+                        screen_buffer.invalidate();
+                    }
+                    Some(Action::Continue)
+                }
+                // Accept like Enter, remembering which key did it
+                Event::Key(key_event) if config.expect.iter().any(|k| k.matches(&key_event)) => {
+                    let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+                    let action = events::handle_async_key_event(
+                        &enter,
+                        &mut fuzzy_finder,
+                        &mut preview_state,
+                        &screen_layout,
+                    )
+                    .await;
+                    let action = accept_unmatched_query(action, &enter, &fuzzy_finder, &config);
+                    if matches!(action, Action::Select(_)) {
+                        accepted_key = config
+                            .expect
+                            .iter()
+                            .find(|k| k.matches(&key_event))
+                            .map(|k| k.name.clone());
+                    }
+                    Some(action)
+                }
+                Event::Key(key_event) => match keys.translate(&key_event) {
+                    Some(key_event) if key_event.code == KeyCode::F(1) => {
+                        help_shown = true;
+                        Some(Action::Continue)
+                    }
+                    Some(key_event) if key_event.code == KeyCode::F(2) => {
+                        details_shown = !details_shown;
+                        Some(Action::Continue)
+                    }
+                    Some(key_event) if key_event.code == KeyCode::F(3) && config.tabs.len() > 1 => {
+                        let next = (tab + 1) % config.tabs.len();
+                        let shown = match other_tabs[next].take() {
+                            Some(state) => state,
+                            None => TabState {
+                                finder: create_finder(multi_select, &mut config).await,
+                                receiver: Incoming::Items(config.tabs[next].source.open()),
+                                exhausted: false,
+                                scroll_offset: 0,
+                            },
+                        };
+                        // An item taken off the channel belongs to this tab
+                        let item = received.take().and_then(|command| {
+                            apply_command(command, &mut item_indicators, &mut global_status)
+                        });
+                        if let Some(item) = item {
+                            fuzzy_finder.add_items(vec![item]).await;
+                        }
+                        other_tabs[tab] = Some(TabState {
+                            finder: mem::replace(&mut fuzzy_finder, shown.finder),
+                            receiver: mem::replace(&mut incoming, shown.receiver),
+                            exhausted: mem::replace(&mut receiver_exhausted, shown.exhausted),
+                            scroll_offset: mem::replace(&mut scroll_offset, shown.scroll_offset),
+                        });
+                        global_status = if receiver_exhausted {
+                            GlobalStatus::Ready(config.ready_message.clone())
+                        } else {
+                            GlobalStatus::Loading(None)
+                        };
+                        tab = next;
+                        maybe_update_preview(
+                            &fuzzy_finder,
+                            &mut preview_state,
+                            &config,
+                            &preview_tx,
+                            &mut preview_task,
+                        );
+                        Some(Action::Continue)
+                    }
+                    Some(key_event) if row_scroll.handle_key(&key_event) => Some(Action::Continue),
+                    Some(key_event) => {
+                        let _watch = terminal.watch(&fuzzy_finder);
+                        let recalled = history
+                            .as_mut()
+                            .and_then(|h| h.recall(&key_event, fuzzy_finder.get_query()));
+                        if let Some(query) = recalled {
+                            fuzzy_finder.set_query(query).await;
+                            Some(Action::Continue)
+                        } else {
+                            let action = events::handle_async_key_event(
+                                &key_event,
+                                &mut fuzzy_finder,
+                                &mut preview_state,
+                                &screen_layout,
+                            )
+                            .await;
+                            Some(accept_unmatched_query(
+                                action,
+                                &key_event,
+                                &fuzzy_finder,
+                                &config,
+                            ))
+                        }
+                    }
+                    // Consumed by the keymap, e.g. a Vim mode switch
+                    None => Some(Action::Continue),
+                },
+                Event::Mouse(mouse_event) => Some(events::handle_mouse_event(
+                    &mouse_event,
                     &mut fuzzy_finder,
                     &mut preview_state,
-                )
-                .await
-                {
+                    &screen_layout,
+                )),
+                Event::Paste(text) => {
+                    let _watch = terminal.watch(&fuzzy_finder);
+                    fuzzy_finder.insert_query_str(&text).await;
+                    Some(Action::Continue)
+                }
+                _ => None,
+            };
+            if let Some(action) = action {
+                match action {
                     Action::Continue => {
                         needs_redraw = true;
                         // Trigger preview update on cursor move or visibility change
@@ -563,556 +1451,579 @@ async fn run_interactive_tui(
                     }
                     Action::Exit => break,
                     Action::Select(items) => {
+                        if let Some(history) = history.as_mut() {
+                            // History is a convenience; failing to save it
+                            // shouldn't lose the selection
+                            let _ = history.record(fuzzy_finder.get_query());
+                        }
                         selected_items = items;
+                        accepted = true;
                         break;
                     }
                 }
             }
         }
 
-        // Update spinner animation if still loading
-        if config.show_loading_indicator
-            && !receiver_exhausted
-            && last_spinner_update.elapsed() >= spinner_interval
-        {
+        // Update spinner animation while anything spins
+        let spinning = (config.show_loading_indicator
+            && matches!(global_status, GlobalStatus::Loading(_)))
+            || item_indicators
+                .values()
+                .any(|i| matches!(i, ItemIndicator::Spinner));
+        if spinning && !config.accessible && last_spinner_update.elapsed() >= spinner_interval {
             spinner_frame = (spinner_frame + 1) % SPINNER_FRAMES.len();
             last_spinner_update = Instant::now();
             needs_redraw = true;
         }
     }
 
-    // Restore terminal
-    if fullscreen {
-        execute!(&mut stdout, crossterm::terminal::LeaveAlternateScreen)?;
-        execute!(&mut stdout, Show)?;
-    } else {
-        for i in 0..config.calculate_height(size()?.1) {
-            execute!(
-                &mut stdout,
-                MoveTo(0, original_cursor.1 + i),
-                Clear(ClearType::CurrentLine)
-            )?;
+    // Restore terminal, starting any selection on a line of its own
+    viewport.leave(&mut stdout, !selected_items.is_empty())?;
+
+    if let Some(path) = config.index_cache.as_deref().filter(|_| receiver_exhausted) {
+        if let Some(index) = fuzzy_finder.built_index().await {
+            // The cache only speeds up the next session; failing to save
+            // it shouldn't lose this one's selection
+            let _ = index.save_cached(path);
         }
-        execute!(
-            &mut stdout,
-            MoveTo(original_cursor.0, original_cursor.1),
-            Show
-        )?;
-        stdout.flush()?;
     }
 
-    // Restore terminal state
-    disable_raw_mode()?;
-
-    if !selected_items.is_empty() && !fullscreen {
-        // Move to the original cursor position only in inline mode
-        execute!(&mut stdout, MoveTo(0, original_cursor.1))?;
+    if config.ansi {
+        strip_selection_ansi(&mut selected_items);
     }
-
-    Ok(selected_items)
+    Ok(TuiOutput {
+        key: accepted_key,
+        selected: selected_items,
+        query: fuzzy_finder.get_query().to_string(),
+        accepted,
+        tab: config.tabs.get(tab).map(|tab| tab.name.clone()),
+    })
 }
 
-/// Trigger preview update if needed
-fn maybe_update_preview(
-    fuzzy_finder: &FuzzyFinder,
-    preview_state: &mut PreviewState,
-    config: &TuiConfig,
-    preview_sender: &std::sync::mpsc::Sender<PreviewResult>,
-    preview_task: &mut Option<tokio::task::JoinHandle<()>>,
-) {
-    if !preview_state.visible || config.preview_rules.is_empty() {
-        return;
-    }
+/// Command `binding` runs for the item under the cursor, or `None` when there
+/// is no item to run it for
+fn bound_command(binding: &KeyBinding, fuzzy_finder: &FuzzyFinder) -> Option<String> {
+    let BindAction::Execute(template) = &binding.action else {
+        return None;
+    };
     let cursor_pos = fuzzy_finder.get_cursor_position();
-    if cursor_pos >= fuzzy_finder.get_filtered_items().len() {
-        return;
-    }
-    let item = fuzzy_finder.get_filtered_items()[cursor_pos].clone();
-    if item == preview_state.current_item && !preview_state.loading {
-        return;
-    }
-    if let Some(task) = preview_task.take() {
-        task.abort();
-    }
-    preview_state.start_loading(&item);
-    if !preview_state.loading {
-        // Was cached
-        return;
-    }
-    let cmd = build_preview_command(&item, &config.preview_rules);
-    if cmd.is_empty() {
-        preview_state.loading = false;
-        preview_state.error = Some("No preview rule matched".to_string());
-        return;
-    }
-    let sender = preview_sender.clone();
-    let task = spawn_preview_task(cmd, sender);
-    *preview_task = Some(task);
+    let item = fuzzy_finder.get_filtered_items().get(cursor_pos)?;
+    let index = fuzzy_finder.get_original_index(cursor_pos)?;
+    Some(expand_placeholders(
+        template,
+        item,
+        fuzzy_finder.get_query(),
+        index,
+    ))
 }
 
-/// Create an mpsc channel for sending items to the TUI
-pub fn create_items_channel() -> (mpsc::Sender<String>, mpsc::Receiver<String>) {
-    mpsc::channel(1000) // Buffer size of 1000 items
-}
+/// Run `command` with the terminal restored to normal, then take it over
+/// again. Inline, the TUI is redrawn below the command's output.
+fn run_bound_command(command: &str, viewport: &mut Viewport) -> io::Result<()> {
+    let mut stdout = io::stderr();
+    viewport.suspend(&mut stdout)?;
+
+    // Our stdout may be captured by the caller, so the command writes to
+    // the terminal the TUI was drawn on. Its exit status is its own concern.
+    let mut shell = if cfg!(target_os = "windows") {
+        let mut shell = std::process::Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = std::process::Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    };
+    let _ = shell.stdout(io::stderr()).status();
 
-/// Create an mpsc channel for sending commands (items with indicators) to the TUI
-pub fn create_command_channel() -> (mpsc::Sender<TuiCommand>, mpsc::Receiver<TuiCommand>) {
-    mpsc::channel(1000) // Buffer size of 1000 commands
+    viewport.resume(&mut stdout)
 }
 
-/// Run an async interactive TUI with command channel support for per-item indicators
-pub async fn run_tui_with_indicators(
-    command_receiver: mpsc::Receiver<TuiCommand>,
-    multi_select: bool,
-    config: TuiConfig,
-) -> Result<Vec<(usize, String)>, Box<dyn std::error::Error + Send + Sync>> {
-    run_interactive_tui_with_indicators(command_receiver, multi_select, config).await
+/// Handle a key typed in jump mode: move the cursor to the item whose label
+/// it completes, accepting it like Enter for [`BindAction::JumpAccept`].
+/// Jump mode ends unless the key only starts a two-character label.
+async fn handle_jump_key(
+    jump: &mut Option<Jump>,
+    key_event: &KeyEvent,
+    fuzzy_finder: &mut FuzzyFinder,
+    preview_state: &mut PreviewState,
+    screen_layout: &ScreenLayout,
+    config: &TuiConfig,
+) -> Action {
+    let Some(active) = jump.as_mut() else {
+        return Action::Continue;
+    };
+    let visible = screen_layout.visible_items();
+    let labels = jump_labels(&config.jump_labels, visible.len());
+    let target = match active.handle_key(key_event, &labels) {
+        JumpKey::Pending => return Action::Continue,
+        JumpKey::Jump(index) => visible[index],
+        JumpKey::Cancel => {
+            *jump = None;
+            return Action::Continue;
+        }
+    };
+    let accept = active.accept;
+    *jump = None;
+    let cursor = fuzzy_finder.get_cursor_position();
+    fuzzy_finder.move_cursor_clamped(target as i32 - cursor as i32);
+    if !accept {
+        return Action::Continue;
+    }
+    let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+    let action =
+        events::handle_async_key_event(&enter, fuzzy_finder, preview_state, screen_layout).await;
+    accept_unmatched_query(action, &enter, fuzzy_finder, config)
 }
 
-/// Run the async interactive TUI with command channel support
-async fn run_interactive_tui_with_indicators(
-    mut command_receiver: mpsc::Receiver<TuiCommand>,
-    multi_select: bool,
-    config: TuiConfig,
-) -> Result<Vec<(usize, String)>, Box<dyn std::error::Error + Send + Sync>> {
-    let mut fuzzy_finder = FuzzyFinder::new(multi_select);
-    let mut stdout = io::stderr();
-
-    // Per-item indicators storage (keyed by item text)
-    let mut item_indicators: std::collections::HashMap<String, ItemIndicator> =
-        std::collections::HashMap::new();
-    let mut global_status = GlobalStatus::Loading(None);
-
-    // Enable raw mode and hide cursor
-    enable_raw_mode()?;
-    execute!(stdout, Hide)?;
+/// With [`TuiConfig::print_query`], turn an Enter that found nothing to
+/// select into accepting the query alone
+fn accept_unmatched_query(
+    action: Action,
+    key_event: &KeyEvent,
+    fuzzy_finder: &FuzzyFinder,
+    config: &TuiConfig,
+) -> Action {
+    let unmatched_enter = key_event.code == KeyCode::Enter
+        && key_event.modifiers == KeyModifiers::NONE
+        && fuzzy_finder.get_filtered_items().is_empty();
+    match action {
+        Action::Continue if config.print_query && unmatched_enter => Action::Select(Vec::new()),
+        action => action,
+    }
+}
 
-    let fullscreen = config.fullscreen;
-    let mut original_cursor = position()?;
-    let (_term_width, term_height) = size()?;
-    let tui_height = config.calculate_height(term_height);
+/// Result decided by [`TuiConfig::select_one`] or [`TuiConfig::exit_zero`]
+/// from fully filtered items, or `None` to show the TUI
+fn output_without_tui(fuzzy_finder: &FuzzyFinder, config: &TuiConfig) -> Option<TuiOutput> {
+    let mut selected = match fuzzy_finder.get_filtered_items() {
+        [] if config.exit_zero => Vec::new(),
+        [item] if config.select_one => {
+            vec![(fuzzy_finder.get_original_index(0)?, item.clone())]
+        }
+        _ => return None,
+    };
+    if config.ansi {
+        strip_selection_ansi(&mut selected);
+    }
+    Some(TuiOutput {
+        selected,
+        key: None,
+        query: fuzzy_finder.get_query().to_string(),
+        accepted: true,
+        tab: config.tabs.first().map(|tab| tab.name.clone()),
+    })
+}
 
-    if fullscreen {
-        execute!(
-            &mut stdout,
-            crossterm::terminal::EnterAlternateScreen,
-            Clear(ClearType::All)
-        )?;
+/// Item count from which a filter pass is abandoned when newer keystrokes arrive
+const CANCEL_FILTER_THRESHOLD: usize = 50_000;
+
+/// Items scored between redraws when filtering huge inputs
+const FILTER_CHUNK: usize = 100_000;
+
+/// How long to wait for input before the next loop iteration: not at all
+/// while a progressive filter pass has chunks left to score
+fn input_timeout(
+    finder: &FuzzyFinder,
+    needs_redraw: bool,
+    last_frame: Option<Instant>,
+) -> std::time::Duration {
+    if finder.is_filtering() {
+        std::time::Duration::ZERO
+    } else if needs_redraw {
+        // Wake up for the frame that was held back
+        until_next_frame(last_frame)
     } else {
-        // If not enough space below, scroll the terminal down
-        if original_cursor.1 + tui_height > term_height {
-            let needed = (original_cursor.1 + tui_height).saturating_sub(term_height);
-            for _ in 0..needed {
-                writeln!(stdout)?;
-            }
-            stdout.flush()?;
-            original_cursor = (0, term_height.saturating_sub(tui_height));
-        }
-        execute!(&mut stdout, MoveTo(0, original_cursor.1))?;
+        std::time::Duration::from_millis(50)
     }
+}
 
-    let mut selected_items = Vec::new();
-    let mut needs_redraw = true;
-    let mut items_buffer = Vec::new();
-    let mut receiver_exhausted = false;
-    let mut scroll_offset = 0;
+/// Shortest time between frames (about 60 per second). Redraws asked for in
+/// between, e.g. as items stream in or keys repeat, are coalesced into the
+/// next frame.
+const FRAME_INTERVAL: std::time::Duration = std::time::Duration::from_millis(16);
 
-    // Preview state
-    let mut preview_state = PreviewState::new();
-    if config.preview_auto && !config.preview_rules.is_empty() {
-        preview_state.visible = true;
+/// Time left before the next frame may be drawn
+fn until_next_frame(last_frame: Option<Instant>) -> std::time::Duration {
+    last_frame.map_or(std::time::Duration::ZERO, |drawn| {
+        FRAME_INTERVAL.saturating_sub(drawn.elapsed())
+    })
+}
+
+/// Remove ANSI escape sequences from selected items, for `ansi` output
+fn strip_selection_ansi(selected: &mut [(usize, String)]) {
+    for (_, item) in selected {
+        *item = strip_ansi_sequences(item);
     }
-    let (preview_tx, preview_rx) = std::sync::mpsc::channel::<PreviewResult>();
-    let mut preview_task: Option<tokio::task::JoinHandle<()>> = None;
+}
 
-    // Spinner animation state
-    let mut spinner_frame: usize = 0;
-    let mut last_spinner_update = Instant::now();
-    let spinner_interval = std::time::Duration::from_millis(80);
+/// What woke the interactive loop up
+enum Wakeup {
+    /// A key press, mouse event or resize
+    Input(Event),
+    /// An item or command arrived, or `None` once the channel closed
+    Received(Option<TuiCommand>),
+    /// A preview command finished
+    Preview(PreviewResult),
+    /// The timeout passed: time to redraw, animate or score another chunk
+    Tick,
+    /// ff was sent SIGINT (Ctrl-C on Windows): abort
+    Interrupt,
+}
 
-    // Create screen buffer for double-buffered rendering
-    let (term_width, _) = size()?;
-    let mut screen_buffer = ScreenBuffer::new(term_width, tui_height);
+#[cfg(unix)]
+type InterruptSignal = tokio::signal::unix::Signal;
+#[cfg(windows)]
+type InterruptSignal = tokio::signal::windows::CtrlC;
+
+/// Listen for SIGINT from outside the terminal, e.g. `kill -INT`, or from
+/// Ctrl-C while a bound command runs (in raw mode Ctrl-C is a key press),
+/// for [`TuiConfig::catch_interrupts`]. `None` if the handler can't be
+/// installed.
+fn interrupt_signal() -> Option<InterruptSignal> {
+    #[cfg(unix)]
+    let signal = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt());
+    #[cfg(windows)]
+    let signal = tokio::signal::windows::ctrl_c();
+    signal.ok()
+}
 
-    loop {
-        // Process commands from channel
-        if !receiver_exhausted {
-            let mut batch_count = 0;
-            const MAX_BATCH_SIZE: usize = 1000;
+/// Forget interrupts already delivered
+fn drain_interrupts(signal: Option<&mut InterruptSignal>) {
+    if let Some(signal) = signal {
+        while let Some(Some(())) = signal.recv().now_or_never() {}
+    }
+}
 
-            loop {
-                match command_receiver.try_recv() {
-                    Ok(command) => {
-                        match command {
-                            TuiCommand::AddItem(item) => {
-                                items_buffer.push(item);
-                            }
-                            TuiCommand::AddItemWithIndicator(item, indicator) => {
-                                if indicator != ItemIndicator::None {
-                                    item_indicators.insert(item.clone(), indicator);
-                                }
-                                items_buffer.push(item);
-                            }
-                            TuiCommand::UpdateIndicator(item, indicator) => {
-                                if indicator == ItemIndicator::None {
-                                    item_indicators.remove(&item);
-                                } else {
-                                    item_indicators.insert(item, indicator);
-                                }
-                                needs_redraw = true;
-                            }
-                            TuiCommand::SetGlobalStatus(status) => {
-                                global_status = status;
-                                needs_redraw = true;
-                            }
-                        }
-                        batch_count += 1;
-                        if batch_count >= MAX_BATCH_SIZE {
-                            break;
-                        }
-                    }
-                    Err(mpsc::error::TryRecvError::Empty) => {
-                        break;
-                    }
-                    Err(mpsc::error::TryRecvError::Disconnected) => {
-                        receiver_exhausted = true;
-                        global_status = GlobalStatus::Ready(config.ready_message.clone());
-                        needs_redraw = true;
-                        break;
-                    }
-                }
-            }
+/// Whether `key_event` is Ctrl-C, which aborts whatever is shown unless
+/// bound to something else
+fn is_abort_key(key_event: &KeyEvent, config: &TuiConfig) -> bool {
+    key_event.code == KeyCode::Char('c')
+        && key_event.modifiers.contains(KeyModifiers::CONTROL)
+        && !config.bindings.iter().any(|b| b.key.matches(key_event))
+}
 
-            if !items_buffer.is_empty() {
-                fuzzy_finder.add_items(mem::take(&mut items_buffer)).await;
-                needs_redraw = true;
-            }
+/// Wait for whichever comes first of terminal input, something on
+/// `receiver`, a preview result or `timeout`. Input is checked first, so a
+/// flood of streamed items can't hold up keystrokes.
+async fn next_wakeup(
+    terminal: &mut TerminalInput,
+    receiver: Option<&mut Incoming>,
+    previews: &mut mpsc::UnboundedReceiver<PreviewResult>,
+    interrupts: Option<&mut InterruptSignal>,
+    timeout: std::time::Duration,
+) -> io::Result<Wakeup> {
+    let received = async {
+        match receiver {
+            Some(receiver) => receiver.recv().await,
+            None => std::future::pending().await,
         }
-
-        // Drain preview results
-        if let Ok(result) = preview_rx.try_recv() {
-            preview_state.apply_result(result);
-            needs_redraw = true;
+    };
+    let interrupted = async {
+        match interrupts {
+            Some(signal) => signal.recv().await,
+            None => std::future::pending().await,
         }
+    };
+    tokio::select! {
+        biased;
+        event = terminal.next() => match event {
+            Some(event) => event.map(Wakeup::Input),
+            None => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "terminal event stream ended",
+            )),
+        },
+        item = received => Ok(Wakeup::Received(item)),
+        Some(result) = previews.recv() => Ok(Wakeup::Preview(result)),
+        Some(()) = interrupted => Ok(Wakeup::Interrupt),
+        _ = tokio::time::sleep(timeout) => Ok(Wakeup::Tick),
+    }
+}
 
-        let (_term_width, term_height) = size()?;
-        let tui_height = config.calculate_height(term_height);
+/// Terminal input for the interactive loop, read through one
+/// [`EventStream`]. While a filter pass is watched (see
+/// [`watch`](Self::watch)), input arriving cancels it: the stream is polled
+/// with a waker that does so, woken by the stream itself, so nothing else
+/// reads the terminal meanwhile.
+struct TerminalInput {
+    events: EventStream,
+    /// An event [`has_input`](Self::has_input) took off the stream, handed
+    /// out next
+    pending: Option<io::Result<Event>>,
+    wake: Arc<InputWake>,
+}
 
-        // Determine layout
-        let preview_active =
-            preview_state.visible && !config.preview_rules.is_empty() && term_width >= 40;
-        let left_width = if preview_active {
-            term_width / 2 - 1
-        } else {
-            term_width
-        };
-        let right_width = if preview_active {
-            term_width - left_width - 1
-        } else {
-            0
-        };
-        let separator_col = left_width;
+/// Waker the event stream is polled with: cancels the watched filter pass,
+/// then wakes the task waiting for input
+#[derive(Default)]
+struct InputWake {
+    watched: Mutex<Option<CancelToken>>,
+    task: AtomicWaker,
+}
 
-        let available_height = if tui_height > 2 {
-            if config.show_help_text {
-                tui_height - 2
-            } else {
-                tui_height - 1
+impl ArcWake for InputWake {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        if let Ok(watched) = arc_self.watched.lock() {
+            if let Some(token) = watched.as_ref() {
+                token.cancel();
             }
-        } else if tui_height == 2 {
-            1
-        } else {
-            0
-        };
-
-        // Update scroll offset to keep cursor in view
-        let cursor_pos = fuzzy_finder.get_cursor_position();
-        if cursor_pos < scroll_offset {
-            scroll_offset = cursor_pos;
-        } else if cursor_pos >= scroll_offset + available_height as usize {
-            scroll_offset = cursor_pos - available_height as usize + 1;
         }
+        arc_self.task.wake();
+    }
+}
 
-        let total_items = fuzzy_finder.get_filtered_items().len();
-        if scroll_offset > total_items {
-            scroll_offset = total_items.saturating_sub(available_height as usize);
+impl TerminalInput {
+    fn new() -> Self {
+        Self {
+            events: EventStream::new(),
+            pending: None,
+            wake: Arc::default(),
         }
+    }
 
-        if needs_redraw {
-            // Resize buffer if terminal size changed
-            let (term_width, _) = size()?;
-            screen_buffer.resize(term_width, tui_height);
-            screen_buffer.clear();
-
-            // Draw search prompt with global status indicator (row 0 in buffer)
-            let mut col: u16 = 0;
-            col += screen_buffer.put_str(col, 0, "> ", Some(Color::Cyan), None, false, false);
-            col +=
-                screen_buffer.put_str(col, 0, fuzzy_finder.get_query(), None, None, false, false);
-
-            // Draw global status indicator
-            if config.show_loading_indicator {
-                col += screen_buffer.put_str(col, 0, " ", None, None, false, false);
-                match &global_status {
-                    GlobalStatus::Loading(msg) => {
-                        let frame = SPINNER_FRAMES[spinner_frame % SPINNER_FRAMES.len()];
-                        col += screen_buffer.put_str(
-                            col,
-                            0,
-                            &frame.to_string(),
-                            Some(Color::Yellow),
-                            None,
-                            false,
-                            false,
-                        );
-                        if let Some(ref m) = msg {
-                            col += screen_buffer.put_str(col, 0, " ", None, None, false, false);
-                            screen_buffer.put_str(
-                                col,
-                                0,
-                                m,
-                                Some(Color::DarkGrey),
-                                None,
-                                false,
-                                false,
-                            );
-                        } else if let Some(ref m) = config.loading_message {
-                            col += screen_buffer.put_str(col, 0, " ", None, None, false, false);
-                            screen_buffer.put_str(
-                                col,
-                                0,
-                                m,
-                                Some(Color::DarkGrey),
-                                None,
-                                false,
-                                false,
-                            );
-                        }
-                    }
-                    GlobalStatus::Ready(msg) => {
-                        if let Some(ref m) = msg {
-                            screen_buffer.put_str(
-                                col,
-                                0,
-                                m,
-                                Some(Color::Green),
-                                None,
-                                false,
-                                false,
-                            );
-                        }
-                    }
-                    GlobalStatus::Custom(text) => {
-                        screen_buffer.put_str(col, 0, text, None, None, false, false);
-                    }
-                    GlobalStatus::Hidden => {}
-                }
+    /// Whether input is pending, without waiting for it
+    fn has_input(&mut self) -> bool {
+        if self.pending.is_none() {
+            if let Poll::Ready(event) = self.poll_events() {
+                self.pending = event;
             }
+        }
+        self.pending.is_some()
+    }
 
-            // Draw items with per-item indicators (confined to left pane)
-            if tui_height >= 2 && available_height > 0 {
-                let filtered_items = fuzzy_finder.get_filtered_items();
-                let visible_items = filtered_items
-                    .iter()
-                    .skip(scroll_offset)
-                    .take(available_height as usize);
+    /// Cancel the finder's filter pass as soon as more input is pending,
+    /// until the returned guard is dropped, so typing quickly over huge
+    /// inputs skips stale queries. `None` when there are too few items to
+    /// bother.
+    fn watch(&mut self, finder: &FuzzyFinder) -> Option<InputWatch> {
+        if finder.stream.len() < CANCEL_FILTER_THRESHOLD {
+            return None;
+        }
+        let token = finder.cancel_token();
+        token.reset();
+        if let Ok(mut watched) = self.wake.watched.lock() {
+            *watched = Some(token.clone());
+        }
+        // Has the stream wake us on input, unless some is already pending
+        if self.has_input() {
+            token.cancel();
+        }
+        Some(InputWatch {
+            wake: Arc::clone(&self.wake),
+            token,
+        })
+    }
 
-                for (i, item) in visible_items.enumerate() {
-                    let absolute_index = scroll_offset + i;
-                    let row = (i + 1) as u16; // Row in buffer (0 is prompt)
+    fn poll_events(&mut self) -> Poll<Option<io::Result<Event>>> {
+        let waker = futures::task::waker(Arc::clone(&self.wake));
+        self.events
+            .poll_next_unpin(&mut Context::from_waker(&waker))
+    }
+}
 
-                    let is_cursor = absolute_index == fuzzy_finder.get_cursor_position();
-                    let original_index = fuzzy_finder.get_original_index(absolute_index);
-                    let is_selected = if let Some(idx) = original_index {
-                        fuzzy_finder.is_selected(idx)
-                    } else {
-                        false
-                    };
-                    let indicator = item_indicators.get(item);
+impl Stream for TerminalInput {
+    type Item = io::Result<Event>;
 
-                    draw_item_with_indicator_to_buffer_left(
-                        &mut screen_buffer,
-                        row,
-                        item,
-                        is_cursor,
-                        is_selected,
-                        fuzzy_finder.get_match_positions(absolute_index),
-                        indicator,
-                        spinner_frame,
-                        left_width,
-                    );
-                }
-            }
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(event) = self.pending.take() {
+            return Poll::Ready(Some(event));
+        }
+        self.wake.task.register(cx.waker());
+        self.poll_events()
+    }
+}
 
-            if tui_height < 2 {
-                screen_buffer.put_str(
-                    0,
-                    1,
-                    "Terminal too small. Please resize to continue...",
-                    Some(Color::Yellow),
-                    None,
-                    false,
-                    false,
-                );
-            }
+/// Watches input for a filter pass (see [`TerminalInput::watch`]); stops
+/// watching when dropped
+struct InputWatch {
+    wake: Arc<InputWake>,
+    token: CancelToken,
+}
 
-            // Draw separator and preview pane
-            if preview_active {
-                // Vertical separator (heavy when preview is focused)
-                let sep_char = if preview_state.focused { '┃' } else { '│' };
-                for row in 0..tui_height.saturating_sub(1) {
-                    screen_buffer.put_char(
-                        separator_col,
-                        row,
-                        sep_char,
-                        Some(Color::DarkGrey),
-                        None,
-                        preview_state.focused,
-                        false,
-                    );
-                }
-                // Preview content
-                let preview_height = if config.show_help_text {
-                    tui_height.saturating_sub(1)
-                } else {
-                    tui_height
-                };
-                render_preview_to_buffer(
-                    &mut screen_buffer,
-                    &preview_state.lines,
-                    preview_state.scroll,
-                    separator_col + 1,
-                    0,
-                    right_width,
-                    preview_height,
-                    preview_state.loading,
-                    preview_state.error.as_deref(),
-                );
-            }
+impl Drop for InputWatch {
+    fn drop(&mut self) {
+        if let Ok(mut watched) = self.wake.watched.lock() {
+            watched.take();
+        }
+        // Input arriving after the pass finished must not cancel later passes
+        self.token.reset();
+    }
+}
 
-            // Draw instructions (always at the bottom of the TUI area)
-            if config.show_help_text {
-                let instructions_row = tui_height.saturating_sub(1);
-                let instructions = if preview_active {
-                    if multi_select {
-                        "Tab/Space: Toggle | Enter: Confirm | Ctrl+P: Preview | →/←: Focus | Esc: Exit"
-                    } else {
-                        "↑/↓: Navigate | Enter: Select | Ctrl+P: Preview | →/←: Focus | Esc: Exit"
-                    }
-                } else if multi_select {
-                    "Tab/Space: Toggle | Enter: Confirm | Esc/Ctrl+C/Ctrl+Q: Exit"
-                } else {
-                    "↑/↓: Navigate | Enter: Select | Esc/Ctrl+C/Ctrl+Q: Exit"
-                };
-                screen_buffer.put_str(
-                    0,
-                    instructions_row,
-                    instructions,
-                    Some(Color::DarkGrey),
-                    None,
-                    false,
-                    false,
-                );
+/// Apply `command` to the indicators and status, returning the item it adds
+/// if any
+fn apply_command(
+    command: TuiCommand,
+    item_indicators: &mut HashMap<String, ItemIndicator>,
+    global_status: &mut GlobalStatus,
+) -> Option<String> {
+    match command {
+        TuiCommand::AddItem(item) => Some(item),
+        TuiCommand::AddItemWithIndicator(item, indicator) => {
+            if indicator != ItemIndicator::None {
+                item_indicators.insert(item.clone(), indicator);
             }
-
-            // Render buffer to terminal in a single write
-            let rendered = if fullscreen {
-                screen_buffer.render_fullscreen()
-            } else {
-                screen_buffer.render(original_cursor.1)
-            };
-            write!(stdout, "{}", rendered)?;
-            stdout.flush()?;
-            needs_redraw = false;
-
-            // Trigger preview on initial load / redraw
-            maybe_update_preview(
-                &fuzzy_finder,
-                &mut preview_state,
-                &config,
-                &preview_tx,
-                &mut preview_task,
-            );
+            Some(item)
         }
-
-        // Handle input
-        if event::poll(std::time::Duration::from_millis(50))? {
-            if let Event::Key(key_event) = event::read()? {
-                let prev_cursor = fuzzy_finder.get_cursor_position();
-                let prev_visible = preview_state.visible;
-                match events::handle_async_key_event(
-                    &key_event,
-                    &mut fuzzy_finder,
-                    &mut preview_state,
-                )
-                .await
-                {
-                    Action::Continue => {
-                        needs_redraw = true;
-                        if fuzzy_finder.get_cursor_position() != prev_cursor
-                            || preview_state.visible != prev_visible
-                        {
-                            maybe_update_preview(
-                                &fuzzy_finder,
-                                &mut preview_state,
-                                &config,
-                                &preview_tx,
-                                &mut preview_task,
-                            );
-                        }
-                        continue;
-                    }
-                    Action::Exit => break,
-                    Action::Select(items) => {
-                        selected_items = items;
-                        break;
-                    }
-                }
+        TuiCommand::UpdateIndicator(item, indicator) => {
+            if indicator == ItemIndicator::None {
+                item_indicators.remove(&item);
+            } else {
+                item_indicators.insert(item, indicator);
             }
+            None
+        }
+        TuiCommand::SetGlobalStatus(status) => {
+            *global_status = status;
+            None
         }
+    }
+}
 
-        // Update spinner animation
-        if last_spinner_update.elapsed() >= spinner_interval {
-            spinner_frame = (spinner_frame + 1) % SPINNER_FRAMES.len();
-            last_spinner_update = Instant::now();
-            // Only redraw if there are any spinning indicators
-            let has_spinners = matches!(global_status, GlobalStatus::Loading(_))
-                || item_indicators
-                    .values()
-                    .any(|i| matches!(i, ItemIndicator::Spinner));
-            if has_spinners {
-                needs_redraw = true;
-            }
+/// Create the finder driven by the TUI, applying the scoring options from the config
+async fn create_finder(multi_select: bool, config: &mut TuiConfig) -> FuzzyFinder {
+    let mut finder = FuzzyFinder::new(multi_select);
+    finder.scoring = config.scoring.clone();
+    finder.min_score = config.min_score;
+    finder.max_results = config.max_results;
+    finder.sort = config.sort;
+    finder.cycle = config.cycle;
+    finder.unique_similar = config.unique_similar;
+    finder.group_similar = config.group_similar;
+    for transform in &config.transforms {
+        finder.add_transform(transform.clone());
+    }
+    finder.set_dedup(config.dedup);
+    finder.index = config.index.take();
+    finder.set_hidden_separator(config.hidden_separator);
+    finder.set_weight_field(config.weight_field);
+    finder.set_filter_chunk(Some(FILTER_CHUNK));
+    finder.set_max_selected(config.max_select);
+    finder.set_selection_order(config.selection_order);
+    finder.preselect(config.preselect.iter().cloned());
+    if !config.query.is_empty() {
+        finder.set_query(config.query.clone()).await;
+    }
+    finder
+}
+
+/// Trigger preview update if needed
+fn maybe_update_preview(
+    fuzzy_finder: &FuzzyFinder,
+    preview_state: &mut PreviewState,
+    config: &TuiConfig,
+    preview_sender: &mpsc::UnboundedSender<PreviewResult>,
+    preview_task: &mut Option<tokio::task::JoinHandle<()>>,
+) {
+    if !preview_state.visible || config.preview_rules.is_empty() {
+        return;
+    }
+    let cursor_pos = fuzzy_finder.get_cursor_position();
+    if cursor_pos >= fuzzy_finder.get_filtered_items().len() {
+        return;
+    }
+    let item = &fuzzy_finder.get_filtered_items()[cursor_pos];
+    let index = fuzzy_finder
+        .get_original_index(cursor_pos)
+        .unwrap_or(cursor_pos);
+    let builtin = builtin_preview_path(item, &config.preview_rules, config.preview_root.as_deref());
+    let cmd = match &builtin {
+        Some(path) => format!("builtin:{}", path.display()),
+        None => {
+            build_preview_command_for(item, fuzzy_finder.get_query(), index, &config.preview_rules)
         }
+    };
+    // Previews are keyed by their command, which covers the item and any
+    // query or index placeholders
+    if cmd == preview_state.current_item && !preview_state.loading {
+        return;
     }
+    if let Some(task) = preview_task.take() {
+        task.abort();
+    }
+    preview_state.start_loading(&cmd);
+    if let Some(scroll) = &config.preview_scroll {
+        preview_state.scroll = scroll.offset_for(item).unwrap_or(0);
+    }
+    if cmd.is_empty() {
+        preview_state.loading = false;
+        preview_state.error = Some("No preview rule matched".to_string());
+        return;
+    }
+    if !preview_state.loading {
+        // Was cached
+        return;
+    }
+    let sender = preview_sender.clone();
+    let task = match builtin {
+        Some(path) => spawn_builtin_preview(path, sender),
+        None => spawn_preview_task(cmd, sender),
+    };
+    *preview_task = Some(task);
+}
 
-    // Restore terminal
-    if fullscreen {
-        execute!(&mut stdout, crossterm::terminal::LeaveAlternateScreen)?;
-        execute!(&mut stdout, Show)?;
-    } else {
-        for i in 0..config.calculate_height(size()?.1) {
-            execute!(
-                &mut stdout,
-                MoveTo(0, original_cursor.1 + i),
-                Clear(ClearType::CurrentLine)
-            )?;
+/// Create an mpsc channel for sending items to the TUI
+pub fn create_items_channel() -> (mpsc::Sender<String>, mpsc::Receiver<String>) {
+    mpsc::channel(1000) // Buffer size of 1000 items
+}
+
+/// Merge several item channels into one receiver for the TUI.
+///
+/// Items are forwarded as they arrive, polling the sources fairly so a fast
+/// producer cannot starve a slow one. The merged channel closes once every
+/// source has closed. Must be called within a Tokio runtime.
+pub fn merge_items_channels(receivers: Vec<mpsc::Receiver<String>>) -> mpsc::Receiver<String> {
+    let (sender, receiver) = create_items_channel();
+    let sources = receivers.into_iter().map(|rx| {
+        Box::pin(futures::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|item| (item, rx))
+        }))
+    });
+    let mut merged = futures::stream::select_all(sources);
+    tokio::spawn(async move {
+        while let Some(item) = merged.next().await {
+            if sender.send(item).await.is_err() {
+                break;
+            }
         }
-        execute!(
-            &mut stdout,
-            MoveTo(original_cursor.0, original_cursor.1),
-            Show
-        )?;
-        stdout.flush()?;
-    }
+    });
+    receiver
+}
 
-    disable_raw_mode()?;
+/// Create an mpsc channel for sending commands (items with indicators) to the TUI
+pub fn create_command_channel() -> (mpsc::Sender<TuiCommand>, mpsc::Receiver<TuiCommand>) {
+    mpsc::channel(1000) // Buffer size of 1000 commands
+}
 
-    if !selected_items.is_empty() {
-        execute!(&mut stdout, MoveTo(0, original_cursor.1))?;
-    }
+/// Run an async interactive TUI with command channel support for per-item indicators
+pub async fn run_tui_with_indicators(
+    command_receiver: mpsc::Receiver<TuiCommand>,
+    multi_select: bool,
+    config: TuiConfig,
+) -> Result<Vec<(usize, String)>, Box<dyn std::error::Error + Send + Sync>> {
+    let output = guarded(run_interactive_tui(
+        Incoming::Commands(command_receiver),
+        multi_select,
+        config,
+    ))
+    .await?;
+    Ok(output.selected)
+}
 
-    Ok(selected_items)
+/// Like [`run_tui_with_indicators`], also reporting the query, the accepting
+/// key and whether the session was cancelled.
+pub async fn run_tui_with_indicators_output(
+    command_receiver: mpsc::Receiver<TuiCommand>,
+    multi_select: bool,
+    config: TuiConfig,
+) -> Result<TuiOutput, Box<dyn std::error::Error + Send + Sync>> {
+    guarded(run_interactive_tui(
+        Incoming::Commands(command_receiver),
+        multi_select,
+        config,
+    ))
+    .await
 }
 
 /// Draw an item with optional per-item indicator
@@ -1328,8 +2239,107 @@ fn draw_highlighted_item_with_matches<W: Write>(
     Ok(())
 }
 
+/// Marker drawn in place of the line breaks of a multi-line item
+const LINE_BREAK_MARKER: char = '↵';
+
+/// Results measured to size an auto grid's columns
+const AUTO_COLUMNS_SAMPLE: usize = 1000;
+
+/// Marker drawn where an item too long for its row is cut off
+const ELLIPSIS: char = '…';
+
+/// A character with its foreground, background, bold and underline style
+type Glyph = (char, Option<Color>, Option<Color>, bool, bool);
+
+/// Text of filtered result `index` as displayed, only the fields of
+/// [`TuiConfig::with_nth`] if set and after its [`TuiConfig::icons`] icon,
+/// and its match positions within that text
+fn displayed_item<'a>(
+    finder: &'a FuzzyFinder,
+    index: usize,
+    config: &TuiConfig,
+) -> (Cow<'a, str>, Option<Cow<'a, MatchPositions>>) {
+    let item = &finder.get_filtered_items()[index];
+    let positions = finder.get_match_positions(index);
+    let (shown, positions) = match &config.with_nth {
+        Some(fields) => {
+            let (shown, positions) = fields.apply(item, positions);
+            (Cow::Owned(shown), positions.map(Cow::Owned))
+        }
+        None => (Cow::Borrowed(item.as_str()), positions.map(Cow::Borrowed)),
+    };
+    match config.icons {
+        Some(style) => {
+            let root = config.preview_root.as_deref();
+            let (shown, positions) = with_icon(&shown, positions.as_deref(), style, root);
+            (Cow::Owned(shown), positions.map(Cow::Owned))
+        }
+        None => (shown, positions),
+    }
+}
+
+/// Styled characters of `item`, numbered as the matcher sees its clean
+/// text, with matches highlighted and each line break shown as
+/// [`LINE_BREAK_MARKER`]
+fn item_glyphs(
+    item: &str,
+    is_cursor: bool,
+    base_fg: Option<Color>,
+    base_bg: Option<Color>,
+    base_bold: bool,
+    match_positions: Option<&crate::fuzzy::finder::MatchPositions>,
+    theme: &Theme,
+) -> Vec<Glyph> {
+    let mut glyphs: Vec<Glyph> = Vec::new();
+    for (line_idx, segments) in parse_ansi_output(item).iter().enumerate() {
+        if line_idx > 0 {
+            // The line break counts as one char of the matched text
+            glyphs.push((LINE_BREAK_MARKER, Some(theme.dim), base_bg, false, false));
+        }
+        for (text, seg_fg, seg_bg, seg_bold, seg_underline) in segments {
+            for ch in text.chars() {
+                let is_match = match_positions
+                    .map(|m| m.positions.contains(&glyphs.len()))
+                    .unwrap_or(false);
+                let (fg, bold, underline) = if is_match {
+                    let fg = if is_cursor {
+                        Some(theme.cursor_match)
+                    } else {
+                        theme.match_fg.or(base_fg)
+                    };
+                    (fg, theme.match_bold, theme.match_underline)
+                } else {
+                    (seg_fg.or(base_fg), base_bold || *seg_bold, *seg_underline)
+                };
+                let bg = if is_match && theme.match_bg.is_some() {
+                    theme.match_bg
+                } else if is_cursor {
+                    base_bg
+                } else {
+                    seg_bg.or(base_bg)
+                };
+                glyphs.push((ch, fg, bg, bold, underline));
+            }
+        }
+    }
+    glyphs
+}
+
+/// Screen lines `item` takes with `--wrap`, its text wrapping at `width`
+/// columns
+fn wrapped_lines(item: &str, width: usize, theme: &Theme) -> Vec<Range<usize>> {
+    let glyphs = item_glyphs(item, false, None, None, false, None, theme);
+    let widths: Vec<usize> = glyphs.iter().map(|g| char_width(g.0) as usize).collect();
+    wrap_ranges(&widths, width)
+}
+
 /// Draw item text with ANSI color support and match highlighting.
 /// `start_col` is where to begin drawing; `max_col` is the right boundary.
+/// Multi-line items are drawn on one row, with each line break shown as
+/// [`LINE_BREAK_MARKER`]. Text too wide for the row is cut off with an
+/// [`ELLIPSIS`]: at the end, scrolled to keep the last match in view, or
+/// with `keep_right` at the start. `hscroll` shifts the row by that many
+/// characters and is clamped to how far it can scroll.
 /// Returns the final column after drawing.
 #[allow(clippy::too_many_arguments)]
 fn draw_ansi_item_text(
@@ -1343,67 +2353,125 @@ fn draw_ansi_item_text(
     base_bg: Option<Color>,
     base_bold: bool,
     match_positions: Option<&crate::fuzzy::finder::MatchPositions>,
+    theme: &Theme,
+    keep_right: bool,
+    hscroll: &mut isize,
 ) -> u16 {
+    let glyphs = item_glyphs(
+        item,
+        is_cursor,
+        base_fg,
+        base_bg,
+        base_bold,
+        match_positions,
+        theme,
+    );
+    let room = max_col.saturating_sub(start_col) as usize;
+    let widths: Vec<usize> = glyphs.iter().map(|g| char_width(g.0) as usize).collect();
+    let ellipsis = |buffer: &mut ScreenBuffer, col: u16| {
+        if col < max_col {
+            buffer.put_char(col, row, ELLIPSIS, Some(theme.dim), base_bg, false, false)
+        } else {
+            0
+        }
+    };
+
     let mut col = start_col;
-    let mut clean_idx: usize = 0;
-    let parsed = parse_ansi_output(item);
-    let segments = parsed.first().map(|l| l.as_slice()).unwrap_or(&[]);
+    if widths.iter().sum::<usize>() <= room {
+        *hscroll = 0;
+        for &(ch, fg, bg, bold, underline) in &glyphs {
+            col += buffer.put_char(col, row, ch, fg, bg, bold, underline);
+        }
+        return col;
+    }
 
-    for (text, seg_fg, seg_bg, seg_bold, seg_underline) in segments {
-        for ch in text.chars() {
-            if col >= max_col {
-                break;
+    // First character drawn after a leading ellipsis for the tail to fit
+    let tail_first = |room: usize, last: usize| {
+        let mut used = 1;
+        let mut first = last;
+        while first > 0 && used + widths[first - 1] <= room {
+            used += widths[first - 1];
+            first -= 1;
+        }
+        first
+    };
+    let max_first = tail_first(room, glyphs.len());
+    let base = if keep_right {
+        max_first
+    } else {
+        // Scroll just far enough to show the last match, if it is cut off
+        let last_match = match_positions
+            .and_then(|m| m.positions.iter().max().copied())
+            .filter(|&p| p < glyphs.len());
+        match last_match {
+            Some(last) if widths[..=last].iter().sum::<usize>() >= room => {
+                tail_first(room.saturating_sub(1), last + 1)
             }
-            let is_match = match_positions
-                .map(|m| m.positions.contains(&clean_idx))
-                .unwrap_or(false);
-            let (fg, bold, underline) = if is_match {
-                if is_cursor {
-                    (Some(Color::White), true, true)
-                } else {
-                    (base_fg, true, true)
-                }
-            } else {
-                (seg_fg.or(base_fg), base_bold || *seg_bold, *seg_underline)
-            };
-            let bg = if is_cursor {
-                base_bg
-            } else {
-                seg_bg.or(base_bg)
-            };
-            buffer.put_char(col, row, ch, fg, bg, bold, underline);
-            col += 1;
-            clean_idx += 1;
+            _ => 0,
+        }
+    };
+    let first = (base as isize + *hscroll).clamp(0, max_first as isize) as usize;
+    *hscroll = first as isize - base as isize;
+
+    if first > 0 {
+        col += ellipsis(buffer, col);
+    }
+    let room_left = max_col.saturating_sub(col) as usize;
+    let fits = widths[first..].iter().sum::<usize>() <= room_left;
+    let mut used = 0;
+    for (&(ch, fg, bg, bold, underline), &width) in glyphs[first..].iter().zip(&widths[first..]) {
+        if !fits && used + width >= room_left {
+            break;
         }
+        used += width;
+        col += buffer.put_char(col, row, ch, fg, bg, bold, underline);
+    }
+    if !fits {
+        col += ellipsis(buffer, col);
     }
 
     col
 }
 
-/// Draw an item to the screen buffer, limited to left pane width
+/// Draw an item to the screen buffer between `start_col` and `max_col`
+#[allow(clippy::too_many_arguments)]
 fn draw_item_to_buffer_left(
     buffer: &mut ScreenBuffer,
     row: u16,
     item: &str,
     is_cursor: bool,
     is_selected: bool,
+    markers: Markers,
     match_positions: Option<&crate::fuzzy::finder::MatchPositions>,
+    start_col: u16,
     max_col: u16,
+    theme: &Theme,
+    keep_right: bool,
+    hscroll: &mut isize,
 ) {
-    let mut col: u16 = 0;
+    let mut col = start_col;
 
     // Determine base styling for this row
     let (base_fg, base_bg, base_bold) = if is_cursor {
-        (Some(Color::Yellow), Some(Color::DarkGrey), true)
+        (Some(theme.cursor_fg), Some(theme.cursor_bg), true)
     } else {
         (None, None, false)
     };
 
     // Draw selection indicator
+    let marker = markers.marker(is_cursor, is_selected);
     if is_selected {
-        col += buffer.put_str(col, row, "✓ ", Some(Color::Green), base_bg, false, false);
+        col += buffer.put_str(
+            col,
+            row,
+            marker,
+            Some(theme.selected),
+            base_bg,
+            false,
+            false,
+        );
     } else {
-        col += buffer.put_str(col, row, "  ", base_fg, base_bg, base_bold, false);
+        col += buffer.put_str(col, row, marker, base_fg, base_bg, base_bold, false);
     }
 
     // Draw item text with ANSI and match highlighting
@@ -1418,6 +2486,9 @@ fn draw_item_to_buffer_left(
         base_bg,
         base_bold,
         match_positions,
+        theme,
+        keep_right,
+        hscroll,
     );
 
     // Fill the rest of the row with background color if cursor is on this row
@@ -1429,7 +2500,421 @@ fn draw_item_to_buffer_left(
     }
 }
 
-/// Draw an item with indicator to the screen buffer, limited to left pane width
+/// Draw the scrollbar's thumb over list rows `thumb` in column `col`
+fn draw_scrollbar(
+    buffer: &mut ScreenBuffer,
+    tui_rows: &TuiRows,
+    col: u16,
+    thumb: Range<usize>,
+    theme: &Theme,
+) {
+    for i in thumb {
+        buffer.put_char(
+            col,
+            tui_rows.list(i),
+            '┃',
+            Some(theme.border),
+            None,
+            false,
+            false,
+        );
+    }
+}
+
+/// Draw screen line `line` of `item` wrapped at `max_col` (`--wrap`): the
+/// selection marker on the first line, then the line's text
+#[allow(clippy::too_many_arguments)]
+fn draw_wrapped_item_line(
+    buffer: &mut ScreenBuffer,
+    row: u16,
+    item: &str,
+    line: usize,
+    is_cursor: bool,
+    is_selected: bool,
+    markers: Markers,
+    match_positions: Option<&crate::fuzzy::finder::MatchPositions>,
+    max_col: u16,
+    theme: &Theme,
+) {
+    let (base_fg, base_bg, base_bold) = if is_cursor {
+        (Some(theme.cursor_fg), Some(theme.cursor_bg), true)
+    } else {
+        (None, None, false)
+    };
+    let marker = markers.marker(is_cursor, is_selected);
+    let mut col = if line > 0 {
+        let blank = " ".repeat(str_width(marker));
+        buffer.put_str(0, row, &blank, base_fg, base_bg, base_bold, false)
+    } else if is_selected {
+        buffer.put_str(0, row, marker, Some(theme.selected), base_bg, false, false)
+    } else {
+        buffer.put_str(0, row, marker, base_fg, base_bg, base_bold, false)
+    };
+    let glyphs = item_glyphs(
+        item,
+        is_cursor,
+        base_fg,
+        base_bg,
+        base_bold,
+        match_positions,
+        theme,
+    );
+    let widths: Vec<usize> = glyphs.iter().map(|g| char_width(g.0) as usize).collect();
+    let lines = wrap_ranges(&widths, max_col.saturating_sub(col) as usize);
+    if let Some(range) = lines.get(line) {
+        for &(ch, fg, bg, bold, underline) in &glyphs[range.clone()] {
+            col += buffer.put_char(col, row, ch, fg, bg, bold, underline);
+        }
+    }
+    if is_cursor {
+        while col < max_col {
+            col += buffer.put_char(col, row, ' ', base_fg, base_bg, false, false);
+        }
+    }
+}
+
+/// Draw a jump label over the selection marker of the item cell at `col`
+fn draw_jump_label(buffer: &mut ScreenBuffer, row: u16, col: u16, label: &str, theme: &Theme) {
+    buffer.put_str(col, row, label, Some(theme.prompt), None, true, false);
+}
+
+/// A row of the item list: a group header, a filtered item position, or
+/// a later screen line of a wrapped item
+#[derive(Debug, PartialEq)]
+enum ListRow {
+    Header(SimilarGroup),
+    Item(usize),
+    Wrapped(usize, usize),
+}
+
+/// Lay out up to `height` list rows starting at `scroll_offset`, inserting a
+/// header where a group starts (and atop the view when it opens mid-group).
+/// Headers take up rows, so `scroll_offset` is advanced if needed to keep
+/// the cursor visible. With `per_row` results to a row (a grid, which is
+/// never grouped), each row is the position of its first result. `wrap`
+/// gives the screen lines of the item at a position when items wrap; the
+/// cursor's item is then kept wholly in view.
+fn list_rows(
+    finder: &FuzzyFinder,
+    scroll_offset: &mut usize,
+    height: usize,
+    per_row: usize,
+    wrap: Option<&dyn Fn(usize) -> usize>,
+) -> Vec<ListRow> {
+    let total = finder.get_filtered_items().len();
+    let groups = finder.get_similar_groups();
+    let cursor = finder.get_cursor_position();
+
+    let build = |offset: usize| {
+        let mut rows = Vec::with_capacity(height);
+        let mut index = offset;
+        while rows.len() < height && index < total {
+            if let Some(group) = groups.iter().find(|g| {
+                g.start == index || (index == offset && g.start < index && index < g.start + g.len)
+            }) {
+                rows.push(ListRow::Header(group.clone()));
+                if rows.len() == height {
+                    break;
+                }
+            }
+            rows.push(ListRow::Item(index));
+            if let Some(lines) = wrap {
+                let more = (1..lines(index)).map(|line| ListRow::Wrapped(index, line));
+                rows.extend(more.take(height - rows.len()));
+            }
+            index += per_row;
+        }
+        rows
+    };
+    // The row that shows the end of the cursor's item
+    let cursor_end = match wrap.filter(|_| cursor < total).map(|lines| lines(cursor)) {
+        Some(count) if count > 1 => ListRow::Wrapped(cursor, count - 1),
+        _ => ListRow::Item(cursor),
+    };
+
+    loop {
+        let rows = build(*scroll_offset);
+        if (groups.is_empty() && wrap.is_none())
+            || *scroll_offset >= cursor
+            || rows.contains(&cursor_end)
+        {
+            return rows;
+        }
+        *scroll_offset += 1;
+    }
+}
+
+/// Display width of the widest of the first results, for sizing an auto
+/// grid's columns
+fn widest_item(finder: &FuzzyFinder) -> usize {
+    finder
+        .get_filtered_items()
+        .iter()
+        .take(AUTO_COLUMNS_SAMPLE)
+        .map(|item| {
+            if item.contains('\x1b') {
+                str_width(&strip_ansi_sequences(item))
+            } else {
+                str_width(item)
+            }
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Draw a group header row, e.g. `── 4 similar ──`
+fn draw_group_header(
+    buffer: &mut ScreenBuffer,
+    row: u16,
+    group: &SimilarGroup,
+    max_col: u16,
+    theme: &Theme,
+) {
+    let label = if group.clustered {
+        format!("── {} similar ──", group.len)
+    } else {
+        format!("── {} other ──", group.len)
+    };
+    let label = truncate_to_width(&label, max_col as usize);
+    buffer.put_str(0, row, label, Some(theme.dim), None, false, false);
+}
+
+/// Draw the match counter (`matched/total`) at (`col`, `row`), followed by
+/// `spinner` while items are still loading, returning the columns used
+fn draw_info(
+    buffer: &mut ScreenBuffer,
+    col: u16,
+    row: u16,
+    finder: &FuzzyFinder,
+    spinner: Option<char>,
+    theme: &Theme,
+) -> u16 {
+    let mut text = format!(
+        "  {}/{}",
+        finder.get_filtered_items().len(),
+        finder.item_count()
+    );
+    // Selections, out of the limit if there is one
+    let selected = finder.selected_count();
+    match finder.get_max_selected() {
+        Some(max) if finder.is_multi_select() => text += &format!(" ({selected}/{max})"),
+        _ if selected > 0 => text += &format!(" ({selected})"),
+        _ => {}
+    }
+    let mut used = buffer.put_str(col, row, &text, Some(theme.dim), None, false, false);
+    if finder.is_selection_capped() {
+        used += buffer.put_str(
+            col + used,
+            row,
+            " selection limit reached",
+            Some(theme.warning),
+            None,
+            false,
+            false,
+        );
+    }
+    if let Some(frame) = spinner {
+        let frame = format!(" {frame}");
+        used += buffer.put_str(
+            col + used,
+            row,
+            &frame,
+            Some(theme.spinner),
+            None,
+            false,
+            false,
+        );
+    }
+    used
+}
+
+/// Draw the query at (`col`, `row`) with the character at byte offset
+/// `cursor` (or a space past its end) in reverse video as the editing
+/// cursor, returning the columns used
+fn draw_query(buffer: &mut ScreenBuffer, col: u16, row: u16, query: &str, cursor: usize) -> u16 {
+    let (before, after) = query.split_at(cursor);
+    let cursor_col = col + buffer.put_str(col, row, before, None, None, false, false);
+    let mut end = cursor_col + buffer.put_str(cursor_col, row, after, None, None, false, false);
+    if after.is_empty() {
+        end += buffer.put_char(cursor_col, row, ' ', None, None, false, false);
+    }
+    buffer.set_reverse(cursor_col, row);
+    end - col
+}
+
+/// Draw `finder` over the whole of `buffer`: the prompt and query, the
+/// counter, then a page of results, scrolling `scroll_offset` to keep the
+/// cursor in view. Returns where the results were drawn, for paging.
+#[cfg(feature = "ratatui")]
+pub(crate) fn draw_finder(
+    buffer: &mut ScreenBuffer,
+    finder: &FuzzyFinder,
+    scroll_offset: &mut usize,
+    prompt: &str,
+    theme: &Theme,
+) -> ScreenLayout {
+    buffer.clear();
+    let width = buffer.width();
+    let col = buffer.put_str(0, 0, prompt, Some(theme.prompt), None, false, false);
+    draw_query(
+        buffer,
+        col,
+        0,
+        finder.get_query(),
+        finder.get_query_cursor(),
+    );
+    if buffer.height() > 1 {
+        draw_info(buffer, 0, 1, finder, None, theme);
+    }
+
+    let height = buffer.height().saturating_sub(2) as usize;
+    let len = finder.get_filtered_items().len();
+    let cursor = finder.get_cursor_position();
+    if cursor < *scroll_offset {
+        *scroll_offset = cursor;
+    } else if height > 0 && cursor >= *scroll_offset + height {
+        *scroll_offset = cursor + 1 - height;
+    }
+    *scroll_offset = (*scroll_offset).min(len.saturating_sub(height));
+
+    let shown = *scroll_offset..(*scroll_offset + height).min(len);
+    for (i, index) in shown.clone().enumerate() {
+        let item = &finder.get_filtered_items()[index];
+        let is_selected = finder
+            .get_original_index(index)
+            .is_some_and(|original| finder.is_selected(original));
+        draw_item_to_buffer_left(
+            buffer,
+            2 + i as u16,
+            item,
+            index == cursor,
+            is_selected,
+            Markers::Glyph,
+            finder.get_match_positions(index),
+            0,
+            width,
+            theme,
+            false,
+            &mut 0,
+        );
+    }
+    ScreenLayout {
+        list_top: 2,
+        rows: shown.map(Some).collect(),
+        columns: 1,
+        cell_width: width,
+        len,
+        ..ScreenLayout::default()
+    }
+}
+
+/// Draw a "did you mean" line offering a corrected query
+fn draw_suggestion(
+    buffer: &mut ScreenBuffer,
+    row: u16,
+    suggestion: &str,
+    max_col: u16,
+    theme: &Theme,
+) {
+    let mut col = 0;
+    let mut put = |text: &str, fg: Color, bold: bool| {
+        let text = truncate_to_width(text, max_col.saturating_sub(col) as usize);
+        col += buffer.put_str(col, row, text, Some(fg), None, bold, false);
+    };
+    put("Did you mean ", theme.dim, false);
+    put(suggestion, theme.warning, true);
+    put("? (Ctrl+Y to accept)", theme.dim, false);
+}
+
+/// Draw the names of `tabs` right-aligned before `max_col`, the `current`
+/// one highlighted, unless they don't fit after `min_col`
+fn draw_tab_bar(
+    buffer: &mut ScreenBuffer,
+    row: u16,
+    tabs: &[Tab],
+    current: usize,
+    min_col: u16,
+    max_col: u16,
+    theme: &Theme,
+) {
+    let width: usize = tabs.iter().map(|tab| str_width(&tab.name) + 2).sum();
+    let Some(mut col) = (max_col as usize).checked_sub(width) else {
+        return;
+    };
+    if col <= min_col as usize {
+        return;
+    }
+    for (i, tab) in tabs.iter().enumerate() {
+        let label = format!(" {} ", tab.name);
+        col += if i == current {
+            let bg = Some(theme.cursor_bg);
+            buffer.put_str(col as u16, row, &label, Some(theme.prompt), bg, true, false)
+        } else {
+            buffer.put_str(col as u16, row, &label, Some(theme.dim), None, false, false)
+        } as usize;
+    }
+}
+
+/// Draw a right-aligned `(xN)` count for a result standing in for
+/// near-duplicates, returning the column it starts at
+fn draw_similar_count(
+    buffer: &mut ScreenBuffer,
+    row: u16,
+    count: usize,
+    is_cursor: bool,
+    max_col: u16,
+    theme: &Theme,
+) -> u16 {
+    if count <= 1 {
+        return max_col;
+    }
+    let label = format!(" (x{count})");
+    let col = max_col.saturating_sub(label.len() as u16);
+    let bg = is_cursor.then_some(theme.cursor_bg);
+    buffer.put_str(col, row, &label, Some(theme.prompt), bg, false, false);
+    col
+}
+
+/// Score breakdown and matched positions of the result at `index`, as in
+/// `Fuzzy 412 match+96 first+15 @0,4,5` (`None` without a query)
+fn score_label(finder: &FuzzyFinder, index: usize) -> Option<String> {
+    if finder.get_query().is_empty() {
+        return None;
+    }
+    let parts = finder.explain_match(index)?;
+    let positions = finder
+        .get_match_positions(index)
+        .map(|m| {
+            let positions: Vec<String> = m.positions.iter().map(usize::to_string).collect();
+            positions.join(",")
+        })
+        .unwrap_or_default();
+    Some(format!(" {parts} @{positions}"))
+}
+
+/// Draw `label` dimmed and right-aligned before `max_col`, over the end of
+/// the item and taking at most half of the row from `start_col`
+fn draw_score_label(
+    buffer: &mut ScreenBuffer,
+    row: u16,
+    label: Option<&str>,
+    is_cursor: bool,
+    start_col: u16,
+    max_col: u16,
+    theme: &Theme,
+) {
+    let Some(label) = label else {
+        return;
+    };
+    let room = max_col.saturating_sub(start_col) as usize / 2;
+    let label = truncate_to_width(label, room);
+    let col = max_col.saturating_sub(str_width(label) as u16);
+    let bg = is_cursor.then_some(theme.cursor_bg);
+    buffer.put_str(col, row, label, Some(theme.dim), bg, false, false);
+}
+
+/// Draw an item with indicator to the screen buffer between `start_col` and
+/// `max_col`
 #[allow(clippy::too_many_arguments)]
 fn draw_item_with_indicator_to_buffer_left(
     buffer: &mut ScreenBuffer,
@@ -1440,13 +2925,17 @@ fn draw_item_with_indicator_to_buffer_left(
     match_positions: Option<&crate::fuzzy::finder::MatchPositions>,
     indicator: Option<&ItemIndicator>,
     spinner_frame: usize,
+    start_col: u16,
     max_col: u16,
+    theme: &Theme,
+    keep_right: bool,
+    hscroll: &mut isize,
 ) {
-    let mut col: u16 = 0;
+    let mut col = start_col;
 
     // Determine base styling for this row
     let (base_fg, base_bg, base_bold) = if is_cursor {
-        (Some(Color::Yellow), Some(Color::DarkGrey), true)
+        (Some(theme.cursor_fg), Some(theme.cursor_bg), true)
     } else {
         (None, None, false)
     };
@@ -1459,7 +2948,7 @@ fn draw_item_with_indicator_to_buffer_left(
                 col,
                 row,
                 &format!("{} ", frame),
-                Some(Color::Yellow),
+                Some(theme.spinner),
                 base_bg,
                 false,
                 false,
@@ -1474,17 +2963,17 @@ fn draw_item_with_indicator_to_buffer_left(
             col += buffer.put_str(col, row, " ", base_fg, base_bg, base_bold, false);
         }
         Some(ItemIndicator::Success) => {
-            col += buffer.put_str(col, row, "✓ ", Some(Color::Green), base_bg, false, false);
+            col += buffer.put_str(col, row, "✓ ", Some(theme.success), base_bg, false, false);
         }
         Some(ItemIndicator::Error) => {
-            col += buffer.put_str(col, row, "✗ ", Some(Color::Red), base_bg, false, false);
+            col += buffer.put_str(col, row, "✗ ", Some(theme.error), base_bg, false, false);
         }
         Some(ItemIndicator::Warning) => {
-            col += buffer.put_str(col, row, "⚠ ", Some(Color::Yellow), base_bg, false, false);
+            col += buffer.put_str(col, row, "⚠ ", Some(theme.warning), base_bg, false, false);
         }
         Some(ItemIndicator::None) | None => {
             if is_selected {
-                col += buffer.put_str(col, row, "✓ ", Some(Color::Green), base_bg, false, false);
+                col += buffer.put_str(col, row, "✓ ", Some(theme.selected), base_bg, false, false);
             } else {
                 col += buffer.put_str(col, row, "  ", base_fg, base_bg, base_bold, false);
             }
@@ -1503,6 +2992,9 @@ fn draw_item_with_indicator_to_buffer_left(
         base_bg,
         base_bold,
         match_positions,
+        theme,
+        keep_right,
+        hscroll,
     );
 
     // Fill the rest of the row with background color if cursor is on this row
@@ -1560,11 +3052,28 @@ mod tests {
         assert!(config.height.is_none());
         assert!(config.height_percentage.is_none());
         assert!(config.show_help_text);
+        assert_eq!(config.prompt, "> ");
         assert!(config.show_loading_indicator);
         assert!(config.loading_message.is_none());
         assert!(config.ready_message.is_none());
     }
 
+    #[tokio::test]
+    async fn test_create_finder_with_initial_query() {
+        let mut config = TuiConfig {
+            query: "ban".to_string(),
+            ..TuiConfig::default()
+        };
+        let mut finder = create_finder(false, &mut config).await;
+        assert_eq!(finder.get_query(), "ban");
+
+        // Items streamed in later are filtered by it
+        finder
+            .add_items(vec!["apple".to_string(), "banana".to_string()])
+            .await;
+        assert_eq!(finder.get_filtered_items(), &["banana"]);
+    }
+
     #[test]
     fn test_tui_config_with_height() {
         let config = TuiConfig::with_height(10);
@@ -1620,6 +3129,24 @@ mod tests {
         assert_eq!(height, 25); // Should be capped at terminal height - 2
     }
 
+    #[test]
+    fn test_calculate_height_auto() {
+        let config = TuiConfig::auto_height(10);
+        // Prompt, info line and help text around the results
+        assert_eq!(config.calculate_height_for(25, 4), 7);
+        assert_eq!(config.calculate_height_for(25, 0), 4);
+        assert_eq!(config.calculate_height_for(25, 100), 10);
+        assert_eq!(config.calculate_height_for(8, 100), 8);
+
+        let config = TuiConfig {
+            show_help_text: false,
+            info: Info::Hidden,
+            ..TuiConfig::auto_height(10)
+        };
+        assert_eq!(config.calculate_height_for(25, 4), 5);
+        assert_eq!(TuiConfig::with_height(10).calculate_height_for(25, 4), 10);
+    }
+
     #[test]
     fn test_cursor_position_logic() {
         // Test cursor wrapping logic
@@ -1659,6 +3186,26 @@ mod tests {
         assert!(output_str.contains("\x1b["));
     }
 
+    #[tokio::test]
+    async fn test_merge_items_channels() {
+        let (dirs, dirs_rx) = create_items_channel();
+        let (stdin, stdin_rx) = create_items_channel();
+        let mut merged = merge_items_channels(vec![dirs_rx, stdin_rx]);
+
+        dirs.send("src/".to_string()).await.unwrap();
+        stdin.send("piped".to_string()).await.unwrap();
+        dirs.send("docs/".to_string()).await.unwrap();
+        drop(dirs);
+        drop(stdin);
+
+        let mut collected = Vec::new();
+        while let Some(item) = merged.recv().await {
+            collected.push(item);
+        }
+        collected.sort();
+        assert_eq!(collected, vec!["docs/", "piped", "src/"]);
+    }
+
     #[tokio::test]
     async fn test_create_items_channel() {
         let (sender, mut receiver) = create_items_channel();
@@ -1686,12 +3233,30 @@ mod tests {
         let mut finder = FuzzyFinder::with_items_async(items, false).await;
 
         let key_event = crossterm::event::KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
-        let action =
-            events::handle_async_key_event(&key_event, &mut finder, &mut PreviewState::new()).await;
+        let action = events::handle_async_key_event(
+            &key_event,
+            &mut finder,
+            &mut PreviewState::new(),
+            &ScreenLayout::default(),
+        )
+        .await;
 
         assert_eq!(action, crate::tui::controls::Action::Exit);
     }
 
+    #[test]
+    fn test_is_abort_key() {
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        let mut config = TuiConfig::default();
+        assert!(is_abort_key(&ctrl_c, &config));
+        assert!(!is_abort_key(
+            &KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE),
+            &config
+        ));
+        config.bindings = vec![KeyBinding::parse("ctrl-c:reload").unwrap()];
+        assert!(!is_abort_key(&ctrl_c, &config));
+    }
+
     #[tokio::test]
     async fn test_handle_async_key_event_escape_with_empty_query() {
         use crate::fuzzy::FuzzyFinder;
@@ -1704,8 +3269,13 @@ mod tests {
         assert!(finder.get_query().is_empty());
 
         let key_event = crossterm::event::KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
-        let action =
-            events::handle_async_key_event(&key_event, &mut finder, &mut PreviewState::new()).await;
+        let action = events::handle_async_key_event(
+            &key_event,
+            &mut finder,
+            &mut PreviewState::new(),
+            &ScreenLayout::default(),
+        )
+        .await;
 
         assert_eq!(action, crate::tui::controls::Action::Exit);
     }
@@ -1724,13 +3294,594 @@ mod tests {
 
         // First Escape should clear the query, not exit
         let key_event = crossterm::event::KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
-        let action =
-            events::handle_async_key_event(&key_event, &mut finder, &mut PreviewState::new()).await;
+        let action = events::handle_async_key_event(
+            &key_event,
+            &mut finder,
+            &mut PreviewState::new(),
+            &ScreenLayout::default(),
+        )
+        .await;
 
         assert_eq!(action, crate::tui::controls::Action::Continue);
         assert!(finder.get_query().is_empty());
     }
 
+    #[tokio::test]
+    async fn test_handle_async_key_event_ctrl_y_accepts_suggestion() {
+        use crate::fuzzy::FuzzyFinder;
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        let items = vec!["apple".to_string(), "banana".to_string()];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("bananna".to_string()).await;
+        assert!(finder.get_filtered_items().is_empty());
+
+        let key_event = crossterm::event::KeyEvent::new(KeyCode::Char('y'), KeyModifiers::CONTROL);
+        let action = events::handle_async_key_event(
+            &key_event,
+            &mut finder,
+            &mut PreviewState::new(),
+            &ScreenLayout::default(),
+        )
+        .await;
+
+        assert_eq!(action, crate::tui::controls::Action::Continue);
+        assert_eq!(finder.get_query(), "banana");
+        assert_eq!(finder.get_filtered_items(), &["banana"]);
+    }
+
+    #[tokio::test]
+    async fn test_handle_mouse_event() {
+        use crate::fuzzy::FuzzyFinder;
+        use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+        let items = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let mut finder = FuzzyFinder::with_items_async(items, true).await;
+        let mut preview = PreviewState::new();
+        preview.visible = true;
+        preview.lines = vec![Vec::new(); 10];
+        let layout = ScreenLayout {
+            list_top: 1,
+            upward: false,
+            rows: vec![Some(0), Some(1), Some(2)],
+            preview_col: Some(40),
+            ..Default::default()
+        };
+        let mut mouse = |kind, column, row| {
+            let event = MouseEvent {
+                kind,
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            };
+            events::handle_mouse_event(&event, &mut finder, &mut preview, &layout)
+        };
+        let click = MouseEventKind::Down(MouseButton::Left);
+
+        // Clicking an item moves the cursor; clicking it again selects it
+        assert_eq!(mouse(click, 5, 2), Action::Continue);
+        assert_eq!(mouse(click, 5, 2), Action::Continue);
+        mouse(MouseEventKind::Down(MouseButton::Middle), 5, 3);
+        // Wheel over the list moves the cursor, over the preview scrolls it
+        mouse(MouseEventKind::ScrollUp, 5, 3);
+        mouse(MouseEventKind::ScrollDown, 50, 3);
+        // Clicking the preview focuses it; the prompt row is ignored
+        mouse(click, 50, 0);
+        mouse(click, 5, 0);
+
+        assert_eq!(finder.get_cursor_position(), 1);
+        assert_eq!(
+            finder.get_selected_items(),
+            vec![(1, "b".to_string()), (2, "c".to_string())]
+        );
+        assert_eq!(preview.scroll, 3);
+        assert!(preview.focused);
+    }
+
+    #[tokio::test]
+    async fn test_grid_navigation() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let items: Vec<String> = (0..10).map(|i| format!("item{i}")).collect();
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        let mut preview = PreviewState::new();
+        preview.visible = true;
+        // Rows of four: 0-3, 4-7, 8-9
+        let layout = ScreenLayout {
+            rows: vec![Some(0), Some(4), Some(8)],
+            columns: 4,
+            cell_width: 10,
+            len: 10,
+            ..Default::default()
+        };
+        let steps = [
+            (KeyCode::Down, 4),
+            (KeyCode::Right, 5),
+            (KeyCode::Right, 6),
+            // No row below column 2 of the last row
+            (KeyCode::Down, 6),
+            (KeyCode::Left, 5),
+            (KeyCode::Up, 1),
+            (KeyCode::Right, 2),
+            (KeyCode::Right, 3),
+            // Right past the end of the row focuses the preview
+            (KeyCode::Right, 3),
+        ];
+        for (code, cursor) in steps {
+            assert!(!preview.focused);
+            let event = KeyEvent::new(code, KeyModifiers::NONE);
+            events::handle_async_key_event(&event, &mut finder, &mut preview, &layout).await;
+            assert_eq!(finder.get_cursor_position(), cursor, "after {code:?}");
+        }
+        assert!(preview.focused);
+    }
+
+    #[tokio::test]
+    async fn test_grid_left_with_query() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let items: Vec<String> = (0..8).map(|i| format!("item{i}")).collect();
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("item".to_string()).await;
+        let mut preview = PreviewState::new();
+        let layout = ScreenLayout {
+            rows: vec![Some(0), Some(4)],
+            columns: 4,
+            cell_width: 10,
+            len: 8,
+            ..Default::default()
+        };
+        finder.move_cursor_clamped(2);
+        let left = KeyEvent::new(KeyCode::Left, KeyModifiers::NONE);
+        events::handle_async_key_event(&left, &mut finder, &mut preview, &layout).await;
+        assert_eq!(finder.get_cursor_position(), 1);
+        assert_eq!(finder.get_query_cursor(), 4);
+
+        // At the start of the row the query's cursor moves instead
+        events::handle_async_key_event(&left, &mut finder, &mut preview, &layout).await;
+        events::handle_async_key_event(&left, &mut finder, &mut preview, &layout).await;
+        assert_eq!(finder.get_cursor_position(), 0);
+        assert_eq!(finder.get_query_cursor(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_keymap_first_result_with_query() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let items: Vec<String> = ["ab", "abc", "abcd"].map(String::from).to_vec();
+        let mut preview = PreviewState::new();
+        let layout = ScreenLayout::default();
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        let g = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE);
+        let alt_lt = KeyEvent::new(KeyCode::Char('<'), KeyModifiers::ALT);
+        for (keymap, keys) in [
+            (Keymap::Vim, vec![esc, g, g]),
+            (Keymap::Emacs, vec![alt_lt]),
+        ] {
+            let mut finder = FuzzyFinder::with_items_async(items.clone(), false).await;
+            finder.set_query("ab".to_string()).await;
+            finder.move_cursor_clamped(2);
+            let mut translator = KeyTranslator::new(keymap, false);
+            for key in keys {
+                if let Some(key) = translator.translate(&key) {
+                    events::handle_async_key_event(&key, &mut finder, &mut preview, &layout).await;
+                }
+            }
+            assert_eq!(finder.get_cursor_position(), 0, "{keymap:?}");
+            assert_eq!(finder.get_query_cursor(), 2, "{keymap:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_editing_keys() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let items: Vec<String> = ["abc", "ac", "xyz"].map(String::from).to_vec();
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        let mut preview = PreviewState::new();
+        let layout = ScreenLayout::default();
+        for code in [
+            KeyCode::Char('a'),
+            KeyCode::Char('c'),
+            KeyCode::Left,
+            KeyCode::Char('b'),
+            KeyCode::Home,
+            KeyCode::Delete,
+            KeyCode::Char('x'),
+            KeyCode::Backspace,
+            KeyCode::Char('a'),
+            KeyCode::End,
+            KeyCode::Down,
+        ] {
+            let event = KeyEvent::new(code, KeyModifiers::NONE);
+            events::handle_async_key_event(&event, &mut finder, &mut preview, &layout).await;
+        }
+        assert_eq!(finder.get_query(), "abc");
+        assert_eq!(finder.get_query_cursor(), 3);
+        assert_eq!(finder.get_filtered_items(), &["abc".to_string()]);
+
+        // At the end of the query, End jumps through the list
+        finder.set_query("a".to_string()).await;
+        let end = KeyEvent::new(KeyCode::End, KeyModifiers::NONE);
+        events::handle_async_key_event(&end, &mut finder, &mut preview, &layout).await;
+        assert_eq!(finder.get_cursor_position(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bound_command() {
+        let items: Vec<String> = ["a b", "it's"].map(String::from).to_vec();
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        let binding = KeyBinding::parse("ctrl-o:execute(open {} --query {q})").unwrap();
+        finder.set_query("it".to_string()).await;
+        assert_eq!(
+            bound_command(&binding, &finder).as_deref(),
+            Some(r#"open 'it'"'"'s' --query 'it'"#)
+        );
+
+        // Nothing under the cursor, nothing to run
+        finder.set_query("zzz".to_string()).await;
+        assert_eq!(bound_command(&binding, &finder), None);
+    }
+
+    #[tokio::test]
+    async fn test_output_without_tui() {
+        let items: Vec<String> = ["apple", "banana"].map(String::from).to_vec();
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        let mut config = TuiConfig {
+            select_one: true,
+            exit_zero: true,
+            ..TuiConfig::default()
+        };
+        assert_eq!(output_without_tui(&finder, &config), None);
+
+        finder.set_query("ban".to_string()).await;
+        let output = output_without_tui(&finder, &config).unwrap();
+        assert_eq!(output.selected, vec![(1, "banana".to_string())]);
+        assert!(output.accepted);
+
+        finder.set_query("zzz".to_string()).await;
+        let output = output_without_tui(&finder, &config).unwrap();
+        assert!(output.selected.is_empty());
+        assert_eq!(output.query, "zzz");
+
+        // Each only when asked for
+        config.exit_zero = false;
+        assert_eq!(output_without_tui(&finder, &config), None);
+        config.select_one = false;
+        finder.set_query("ban".to_string()).await;
+        assert_eq!(output_without_tui(&finder, &config), None);
+    }
+
+    #[tokio::test]
+    async fn test_accept_unmatched_query() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let items: Vec<String> = ["abc"].map(String::from).to_vec();
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("new".to_string()).await;
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        let mut config = TuiConfig::default();
+
+        let action = accept_unmatched_query(Action::Continue, &enter, &finder, &config);
+        assert!(matches!(action, Action::Continue));
+
+        config.print_query = true;
+        let action = accept_unmatched_query(Action::Continue, &enter, &finder, &config);
+        assert!(matches!(action, Action::Select(items) if items.is_empty()));
+
+        // Only when nothing matches
+        finder.set_query("ab".to_string()).await;
+        let action = accept_unmatched_query(Action::Continue, &enter, &finder, &config);
+        assert!(matches!(action, Action::Continue));
+    }
+
+    #[tokio::test]
+    async fn test_readline_editing_keys() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut finder = FuzzyFinder::with_items_async(vec!["bar".to_string()], false).await;
+        let mut preview = PreviewState::new();
+        let layout = ScreenLayout::default();
+        finder.set_query("foo-bar baz".to_string()).await;
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        let alt = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT);
+        let alt_backspace = KeyEvent::new(KeyCode::Backspace, KeyModifiers::ALT);
+        let steps = [
+            (ctrl('w'), "foo-bar ", 8),
+            (alt('b'), "foo-bar ", 4),
+            (alt_backspace, "bar ", 0),
+            (ctrl('e'), "bar ", 4),
+            (alt('b'), "bar ", 0),
+            (alt('f'), "bar ", 3),
+            (ctrl('k'), "bar", 3),
+            (ctrl('a'), "bar", 0),
+            (ctrl('e'), "bar", 3),
+            (ctrl('u'), "", 0),
+        ];
+        for (event, query, cursor) in steps {
+            events::handle_async_key_event(&event, &mut finder, &mut preview, &layout).await;
+            assert_eq!(finder.get_query(), query, "after {event:?}");
+            assert_eq!(finder.get_query_cursor(), cursor, "after {event:?}");
+        }
+
+        // With the preview shown, Ctrl+U scrolls it instead
+        finder.set_query("bar".to_string()).await;
+        preview.visible = true;
+        events::handle_async_key_event(&ctrl('u'), &mut finder, &mut preview, &layout).await;
+        assert_eq!(finder.get_query(), "bar");
+    }
+
+    #[test]
+    fn test_draw_query_cursor() {
+        let mut buffer = ScreenBuffer::new(10, 1);
+        assert_eq!(draw_query(&mut buffer, 2, 0, "日本", 3), 4);
+        assert!(buffer.get_cell(4, 0).unwrap().reverse);
+        assert!(!buffer.get_cell(2, 0).unwrap().reverse);
+
+        // Past the end, the cursor takes a column of its own
+        let mut buffer = ScreenBuffer::new(10, 1);
+        assert_eq!(draw_query(&mut buffer, 0, 0, "ab", 2), 3);
+        assert!(buffer.get_cell(2, 0).unwrap().reverse);
+    }
+
+    #[test]
+    fn test_draw_suggestion_clipped_to_pane() {
+        let mut buffer = ScreenBuffer::new(40, 3);
+        draw_suggestion(&mut buffer, 1, "guide", 20, &Theme::default());
+        let row: String = (0..40)
+            .map(|x| buffer.get_cell(x, 1).map_or(' ', |c| c.ch))
+            .collect();
+        assert_eq!(row.trim_end(), "Did you mean guide?");
+    }
+
+    #[test]
+    fn test_draw_suggestion_clips_wide_chars() {
+        let mut buffer = ScreenBuffer::new(40, 1);
+        draw_suggestion(&mut buffer, 0, "東京都", 16, &Theme::default());
+        let row: String = (0..40)
+            .map(|x| buffer.get_cell(x, 0).map_or(' ', |c| c.ch))
+            .filter(|&ch| ch != crate::tui::buffer::WIDE_CONTINUATION)
+            .collect();
+        // `京` would straddle the pane edge, so it is left out
+        assert_eq!(row.trim_end(), "Did you mean 東?");
+    }
+
+    #[test]
+    fn test_strip_selection_ansi() {
+        let mut selected = vec![
+            (
+                0,
+                "\x1b[35msrc/main.rs\x1b[0m:\x1b[32m12\x1b[0m".to_string(),
+            ),
+            (3, "plain".to_string()),
+        ];
+        strip_selection_ansi(&mut selected);
+        assert_eq!(
+            selected,
+            vec![(0, "src/main.rs:12".to_string()), (3, "plain".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_until_next_frame() {
+        assert!(until_next_frame(None).is_zero());
+        assert!(!until_next_frame(Some(Instant::now())).is_zero());
+        let long_ago = Instant::now() - FRAME_INTERVAL * 2;
+        assert!(until_next_frame(Some(long_ago)).is_zero());
+    }
+
+    #[test]
+    fn test_input_wake_cancels_watched_pass() {
+        let wake = Arc::new(InputWake::default());
+        let waker = futures::task::waker(Arc::clone(&wake));
+        let token = CancelToken::new();
+        waker.wake_by_ref();
+        assert!(!token.is_cancelled());
+
+        *wake.watched.lock().unwrap() = Some(token.clone());
+        waker.wake_by_ref();
+        assert!(token.is_cancelled());
+
+        // Dropping the watch stops it and forgets the cancel
+        drop(InputWatch {
+            wake: Arc::clone(&wake),
+            token: token.clone(),
+        });
+        assert!(!token.is_cancelled());
+        waker.wake_by_ref();
+        assert!(!token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_draw_info_counts_matches() {
+        let items = vec!["apple".to_string(), "banana".to_string()];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        finder.set_query("ban".to_string()).await;
+
+        let mut buffer = ScreenBuffer::new(20, 2);
+        let used = draw_info(&mut buffer, 3, 1, &finder, None, &Theme::default());
+        let row: String = (0..20)
+            .map(|x| buffer.get_cell(x, 1).map_or(' ', |c| c.ch))
+            .collect();
+        assert_eq!(row.trim_end(), "     1/2");
+        assert_eq!(used, 5);
+    }
+
+    #[test]
+    fn test_draw_tab_bar() {
+        let tab = |name: &str| Tab {
+            name: name.to_string(),
+            source: ItemSource::new(|| create_items_channel().1),
+        };
+        let tabs = [tab("files"), tab("git")];
+        let mut buffer = ScreenBuffer::new(20, 1);
+        draw_tab_bar(&mut buffer, 0, &tabs, 1, 2, 20, &Theme::default());
+        let row: String = (0..20)
+            .map(|x| buffer.get_cell(x, 0).map_or(' ', |c| c.ch))
+            .collect();
+        assert_eq!(row, "         files  git ");
+        assert!(buffer.get_cell(16, 0).unwrap().bold);
+
+        // Too narrow: nothing is drawn
+        let mut buffer = ScreenBuffer::new(20, 1);
+        draw_tab_bar(&mut buffer, 0, &tabs, 1, 10, 20, &Theme::default());
+        assert_eq!(buffer.get_cell(15, 0).unwrap().ch, ' ');
+    }
+
+    #[tokio::test]
+    async fn test_score_label() {
+        let items = vec!["apple".to_string(), "banana".to_string()];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        assert_eq!(score_label(&finder, 0), None);
+        finder.set_query("ban".to_string()).await;
+        let label = score_label(&finder, 0).unwrap();
+        assert!(label.starts_with(" Prefix "), "{label}");
+        assert!(label.ends_with(" @0,1,2"), "{label}");
+
+        // Right-aligned, over at most half the row
+        let mut buffer = ScreenBuffer::new(20, 1);
+        buffer.put_str_plain(0, 0, "banana");
+        draw_score_label(
+            &mut buffer,
+            0,
+            Some(&label),
+            false,
+            0,
+            20,
+            &Theme::default(),
+        );
+        let row: String = (0..20)
+            .map(|x| buffer.get_cell(x, 0).map_or(' ', |c| c.ch))
+            .collect();
+        assert!(row.starts_with("banana"), "{row}");
+        assert_eq!(&row[10..], &label[..10]);
+    }
+
+    #[tokio::test]
+    async fn test_draw_info_selection_limit() {
+        let items = vec!["apple".to_string(), "banana".to_string()];
+        let mut finder = FuzzyFinder::with_items_async(items, true).await;
+        finder.set_max_selected(Some(1));
+        finder.select_all();
+
+        let mut buffer = ScreenBuffer::new(40, 1);
+        draw_info(&mut buffer, 0, 0, &finder, None, &Theme::default());
+        let row: String = (0..40)
+            .map(|x| buffer.get_cell(x, 0).map_or(' ', |c| c.ch))
+            .collect();
+        assert_eq!(row.trim_end(), "  2/2 (1/1) selection limit reached");
+    }
+
+    #[tokio::test]
+    async fn test_draw_info_spinner_while_loading() {
+        let items = vec!["apple".to_string(), "banana".to_string()];
+        let finder = FuzzyFinder::with_items_async(items, false).await;
+
+        let mut buffer = ScreenBuffer::new(20, 1);
+        let used = draw_info(
+            &mut buffer,
+            0,
+            0,
+            &finder,
+            Some(SPINNER_FRAMES[0]),
+            &Theme::default(),
+        );
+        let row: String = (0..20)
+            .map(|x| buffer.get_cell(x, 0).map_or(' ', |c| c.ch))
+            .collect();
+        assert_eq!(row.trim_end(), format!("  2/2 {}", SPINNER_FRAMES[0]));
+        assert_eq!(used, 7);
+        assert_eq!(
+            buffer.get_cell(6, 0).unwrap().fg,
+            Some(Theme::default().spinner)
+        );
+    }
+
+    #[test]
+    fn test_item_indicators_use_theme_colors() {
+        let theme = Theme::parse("success:blue,error:magenta").unwrap();
+        let mut buffer = ScreenBuffer::new(10, 2);
+        for (row, indicator) in [ItemIndicator::Success, ItemIndicator::Error]
+            .iter()
+            .enumerate()
+        {
+            draw_item_with_indicator_to_buffer_left(
+                &mut buffer,
+                row as u16,
+                "item",
+                false,
+                false,
+                None,
+                Some(indicator),
+                0,
+                0,
+                10,
+                &theme,
+                false,
+                &mut 0,
+            );
+        }
+        assert_eq!(buffer.get_cell(0, 0).unwrap().fg, Some(Color::Blue));
+        assert_eq!(buffer.get_cell(0, 1).unwrap().fg, Some(Color::Magenta));
+
+        // Dropped along with every other color
+        buffer.set_color_depth(ColorDepth::NoColor);
+        assert!(!buffer.render_fullscreen().contains("\x1b[38"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_async_key_event_scrolls_preview() {
+        use crate::fuzzy::FuzzyFinder;
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        let items = vec!["apple".to_string(), "banana".to_string()];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        let mut preview = PreviewState::new();
+        preview.visible = true;
+        preview.height = 10;
+        preview.lines = crate::tui::preview::parse_ansi_output(&"line\n".repeat(50));
+
+        let key = |code| crossterm::event::KeyEvent::new(code, KeyModifiers::SHIFT);
+        events::handle_async_key_event(
+            &key(KeyCode::Down),
+            &mut finder,
+            &mut preview,
+            &ScreenLayout::default(),
+        )
+        .await;
+        assert_eq!(preview.scroll, 1);
+        events::handle_async_key_event(
+            &key(KeyCode::PageDown),
+            &mut finder,
+            &mut preview,
+            &ScreenLayout::default(),
+        )
+        .await;
+        assert_eq!(preview.scroll, 11);
+        events::handle_async_key_event(
+            &key(KeyCode::Up),
+            &mut finder,
+            &mut preview,
+            &ScreenLayout::default(),
+        )
+        .await;
+        assert_eq!(preview.scroll, 10);
+        // The list cursor stays put
+        assert_eq!(finder.get_cursor_position(), 0);
+
+        // Plain paging scrolls the preview once it is focused
+        preview.focused = true;
+        let page_up = crossterm::event::KeyEvent::new(KeyCode::PageUp, KeyModifiers::NONE);
+        events::handle_async_key_event(
+            &page_up,
+            &mut finder,
+            &mut preview,
+            &ScreenLayout::default(),
+        )
+        .await;
+        assert_eq!(preview.scroll, 0);
+    }
+
     #[tokio::test]
     async fn test_handle_async_key_event_escape_twice_exits() {
         use crate::fuzzy::FuzzyFinder;
@@ -1746,14 +3897,24 @@ mod tests {
         let key_event = crossterm::event::KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
 
         // First Escape: clears query
-        let action1 =
-            events::handle_async_key_event(&key_event, &mut finder, &mut PreviewState::new()).await;
+        let action1 = events::handle_async_key_event(
+            &key_event,
+            &mut finder,
+            &mut PreviewState::new(),
+            &ScreenLayout::default(),
+        )
+        .await;
         assert_eq!(action1, crate::tui::controls::Action::Continue);
         assert!(finder.get_query().is_empty());
 
         // Second Escape: exits
-        let action2 =
-            events::handle_async_key_event(&key_event, &mut finder, &mut PreviewState::new()).await;
+        let action2 = events::handle_async_key_event(
+            &key_event,
+            &mut finder,
+            &mut PreviewState::new(),
+            &ScreenLayout::default(),
+        )
+        .await;
         assert_eq!(action2, crate::tui::controls::Action::Exit);
     }
 
@@ -1774,8 +3935,13 @@ mod tests {
 
         // Escape to clear query
         let key_event = crossterm::event::KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
-        let action =
-            events::handle_async_key_event(&key_event, &mut finder, &mut PreviewState::new()).await;
+        let action = events::handle_async_key_event(
+            &key_event,
+            &mut finder,
+            &mut PreviewState::new(),
+            &ScreenLayout::default(),
+        )
+        .await;
 
         assert_eq!(action, crate::tui::controls::Action::Continue);
         assert!(finder.get_query().is_empty());
@@ -1939,4 +4105,313 @@ mod tests {
         let output_str = String::from_utf8(output).unwrap();
         assert!(output_str.contains("[*]"));
     }
+
+    fn draw_long_item(keep_right: bool, positions: Vec<usize>, hscroll: &mut isize) -> String {
+        let mut buffer = ScreenBuffer::new(10, 1);
+        let matches = crate::fuzzy::finder::MatchPositions {
+            positions,
+            score: 0,
+            tier: crate::fuzzy::scoring::MatchTier::Fuzzy,
+        };
+        draw_ansi_item_text(
+            &mut buffer,
+            0,
+            "src/日本/main.rs",
+            0,
+            10,
+            false,
+            None,
+            None,
+            false,
+            Some(&matches),
+            &Theme::default(),
+            keep_right,
+            hscroll,
+        );
+        (0..10)
+            .filter_map(|x| buffer.get_cell(x, 0))
+            .map(|c| if c.underline { '_' } else { c.ch })
+            .filter(|&ch| ch != crate::tui::buffer::WIDE_CONTINUATION)
+            .collect()
+    }
+
+    #[test]
+    fn test_long_item_truncated_with_ellipsis() {
+        // "src/日本/main.rs" is 16 columns wide; the wide chars take two
+        assert_eq!(draw_long_item(false, vec![], &mut 0), "src/日本/…");
+        // Matches in view don't scroll (underlined matches drawn as `_`)
+        assert_eq!(draw_long_item(false, vec![1], &mut 0), "s_c/日本/…");
+    }
+
+    #[test]
+    fn test_long_item_keep_right() {
+        // `本` would not fit next to the ellipsis, leaving a blank column
+        assert_eq!(draw_long_item(true, vec![7], &mut 0), "…/_ain.rs ");
+    }
+
+    #[test]
+    fn test_long_item_scrolls_to_last_match() {
+        // `.` (char 11) is cut off, so the row scrolls to show it
+        assert_eq!(draw_long_item(false, vec![11], &mut 0), "…本/main_…");
+    }
+
+    #[test]
+    fn test_long_item_hscroll_is_clamped() {
+        let mut hscroll = 2;
+        assert_eq!(draw_long_item(false, vec![], &mut hscroll), "…c/日本/m…");
+        let mut hscroll = 100;
+        assert_eq!(draw_long_item(false, vec![], &mut hscroll), "…/main.rs ");
+        assert_eq!(hscroll, 6);
+        let mut hscroll = -5;
+        draw_long_item(false, vec![], &mut hscroll);
+        assert_eq!(hscroll, 0);
+    }
+
+    #[test]
+    fn test_multiline_item_drawn_on_one_row() {
+        let mut buffer = ScreenBuffer::new(20, 1);
+        let matches = crate::fuzzy::finder::MatchPositions {
+            positions: vec![3],
+            score: 0,
+            tier: crate::fuzzy::scoring::MatchTier::Fuzzy,
+        };
+        let end = draw_ansi_item_text(
+            &mut buffer,
+            0,
+            "ab\ncd",
+            0,
+            20,
+            false,
+            None,
+            None,
+            false,
+            Some(&matches),
+            &Theme::default(),
+            false,
+            &mut 0,
+        );
+        assert_eq!(end, 5);
+        let row: String = (0..5).map(|x| buffer.get_cell(x, 0).unwrap().ch).collect();
+        assert_eq!(row, "ab↵cd");
+        // Positions count the line break, so `c` (index 3) is highlighted
+        assert!(buffer.get_cell(3, 0).unwrap().underline);
+        assert!(!buffer.get_cell(4, 0).unwrap().underline);
+    }
+
+    #[test]
+    fn test_match_style_from_theme() {
+        let matches = crate::fuzzy::finder::MatchPositions {
+            positions: vec![0],
+            score: 0,
+            tier: crate::fuzzy::scoring::MatchTier::Fuzzy,
+        };
+        let draw = |theme: &Theme| {
+            let mut buffer = ScreenBuffer::new(5, 1);
+            draw_ansi_item_text(
+                &mut buffer,
+                0,
+                "ab",
+                0,
+                5,
+                false,
+                None,
+                None,
+                false,
+                Some(&matches),
+                theme,
+                false,
+                &mut 0,
+            );
+            buffer.get_cell(0, 0).unwrap().clone()
+        };
+        let cell = draw(&Theme::parse("hl:underline").unwrap());
+        assert!(cell.underline && !cell.bold);
+        assert_eq!((cell.fg, cell.bg), (None, None));
+
+        let cell = draw(&Theme::parse("hl:black:bold,hl-bg:yellow").unwrap());
+        assert!(cell.bold && !cell.underline);
+        assert_eq!(
+            (cell.fg, cell.bg),
+            (Some(Color::Black), Some(Color::Yellow))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_rows_grid() {
+        let items: Vec<String> = (0..10).map(|i| format!("item{i}")).collect();
+        let finder = FuzzyFinder::with_items_async(items, false).await;
+        let mut offset = 4;
+        assert_eq!(
+            list_rows(&finder, &mut offset, 3, 4, None),
+            vec![ListRow::Item(4), ListRow::Item(8)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_rows_inserts_group_headers() {
+        let items = vec![
+            "GET /api/users/17 200 12ms".to_string(),
+            "something unrelated".to_string(),
+            "GET /api/users/18 200 13ms".to_string(),
+        ];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        let mut offset = 0;
+        assert_eq!(
+            list_rows(&finder, &mut offset, 10, 1, None),
+            vec![ListRow::Item(0), ListRow::Item(1), ListRow::Item(2)]
+        );
+
+        finder.set_group_similar(true).await;
+        let rows = list_rows(&finder, &mut offset, 10, 1, None);
+        assert_eq!(rows.len(), 5);
+        assert!(matches!(
+            rows[0],
+            ListRow::Header(SimilarGroup {
+                len: 2,
+                clustered: true,
+                ..
+            })
+        ));
+        assert_eq!(rows[1..3], [ListRow::Item(0), ListRow::Item(1)]);
+        assert!(matches!(
+            rows[3],
+            ListRow::Header(SimilarGroup {
+                clustered: false,
+                ..
+            })
+        ));
+
+        // Headers push the last item out of a short view; scrolling keeps the cursor visible
+        finder.move_cursor(2);
+        let rows = list_rows(&finder, &mut offset, 3, 1, None);
+        assert!(rows.contains(&ListRow::Item(2)));
+        assert!(offset > 0);
+    }
+
+    #[tokio::test]
+    async fn test_list_rows_wrapped() {
+        let items = vec!["a".repeat(10), "b".to_string(), "c".repeat(6)];
+        let mut finder = FuzzyFinder::with_items_async(items, false).await;
+        let lines = |index: usize| {
+            let item = &finder.get_filtered_items()[index];
+            wrapped_lines(item, 4, &Theme::default()).len()
+        };
+        let mut offset = 0;
+        assert_eq!(
+            list_rows(&finder, &mut offset, 5, 1, Some(&lines)),
+            vec![
+                ListRow::Item(0),
+                ListRow::Wrapped(0, 1),
+                ListRow::Wrapped(0, 2),
+                ListRow::Item(1),
+                ListRow::Item(2),
+            ]
+        );
+
+        // Scrolled a whole item at a time to show all of the cursor's
+        finder.move_cursor(2);
+        let lines = |index: usize| {
+            let item = &finder.get_filtered_items()[index];
+            wrapped_lines(item, 4, &Theme::default()).len()
+        };
+        let rows = list_rows(&finder, &mut offset, 5, 1, Some(&lines));
+        assert_eq!(offset, 1);
+        assert_eq!(
+            rows,
+            vec![ListRow::Item(1), ListRow::Item(2), ListRow::Wrapped(2, 1)]
+        );
+    }
+
+    #[test]
+    fn test_draw_wrapped_item_line() {
+        let mut buffer = ScreenBuffer::new(6, 2);
+        let theme = Theme::default();
+        let markers = Markers::Glyph;
+        draw_wrapped_item_line(
+            &mut buffer,
+            0,
+            "abcdef",
+            0,
+            false,
+            true,
+            markers,
+            None,
+            6,
+            &theme,
+        );
+        draw_wrapped_item_line(
+            &mut buffer,
+            1,
+            "abcdef",
+            1,
+            false,
+            true,
+            markers,
+            None,
+            6,
+            &theme,
+        );
+        let row = |y| {
+            (0..6)
+                .map(|x| buffer.get_cell(x, y).unwrap().ch)
+                .collect::<String>()
+        };
+        assert_eq!(row(0), "✓ abcd");
+        assert_eq!(row(1), "  ef  ");
+    }
+
+    #[test]
+    fn test_text_markers() {
+        let mut buffer = ScreenBuffer::new(10, 3);
+        let theme = Theme::default();
+        let markers = Markers::Text { checkboxes: true };
+        draw_wrapped_item_line(
+            &mut buffer,
+            0,
+            "abcdef",
+            0,
+            true,
+            true,
+            markers,
+            None,
+            10,
+            &theme,
+        );
+        draw_wrapped_item_line(
+            &mut buffer,
+            1,
+            "abcdef",
+            1,
+            true,
+            true,
+            markers,
+            None,
+            10,
+            &theme,
+        );
+        let mut hscroll = 0;
+        draw_item_to_buffer_left(
+            &mut buffer,
+            2,
+            "xy",
+            false,
+            false,
+            markers,
+            None,
+            0,
+            10,
+            &theme,
+            false,
+            &mut hscroll,
+        );
+        let row = |y| {
+            (0..10)
+                .map(|x| buffer.get_cell(x, y).unwrap().ch)
+                .collect::<String>()
+        };
+        assert_eq!(row(0), "> [x] abcd");
+        assert_eq!(row(1), "      ef  ");
+        assert_eq!(row(2), "  [ ] xy  ");
+        assert_eq!(Markers::Text { checkboxes: false }.marker(true, true), "> ");
+    }
 }
@@ -0,0 +1,370 @@
+//! Where the TUI is drawn on the terminal: the whole alternate screen, or
+//! an inline area of rows from the cursor down, left behind cleared.
+//!
+//! Inline, the area is kept on screen by scrolling the terminal from its
+//! bottom row, so the output above moves up by exactly the rows needed.
+//!
+//! The viewport also owns the terminal modes the TUI needs (raw mode,
+//! bracketed paste, mouse capture), and is kept where a panic hook can find
+//! it, to give the terminal back before the panic message is printed.
+
+use crate::tui::layout;
+use crossterm::{
+    cursor::{position, Hide, MoveTo, Show},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
+    execute, queue,
+    terminal::{
+        disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::sync::{Mutex, Once};
+
+/// What a TUI that has the terminal changed about it, if any
+static ACTIVE: Mutex<Option<Active>> = Mutex::new(None);
+
+thread_local! {
+    /// Whether this thread is running a step of a TUI session (see
+    /// [`in_session`])
+    static IN_SESSION: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Terminal state to undo in [`restore_terminal`]
+#[derive(Debug, Clone, Copy)]
+struct Active {
+    mouse: bool,
+    /// The area drawn in, once there is one
+    viewport: Option<Viewport>,
+}
+
+/// The terminal area the TUI is drawn in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    fullscreen: bool,
+    /// Whether mouse events are captured
+    mouse: bool,
+    /// Cursor position when ff started, restored on leaving
+    cursor: (u16, u16),
+    /// First row of the inline area
+    top: u16,
+    /// Rows of the inline area
+    height: u16,
+}
+
+impl Viewport {
+    /// Take over the terminal for a TUI `height` rows high, on the
+    /// alternate screen if `fullscreen` or if the cursor position can't be
+    /// found, otherwise from the cursor row down. Raw mode and bracketed
+    /// paste are turned on, and mouse capture if `mouse`; should this fail
+    /// partway, [`restore_terminal`] turns them off again.
+    pub fn enter(
+        out: &mut impl Write,
+        fullscreen: bool,
+        height: u16,
+        mouse: bool,
+    ) -> io::Result<Self> {
+        enable_raw_mode()?;
+        if let Ok(mut active) = ACTIVE.lock() {
+            *active = Some(Active {
+                mouse,
+                viewport: None,
+            });
+        }
+        execute!(out, Hide, EnableBracketedPaste)?;
+        if mouse {
+            execute!(out, EnableMouseCapture)?;
+        }
+
+        let cursor = if fullscreen {
+            None
+        } else {
+            // Queried on stderr too, for when stdout isn't the terminal
+            position()
+                .or_else(|_| layout::get_cursor_position_from_stderr())
+                .ok()
+        };
+        let Some(cursor) = cursor else {
+            execute!(out, EnterAlternateScreen, Clear(ClearType::All))?;
+            let viewport = Self {
+                fullscreen: true,
+                mouse,
+                cursor: (0, 0),
+                top: 0,
+                height,
+            };
+            viewport.publish();
+            return Ok(viewport);
+        };
+        let mut viewport = Self {
+            fullscreen: false,
+            mouse,
+            cursor,
+            top: cursor.1,
+            height,
+        };
+        viewport.place(out, cursor.1, terminal_height())?;
+        Ok(viewport)
+    }
+
+    /// Terminal row the TUI's first row is drawn at
+    pub fn top(&self) -> u16 {
+        self.top
+    }
+
+    /// Resize the inline area to `height` rows: clear the rows it gives up,
+    /// or scroll the terminal up if the new ones don't fit. Returns whether
+    /// the size changed.
+    pub fn resize(
+        &mut self,
+        out: &mut impl Write,
+        height: u16,
+        term_height: u16,
+    ) -> io::Result<bool> {
+        if height == self.height {
+            return Ok(false);
+        }
+        let old_height = self.height;
+        self.height = height;
+        self.publish();
+        if self.fullscreen {
+            return Ok(true);
+        }
+        for row in self.top + height..self.top + old_height {
+            queue!(out, MoveTo(0, row), Clear(ClearType::CurrentLine))?;
+        }
+        self.place(out, self.top, term_height)?;
+        Ok(true)
+    }
+
+    /// Give the terminal back for a while (for a command to run in it): the
+    /// main screen, or the inline area cleared with the cursor at its top,
+    /// with the terminal modes off
+    pub fn suspend(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.fullscreen {
+            execute!(out, LeaveAlternateScreen)?;
+        } else {
+            self.clear(out)?;
+            execute!(out, MoveTo(0, self.top))?;
+        }
+        release(out, self.mouse)
+    }
+
+    /// Take the terminal over again after [`suspend`](Self::suspend).
+    /// Inline, the area starts on a fresh row below what was written
+    /// meanwhile.
+    pub fn resume(&mut self, out: &mut impl Write) -> io::Result<()> {
+        enable_raw_mode()?;
+        execute!(out, Hide, EnableBracketedPaste)?;
+        if self.mouse {
+            execute!(out, EnableMouseCapture)?;
+        }
+        if self.fullscreen {
+            return execute!(out, EnterAlternateScreen, Clear(ClearType::All));
+        }
+        let row = match position() {
+            Ok((0, row)) => row,
+            Ok((_, row)) => row + 1,
+            Err(_) => self.top,
+        };
+        self.place(out, row, terminal_height())
+    }
+
+    /// Give the terminal back for good, with the terminal modes off and the
+    /// cursor shown: the main screen, or the inline area cleared with the
+    /// cursor where ff started (at the start of its row if `line_start`, for
+    /// output to follow)
+    pub fn leave(&self, out: &mut impl Write, line_start: bool) -> io::Result<()> {
+        if let Ok(mut active) = ACTIVE.lock() {
+            active.take();
+        }
+        self.leave_screen(out, line_start)?;
+        release(out, self.mouse)
+    }
+
+    fn leave_screen(&self, out: &mut impl Write, line_start: bool) -> io::Result<()> {
+        if self.fullscreen {
+            return execute!(out, LeaveAlternateScreen);
+        }
+        self.clear(out)?;
+        let col = if line_start { 0 } else { self.cursor.0 };
+        execute!(out, MoveTo(col, self.top))
+    }
+
+    fn clear(&self, out: &mut impl Write) -> io::Result<()> {
+        for row in self.top..self.top + self.height {
+            queue!(out, MoveTo(0, row), Clear(ClearType::CurrentLine))?;
+        }
+        Ok(())
+    }
+
+    /// Start the inline area at `row`, or as far down as it fits, scrolling
+    /// the terminal for it
+    fn place(&mut self, out: &mut impl Write, row: u16, term_height: u16) -> io::Result<()> {
+        let (top, scroll) = placement(row, self.height, term_height);
+        if scroll > 0 {
+            // From the bottom row each newline scrolls by one
+            queue!(out, MoveTo(0, term_height.saturating_sub(1)))?;
+            for _ in 0..scroll {
+                writeln!(out)?;
+            }
+        }
+        self.top = top;
+        self.publish();
+        execute!(out, MoveTo(0, top))
+    }
+
+    /// Make this the viewport restored after a panic
+    fn publish(&self) {
+        if let Ok(mut active) = ACTIVE.lock() {
+            *active = Some(Active {
+                mouse: self.mouse,
+                viewport: Some(*self),
+            });
+        }
+    }
+}
+
+/// Show the cursor and turn off the modes [`Viewport::enter`] turned on
+fn release(out: &mut impl Write, mouse: bool) -> io::Result<()> {
+    execute!(out, Show, DisableBracketedPaste)?;
+    if mouse {
+        execute!(out, DisableMouseCapture)?;
+    }
+    disable_raw_mode()
+}
+
+/// Run `f`, a step of a TUI session, on this thread, so that a panic in it
+/// gives the terminal back (see [`install_panic_hook`])
+pub fn in_session<T>(f: impl FnOnce() -> T) -> T {
+    struct Reset(bool);
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            IN_SESSION.set(self.0);
+        }
+    }
+    let _reset = Reset(IN_SESSION.replace(true));
+    f()
+}
+
+/// Install, once, a panic hook that gives the terminal back (see
+/// [`restore_terminal`]) before running the hook it replaces.
+///
+/// Only panics in a session step (see [`in_session`]) restore it: a panic
+/// in another thread or task, e.g. a preview, leaves the TUI running. Built
+/// with `panic = "abort"` (as ff's release profile is) any panic ends the
+/// process, so the hook restores the terminal whichever thread panicked; it
+/// is also all that does, since nothing unwinds back to the session.
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if cfg!(panic = "abort") || IN_SESSION.get() {
+                restore_terminal();
+            }
+            previous(info);
+        }));
+    });
+}
+
+/// Give the terminal back if a TUI still has it, as after a panic or an
+/// error mid-session: leave the viewport, show the cursor, stop mouse
+/// capture and bracketed paste and leave raw mode. Best effort; errors are
+/// ignored.
+pub fn restore_terminal() {
+    // A panic while the lock is held leaves the terminal as it is
+    let Some(active) = ACTIVE.try_lock().ok().and_then(|mut active| active.take()) else {
+        return;
+    };
+    let mut out = io::stderr();
+    if let Some(viewport) = active.viewport {
+        let _ = viewport.leave_screen(&mut out, true);
+    }
+    let _ = release(&mut out, active.mouse);
+}
+
+/// Where an area `height` rows high wanted at `row` starts on a terminal
+/// `term_height` rows high, and how many rows the terminal scrolls up for it
+fn placement(row: u16, height: u16, term_height: u16) -> (u16, u16) {
+    let height = height.min(term_height);
+    match (row + height).checked_sub(term_height) {
+        Some(scroll) if scroll > 0 => (term_height - height, scroll),
+        _ => (row, 0),
+    }
+}
+
+fn terminal_height() -> u16 {
+    crossterm::terminal::size()
+        .or_else(|_| layout::get_terminal_size_from_stderr())
+        .map_or(24, |(_, height)| height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inline(top: u16, height: u16) -> Viewport {
+        Viewport {
+            fullscreen: false,
+            mouse: false,
+            cursor: (4, top),
+            top,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_placement() {
+        assert_eq!(placement(3, 10, 24), (3, 0));
+        assert_eq!(placement(14, 10, 24), (14, 0));
+        // Scrolled just enough to fit below the cursor row's output
+        assert_eq!(placement(20, 10, 24), (14, 6));
+        // A fresh row past the bottom
+        assert_eq!(placement(24, 5, 24), (19, 5));
+        // Taller than the terminal
+        assert_eq!(placement(5, 40, 24), (0, 5));
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut out = Vec::new();
+        let mut viewport = inline(10, 5);
+        assert!(!viewport.resize(&mut out, 5, 24).unwrap());
+        assert!(out.is_empty());
+
+        // Shrinking clears the rows given up
+        assert!(viewport.resize(&mut out, 3, 24).unwrap());
+        let written = String::from_utf8(out).unwrap();
+        assert!(
+            written.contains("\x1b[14;1H\x1b[2K\x1b[15;1H\x1b[2K"),
+            "{written:?}"
+        );
+        assert_eq!(viewport.top(), 10);
+
+        // Growing past the bottom scrolls
+        let mut out = Vec::new();
+        viewport.resize(&mut out, 20, 24).unwrap();
+        assert_eq!(viewport.top(), 4);
+        let written = String::from_utf8(out).unwrap();
+        assert_eq!(written.matches('\n').count(), 6);
+    }
+
+    #[test]
+    fn test_leave() {
+        let mut out = Vec::new();
+        inline(2, 2).leave(&mut out, false).unwrap();
+        let written = String::from_utf8(out).unwrap();
+        assert!(
+            written.starts_with("\x1b[3;1H\x1b[2K\x1b[4;1H\x1b[2K\x1b[3;5H"),
+            "{written:?}"
+        );
+
+        let mut out = Vec::new();
+        inline(2, 2).leave(&mut out, true).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .contains("\x1b[3;1H\x1b[?25h"));
+    }
+}
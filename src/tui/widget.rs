@@ -0,0 +1,206 @@
+//! [`FuzzyFinderWidget`]: ff's prompt and results as a ratatui widget, for
+//! applications that run their own terminal and event loop. Enabled by the
+//! `ratatui` feature.
+//!
+//! ```no_run
+//! # async fn example(frame_area: ratatui_core::layout::Rect, buf: &mut ratatui_core::buffer::Buffer) {
+//! use ff::tui::widget::{FuzzyFinderState, FuzzyFinderWidget};
+//! use ratatui_core::widgets::StatefulWidget;
+//!
+//! let finder = ff::FuzzyFinder::with_items_async(vec!["apple".into()], false).await;
+//! let mut state = FuzzyFinderState::new(finder);
+//! FuzzyFinderWidget::new().prompt("fruit> ").render(frame_area, buf, &mut state);
+//! # }
+//! ```
+
+use crate::fuzzy::FuzzyFinder;
+use crate::tui::buffer::{ScreenBuffer, WIDE_CONTINUATION};
+use crate::tui::controls::Action;
+use crate::tui::events::handle_async_key_event;
+use crate::tui::layout::ScreenLayout;
+use crate::tui::preview::PreviewState;
+use crate::tui::theme::Theme;
+use crate::tui::ui::draw_finder;
+use crossterm::event::KeyEvent;
+use crossterm::style::Color as CrosstermColor;
+use ratatui_core::buffer::Buffer;
+use ratatui_core::layout::Rect;
+use ratatui_core::style::{Color, Modifier, Style};
+use ratatui_core::widgets::StatefulWidget;
+
+/// State of a [`FuzzyFinderWidget`]: the finder, and how its results were
+/// last laid out
+pub struct FuzzyFinderState {
+    pub finder: FuzzyFinder,
+    scroll_offset: usize,
+    layout: ScreenLayout,
+    preview_state: PreviewState,
+}
+
+impl FuzzyFinderState {
+    pub fn new(finder: FuzzyFinder) -> Self {
+        Self {
+            finder,
+            scroll_offset: 0,
+            layout: ScreenLayout::default(),
+            preview_state: PreviewState::new(),
+        }
+    }
+
+    /// Edit the query or move through the results for `key_event`, as in
+    /// ff's own TUI. Pages are the height last rendered.
+    pub async fn handle_key(&mut self, key_event: &KeyEvent) -> Action {
+        handle_async_key_event(
+            key_event,
+            &mut self.finder,
+            &mut self.preview_state,
+            &self.layout,
+        )
+        .await
+    }
+}
+
+/// The prompt and query on the first row of its area, the result counter
+/// on the second and as many results as fit below
+#[derive(Debug, Clone, Default)]
+pub struct FuzzyFinderWidget<'a> {
+    prompt: &'a str,
+    theme: Theme,
+}
+
+impl<'a> FuzzyFinderWidget<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Text in front of the query
+    pub fn prompt(mut self, prompt: &'a str) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl StatefulWidget for FuzzyFinderWidget<'_> {
+    type State = FuzzyFinderState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = area.intersection(buf.area);
+        if area.is_empty() {
+            return;
+        }
+        let mut screen = ScreenBuffer::new(area.width, area.height);
+        state.layout = draw_finder(
+            &mut screen,
+            &state.finder,
+            &mut state.scroll_offset,
+            self.prompt,
+            &self.theme,
+        );
+        for y in 0..area.height {
+            for x in 0..area.width {
+                let (Some(from), Some(to)) = (
+                    screen.get_cell(x, y),
+                    buf.cell_mut((area.x + x, area.y + y)),
+                ) else {
+                    continue;
+                };
+                // The cell after a wide character is covered by it
+                if from.ch == WIDE_CONTINUATION {
+                    to.reset();
+                    continue;
+                }
+                let mut style = Style::reset();
+                if let Some(fg) = from.fg {
+                    style = style.fg(color(fg));
+                }
+                if let Some(bg) = from.bg {
+                    style = style.bg(color(bg));
+                }
+                for (on, modifier) in [
+                    (from.bold, Modifier::BOLD),
+                    (from.underline, Modifier::UNDERLINED),
+                    (from.reverse, Modifier::REVERSED),
+                ] {
+                    if on {
+                        style = style.add_modifier(modifier);
+                    }
+                }
+                to.set_char(from.ch).set_style(style);
+            }
+        }
+    }
+}
+
+/// The ratatui color for a crossterm one
+fn color(color: CrosstermColor) -> Color {
+    match color {
+        CrosstermColor::Reset => Color::Reset,
+        CrosstermColor::Black => Color::Black,
+        CrosstermColor::DarkGrey => Color::DarkGray,
+        CrosstermColor::Red => Color::LightRed,
+        CrosstermColor::DarkRed => Color::Red,
+        CrosstermColor::Green => Color::LightGreen,
+        CrosstermColor::DarkGreen => Color::Green,
+        CrosstermColor::Yellow => Color::LightYellow,
+        CrosstermColor::DarkYellow => Color::Yellow,
+        CrosstermColor::Blue => Color::LightBlue,
+        CrosstermColor::DarkBlue => Color::Blue,
+        CrosstermColor::Magenta => Color::LightMagenta,
+        CrosstermColor::DarkMagenta => Color::Magenta,
+        CrosstermColor::Cyan => Color::LightCyan,
+        CrosstermColor::DarkCyan => Color::Cyan,
+        CrosstermColor::White => Color::White,
+        CrosstermColor::Grey => Color::Gray,
+        CrosstermColor::Rgb { r, g, b } => Color::Rgb(r, g, b),
+        CrosstermColor::AnsiValue(value) => Color::Indexed(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    fn row_text(buf: &Buffer, y: u16) -> String {
+        (buf.area.x..buf.area.right())
+            .map(|x| buf[(x, y)].symbol())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_render_and_keys() {
+        let items = (1..=10).map(|i| format!("item{i}")).collect();
+        let finder = FuzzyFinder::with_items_async(items, false).await;
+        let mut state = FuzzyFinderState::new(finder);
+        let area = Rect::new(0, 0, 12, 5);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 14, 6));
+        FuzzyFinderWidget::new()
+            .prompt("> ")
+            .render(area, &mut buf, &mut state);
+        assert_eq!(row_text(&buf, 0), ">             ");
+        assert_eq!(row_text(&buf, 1), "  10/10       ");
+        assert_eq!(row_text(&buf, 2), "  item1       ");
+        assert_eq!(row_text(&buf, 4), "  item3       ");
+        assert!(buf[(0, 2)].modifier.contains(Modifier::BOLD));
+
+        // Typing filters, and the cursor moves down to the second match
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        state.handle_key(&key(KeyCode::Char('1'))).await;
+        state.handle_key(&key(KeyCode::Down)).await;
+        FuzzyFinderWidget::new()
+            .prompt("> ")
+            .render(area, &mut buf, &mut state);
+        assert_eq!(row_text(&buf, 0), "> 1           ");
+        assert_eq!(row_text(&buf, 1), "  2/10        ");
+        assert!(buf[(0, 3)].modifier.contains(Modifier::BOLD));
+        assert_eq!(
+            state.handle_key(&key(KeyCode::Enter)).await,
+            Action::Select(vec![(0, "item1".to_string())])
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Terminal display width of characters.
+//!
+//! East Asian wide characters and emoji take two columns, combining marks
+//! and other zero-width characters none. This covers the common ranges
+//! rather than the full Unicode tables.
+
+use std::ops::Range;
+
+/// Wide (two-column) character ranges
+const WIDE: &[(u32, u32)] = &[
+    (0x1100, 0x115F),
+    (0x231A, 0x231B),
+    (0x2329, 0x232A),
+    (0x23E9, 0x23EC),
+    (0x23F0, 0x23F0),
+    (0x23F3, 0x23F3),
+    (0x25FD, 0x25FE),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x267F, 0x267F),
+    (0x2693, 0x2693),
+    (0x26A1, 0x26A1),
+    (0x26AA, 0x26AB),
+    (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5),
+    (0x26CE, 0x26CE),
+    (0x26D4, 0x26D4),
+    (0x26EA, 0x26EA),
+    (0x26F2, 0x26F3),
+    (0x26F5, 0x26F5),
+    (0x26FA, 0x26FA),
+    (0x26FD, 0x26FD),
+    (0x2705, 0x2705),
+    (0x270A, 0x270B),
+    (0x2728, 0x2728),
+    (0x274C, 0x274C),
+    (0x274E, 0x274E),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27B0, 0x27B0),
+    (0x27BF, 0x27BF),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55),
+    (0x2E80, 0x303E),
+    (0x3041, 0x33FF),
+    (0x3400, 0x4DBF),
+    (0x4E00, 0x9FFF),
+    (0xA000, 0xA4CF),
+    (0xA960, 0xA97F),
+    (0xAC00, 0xD7A3),
+    (0xF900, 0xFAFF),
+    (0xFE10, 0xFE19),
+    (0xFE30, 0xFE6F),
+    (0xFF00, 0xFF60),
+    (0xFFE0, 0xFFE6),
+    (0x16FE0, 0x16FE4),
+    (0x17000, 0x18AFF),
+    (0x1B000, 0x1B2FF),
+    (0x1F004, 0x1F004),
+    (0x1F0CF, 0x1F0CF),
+    (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A),
+    (0x1F200, 0x1F251),
+    (0x1F300, 0x1F64F),
+    (0x1F680, 0x1F6FF),
+    (0x1F900, 0x1F9FF),
+    (0x1FA70, 0x1FAFF),
+    (0x20000, 0x2FFFD),
+    (0x30000, 0x3FFFD),
+];
+
+/// Zero-width character ranges: combining marks, joiners and variation
+/// selectors
+const ZERO: &[(u32, u32)] = &[
+    (0x0300, 0x036F),
+    (0x0483, 0x0489),
+    (0x0591, 0x05BD),
+    (0x0610, 0x061A),
+    (0x064B, 0x065F),
+    (0x1AB0, 0x1AFF),
+    (0x1DC0, 0x1DFF),
+    (0x200B, 0x200F),
+    (0x2060, 0x2064),
+    (0x20D0, 0x20FF),
+    (0xFE00, 0xFE0F),
+    (0xFE20, 0xFE2F),
+    (0xFEFF, 0xFEFF),
+    (0xE0100, 0xE01EF),
+];
+
+fn in_ranges(ranges: &[(u32, u32)], c: u32) -> bool {
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if end < c {
+                std::cmp::Ordering::Less
+            } else if start > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Number of terminal columns `ch` takes: 0, 1 or 2
+pub fn char_width(ch: char) -> u16 {
+    let c = ch as u32;
+    if c < 0x300 {
+        1
+    } else if in_ranges(ZERO, c) {
+        0
+    } else if in_ranges(WIDE, c) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Number of terminal columns `text` takes
+pub fn str_width(text: &str) -> usize {
+    text.chars().map(|ch| char_width(ch) as usize).sum()
+}
+
+/// The longest prefix of `text` that fits in `width` columns
+pub fn truncate_to_width(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, ch) in text.char_indices() {
+        used += char_width(ch) as usize;
+        if used > width {
+            return &text[..i];
+        }
+    }
+    text
+}
+
+/// Glyphs on each screen line when glyphs `widths` columns wide wrap at
+/// `width` columns; always at least one line
+pub fn wrap_ranges(widths: &[usize], width: usize) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let (mut start, mut used) = (0, 0);
+    for (i, &w) in widths.iter().enumerate() {
+        if used + w > width && i > start {
+            lines.push(start..i);
+            (start, used) = (i, 0);
+        }
+        used += w;
+    }
+    lines.push(start..widths.len());
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_ranges() {
+        assert_eq!(wrap_ranges(&[1, 1, 1, 1, 1], 2), [0..2, 2..4, 4..5]);
+        assert_eq!(wrap_ranges(&[1, 2, 2], 3), [0..2, 2..3]);
+        assert_eq!(wrap_ranges(&[], 3).len(), 1);
+        // A glyph wider than the line still gets one
+        assert_eq!(wrap_ranges(&[2, 2], 1), [0..1, 1..2]);
+    }
+
+    #[test]
+    fn test_char_width() {
+        assert_eq!(char_width('a'), 1);
+        assert_eq!(char_width('✓'), 1);
+        assert_eq!(char_width('\u{f016}'), 1);
+        assert_eq!(char_width('日'), 2);
+        assert_eq!(char_width('한'), 2);
+        assert_eq!(char_width('🦀'), 2);
+        assert_eq!(char_width('\u{0301}'), 0);
+        assert_eq!(char_width('\u{200d}'), 0);
+    }
+
+    #[test]
+    fn test_str_width() {
+        assert_eq!(str_width("abc"), 3);
+        assert_eq!(str_width("日本語.txt"), 10);
+        assert_eq!(str_width("e\u{0301}"), 1);
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(truncate_to_width("abcdef", 4), "abcd");
+        assert_eq!(truncate_to_width("日本語", 5), "日本");
+        assert_eq!(truncate_to_width("日本語", 6), "日本語");
+        assert_eq!(truncate_to_width("abc", 0), "");
+    }
+}
@@ -194,6 +194,42 @@ async fn test_toggle_selection_remove() {
     assert!(finder.get_selected_items().is_empty());
 }
 
+#[tokio::test]
+async fn test_select_deselect_toggle_all_filtered() {
+    let items = vec![
+        "apple".to_string(),
+        "banana".to_string(),
+        "apricot".to_string(),
+    ];
+    let mut finder = FuzzyFinder::with_items_async(items, true).await;
+    finder.move_cursor(1);
+    finder.toggle_selection(); // banana
+
+    finder.set_query("ap".to_string()).await;
+    finder.select_all();
+    assert_eq!(
+        finder.get_selected_items(),
+        vec![
+            (0, "apple".to_string()),
+            (1, "banana".to_string()),
+            (2, "apricot".to_string())
+        ]
+    );
+
+    // Selections hidden by the query are left alone
+    finder.deselect_all();
+    assert_eq!(finder.get_selected_items(), vec![(1, "banana".to_string())]);
+
+    finder.set_query("apple".to_string()).await;
+    finder.toggle_all();
+    finder.set_query(String::new()).await;
+    finder.toggle_all();
+    assert_eq!(
+        finder.get_selected_items(),
+        vec![(2, "apricot".to_string())]
+    );
+}
+
 #[tokio::test]
 async fn test_get_selected_items_single_mode() {
     let mut finder =